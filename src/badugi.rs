@@ -0,0 +1,187 @@
+//! Badugi, a lowball game of four cards where a hand only plays cards of
+//! different ranks and different suits. Four such cards are a badugi and beat
+//! any three-card hand, which beat any two-card hand, and so on. Between hands
+//! of the same size, the lowest highest card wins as in ace-to-five lowball,
+//! aces low, so A-2-3-4 of four suits is the best hand.
+//!
+//! Jokers can be any card, and wild cards any suit.
+
+use crate::{Card, HandCount, Rank, NUM_RANKS, NUM_SUITS};
+
+pub const HAND_SIZE: usize = 4;
+
+// A badugi hand. Lower compares as better.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BadugiHand {
+    // Cards short of a badugi, so that bigger hands compare lower.
+    missing: u8,
+    // Values of the cards played, aces 1, highest first, then zeros.
+    values: [u8; HAND_SIZE],
+}
+
+impl BadugiHand {
+    fn new(mut values: Vec<u8>) -> Self {
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        let mut padded = [0; HAND_SIZE];
+        padded[..values.len()].copy_from_slice(&values);
+        Self {
+            missing: (HAND_SIZE - values.len()) as u8,
+            values: padded,
+        }
+    }
+
+    // How many cards play, 4 for a badugi.
+    pub fn size(self) -> usize {
+        HAND_SIZE - self.missing as usize
+    }
+}
+
+fn value(rank: Rank) -> u8 {
+    match rank {
+        Rank::Ace => 1,
+        _ => rank as u8 + 2,
+    }
+}
+
+// The best hand `cards` and `num_jokers` jokers make, or None without any.
+pub fn evaluate_badugi(cards: &[Card], num_jokers: u8) -> Option<BadugiHand> {
+    if cards.is_empty() && num_jokers == 0 {
+        return None;
+    }
+    // Values of each suit's plain cards, as bitmasks.
+    let mut by_suit = [0u16; NUM_SUITS as usize];
+    let mut wild = Vec::new();
+    for c in cards {
+        if c.wild {
+            wild.push(value(c.rank));
+        } else {
+            by_suit[c.suit as usize] |= 1 << value(c.rank);
+        }
+    }
+    let mut best = None;
+    fill_suits(
+        NUM_SUITS as usize,
+        &by_suit,
+        &wild,
+        num_jokers,
+        &mut Vec::with_capacity(HAND_SIZE),
+        &mut best,
+    );
+    best
+}
+
+// Tries each way of giving the first `suits` suits a plain card, a wild card
+// or nothing, with jokers then taking the lowest free values in the suits
+// left over, keeping the best hand in `best`.
+fn fill_suits(
+    suits: usize,
+    by_suit: &[u16],
+    wild: &[u8],
+    num_jokers: u8,
+    played: &mut Vec<u8>,
+    best: &mut Option<BadugiHand>,
+) {
+    if suits == 0 {
+        let empty = HAND_SIZE - played.len();
+        let mut values = played.clone();
+        let free = (1..=NUM_RANKS).filter(|v| !played.contains(v));
+        values.extend(free.take(empty.min(num_jokers as usize)));
+        let hand = BadugiHand::new(values);
+        if best.is_none_or(|b| hand < b) {
+            *best = Some(hand);
+        }
+        return;
+    }
+    let suit = suits - 1;
+    fill_suits(suit, by_suit, wild, num_jokers, played, best);
+    for v in 1..=NUM_RANKS {
+        if by_suit[suit] & 1 << v != 0 && !played.contains(&v) {
+            played.push(v);
+            fill_suits(suit, by_suit, wild, num_jokers, played, best);
+            played.pop();
+        }
+    }
+    for (i, &v) in wild.iter().enumerate() {
+        // Skipping wild cards of a value already tried for this suit.
+        if !played.contains(&v) && !wild[..i].contains(&v) {
+            played.push(v);
+            let rest = [&wild[..i], &wild[i + 1..]].concat();
+            fill_suits(suit, by_suit, &rest, num_jokers, played, best);
+            played.pop();
+        }
+    }
+}
+
+fn name(size: usize) -> &'static str {
+    match size {
+        4 => "Badugi",
+        3 => "3-Card Hand",
+        2 => "2-Card Hand",
+        _ => "1-Card Hand",
+    }
+}
+
+// One count per hand size, best first, which partition the deals.
+pub fn counts() -> Vec<HandCount> {
+    (1..=HAND_SIZE)
+        .rev()
+        .map(|size| HandCount {
+            badugi: Some(size),
+            ..HandCount::new(name(size), |_, _| false)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, cards};
+
+    fn badugi(hand: &str, num_jokers: u8) -> BadugiHand {
+        evaluate_badugi(&cards!(hand), num_jokers).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate_badugi() {
+        assert_eq!(4, badugi("As 2h 3d 4c", 0).size());
+        assert_eq!(3, badugi("As 2s 3d 4c", 0).size());
+        assert_eq!(2, badugi("As Ah Ad 4d", 0).size());
+        assert_eq!(1, badugi("As Ah Ad Ac", 0).size());
+        assert_eq!(4, badugi("Ks 2s Qh 3h Jd Tc 9c", 0).size());
+        // The lowest card of each suit, when their ranks differ.
+        assert_eq!(badugi("2s 3h Jd 9c", 0), badugi("Ks 2s Qh 3h Jd Tc 9c", 0));
+        assert_eq!(badugi("2s 3h 4d Jc", 0), badugi("2s 3h 4d 2c Jc", 0));
+        assert!(badugi("Ks Qh Jd Tc", 0) < badugi("As 2h 3d", 0));
+        assert!(badugi("As 2h 3d", 0) < badugi("As 2h 4d", 0));
+        assert!(badugi("4s 3h 2d Ac", 0) < badugi("5s 3h 2d Ac", 0));
+        // A wild card can fill whichever suit is missing.
+        let mut cards = cards!("As 2s 3h 4d");
+        cards[0] = card!("As").made_wild();
+        assert_eq!(4, evaluate_badugi(&cards, 0).unwrap().size());
+        // Jokers take the lowest free values in the empty suits.
+        assert_eq!(badugi("Ks 2h Ac", 0), badugi("Ks Kh Kd", 2));
+        assert_eq!(badugi("As 2h 3d 4c", 0), badugi("Ks", 4));
+        assert_eq!(4, evaluate_badugi(&[], 4).unwrap().size());
+        assert_eq!(None, evaluate_badugi(&[], 0));
+    }
+
+    #[test]
+    fn test_counts() {
+        let counts = counts();
+        let names = counts.iter().map(|c| c.name).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["Badugi", "3-Card Hand", "2-Card Hand", "1-Card Hand"],
+            names
+        );
+        let deal = cards!("Ks 2s Qh 3s Jh");
+        let made = counts
+            .iter()
+            .filter(|c| c.matches(&deal, 0))
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["2-Card Hand"], made);
+        assert!(counts[0].possible_with(4));
+        assert!(!counts[0].possible_with(3));
+        assert!(counts[3].possible_with(4));
+    }
+}
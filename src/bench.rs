@@ -0,0 +1,157 @@
+//! `poker bench`: a fixed workload for catching evaluator slowdowns. Deals are
+//! drawn once from a seeded generator and reused, so every run and every
+//! sample times the same deals and only evaluation, not shuffling, is
+//! measured. As with criterion, a warmup runs first and the timing is
+//! repeated over several samples, reporting the median and the spread.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use arrayvec::ArrayVec;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::plan::Plan;
+use crate::{split_jokers_iter, Card, CardOrJoker, HandCount, MAX_CARDS};
+
+// Deals cycled through, enough to not all be in cache at once.
+pub const POOL_SIZE: usize = 1 << 16;
+
+pub type Deal = (ArrayVec<Card, MAX_CARDS>, u8);
+
+pub struct Config {
+    // Timed iterations across all samples, for every hand together and again
+    // for each hand on its own.
+    pub iters: u64,
+    pub samples: u64,
+    // Untimed iterations before each measurement.
+    pub warmup: u64,
+}
+
+// Nanoseconds per iteration over the samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    // None without any samples.
+    pub fn new(samples: &[f64]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        let median = match n {
+            0 => return None,
+            _ if n % 2 == 1 => sorted[n / 2],
+            _ => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        };
+        Some(Self {
+            median,
+            min: sorted[0],
+            max: sorted[n - 1],
+        })
+    }
+}
+
+pub struct Report {
+    // Evaluating every hand at once, as the simulation does.
+    pub all: Stats,
+    // Each hand's name and its own evaluation, slowest first.
+    pub detectors: Vec<(&'static str, Stats)>,
+}
+
+// `POOL_SIZE` deals of `num_cards` cards, the same for the same seed.
+pub fn deals(deck: &[CardOrJoker], num_cards: usize, seed: u64) -> Vec<Deal> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut deck = deck.to_vec();
+    (0..POOL_SIZE)
+        .map(|_| {
+            let (drawn, _) = deck.partial_shuffle(&mut rng, num_cards);
+            split_jokers_iter(drawn.iter().copied())
+        })
+        .collect()
+}
+
+// Warms up, then times `f` on `iters` deals in each of `config.samples`
+// samples, cycling through `deals`.
+fn measure(config: &Config, deals: &[Deal], mut f: impl FnMut(&Deal)) -> Stats {
+    let mut run = |iters: u64| {
+        for deal in deals.iter().cycle().take(iters as usize) {
+            f(deal);
+        }
+    };
+    run(config.warmup);
+    let iters = (config.iters / config.samples).max(1);
+    let samples = (0..config.samples)
+        .map(|_| {
+            let start = Instant::now();
+            run(iters);
+            start.elapsed().as_nanos() as f64 / iters as f64
+        })
+        .collect::<Vec<_>>();
+    Stats::new(&samples).unwrap()
+}
+
+pub fn run(counts: &[HandCount], deals: &[Deal], config: &Config) -> Report {
+    let plan = Plan::new(counts);
+    let all = measure(config, deals, |(cards, num_jokers)| {
+        black_box(plan.evaluate(counts, cards, *num_jokers));
+    });
+    let mut detectors = counts
+        .iter()
+        .map(|c| {
+            let stats = measure(config, deals, |(cards, num_jokers)| {
+                black_box(c.matches(cards, *num_jokers));
+            });
+            (c.name, stats)
+        })
+        .collect::<Vec<_>>();
+    detectors.sort_by(|(_, a), (_, b)| b.median.total_cmp(&a.median));
+    Report { all, detectors }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hand_counts, standard_deck};
+
+    #[test]
+    fn test_stats() {
+        assert_eq!(None, Stats::new(&[]));
+        let stats = Stats::new(&[3.0, 1.0, 2.0]).unwrap();
+        assert_eq!((2.0, 1.0, 3.0), (stats.median, stats.min, stats.max));
+        assert_eq!(2.5, Stats::new(&[4.0, 1.0, 2.0, 3.0]).unwrap().median);
+    }
+
+    #[test]
+    fn test_deals() {
+        let deck = standard_deck(1, 2);
+        let deals = deals(&deck, 7, 0);
+        assert_eq!(POOL_SIZE, deals.len());
+        assert!(deals
+            .iter()
+            .all(|(cards, num_jokers)| cards.len() + *num_jokers as usize == 7));
+        assert_eq!(deals, super::deals(&deck, 7, 0));
+        assert_ne!(deals, super::deals(&deck, 7, 1));
+    }
+
+    #[test]
+    fn test_run() {
+        let counts = hand_counts(5).unwrap();
+        let deals = deals(&standard_deck(1, 0), 5, 0);
+        let config = Config {
+            iters: 1000,
+            samples: 3,
+            warmup: 100,
+        };
+        let report = run(&counts, &deals, &config);
+        assert_eq!(counts.len(), report.detectors.len());
+        assert!(report.all.min <= report.all.median && report.all.median <= report.all.max);
+        assert!(report
+            .detectors
+            .windows(2)
+            .all(|w| w[0].1.median >= w[1].1.median));
+    }
+}
@@ -0,0 +1,169 @@
+//! Cards packed into a u64, a 16-bit lane per suit with a bit per rank. Built
+//! once per deal, it turns flushes into popcounts and straights into shifts
+//! and masks over the lanes ORed together.
+//!
+//! A bit only says whether a card is there, so with several decks a hand can
+//! hold more cards of a suit than its lane has bits. `suit_counts` notices and
+//! returns None, and callers count the cards instead. Straights only ask which
+//! ranks are there, which the bits answer for any deck.
+
+use crate::{Card, Rank, RankCounts, NUM_RANKS, NUM_SUITS};
+
+const LANE: u32 = 16;
+// Multiplying a rank's bit by this sets it in every lane.
+const EVERY_SUIT: u64 = 0x0001_0001_0001_0001;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bitboard {
+    bits: u64,
+    // Whether no two cards share a bit, so that popcounts count cards.
+    distinct: bool,
+}
+
+impl Bitboard {
+    // Wild cards are in every suit.
+    pub fn new(cards: &[Card]) -> Self {
+        let mut bits = 0u64;
+        let mut num_bits = 0;
+        for c in cards {
+            let bit = 1u64 << c.rank as u32;
+            if c.wild {
+                bits |= bit * EVERY_SUIT;
+                num_bits += NUM_SUITS as u32;
+            } else {
+                bits |= bit << (c.suit as u32 * LANE);
+                num_bits += 1;
+            }
+        }
+        Self {
+            bits,
+            distinct: bits.count_ones() == num_bits,
+        }
+    }
+
+    // The ranks present in `suit`, indexed like `Suit`.
+    pub fn suit(self, suit: usize) -> u16 {
+        (self.bits >> (suit as u32 * LANE)) as u16
+    }
+
+    pub fn suits(self) -> [u16; NUM_SUITS as usize] {
+        std::array::from_fn(|s| self.suit(s))
+    }
+
+    // The ranks present in any suit.
+    pub fn ranks(self) -> u16 {
+        let bits = self.bits | self.bits >> (2 * LANE);
+        (bits | bits >> LANE) as u16
+    }
+
+    // `suit_counts` from the bits, or None if duplicate cards make them
+    // undercount.
+    pub fn suit_counts(self) -> Option<RankCounts> {
+        self.distinct.then(|| {
+            let mut ret = RankCounts::default();
+            for (count, suit) in ret.iter_mut().zip(self.suits()) {
+                *count = suit.count_ones() as u8;
+            }
+            ret
+        })
+    }
+}
+
+// `ranks` laid out in straight order, as listed by `straight_order(skipped)`:
+// bit 0 for a low ace, then each rank not in `skipped`. Also returns how many
+// bits that order has.
+pub fn straight_mask(ranks: u16, skipped: u16) -> (u16, usize) {
+    let ace = 1 << Rank::Ace as u16;
+    if skipped == 0 {
+        return (
+            ranks << 1 | u16::from(ranks & ace != 0),
+            NUM_RANKS as usize + 1,
+        );
+    }
+    let kept = !skipped & ((1 << NUM_RANKS) - 1);
+    let mut len = usize::from(kept & ace != 0);
+    let mut ret = u16::from(len == 1 && ranks & ace != 0);
+    for rank in 0..NUM_RANKS {
+        let bit = 1 << rank;
+        if kept & bit != 0 {
+            ret |= u16::from(ranks & bit != 0) << len;
+            len += 1;
+        }
+    }
+    (ret, len)
+}
+
+// Where the highest run of `size` bits within the first `len` of `mask`
+// starts, counting `num_jokers` towards any missing ones.
+pub fn highest_straight(mask: u16, len: usize, num_jokers: u8, size: usize) -> Option<usize> {
+    if size == 0 || size > len {
+        return None;
+    }
+    let mask = u32::from(mask);
+    if num_jokers == 0 {
+        // Bit i survives when bits i through i + size - 1 are all set.
+        let runs = (1..size).fold(mask, |runs, i| runs & mask >> i);
+        return (runs != 0).then(|| 31 - runs.leading_zeros() as usize);
+    }
+    let window = (1 << size) - 1;
+    (0..=len - size)
+        .rev()
+        .find(|&start| (mask >> start & window).count_ones() as usize + num_jokers as usize >= size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card;
+
+    #[test]
+    fn test_bitboard() {
+        let bits = Bitboard::new(&[
+            card!("3c"),
+            card!("5h"),
+            card!("Ah"),
+            card!("2d").made_wild(),
+        ]);
+        assert_eq!(0b11, bits.suit(0));
+        assert_eq!(0b1_0000_0000_1001, bits.suit(2));
+        assert_eq!(0b1_0000_0000_1011, bits.ranks());
+        let mut expected = RankCounts::default();
+        expected[..4].copy_from_slice(&[2, 1, 3, 1]);
+        assert_eq!(Some(expected), bits.suit_counts());
+        assert_eq!(
+            None,
+            Bitboard::new(&[card!("3c"), card!("3c")]).suit_counts()
+        );
+        // A wild card shares a bit with the plain card it could be.
+        assert_eq!(
+            None,
+            Bitboard::new(&[card!("3c"), card!("3s").made_wild()]).suit_counts()
+        );
+    }
+
+    #[test]
+    fn test_straight_mask() {
+        assert_eq!((0, 14), straight_mask(0, 0));
+        // Aces also play low.
+        assert_eq!(
+            (0b10_0000_0000_1011, 14),
+            straight_mask(0b1_0000_0000_0101, 0)
+        );
+        // Without 2-5, 6 is right above a low ace.
+        assert_eq!(
+            (0b10_0000_0011, 10),
+            straight_mask(0b1_0000_0001_0001, 0b1111)
+        );
+    }
+
+    #[test]
+    fn test_highest_straight() {
+        assert_eq!(Some(9), highest_straight(0b11_1110_0000_0000, 14, 0, 5));
+        assert_eq!(Some(0), highest_straight(0b1_1111, 14, 0, 5));
+        assert_eq!(Some(0), highest_straight(0b11_1011_1101_1111, 14, 0, 5));
+        assert_eq!(None, highest_straight(0b11_0111_1011_1101, 14, 0, 5));
+        assert_eq!(Some(9), highest_straight(0b11_0111_1011_1101, 14, 1, 5));
+        assert_eq!(Some(9), highest_straight(0, 14, 5, 5));
+        assert_eq!(None, highest_straight(0b1_1111, 4, 0, 5));
+    }
+}
@@ -0,0 +1,265 @@
+//! Several boards dealt from one deck, as in double-board bomb pots.
+//!
+//! `MultiBoard` counts how many boards make each hand, each board its own
+//! draw of `num_cards` from the shared deck, so what one board takes the
+//! others can't have.
+//!
+//! `BombPot` splits the pot: players' hole cards play with every board, each
+//! board wins an equal share of the pot, and a board's share splits between
+//! the players whose hands rank best on it. The caller ranks hands, such as
+//! by `classes::best_strength` so that kickers break ties as at a showdown.
+
+use crate::{split_jokers, split_jokers_iter, Card, CardOrJoker, HandCount};
+use rand::seq::SliceRandom;
+
+pub struct MultiBoard {
+    pub iters: u64,
+    pub boards: usize,
+    // `made[hand][k]` deals where exactly `k` of the boards made the hand.
+    pub made: Vec<Vec<u64>>,
+}
+
+impl MultiBoard {
+    pub fn run(
+        deck: &[CardOrJoker],
+        num_cards: usize,
+        counts: &[HandCount],
+        boards: usize,
+        iters: u64,
+        rng: &mut impl rand::Rng,
+    ) -> Result<Self, String> {
+        if num_cards * boards > deck.len() {
+            return Err(format!(
+                "{boards} boards of {num_cards} cards need more than the {} cards in the deck",
+                deck.len()
+            ));
+        }
+        let mut deck = deck.to_vec();
+        let mut made = vec![vec![0; boards + 1]; counts.len()];
+        for _ in 0..iters {
+            let (drawn, _) = deck.partial_shuffle(rng, num_cards * boards);
+            let mut hits = vec![0; counts.len()];
+            for board in drawn.chunks(num_cards) {
+                let (cards, num_jokers) = split_jokers(board);
+                for (h, c) in counts.iter().enumerate() {
+                    hits[h] += c.matches(&cards, num_jokers) as usize;
+                }
+            }
+            for (h, &k) in hits.iter().enumerate() {
+                made[h][k] += 1;
+            }
+        }
+        Ok(Self {
+            iters,
+            boards,
+            made,
+        })
+    }
+
+    fn fraction(&self, n: u64) -> f64 {
+        n as f64 / self.iters as f64
+    }
+
+    // Chance that a given board makes the hand.
+    pub fn per_board(&self, hand: usize) -> f64 {
+        let total = self.made[hand]
+            .iter()
+            .enumerate()
+            .map(|(k, &n)| k as u64 * n)
+            .sum::<u64>();
+        self.fraction(total) / self.boards as f64
+    }
+
+    pub fn any(&self, hand: usize) -> f64 {
+        1.0 - self.fraction(self.made[hand][0])
+    }
+
+    // Some boards make the hand but not all.
+    pub fn some(&self, hand: usize) -> f64 {
+        self.any(hand) - self.every(hand)
+    }
+
+    pub fn every(&self, hand: usize) -> f64 {
+        self.fraction(self.made[hand][self.boards])
+    }
+}
+
+pub struct Table {
+    pub players: usize,
+    pub hole_cards: usize,
+    pub boards: usize,
+    pub board_size: usize,
+}
+
+pub struct BombPot {
+    pub iters: u64,
+    // Per player: the share of the pot won, summed over the deals.
+    pub equity: Vec<f64>,
+    // Per player: deals where they won every board alone.
+    pub scoops: Vec<u64>,
+    // Per player: deals where they won some of the pot but not all of it.
+    pub splits: Vec<u64>,
+}
+
+impl BombPot {
+    // `rank` ranks a player's hole cards followed by a board, None for no
+    // hand at all. Players with equal ranks split the board.
+    pub fn run<R: Ord + Clone>(
+        deck: &[CardOrJoker],
+        table: &Table,
+        rank: impl Fn(&[Card], u8) -> Option<R>,
+        iters: u64,
+        rng: &mut impl rand::Rng,
+    ) -> Result<Self, String> {
+        let Table {
+            players,
+            hole_cards,
+            boards,
+            board_size,
+        } = *table;
+        let num_drawn = players * hole_cards + boards * board_size;
+        if num_drawn > deck.len() {
+            return Err(format!(
+                "{players} players of {hole_cards} cards and {boards} boards of {board_size} cards need more than the {} cards in the deck",
+                deck.len()
+            ));
+        }
+        let mut deck = deck.to_vec();
+        let mut equity = vec![0.0; players];
+        let mut scoops = vec![0; players];
+        let mut splits = vec![0; players];
+        let mut ranks = vec![None; players];
+        let mut shares = vec![0.0; players];
+        // Per player, boards won without sharing them.
+        let mut alone = vec![0; players];
+        for _ in 0..iters {
+            let (drawn, _) = deck.partial_shuffle(rng, num_drawn);
+            let (board_cards, hands) = drawn.split_at(boards * board_size);
+            shares.fill(0.0);
+            alone.fill(0);
+            for board in board_cards.chunks(board_size) {
+                for (r, hand) in ranks.iter_mut().zip(hands.chunks(hole_cards)) {
+                    let (cards, num_jokers) = split_jokers_iter(hand.iter().chain(board).copied());
+                    *r = rank(&cards, num_jokers);
+                }
+                let best = ranks.iter().max().unwrap();
+                let winners = ranks.iter().filter(|&r| r == best).count();
+                for (p, r) in ranks.iter().enumerate() {
+                    if r == best {
+                        shares[p] += 1.0 / (boards * winners) as f64;
+                        alone[p] += (winners == 1) as usize;
+                    }
+                }
+            }
+            for p in 0..players {
+                equity[p] += shares[p];
+                if alone[p] == boards {
+                    scoops[p] += 1;
+                } else if shares[p] > 0.0 {
+                    splits[p] += 1;
+                }
+            }
+        }
+        Ok(Self {
+            iters,
+            equity,
+            scoops,
+            splits,
+        })
+    }
+
+    // `player`'s average share of the pot, counting from 0.
+    pub fn equity(&self, player: usize) -> f64 {
+        self.equity[player] / self.iters as f64
+    }
+
+    pub fn scoop(&self, player: usize) -> f64 {
+        self.scoops[player] as f64 / self.iters as f64
+    }
+
+    pub fn split(&self, player: usize) -> f64 {
+        self.splits[player] as f64 / self.iters as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::best_strength;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_multi_board() {
+        let deck = crate::standard_deck(1, 0);
+        let counts = vec![HandCount::new("Pair", |cards, num_jokers| {
+            crate::is_n_of_a_kind(cards, 2, num_jokers)
+        })];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let boards = MultiBoard::run(&deck, 2, &counts, 2, 20000, &mut rng).unwrap();
+        assert_eq!(20000, boards.made[0].iter().sum::<u64>());
+        // Each board is a pair 3/51 of the time.
+        assert!((boards.per_board(0) - 3.0 / 51.0).abs() < 0.005);
+        assert!((boards.any(0) - boards.some(0) - boards.every(0)).abs() < 1e-12);
+        assert!(boards.every(0) < 0.01);
+
+        assert!(MultiBoard::run(&deck, 7, &counts, 8, 1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_bomb_pot() {
+        let deck = crate::standard_deck(1, 0);
+        let table = Table {
+            players: 3,
+            hole_cards: 2,
+            boards: 2,
+            board_size: 5,
+        };
+        let rank = |cards: &[Card], _| best_strength(cards);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let pot = BombPot::run(&deck, &table, rank, 20000, &mut rng).unwrap();
+        // The whole pot goes to someone every deal, and the seats are alike.
+        let total = (0..3).map(|p| pot.equity(p)).sum::<f64>();
+        assert!((1.0 - total).abs() < 1e-9);
+        for p in 0..3 {
+            assert!((pot.equity(p) - 1.0 / 3.0).abs() < 0.02);
+            assert!(pot.scoop(p) + pot.split(p) <= 1.0);
+            assert!(pot.scoop(p) < pot.equity(p));
+        }
+        // On one board, splits are ties, and kickers settle most hands of
+        // the same category.
+        let table = Table {
+            players: 2,
+            hole_cards: 2,
+            boards: 1,
+            board_size: 5,
+        };
+        let category =
+            |cards: &[Card], num_jokers| Some(crate::evaluate_best(cards, num_jokers, 5));
+        let pot = BombPot::run(&deck, &table, rank, 5000, &mut rng).unwrap();
+        let by_category = BombPot::run(&deck, &table, category, 5000, &mut rng).unwrap();
+        assert!(pot.splits[0] * 4 < by_category.splits[0]);
+
+        // Equal ranks split every board.
+        let rank = category;
+        let deck = crate::cards!("2c 3d 4h 5s 7c 8d")
+            .into_iter()
+            .map(CardOrJoker::Card)
+            .collect::<Vec<_>>();
+        let table = Table {
+            players: 2,
+            hole_cards: 1,
+            boards: 2,
+            board_size: 2,
+        };
+        let pot = BombPot::run(&deck, &table, rank, 100, &mut rng).unwrap();
+        assert_eq!(vec![100, 100], pot.splits);
+        assert_eq!(vec![0, 0], pot.scoops);
+        assert_eq!(0.5, pot.equity(0));
+
+        let table = Table {
+            players: 4,
+            ..table
+        };
+        assert!(BombPot::run(&deck, &table, rank, 1, &mut rng).is_err());
+    }
+}
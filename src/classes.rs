@@ -0,0 +1,253 @@
+//! Canonical 5-card hand classes: groups of hands which always tie, ordered
+//! from strongest to weakest.
+//!
+//! A class is a category from `evaluate_best` plus the ranks that break ties
+//! within it, so two hands are in the same class exactly when neither beats
+//! the other. A single standard deck has the well known 7462 classes. With
+//! the extended set, hands which need more than one deck (five of a kind,
+//! flush houses, flush fives and flushes with repeated ranks) are included
+//! too.
+
+use crate::{evaluate_best, format_hand, Card, HandRank, Rank, Suit, NUM_RANKS, NUM_SUITS};
+use std::io::Write;
+
+pub struct HandClass {
+    // 1 for the strongest class.
+    pub rank: u32,
+    pub category: HandRank,
+    // Ranks in tie-breaking order: most repeated first, then highest first,
+    // with the ace of a wheel straight last.
+    pub ranks: [Rank; 5],
+    // An example hand in the class.
+    pub example: [Card; 5],
+}
+
+// Whether `ranks`, sorted by descending rank, form an ace-low straight.
+fn is_wheel(category: HandRank, ranks: &[Rank; 5]) -> bool {
+    matches!(category, HandRank::Straight | HandRank::StraightFlush)
+        && ranks[0] == Rank::Ace
+        && ranks[1] == Rank::Five
+}
+
+fn tiebreak_order(category: HandRank, ranks: [Rank; 5]) -> [Rank; 5] {
+    let count = |r: Rank| ranks.iter().filter(|&&x| x == r).count();
+    let mut sorted = ranks;
+    sorted.sort_by_key(|&r| std::cmp::Reverse((count(r), r)));
+    if is_wheel(category, &sorted) {
+        sorted.rotate_left(1);
+    }
+    sorted
+}
+
+// Comparable strength of ranks in tie-breaking order, where a wheel's ace
+// counts below a deuce.
+fn tiebreak_key(category: HandRank, ranks: &[Rank; 5]) -> [u8; 5] {
+    let mut key = ranks.map(|r| r as u8 + 1);
+    if matches!(category, HandRank::Straight | HandRank::StraightFlush) && ranks[4] == Rank::Ace {
+        key[4] = 0;
+    }
+    key
+}
+
+// How strong a 5-card hand without wild cards is at showdown: its category,
+// then its ranks in tie-breaking order. The stronger hand has the greater
+// strength, and hands of equal strength split the pot.
+pub type Strength = (HandRank, [u8; 5]);
+
+pub fn strength(hand: &[Card; 5]) -> Strength {
+    let category = evaluate_best(hand, 0, 5);
+    let ranks = tiebreak_order(category, hand.map(|c| c.rank));
+    (category, tiebreak_key(category, &ranks))
+}
+
+// The strength of the best 5 of `cards`, or None for fewer than 5 cards.
+pub fn best_strength(cards: &[Card]) -> Option<Strength> {
+    let n = cards.len();
+    // Every 5 card subset, as the bitmasks with 5 bits set.
+    (0u32..1 << n)
+        .filter(|mask| mask.count_ones() == 5)
+        .map(|mask| {
+            let mut hand = (0..n).filter(|i| mask & 1 << i != 0).map(|i| cards[i]);
+            strength(&std::array::from_fn(|_| hand.next().unwrap()))
+        })
+        .max()
+}
+
+// Every hand class, strongest first. `extended` adds classes which need
+// more than one deck.
+pub fn hand_classes(extended: bool) -> Vec<HandClass> {
+    fn recurse(start: usize, ranks: &mut Vec<Rank>, max_copies: usize, out: &mut Vec<[Rank; 5]>) {
+        if ranks.len() == 5 {
+            out.push(ranks.clone().try_into().unwrap());
+            return;
+        }
+        for i in start..NUM_RANKS as usize {
+            let rank = Rank::ALL[i];
+            if ranks.iter().filter(|&&r| r == rank).count() < max_copies {
+                ranks.push(rank);
+                recurse(i, ranks, max_copies, out);
+                ranks.pop();
+            }
+        }
+    }
+
+    let mut multisets = Vec::new();
+    recurse(
+        0,
+        &mut Vec::new(),
+        if extended { 5 } else { 4 },
+        &mut multisets,
+    );
+    let mut classes = Vec::new();
+    for ranks in multisets {
+        let distinct = (1..5).all(|i| ranks[i] != ranks[i - 1]);
+        for suited in [false, true] {
+            if suited && !distinct && !extended {
+                continue;
+            }
+            // Cycling through suits never makes a flush from 5 cards.
+            let example = std::array::from_fn(|i| {
+                let suit = if suited {
+                    Suit::Spades
+                } else {
+                    Suit::ALL[i % NUM_SUITS as usize]
+                };
+                Card::new(ranks[4 - i], suit)
+            });
+            let category = evaluate_best(&example, 0, 5);
+            let ranks = tiebreak_order(category, ranks);
+            classes.push(HandClass {
+                rank: 0,
+                category,
+                ranks,
+                example,
+            });
+        }
+    }
+    classes.sort_by_key(|c| std::cmp::Reverse((c.category, tiebreak_key(c.category, &c.ranks))));
+    classes.dedup_by_key(|c| (c.category, c.ranks));
+    for (i, c) in classes.iter_mut().enumerate() {
+        c.rank = i as u32 + 1;
+    }
+    classes
+}
+
+fn rank_chars(ranks: &[Rank; 5]) -> String {
+    ranks.iter().map(|r| r.to_string()).collect()
+}
+
+// One row per class: rank, category, tie-breaking ranks like "KKQQ2", and an
+// example hand.
+pub fn write_csv(classes: &[HandClass], mut w: impl Write) -> std::io::Result<()> {
+    writeln!(w, "rank,category,ranks,example")?;
+    for c in classes {
+        writeln!(
+            w,
+            "{},{},{},{}",
+            c.rank,
+            c.category.id(),
+            rank_chars(&c.ranks),
+            format_hand(&c.example, 0)
+        )?;
+    }
+    Ok(())
+}
+
+// "PKHC", a little-endian u32 count, then 13 bytes per class: a u16 rank,
+// the category's index in `HandRank::ALL`, the five tie-breaking ranks
+// (0 for deuces through 12 for aces) and the example hand as five card
+// bytes.
+pub fn write_binary(classes: &[HandClass], mut w: impl Write) -> std::io::Result<()> {
+    w.write_all(b"PKHC")?;
+    w.write_all(&(classes.len() as u32).to_le_bytes())?;
+    for c in classes {
+        w.write_all(&(c.rank as u16).to_le_bytes())?;
+        w.write_all(&[c.category as u8])?;
+        w.write_all(&c.ranks.map(|r| r as u8))?;
+        w.write_all(&c.example.map(u8::from))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category_count(classes: &[HandClass], category: HandRank) -> usize {
+        classes.iter().filter(|c| c.category == category).count()
+    }
+
+    #[test]
+    fn test_standard_classes() {
+        let classes = hand_classes(false);
+        assert_eq!(7462, classes.len());
+        for (category, count) in [
+            (HandRank::StraightFlush, 10),
+            (HandRank::FourOfAKind, 156),
+            (HandRank::FullHouse, 156),
+            (HandRank::Flush, 1277),
+            (HandRank::Straight, 10),
+            (HandRank::ThreeOfAKind, 858),
+            (HandRank::TwoPair, 858),
+            (HandRank::Pair, 2860),
+            (HandRank::HighCard, 1277),
+        ] {
+            assert_eq!(count, category_count(&classes, category), "{category}");
+        }
+        assert_eq!("AKQJT", rank_chars(&classes[0].ranks));
+        // The wheel is the weakest straight flush, and 7-high the weakest
+        // hand.
+        assert_eq!("5432A", rank_chars(&classes[9].ranks));
+        assert_eq!(HandRank::FourOfAKind, classes[10].category);
+        assert_eq!("AAAAK", rank_chars(&classes[10].ranks));
+        assert_eq!("75432", rank_chars(&classes[7461].ranks));
+        assert_eq!(7462, classes[7461].rank);
+    }
+
+    #[test]
+    fn test_extended_classes() {
+        let classes = hand_classes(true);
+        assert_eq!(HandRank::FlushFive, classes[0].category);
+        assert_eq!("AAAAA", rank_chars(&classes[0].ranks));
+        assert_eq!(13, category_count(&classes, HandRank::FlushFive));
+        assert_eq!(156, category_count(&classes, HandRank::FlushHouse));
+        assert_eq!(13, category_count(&classes, HandRank::FiveOfAKind));
+        // A flush may now hold any ranks except a full house or better.
+        assert!(category_count(&classes, HandRank::Flush) > 1277);
+        for c in &classes {
+            assert_eq!(c.category, evaluate_best(&c.example, 0, 5));
+        }
+    }
+
+    #[test]
+    fn test_strength() {
+        let classes = hand_classes(false);
+        assert!(classes
+            .windows(2)
+            .all(|w| strength(&w[0].example) > strength(&w[1].example)));
+        let best = |hand| best_strength(&crate::cards!(hand));
+        assert!(best("As Ad Kc 7h 2s") > best("Ah Ac Qd Js Ts"));
+        assert_eq!(best("As Ad Kc 7h 2s"), best("Ah Ac Kd 7s 2h"));
+        assert!(best("2c 3d 4h 5s 6c") > best("Ac 2d 3h 4s 5c"));
+        // The board plays when the hole cards don't improve it.
+        assert_eq!(best("Kc Qd Jh Ts 9c"), best("2h 3s Kc Qd Jh Ts 9c"));
+        assert_eq!(None, best("As Ad Kc 7h"));
+    }
+
+    #[test]
+    fn test_write() {
+        let classes = hand_classes(false);
+        let mut csv = Vec::new();
+        write_csv(&classes[..2], &mut csv).unwrap();
+        assert_eq!(
+            "rank,category,ranks,example\n\
+             1,straight_flush,AKQJT,As Ks Qs Js Ts\n\
+             2,straight_flush,KQJT9,Ks Qs Js Ts 9s\n",
+            String::from_utf8(csv).unwrap()
+        );
+        let mut binary = Vec::new();
+        write_binary(&classes, &mut binary).unwrap();
+        assert_eq!(8 + 13 * 7462, binary.len());
+        assert_eq!(7462u32.to_le_bytes(), binary[4..8]);
+    }
+}
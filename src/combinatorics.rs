@@ -0,0 +1,156 @@
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+
+// Exact probability, as used by the analytic and enumeration modes.
+pub type Probability = BigRational;
+
+// Number of ways to choose `k` items from `n`, as a float so large decks
+// don't overflow.
+pub fn binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// Probability of drawing exactly `k` successes when drawing `draw` cards
+// without replacement from `deck` cards, `successes` of which are successes.
+pub fn hypergeometric(deck: u64, successes: u64, draw: u64, k: u64) -> f64 {
+    if successes > deck || draw > deck || k > successes || k > draw {
+        return 0.0;
+    }
+    if draw - k > deck - successes {
+        return 0.0;
+    }
+    binomial(successes, k) * binomial(deck - successes, draw - k) / binomial(deck, draw)
+}
+
+// Exact number of ways to choose `k` items from `n`.
+pub fn binomial_exact(n: u64, k: u64) -> BigUint {
+    if k > n {
+        return BigUint::zero();
+    }
+    let k = k.min(n - k);
+    // Each partial product is itself a binomial coefficient, so the division
+    // is always exact.
+    (0..k).fold(BigUint::one(), |acc, i| acc * (n - i) / (i + 1))
+}
+
+// Number of multisets of size `k` drawn from `n` kinds of items.
+pub fn multiset_coefficient(n: u64, k: u64) -> BigUint {
+    if n == 0 {
+        return if k == 0 {
+            BigUint::one()
+        } else {
+            BigUint::zero()
+        };
+    }
+    binomial_exact(n + k - 1, k)
+}
+
+// Number of ways to split `counts.iter().sum()` items into groups of the
+// given sizes.
+pub fn multinomial(counts: &[u64]) -> BigUint {
+    let mut total = 0;
+    let mut ret = BigUint::one();
+    for &c in counts {
+        total += c;
+        ret *= binomial_exact(total, c);
+    }
+    ret
+}
+
+// Exact version of `hypergeometric`.
+pub fn hypergeometric_exact(deck: u64, successes: u64, draw: u64, k: u64) -> Probability {
+    if successes > deck || draw > deck || k > successes || k > draw {
+        return Probability::zero();
+    }
+    if draw - k > deck - successes {
+        return Probability::zero();
+    }
+    let ways = binomial_exact(successes, k) * binomial_exact(deck - successes, draw - k);
+    Probability::new(BigInt::from(ways), BigInt::from(binomial_exact(deck, draw)))
+}
+
+// Closest float to an exact probability.
+pub fn to_f64(p: &Probability) -> f64 {
+    p.to_f64().unwrap_or(f64::NAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binomial() {
+        assert_eq!(1.0, binomial(0, 0));
+        assert_eq!(1.0, binomial(5, 0));
+        assert_eq!(5.0, binomial(5, 1));
+        assert_eq!(10.0, binomial(5, 3));
+        assert_eq!(0.0, binomial(3, 5));
+        assert_eq!(2598960.0, binomial(52, 5));
+    }
+
+    #[test]
+    fn test_hypergeometric() {
+        // Drawing 2 aces in a 5-card hand.
+        let p = hypergeometric(52, 4, 5, 2);
+        assert!((p - 0.0399298).abs() < 1e-6);
+        let total = (0..=4).map(|k| hypergeometric(52, 4, 5, k)).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert_eq!(0.0, hypergeometric(52, 4, 5, 5));
+        assert_eq!(0.0, hypergeometric(10, 9, 5, 3));
+        assert!((hypergeometric(10, 9, 5, 4) + hypergeometric(10, 9, 5, 5) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_binomial_exact() {
+        assert_eq!(BigUint::one(), binomial_exact(0, 0));
+        assert_eq!(BigUint::zero(), binomial_exact(3, 5));
+        assert_eq!(BigUint::from(2598960u32), binomial_exact(52, 5));
+        for n in 0..30 {
+            for k in 0..=n {
+                assert_eq!(
+                    binomial(n, k).round(),
+                    binomial_exact(n, k).to_f64().unwrap()
+                );
+            }
+        }
+        // Too big for a u128.
+        assert_eq!(
+            "90548514656103281165404177077484163874504589675413336841320",
+            binomial_exact(200, 100).to_string()
+        );
+    }
+
+    #[test]
+    fn test_multiset_coefficient() {
+        assert_eq!(BigUint::one(), multiset_coefficient(0, 0));
+        assert_eq!(BigUint::zero(), multiset_coefficient(0, 2));
+        assert_eq!(BigUint::from(6u32), multiset_coefficient(3, 2));
+        // Distinct rank multisets in a 5-card hand.
+        assert_eq!(BigUint::from(6188u32), multiset_coefficient(13, 5));
+    }
+
+    #[test]
+    fn test_multinomial() {
+        assert_eq!(BigUint::one(), multinomial(&[]));
+        assert_eq!(BigUint::from(30u32), multinomial(&[2, 2, 1]));
+        assert_eq!(binomial_exact(52, 5), multinomial(&[5, 47]));
+    }
+
+    #[test]
+    fn test_hypergeometric_exact() {
+        assert_eq!(
+            Probability::new(BigInt::from(103776), BigInt::from(2598960)),
+            hypergeometric_exact(52, 4, 5, 2)
+        );
+        let total = (0..=4)
+            .map(|k| hypergeometric_exact(52, 4, 5, k))
+            .fold(Probability::zero(), |a, b| a + b);
+        assert_eq!(Probability::one(), total);
+        assert_eq!(Probability::zero(), hypergeometric_exact(52, 4, 5, 5));
+    }
+}
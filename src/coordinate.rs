@@ -0,0 +1,192 @@
+//! Splits simulations and exact enumerations across machines running
+//! `poker serve`, using its SIMULATE and ENUMERATE requests.
+
+use crate::exact::{CombinationSpace, ExactCounts};
+use crate::serve::{request, DeckConfig, MAX_ITERATIONS, OP_ENUMERATE, OP_SIMULATE};
+use std::sync::Mutex;
+
+// Sends every request body to some worker and parses the responses, returned
+// in the same order as `bodies`. Workers pull the next body as soon as they
+// finish the last, so faster machines take on more of the work. When a
+// worker fails, its body goes back in the queue for the others.
+fn run_requests<T: Send>(
+    workers: &[String],
+    bodies: Vec<Vec<u8>>,
+    parse: impl Fn(&[u8]) -> Result<T, String> + Sync,
+) -> Result<Vec<T>, String> {
+    let num_bodies = bodies.len();
+    let queue = Mutex::new(bodies.into_iter().enumerate().collect::<Vec<_>>());
+    let results = Mutex::new((0..num_bodies).map(|_| None).collect::<Vec<_>>());
+    let last_error = Mutex::new(None);
+    std::thread::scope(|s| {
+        for worker in workers {
+            let (queue, results, last_error, parse) = (&queue, &results, &last_error, &parse);
+            s.spawn(move || {
+                let mut stream = match std::net::TcpStream::connect(worker) {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        eprintln!("{worker}: {e}");
+                        *last_error.lock().unwrap() = Some(format!("{worker}: {e}"));
+                        return;
+                    }
+                };
+                loop {
+                    let Some((i, body)) = queue.lock().unwrap().pop() else {
+                        return;
+                    };
+                    match request(&mut stream, &body).and_then(|r| parse(&r)) {
+                        Ok(result) => results.lock().unwrap()[i] = Some(result),
+                        Err(e) => {
+                            eprintln!("{worker}: {e}");
+                            *last_error.lock().unwrap() = Some(format!("{worker}: {e}"));
+                            queue.lock().unwrap().push((i, body));
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    let results = results.into_inner().unwrap();
+    if results.iter().any(|r| r.is_none()) {
+        let error = last_error.into_inner().unwrap();
+        return Err(error.unwrap_or_else(|| "no workers".to_string()));
+    }
+    Ok(results.into_iter().map(|r| r.unwrap()).collect())
+}
+
+fn read_u64s(bytes: &[u8], n: usize) -> Result<Vec<u64>, String> {
+    if bytes.len() != 8 * n {
+        return Err("response has the wrong length".to_string());
+    }
+    Ok(bytes
+        .chunks(8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .collect())
+}
+
+fn read_u128s(bytes: &[u8], n: usize) -> Result<Vec<u128>, String> {
+    if bytes.len() != 16 * n {
+        return Err("response has the wrong length".to_string());
+    }
+    Ok(bytes
+        .chunks(16)
+        .map(|b| u128::from_le_bytes(b.try_into().unwrap()))
+        .collect())
+}
+
+// Deals `iterations` hands in chunks of `chunk`, returning how many made each
+// entry of `config.counts()`.
+pub fn simulate(
+    workers: &[String],
+    config: DeckConfig,
+    iterations: u64,
+    chunk: u64,
+) -> Result<Vec<u64>, String> {
+    let num_hands = config.counts()?.len();
+    let bodies = (0..iterations.div_ceil(chunk))
+        .map(|i| {
+            let n = chunk.min(iterations - i * chunk);
+            let mut body = vec![OP_SIMULATE];
+            body.extend(config.encode());
+            body.extend(n.to_le_bytes());
+            body
+        })
+        .collect();
+    let results = run_requests(workers, bodies, |r| read_u64s(r, num_hands))?;
+    let mut hits = vec![0; num_hands];
+    for result in results {
+        for (h, r) in hits.iter_mut().zip(result) {
+            *h += r;
+        }
+    }
+    Ok(hits)
+}
+
+// Exact counts for every draw, split into `num_chunks` ranges.
+pub fn enumerate(
+    workers: &[String],
+    config: DeckConfig,
+    num_chunks: u128,
+) -> Result<ExactCounts, String> {
+    let num_hands = config.counts()?.len();
+    config.check_enumerable()?;
+    let space = CombinationSpace::new(&config.deck(), config.cards as usize);
+    // Workers refuse ranges of more than `MAX_ITERATIONS` draws.
+    let chunk = space
+        .len()
+        .div_ceil(num_chunks)
+        .clamp(1, MAX_ITERATIONS as u128);
+    let bodies = (0..space.len().div_ceil(chunk))
+        .map(|i| {
+            let start = i * chunk;
+            let end = (start + chunk).min(space.len());
+            let mut body = vec![OP_ENUMERATE];
+            body.extend(config.encode());
+            body.extend(start.to_le_bytes());
+            body.extend(end.to_le_bytes());
+            body
+        })
+        .collect();
+    let results = run_requests(workers, bodies, |r| {
+        let values = read_u128s(r, num_hands + 1)?;
+        Ok(ExactCounts {
+            total: values[0],
+            hits: values[1..].to_vec(),
+        })
+    })?;
+    Ok(results
+        .into_iter()
+        .fold(ExactCounts::new(num_hands), ExactCounts::merge))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: DeckConfig = DeckConfig {
+        cards: 3,
+        decks: 1,
+        jokers: 1,
+        hand_size: 5,
+    };
+
+    fn spawn_worker() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || crate::serve::serve_tcp_listener(listener));
+        addr
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let workers = vec![spawn_worker(), spawn_worker()];
+        let distributed = enumerate(&workers, CONFIG, 7).unwrap();
+        let local = crate::exact::enumerate(&CONFIG.deck(), 3, &CONFIG.counts().unwrap());
+        assert_eq!(local.total, distributed.total);
+        assert_eq!(local.hits, distributed.hits);
+    }
+
+    #[test]
+    fn test_simulate() {
+        let workers = vec![spawn_worker()];
+        let hits = simulate(&workers, CONFIG, 1000, 300).unwrap();
+        assert_eq!(CONFIG.counts().unwrap().len(), hits.len());
+        assert!(hits[0] > 0 && hits[0] < 1000);
+    }
+
+    #[test]
+    fn test_failed_workers() {
+        // Nothing listens on a port that was just released.
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        assert!(simulate(std::slice::from_ref(&closed), CONFIG, 10, 10).is_err());
+        // The live worker picks up all of the work.
+        let hits = simulate(&[closed, spawn_worker()], CONFIG, 10, 1).unwrap();
+        assert!(hits[0] <= 10);
+        assert!(simulate(&[], CONFIG, 10, 10).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! Deck files, for decks edited beyond what `--decks` and `--jokers` can
+//! describe. Each line sets how many copies of a card the deck has, starting
+//! from one standard deck:
+//!
+//! ```text
+//! # Balatro deck after a few Cryptids and a Hanged Man
+//! As x3
+//! Kh x0
+//! 2 jokers
+//! ```
+//!
+//! "decks N" starts from N standard decks instead, so "decks 0" lists every
+//! card explicitly. Wild cards are written with a trailing "*", like "7h* x2".
+//!
+//! Stripped decks like short deck hold'em's are also available as presets.
+
+use crate::{standard_deck, Card, CardOrJoker, Rank, Suit};
+
+// Decks with ranks stripped out, or doubled up as in pinochle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    Standard,
+    // 6 and up, as in short deck hold'em.
+    Short36,
+    // 7 and up, as in piquet.
+    Short32,
+    // Two copies of each card from 9 up.
+    Pinochle,
+    // No face cards, like Balatro's Abandoned Deck.
+    Abandoned,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 5] = [
+        Preset::Standard,
+        Preset::Short36,
+        Preset::Short32,
+        Preset::Pinochle,
+        Preset::Abandoned,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Preset::Standard => "standard",
+            Preset::Short36 => "short36",
+            Preset::Short32 => "short32",
+            Preset::Pinochle => "pinochle",
+            Preset::Abandoned => "abandoned",
+        }
+    }
+
+    fn has_rank(self, rank: Rank) -> bool {
+        match self {
+            Preset::Standard => true,
+            Preset::Short36 => rank >= Rank::Six,
+            Preset::Short32 => rank >= Rank::Seven,
+            Preset::Pinochle => rank >= Rank::Nine,
+            Preset::Abandoned => !matches!(rank, Rank::Jack | Rank::Queen | Rank::King),
+        }
+    }
+
+    // `decks` copies of the preset, in the same order as `standard_deck`.
+    pub fn deck(self, decks: usize, jokers: u8) -> Vec<CardOrJoker> {
+        let copies = if self == Preset::Pinochle { 2 } else { 1 };
+        let mut deck = Vec::new();
+        for _ in 0..decks * copies {
+            for suit in Suit::iter() {
+                for rank in Rank::iter().filter(|&r| self.has_rank(r)) {
+                    deck.push(CardOrJoker::Card(Card::new(rank, suit)));
+                }
+            }
+        }
+        deck.extend(std::iter::repeat_n(CardOrJoker::Joker, jokers as usize));
+        deck
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .iter()
+            .copied()
+            .find(|p| p.id() == s)
+            .ok_or_else(|| {
+                let ids = Preset::ALL.iter().map(|p| p.id()).collect::<Vec<_>>();
+                format!(
+                    "unknown deck preset '{s}', expected one of: {}",
+                    ids.join(", ")
+                )
+            })
+    }
+}
+
+// Ranks with no cards in `deck`, for `skipped_ranks`.
+pub fn missing_ranks(deck: &[CardOrJoker]) -> Vec<Rank> {
+    Rank::iter()
+        .filter(|&r| {
+            !deck
+                .iter()
+                .any(|c| matches!(c, CardOrJoker::Card(card) if card.rank == r))
+        })
+        .collect()
+}
+
+// Cards in the order `standard_deck` deals them, with how many copies of each.
+fn card_counts(deck: &[CardOrJoker]) -> Vec<(Card, usize)> {
+    let mut counts: Vec<(Card, usize)> = Vec::new();
+    for &c in deck {
+        if let CardOrJoker::Card(card) = c {
+            match counts.iter_mut().find(|(c, _)| *c == card) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((card, 1)),
+            }
+        }
+    }
+    counts
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    s.parse().map_err(|_| format!("invalid count '{s}'"))
+}
+
+pub fn parse(text: &str) -> Result<Vec<CardOrJoker>, String> {
+    let mut decks = None;
+    let mut jokers = None;
+    let mut overrides: Vec<(Card, usize)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |e: String| format!("line {}: {e}", i + 1);
+        let line = line.split('#').next().unwrap().trim();
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (setting, value) = match words[..] {
+            [] => continue,
+            ["decks", n] => (&mut decks, n),
+            ["jokers", n] | [n, "jokers" | "joker"] => (&mut jokers, n),
+            [card, count] => {
+                let card = card.parse::<Card>().map_err(error)?;
+                let count = count
+                    .strip_prefix(['x', 'X'])
+                    .ok_or_else(|| error(format!("expected a count like x2, not '{count}'")))?;
+                let count = parse_count(count).map_err(error)?;
+                if overrides.iter().any(|(c, _)| *c == card) {
+                    return Err(error(format!("{card} is listed twice")));
+                }
+                overrides.push((card, count));
+                continue;
+            }
+            _ => {
+                return Err(error(format!(
+                    "expected a card and count like 'As x3', 'decks N' or 'N jokers', not '{line}'"
+                )))
+            }
+        };
+        if setting.is_some() {
+            return Err(error(format!("'{line}' repeats an earlier setting")));
+        }
+        *setting = Some(parse_count(value).map_err(error)?);
+    }
+    let jokers = u8::try_from(jokers.unwrap_or(0)).map_err(|_| "too many jokers".to_string())?;
+    let mut counts = card_counts(&standard_deck(decks.unwrap_or(1), 0));
+    for (card, count) in overrides {
+        match counts.iter_mut().find(|(c, _)| *c == card) {
+            Some((_, n)) => *n = count,
+            None => counts.push((card, count)),
+        }
+    }
+    // Deal copies round-robin like `standard_deck` does for several decks, so
+    // `--wild` makes the same cards wild either way.
+    let mut deck = Vec::new();
+    let max_copies = counts.iter().map(|&(_, n)| n).max().unwrap_or(0);
+    for copy in 0..max_copies {
+        for &(card, n) in &counts {
+            if copy < n {
+                deck.push(CardOrJoker::Card(card));
+            }
+        }
+    }
+    deck.extend(std::iter::repeat_n(CardOrJoker::Joker, jokers as usize));
+    if deck.is_empty() {
+        return Err("the deck has no cards".to_string());
+    }
+    Ok(deck)
+}
+
+pub fn read(path: &std::path::Path) -> Result<Vec<CardOrJoker>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+// Takes one copy of each of `cards` out of `deck`, for cards guaranteed to
+// be in every draw.
+pub fn take_cards(deck: &mut Vec<CardOrJoker>, cards: &[CardOrJoker]) -> Result<(), String> {
+    for card in cards {
+        let i = deck
+            .iter()
+            .position(|c| c == card)
+            .ok_or_else(|| match card {
+                CardOrJoker::Card(card) => format!("the deck doesn't have enough {card}"),
+                CardOrJoker::Joker => "the deck doesn't have enough jokers".to_string(),
+            })?;
+        deck.swap_remove(i);
+    }
+    Ok(())
+}
+
+// How `deck` differs from a standard deck, like "As x3, Kh x0, 2 jokers", or
+// "standard" if it doesn't.
+pub fn describe(deck: &[CardOrJoker]) -> String {
+    let edits = edits(deck);
+    if edits.is_empty() {
+        "standard".to_string()
+    } else {
+        edits.join(", ")
+    }
+}
+
+// `deck` as a deck file, which `parse` reads back as the same cards.
+pub fn to_text(deck: &[CardOrJoker]) -> String {
+    edits(deck).iter().map(|e| e.clone() + "\n").collect()
+}
+
+// The lines of a deck file for `deck`, starting from one standard deck.
+fn edits(deck: &[CardOrJoker]) -> Vec<String> {
+    let counts = card_counts(deck);
+    let mut ret = Vec::new();
+    for (card, n) in card_counts(&standard_deck(1, 0)) {
+        if !counts.contains(&(card, n)) {
+            let n = counts
+                .iter()
+                .find(|(c, _)| *c == card)
+                .map_or(0, |&(_, n)| n);
+            ret.push(format!("{card} x{n}"));
+        }
+    }
+    for &(card, n) in &counts {
+        if card.wild {
+            ret.push(format!("{card} x{n}"));
+        }
+    }
+    match deck.iter().filter(|c| **c == CardOrJoker::Joker).count() {
+        0 => {}
+        1 => ret.push("1 joker".to_string()),
+        n => ret.push(format!("{n} jokers")),
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card;
+
+    fn count(deck: &[CardOrJoker], card: Card) -> usize {
+        deck.iter()
+            .filter(|c| **c == CardOrJoker::Card(card))
+            .count()
+    }
+
+    #[test]
+    fn test_parse() {
+        let deck = parse("# edited\nAs x3\nkh X0 # gone\n\n2 jokers\n7h* x2\n").unwrap();
+        assert_eq!(52 + 2 - 1 + 2 + 2, deck.len());
+        assert_eq!(3, count(&deck, card!("As")));
+        assert_eq!(0, count(&deck, card!("Kh")));
+        assert_eq!(1, count(&deck, card!("7h")));
+        assert_eq!(
+            2,
+            count(&deck, Card::new(Rank::Seven, Suit::Hearts).made_wild())
+        );
+        assert_eq!("Kh x0, As x3, 7h* x2, 2 jokers", describe(&deck));
+
+        let deck = parse("decks 0\nAs x2\njokers 1").unwrap();
+        assert_eq!(
+            vec![
+                CardOrJoker::Card(card!("As")),
+                CardOrJoker::Card(card!("As")),
+                CardOrJoker::Joker
+            ],
+            deck
+        );
+        assert_eq!(standard_deck(2, 0), parse("decks 2").unwrap());
+        assert_eq!("standard", describe(&parse("").unwrap()));
+    }
+
+    #[test]
+    fn test_to_text() {
+        let deck = parse("As x3\nKh x0\n7h* x2\n2 jokers").unwrap();
+        assert_eq!("Kh x0\nAs x3\n7h* x2\n2 jokers\n", to_text(&deck));
+        let mut sorted = parse(&to_text(&deck)).unwrap();
+        let mut expected = deck.clone();
+        sorted.sort_by_key(|c| format!("{c:?}"));
+        expected.sort_by_key(|c| format!("{c:?}"));
+        assert_eq!(expected, sorted);
+        assert_eq!("", to_text(&standard_deck(1, 0)));
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(standard_deck(2, 1), Preset::Standard.deck(2, 1));
+        assert_eq!(36, Preset::Short36.deck(1, 0).len());
+        assert_eq!(32, Preset::Short32.deck(1, 0).len());
+        assert_eq!(48, Preset::Pinochle.deck(1, 0).len());
+        assert_eq!(2, count(&Preset::Pinochle.deck(1, 0), card!("9s")));
+        assert_eq!(41, Preset::Abandoned.deck(1, 1).len());
+        assert_eq!(Ok(Preset::Short32), "short32".parse());
+        assert!("short".parse::<Preset>().is_err());
+
+        assert_eq!(
+            vec![Rank::Two, Rank::Three, Rank::Four, Rank::Five],
+            missing_ranks(&Preset::Short36.deck(1, 0))
+        );
+        assert_eq!(
+            vec![Rank::Jack, Rank::Queen, Rank::King],
+            missing_ranks(&Preset::Abandoned.deck(1, 0))
+        );
+        assert!(missing_ranks(&standard_deck(1, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_take_cards() {
+        let mut deck = standard_deck(1, 1);
+        let cards = [CardOrJoker::Card(card!("Kh")), CardOrJoker::Joker];
+        take_cards(&mut deck, &cards).unwrap();
+        assert_eq!(51, deck.len());
+        assert_eq!(0, count(&deck, card!("Kh")));
+        assert!(!deck.contains(&CardOrJoker::Joker));
+        assert_eq!(
+            Err("the deck doesn't have enough Kh".to_string()),
+            take_cards(&mut deck, &cards)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err("line 2: invalid rank in card 'Zz'".to_string()),
+            parse("\nZz x1")
+        );
+        assert!(parse("As 3").is_err());
+        assert!(parse("As x-1").is_err());
+        assert!(parse("As x1\nAs x2").is_err());
+        assert!(parse("decks 1\ndecks 2").is_err());
+        assert!(parse("300 jokers").is_err());
+        assert!(parse("decks 0").is_err());
+        assert!(parse("As x1 extra").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Differential testing of `evaluate_best` against a reference evaluator, on
+//! random 5 to 7 card hands from one standard deck without jokers, the rules
+//! every evaluator agrees on. Hands are compared by category, since that's all
+//! `evaluate_best` ranks.
+//!
+//! A reference is anything implementing `ReferenceEvaluator`. `Textbook` is a
+//! deliberately naive one written without any of this crate's detectors: it
+//! scores every 5 card subset by counting ranks and suits and keeps the best.
+//! It's the only reference so far, so this checks `evaluate_best` against
+//! independent code in this crate rather than an established evaluator. An
+//! adapter for an external evaluator crate, implementing the same trait
+//! behind this feature, is still a follow-up request.
+
+use crate::test_util::Dealer;
+use crate::{evaluate_best, format_hand, Card, HandRank, Rank};
+
+pub trait ReferenceEvaluator {
+    fn name(&self) -> &str;
+
+    // The best standard poker category among `cards`, 5 to 7 cards with no
+    // jokers.
+    fn evaluate(&self, cards: &[Card]) -> HandRank;
+}
+
+pub struct Textbook;
+
+impl Textbook {
+    fn evaluate_five(cards: &[Card]) -> HandRank {
+        let mut rank_counts = [0; 13];
+        for c in cards {
+            rank_counts[c.rank as usize] += 1;
+        }
+        let mut groups = rank_counts
+            .iter()
+            .copied()
+            .filter(|&n| n > 0)
+            .collect::<Vec<_>>();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        let flush = cards.iter().all(|c| c.suit == cards[0].suit);
+        let mut ranks = cards.iter().map(|c| c.rank as u8).collect::<Vec<_>>();
+        ranks.sort_unstable();
+        let wheel = ranks == [0, 1, 2, 3, Rank::Ace as u8];
+        let straight = groups.len() == 5 && (ranks[4] - ranks[0] == 4 || wheel);
+        match (straight, flush, groups.as_slice()) {
+            (true, true, _) => HandRank::StraightFlush,
+            (_, _, [4, ..]) => HandRank::FourOfAKind,
+            (_, _, [3, 2]) => HandRank::FullHouse,
+            (_, true, _) => HandRank::Flush,
+            (true, _, _) => HandRank::Straight,
+            (_, _, [3, ..]) => HandRank::ThreeOfAKind,
+            (_, _, [2, 2, ..]) => HandRank::TwoPair,
+            (_, _, [2, ..]) => HandRank::Pair,
+            _ => HandRank::HighCard,
+        }
+    }
+}
+
+impl ReferenceEvaluator for Textbook {
+    fn name(&self) -> &str {
+        "textbook"
+    }
+
+    fn evaluate(&self, cards: &[Card]) -> HandRank {
+        let n = cards.len();
+        let mut best = HandRank::HighCard;
+        // Every 5 card subset, as the bitmasks with 5 bits set.
+        for mask in 0u32..1 << n {
+            if mask.count_ones() != 5 {
+                continue;
+            }
+            let five = (0..n)
+                .filter(|i| mask & 1 << i != 0)
+                .map(|i| cards[i])
+                .collect::<Vec<_>>();
+            best = best.max(Self::evaluate_five(&five));
+        }
+        best
+    }
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+    pub hand: String,
+    pub ours: HandRank,
+    pub reference: HandRank,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} here, {} from the reference",
+            self.hand, self.ours, self.reference
+        )
+    }
+}
+
+// Compares `num_hands` random hands of 5 to 7 cards, returning those where
+// `evaluate_best` and `reference` disagree.
+pub fn compare(reference: &dyn ReferenceEvaluator, num_hands: usize, seed: u64) -> Vec<Mismatch> {
+    let mut dealer = Dealer::new(1, 0, seed);
+    let mut ret = Vec::new();
+    for i in 0..num_hands {
+        let (cards, _) = dealer.deal(5 + i % 3);
+        let ours = evaluate_best(&cards, 0, 5);
+        let theirs = reference.evaluate(&cards);
+        if ours != theirs {
+            ret.push(Mismatch {
+                hand: format_hand(&cards, 0),
+                ours,
+                reference: theirs,
+            });
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards;
+
+    #[test]
+    fn test_textbook() {
+        for (expected, hand) in [
+            (HandRank::StraightFlush, "Ah 2h 3h 4h 5h Kd Kc"),
+            (HandRank::Straight, "Ah 2h 3h 4h 5d"),
+            (HandRank::HighCard, "Qh Kh Ah 2d 3c"),
+            (HandRank::FullHouse, "Ah Ad As Kd Kc Kh 2c"),
+            (HandRank::Flush, "2h 4h 6h 8h Th Td Tc"),
+            (HandRank::TwoPair, "2h 2d 3c 3d 4s 4h 9c"),
+        ] {
+            assert_eq!(expected, Textbook.evaluate(&cards!(hand)), "{hand}");
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let mismatches = compare(&Textbook, 20_000, 0);
+        assert!(
+            mismatches.is_empty(),
+            "{} mismatches, e.g. {}",
+            mismatches.len(),
+            mismatches[0]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! `poker edit`: a deck editor for planning a Balatro deck, which starts a
+//! short simulation of the deck after every change so the probabilities on
+//! screen follow the edits. Commands are typed one per line:
+//!
+//! - `add CARD [N]` adds N copies of a card, 1 by default. Cards are written
+//!   as in deck files, like "As" or "7h*" for a wild card, or "joker".
+//! - `remove CARD [N]` takes copies out.
+//! - `dup CARD` copies a card already in the deck, like Balatro's Cryptid.
+//! - `wild CARD` toggles the Wild enhancement on one copy of a card. Other
+//!   enhancements don't change which hands a deal makes.
+//! - `undo` and `reset` go back one edit or to the starting deck.
+//! - `rules independent|best` switches between counting every hand a deal
+//!   makes and only its best one.
+//! - `results HAND` compares HAND across the session's recent simulations.
+//! - `save PATH` writes a deck file for `--deck-file`, and `q` quits.
+
+use crate::plan::Plan;
+use crate::rules::RuleSet;
+use crate::{split_jokers, Card, CardOrJoker, HandCount};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Add(CardOrJoker, usize),
+    Remove(CardOrJoker, usize),
+    Duplicate(Card),
+    ToggleWild(Card),
+    Undo,
+    Reset,
+    Rules(RuleSet),
+    Results(String),
+    Save(std::path::PathBuf),
+    Quit,
+}
+
+fn parse_card_or_joker(s: &str) -> Result<CardOrJoker, String> {
+    if s.eq_ignore_ascii_case("joker") {
+        return Ok(CardOrJoker::Joker);
+    }
+    s.parse().map(CardOrJoker::Card)
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let copies = |n: Option<&&str>| match n {
+            None => Ok(1),
+            Some(n) => n
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("invalid count '{n}'")),
+        };
+        Ok(match words[..] {
+            ["add", card, ref rest @ ..] if rest.len() <= 1 => {
+                Command::Add(parse_card_or_joker(card)?, copies(rest.first())?)
+            }
+            ["remove", card, ref rest @ ..] if rest.len() <= 1 => {
+                Command::Remove(parse_card_or_joker(card)?, copies(rest.first())?)
+            }
+            ["dup", card] => Command::Duplicate(card.parse()?),
+            ["wild", card] => Command::ToggleWild(card.parse::<Card>()?),
+            ["undo"] => Command::Undo,
+            ["reset"] => Command::Reset,
+            ["rules", rules] => Command::Rules(
+                <RuleSet as clap::ValueEnum>::from_str(rules, true)
+                    .map_err(|_| format!("unknown rules '{rules}', expected independent or best"))?,
+            ),
+            ["results", ref hand @ ..] if !hand.is_empty() => Command::Results(hand.join(" ")),
+            ["save", path] => Command::Save(path.into()),
+            ["q" | "quit"] => Command::Quit,
+            _ => {
+                return Err(format!(
+                    "unknown command '{s}', expected add, remove, dup, wild, undo, reset, rules, results, save or q"
+                ))
+            }
+        })
+    }
+}
+
+pub struct Editor {
+    pub deck: Vec<CardOrJoker>,
+    start: Vec<CardOrJoker>,
+    history: Vec<Vec<CardOrJoker>>,
+}
+
+impl Editor {
+    pub fn new(deck: Vec<CardOrJoker>) -> Self {
+        Self {
+            start: deck.clone(),
+            deck,
+            history: Vec::new(),
+        }
+    }
+
+    // Applies an edit to the deck. Commands that don't change the deck are
+    // left to the caller.
+    pub fn apply(&mut self, command: &Command) -> Result<(), String> {
+        let mut deck = self.deck.clone();
+        match *command {
+            Command::Add(card, n) => deck.extend(std::iter::repeat_n(card, n)),
+            Command::Remove(card, n) => {
+                for _ in 0..n {
+                    let i = position(&deck, card)?;
+                    deck.remove(i);
+                }
+            }
+            Command::Duplicate(card) => {
+                let card = CardOrJoker::Card(card);
+                position(&deck, card)?;
+                deck.push(card);
+            }
+            Command::ToggleWild(card) => {
+                let i = position(&deck, CardOrJoker::Card(card))?;
+                deck[i] = CardOrJoker::Card(Card {
+                    wild: !card.wild,
+                    ..card
+                });
+            }
+            Command::Undo => {
+                self.deck = self.history.pop().ok_or("nothing to undo")?;
+                return Ok(());
+            }
+            Command::Reset => deck = self.start.clone(),
+            Command::Rules(_) | Command::Results(_) | Command::Save(_) | Command::Quit => {
+                return Ok(())
+            }
+        }
+        if deck.is_empty() {
+            return Err("the deck would have no cards".to_string());
+        }
+        self.history.push(std::mem::replace(&mut self.deck, deck));
+        Ok(())
+    }
+}
+
+fn position(deck: &[CardOrJoker], card: CardOrJoker) -> Result<usize, String> {
+    deck.iter()
+        .position(|&c| c == card)
+        .ok_or_else(|| match card {
+            CardOrJoker::Card(card) => format!("the deck has no {card}"),
+            CardOrJoker::Joker => "the deck has no jokers".to_string(),
+        })
+}
+
+// A simulation of one version of the deck, run a batch at a time so that an
+// edit can replace it between batches.
+pub struct Live {
+    pub deck: Vec<CardOrJoker>,
+    pub counts: Vec<HandCount>,
+    pub num_iters: u64,
+    num_cards: usize,
+    plan: Plan,
+}
+
+impl Live {
+    // Draws `num_cards` cards, or the whole deck if it's smaller.
+    pub fn new(deck: Vec<CardOrJoker>, num_cards: usize, mut counts: Vec<HandCount>) -> Self {
+        for c in &mut counts {
+            c.count = 0;
+            c.first_seen = None;
+            c.last_seen = 0;
+            c.max_gap = 0;
+        }
+        Self {
+            num_cards: num_cards.min(deck.len()),
+            plan: Plan::new(&counts),
+            deck,
+            counts,
+            num_iters: 0,
+        }
+    }
+
+    pub fn run(&mut self, iters: u64, rng: &mut impl rand::Rng) {
+        use rand::seq::SliceRandom;
+
+        for _ in 0..iters {
+            let (drawn, _) = self.deck.partial_shuffle(rng, self.num_cards);
+            let (cards, num_jokers) = split_jokers(drawn);
+            let mask = self.plan.evaluate(&self.counts, &cards, num_jokers);
+            for (i, c) in self.counts.iter_mut().enumerate() {
+                if mask >> i & 1 != 0 {
+                    c.record(self.num_iters);
+                }
+            }
+            self.num_iters += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, hand_counts, standard_deck};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(Command::Add(CardOrJoker::Card(card!("As")), 3)),
+            "add As 3".parse()
+        );
+        assert_eq!(
+            Ok(Command::Remove(CardOrJoker::Joker, 1)),
+            "remove joker".parse()
+        );
+        assert_eq!(
+            Ok(Command::ToggleWild(card!("7h").made_wild())),
+            "wild 7h*".parse()
+        );
+        assert_eq!(Ok(Command::Quit), " q ".parse());
+        assert_eq!(Ok(Command::Rules(RuleSet::Best)), "rules Best".parse());
+        assert_eq!(
+            Ok(Command::Results("Full House".to_string())),
+            "results Full  House".parse()
+        );
+        assert!("rules lowball".parse::<Command>().is_err());
+        assert!("results".parse::<Command>().is_err());
+        assert!("add As 0".parse::<Command>().is_err());
+        assert!("add".parse::<Command>().is_err());
+        assert!("dup joker".parse::<Command>().is_err());
+        assert!("shuffle".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut editor = Editor::new(standard_deck(1, 0));
+        let mut apply = |s: &str| editor.apply(&s.parse().unwrap());
+        apply("dup As").unwrap();
+        apply("remove Kh").unwrap();
+        apply("wild 7h").unwrap();
+        apply("add joker 2").unwrap();
+        assert_eq!(Err("the deck has no Kh".to_string()), apply("dup Kh"));
+        assert_eq!(
+            "7h x0, Kh x0, As x2, 7h* x1, 2 jokers",
+            crate::deck::describe(&editor.deck)
+        );
+        editor.apply(&Command::Undo).unwrap();
+        assert_eq!(53 - 1, editor.deck.len());
+        editor.apply(&"wild 7h*".parse().unwrap()).unwrap();
+        assert_eq!("Kh x0, As x2", crate::deck::describe(&editor.deck));
+        editor.apply(&Command::Reset).unwrap();
+        assert_eq!(standard_deck(1, 0), editor.deck);
+        assert!(Editor::new(standard_deck(1, 0))
+            .apply(&Command::Undo)
+            .is_err());
+        let mut tiny = Editor::new(vec![CardOrJoker::Joker]);
+        assert!(tiny.apply(&"remove joker".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_live() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut live = Live::new(vec![CardOrJoker::Joker; 5], 7, hand_counts(5).unwrap());
+        live.run(10, &mut rng);
+        assert_eq!(10, live.num_iters);
+        assert_eq!(10, live.counts[0].count);
+        let live = Live::new(live.deck, 7, live.counts);
+        assert_eq!(0, live.counts[0].count);
+    }
+}
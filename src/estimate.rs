@@ -0,0 +1,278 @@
+//! Analytic probability estimates, available before any deals are simulated.
+//!
+//! Draws are grouped by profile: how many cards of each rank (or each suit)
+//! they contain, ignoring which ranks or suits those are. A detector which
+//! only looks at rank counts, like n of a kind or full house, gives the same
+//! answer for every draw with the same rank profile, so summing the
+//! probabilities of the profiles it accepts is exact. Likewise for suit
+//! counts and flushes. Detectors are probed with differently arranged draws
+//! of each profile to check which case, if either, they fall into; anything
+//! else, like straights, gets no estimate.
+
+use crate::combinatorics::binomial;
+use crate::{Card, HandCount, Rank, Suit, NUM_RANKS, NUM_SUITS};
+
+// A draw profile: non-increasing counts of cards per group plus a number of
+// jokers, and its probability.
+struct Profile {
+    counts: Vec<usize>,
+    num_jokers: usize,
+    probability: f64,
+}
+
+// Every profile of `num_cards` cards drawn from `num_groups` groups of
+// `copies` cards each plus `jokers` jokers.
+fn profiles(num_groups: usize, copies: usize, jokers: usize, num_cards: usize) -> Vec<Profile> {
+    fn recurse(
+        max_part: usize,
+        remaining: usize,
+        parts: &mut Vec<usize>,
+        num_groups: usize,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        out.push(parts.clone());
+        if parts.len() == num_groups {
+            return;
+        }
+        for part in (1..=max_part.min(remaining)).rev() {
+            parts.push(part);
+            recurse(part, remaining - part, parts, num_groups, out);
+            parts.pop();
+        }
+    }
+
+    let total = binomial((num_groups * copies + jokers) as u64, num_cards as u64);
+    let mut partitions = Vec::new();
+    recurse(
+        copies,
+        num_cards,
+        &mut Vec::new(),
+        num_groups,
+        &mut partitions,
+    );
+    partitions
+        .into_iter()
+        .filter_map(|counts| {
+            let num_jokers = num_cards - counts.iter().sum::<usize>();
+            if num_jokers > jokers {
+                return None;
+            }
+            // Ways to pick which groups get which counts...
+            let mut ways = binomial(num_groups as u64, counts.len() as u64);
+            let mut remaining = counts.len() as u64;
+            for run in counts.chunk_by(|a, b| a == b) {
+                ways *= binomial(remaining, run.len() as u64);
+                remaining -= run.len() as u64;
+            }
+            // ...then which copies within each group, and which jokers.
+            for &c in &counts {
+                ways *= binomial(copies as u64, c as u64);
+            }
+            ways *= binomial(jokers as u64, num_jokers as u64);
+            Some(Profile {
+                counts,
+                num_jokers,
+                probability: ways / total,
+            })
+        })
+        .collect()
+}
+
+// Counting down from the ace, so that runs of probe cards reach the ranks
+// royal flushes need.
+fn rank_from_top(i: usize) -> Rank {
+    Rank::ALL[NUM_RANKS as usize - 1 - i % NUM_RANKS as usize]
+}
+
+// Draws with the given rank profile, arranged differently: spread out and
+// rainbow, consecutive and rainbow, and all one suit.
+fn rank_probes(counts: &[usize]) -> [Vec<Card>; 3] {
+    let rank = rank_from_top;
+    let suit = |i: usize| Suit::ALL[i % NUM_SUITS as usize];
+    let build = |rank_of: &dyn Fn(usize) -> Rank, suit_of: &dyn Fn(usize) -> Suit| {
+        counts
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &c)| (0..c).map(move |j| (i, j)))
+            .map(|(i, j)| Card::new(rank_of(i), suit_of(j)))
+            .collect()
+    };
+    [
+        build(&|i| rank(i * 2), &suit),
+        build(&rank, &suit),
+        build(&rank, &|_| Suit::Spades),
+    ]
+}
+
+// Draws with the given suit profile, arranged differently: distinct spread
+// out ranks, consecutive ranks, and all one rank.
+fn suit_probes(counts: &[usize]) -> [Vec<Card>; 3] {
+    let build = |rank_of: &dyn Fn(usize) -> Rank| {
+        counts
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &c)| (0..c).map(move |_| Suit::ALL[i]))
+            .enumerate()
+            .map(|(t, suit)| Card::new(rank_of(t), suit))
+            .collect()
+    };
+    [
+        build(&|t| rank_from_top(t * 2)),
+        build(&rank_from_top),
+        build(&|_| Rank::Ace),
+    ]
+}
+
+// Probability of `count`, if every probe of every profile agrees.
+fn profile_estimate(
+    count: &HandCount,
+    profiles: &[Profile],
+    probes: fn(&[usize]) -> [Vec<Card>; 3],
+) -> Option<f64> {
+    let mut p = 0.0;
+    for profile in profiles {
+        let num_jokers = profile.num_jokers as u8;
+        let [first, rest @ ..] =
+            probes(&profile.counts).map(|cards| count.matches(&cards, num_jokers));
+        if rest.iter().any(|&r| r != first) {
+            return None;
+        }
+        if first {
+            p += profile.probability;
+        }
+    }
+    Some(p)
+}
+
+// Estimated probability of each hand in `counts` when drawing `num_cards`
+// cards from `decks` standard decks plus `jokers` jokers, or None for hands
+// which depend on more than rank or suit counts.
+pub fn estimate(
+    decks: usize,
+    jokers: u8,
+    num_cards: usize,
+    counts: &[HandCount],
+) -> Vec<Option<f64>> {
+    let jokers = jokers as usize;
+    let num_cards = num_cards.min(decks * (NUM_RANKS * NUM_SUITS) as usize + jokers);
+    let by_rank = profiles(
+        NUM_RANKS as usize,
+        decks * NUM_SUITS as usize,
+        jokers,
+        num_cards,
+    );
+    let by_suit = profiles(
+        NUM_SUITS as usize,
+        decks * NUM_RANKS as usize,
+        jokers,
+        num_cards,
+    );
+    counts
+        .iter()
+        .map(|c| {
+            profile_estimate(c, &by_rank, rank_probes)
+                .or_else(|| profile_estimate(c, &by_suit, suit_probes))
+        })
+        .collect()
+}
+
+// Iterations until the 99.73% confidence intervals of every pair of
+// estimated, distinct, nonzero probabilities stop overlapping.
+pub fn iterations_to_separate(estimates: &[Option<f64>]) -> Option<u64> {
+    let known = estimates
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|&p| p > 0.0)
+        .collect::<Vec<_>>();
+    let sd = |p: f64| (p * (1.0 - p)).sqrt();
+    let mut needed: Option<f64> = None;
+    for (i, &a) in known.iter().enumerate() {
+        for &b in &known[i + 1..] {
+            if a == b {
+                continue;
+            }
+            let n = (3.0 * (sd(a) + sd(b)) / (a - b).abs()).powi(2);
+            needed = Some(needed.map_or(n, |m| m.max(n)));
+        }
+    }
+    needed.map(|n| n.ceil() as u64)
+}
+
+// Iterations needed for the normal interval on a probability `p` to be
+// within `rel_error * p` of it at `confidence`.
+pub fn iterations_for_error(p: f64, rel_error: f64, confidence: f64) -> u64 {
+    let z = two_sided_z(confidence);
+    ((z / rel_error).powi(2) * (1.0 - p) / p).ceil() as u64
+}
+
+// The inverse of `iterations_for_error`: the relative half-width of the
+// interval on `p` after `iterations` deals.
+pub fn error_after(p: f64, iterations: u64, confidence: f64) -> f64 {
+    two_sided_z(confidence) * ((1.0 - p) / (p * iterations as f64)).sqrt()
+}
+
+fn two_sided_z(confidence: f64) -> f64 {
+    crate::interval::normal_quantile(0.5 + confidence / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        for (decks, jokers, num_cards) in [(1, 0, 5), (2, 3, 7), (1, 2, 2)] {
+            for (groups, copies) in [(13, 4 * decks), (4, 13 * decks)] {
+                let total = profiles(groups, copies, jokers, num_cards)
+                    .iter()
+                    .map(|p| p.probability)
+                    .sum::<f64>();
+                assert!((total - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_estimate() {
+        let counts = crate::hand_counts(5).unwrap();
+        let estimates = estimate(1, 0, 5, &counts);
+        let get = |name: &str| estimates[counts.iter().position(|c| c.name == name).unwrap()];
+        // Standard 5-card poker odds, counting better hands as containing
+        // worse ones.
+        assert!((get("Pair").unwrap() - (1.0 - 1317888.0 / 2598960.0)).abs() < 1e-12);
+        assert!((get("Full House").unwrap() - 3744.0 / 2598960.0).abs() < 1e-12);
+        assert!((get("4oak").unwrap() - 624.0 / 2598960.0).abs() < 1e-12);
+        assert_eq!(Some(0.0), get("5oak"));
+        assert_eq!(None, get("Strt Flush"));
+        assert_eq!(None, get("Royal Flush"));
+
+        let counts = crate::hand_counts(6).unwrap();
+        let estimates = estimate(1, 0, 6, &counts);
+        let flush = counts.iter().position(|c| c.name == "Flush").unwrap();
+        // All 6 cards from one of the 4 suits.
+        let expected = 4.0 * binomial(13, 6) / binomial(52, 6);
+        assert!((estimates[flush].unwrap() - expected).abs() < 1e-12);
+        let straight = counts.iter().position(|c| c.name == "Straight").unwrap();
+        assert_eq!(None, estimates[straight]);
+    }
+
+    #[test]
+    fn test_iterations_for_error() {
+        // z = 1.96: (1.96 / 0.1)^2 * 0.5 / 0.5.
+        assert_eq!(385, iterations_for_error(0.5, 0.1, 0.95));
+        let n = iterations_for_error(0.0002, 0.05, 0.99);
+        assert!((error_after(0.0002, n, 0.99) - 0.05).abs() < 1e-6);
+        assert!(error_after(0.0002, n / 4, 0.99) > 0.099);
+    }
+
+    #[test]
+    fn test_iterations_to_separate() {
+        assert_eq!(None, iterations_to_separate(&[None, Some(0.0), Some(0.5)]));
+        assert_eq!(None, iterations_to_separate(&[Some(0.5), Some(0.5)]));
+        // Intervals of +-3 standard errors meet in the middle.
+        let n = iterations_to_separate(&[Some(0.5), Some(0.4)]).unwrap();
+        let sd = 0.5 + (0.24f64).sqrt();
+        assert_eq!((30.0 * sd).powi(2).ceil() as u64, n);
+    }
+}
@@ -0,0 +1,423 @@
+use crate::combinatorics::Probability;
+use crate::plan::Plan;
+use crate::{split_jokers, Card, CardOrJoker, HandCount, MAX_CARDS};
+use num_bigint::BigInt;
+
+// Exact hit counts over every possible draw. Identical cards (from multiple
+// decks, or jokers) are grouped so each distinct multiset of cards is only
+// evaluated once, weighted by the number of draws that produce it.
+pub struct ExactCounts {
+    pub total: u128,
+    pub hits: Vec<u128>,
+}
+
+impl ExactCounts {
+    pub fn new(num_hands: usize) -> Self {
+        Self {
+            total: 0,
+            hits: vec![0; num_hands],
+        }
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        self.total += other.total;
+        for (a, b) in self.hits.iter_mut().zip(other.hits) {
+            *a += b;
+        }
+        self
+    }
+
+    pub fn probability(&self, hand: usize) -> Probability {
+        Probability::new(BigInt::from(self.hits[hand]), BigInt::from(self.total))
+    }
+}
+
+// Distinct cards in the deck and how many copies of each there are.
+fn group_deck(deck: &[CardOrJoker]) -> Vec<(CardOrJoker, u64)> {
+    let mut groups: Vec<(CardOrJoker, u64)> = Vec::new();
+    for &coj in deck {
+        match groups.iter_mut().find(|(g, _)| *g == coj) {
+            Some((_, n)) => *n += 1,
+            None => groups.push((coj, 1)),
+        }
+    }
+    groups
+}
+
+// The number of draws of `num_cards` from a deck of `deck_len` cards,
+// counting identical cards separately, or None when computing it overflows
+// a u128. Every count an enumeration keeps is at most this, so it's safe to
+// enumerate exactly when this is Some.
+pub fn num_draws(deck_len: usize, num_cards: usize) -> Option<u128> {
+    let (n, k) = (deck_len as u128, num_cards as u128);
+    if k > n {
+        return Some(0);
+    }
+    // The same steps as `binomial_u128`.
+    (0..k.min(n - k)).try_fold(1u128, |acc, i| Some(acc.checked_mul(n - i)? / (i + 1)))
+}
+
+fn binomial_u128(n: u64, k: u64) -> u128 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    (0..k as u128).fold(1, |acc, i| acc * (n as u128 - i) / (i + 1))
+}
+
+// Every distinct draw of `num_cards` cards from a deck, numbered from 0 in
+// the order `enumerate` visits them. Draws are ordered by how many copies of
+// the first distinct card they take, then the second, and so on.
+pub struct CombinationSpace {
+    groups: Vec<(CardOrJoker, u64)>,
+    num_cards: usize,
+    // `ways[group][remaining]` is the number of distinct ways to draw
+    // `remaining` cards using only `groups[group..]`.
+    ways: Vec<Vec<u128>>,
+}
+
+impl CombinationSpace {
+    pub fn new(deck: &[CardOrJoker], num_cards: usize) -> Self {
+        let groups = group_deck(deck);
+        let mut ways = vec![vec![0; num_cards + 1]; groups.len() + 1];
+        ways[groups.len()][0] = 1;
+        for g in (0..groups.len()).rev() {
+            for remaining in 0..=num_cards {
+                ways[g][remaining] = (0..=(groups[g].1 as usize).min(remaining))
+                    .map(|take| ways[g + 1][remaining - take])
+                    .sum();
+            }
+        }
+        Self {
+            groups,
+            num_cards,
+            ways,
+        }
+    }
+
+    // Number of distinct draws.
+    pub fn len(&self) -> u128 {
+        self.ways[0][self.num_cards]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The draw numbered `index`.
+    pub fn unrank_combination(&self, mut index: u128) -> Vec<CardOrJoker> {
+        assert!(index < self.len());
+        let mut drawn = Vec::with_capacity(self.num_cards);
+        let mut remaining = self.num_cards;
+        for (g, &(coj, copies)) in self.groups.iter().enumerate() {
+            for take in 0..=(copies as usize).min(remaining) {
+                let sub = self.ways[g + 1][remaining - take];
+                if index < sub {
+                    drawn.extend(std::iter::repeat_n(coj, take));
+                    remaining -= take;
+                    break;
+                }
+                index -= sub;
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        drawn
+    }
+
+    // Inverse of `unrank_combination`, or None if `drawn` isn't a possible
+    // draw from this deck. Order within `drawn` doesn't matter.
+    pub fn rank_combination(&self, drawn: &[CardOrJoker]) -> Option<u128> {
+        if drawn.len() != self.num_cards {
+            return None;
+        }
+        let mut index = 0;
+        let mut remaining = self.num_cards;
+        let mut num_matched = 0;
+        for (g, &(coj, copies)) in self.groups.iter().enumerate() {
+            let take = drawn.iter().filter(|&&d| d == coj).count();
+            if take > copies as usize {
+                return None;
+            }
+            index += (0..take)
+                .map(|t| self.ways[g + 1][remaining - t])
+                .sum::<u128>();
+            remaining -= take;
+            num_matched += take;
+        }
+        (num_matched == drawn.len()).then_some(index)
+    }
+
+    // Number of physical draws, counting identical cards from different
+    // decks separately, which give `drawn`.
+    pub fn weight(&self, drawn: &[CardOrJoker]) -> u128 {
+        self.groups
+            .iter()
+            .map(|&(coj, copies)| {
+                binomial_u128(copies, drawn.iter().filter(|&&d| d == coj).count() as u64)
+            })
+            .product()
+    }
+}
+
+struct Enumerator<'a> {
+    space: &'a CombinationSpace,
+    counts: &'a [HandCount],
+    plan: Plan,
+    range: std::ops::Range<u128>,
+    cards: arrayvec::ArrayVec<Card, MAX_CARDS>,
+    num_jokers: u8,
+    result: ExactCounts,
+}
+
+impl Enumerator<'_> {
+    // Visits the draws numbered `first..first + ways[group][remaining]` which
+    // fall inside `self.range`.
+    fn recurse(&mut self, group: usize, remaining: usize, weight: u128, first: u128) {
+        if remaining == 0 {
+            self.result.total += weight;
+            let mask = self
+                .plan
+                .evaluate(self.counts, &self.cards, self.num_jokers);
+            for (h, hits) in self.result.hits.iter_mut().enumerate() {
+                if mask & 1 << h != 0 {
+                    *hits += weight;
+                }
+            }
+            return;
+        }
+        let (coj, copies) = self.space.groups[group];
+        let mut first = first;
+        for take in 0..=(copies as usize).min(remaining) {
+            let sub = self.space.ways[group + 1][remaining - take];
+            if first >= self.range.end {
+                break;
+            }
+            if sub == 0 {
+                continue;
+            }
+            if first + sub <= self.range.start {
+                first += sub;
+                continue;
+            }
+            for _ in 0..take {
+                match coj {
+                    CardOrJoker::Card(c) => self.cards.push(c),
+                    CardOrJoker::Joker => self.num_jokers += 1,
+                }
+            }
+            let ways = binomial_u128(copies, take as u64);
+            self.recurse(group + 1, remaining - take, weight * ways, first);
+            for _ in 0..take {
+                match coj {
+                    CardOrJoker::Card(_) => {
+                        self.cards.pop();
+                    }
+                    CardOrJoker::Joker => self.num_jokers -= 1,
+                }
+            }
+            first += sub;
+        }
+    }
+}
+
+// Counts the draws numbered `range` in `space` which make each hand.
+pub fn enumerate_range(
+    space: &CombinationSpace,
+    range: std::ops::Range<u128>,
+    counts: &[HandCount],
+) -> ExactCounts {
+    enumerate_range_held(space, range, &[], counts)
+}
+
+// `enumerate_range`, with the `held` cards added to every draw.
+fn enumerate_range_held(
+    space: &CombinationSpace,
+    range: std::ops::Range<u128>,
+    held: &[CardOrJoker],
+    counts: &[HandCount],
+) -> ExactCounts {
+    assert!(space.num_cards + held.len() <= MAX_CARDS);
+    let (cards, num_jokers) = split_jokers(held);
+    let mut enumerator = Enumerator {
+        space,
+        counts,
+        plan: Plan::new(counts),
+        range,
+        cards,
+        num_jokers,
+        result: ExactCounts::new(counts.len()),
+    };
+    enumerator.recurse(0, space.num_cards, 1, 0);
+    enumerator.result
+}
+
+// Draws per unit of work handed to a thread.
+const RANGE_SIZE: u128 = 1 << 12;
+
+// Counts the draws numbered `range` in `space` which make each hand,
+// splitting them into smaller ranges spread across threads.
+pub fn enumerate_range_par(
+    space: &CombinationSpace,
+    range: std::ops::Range<u128>,
+    counts: &[HandCount],
+) -> ExactCounts {
+    enumerate_range_par_held(space, range, &[], counts)
+}
+
+fn enumerate_range_par_held(
+    space: &CombinationSpace,
+    range: std::ops::Range<u128>,
+    held: &[CardOrJoker],
+    counts: &[HandCount],
+) -> ExactCounts {
+    use rayon::prelude::*;
+
+    let num_ranges = (range.end - range.start).div_ceil(RANGE_SIZE) as u64;
+    (0..num_ranges)
+        .into_par_iter()
+        .map(|i| {
+            let start = range.start + i as u128 * RANGE_SIZE;
+            let end = (start + RANGE_SIZE).min(range.end);
+            enumerate_range_held(space, start..end, held, counts)
+        })
+        .reduce(|| ExactCounts::new(counts.len()), ExactCounts::merge)
+}
+
+// Counts how many of the C(deck.len(), num_cards) possible draws make each
+// hand.
+pub fn enumerate(deck: &[CardOrJoker], num_cards: usize, counts: &[HandCount]) -> ExactCounts {
+    let space = CombinationSpace::new(deck, num_cards);
+    enumerate_range_par(&space, 0..space.len(), counts)
+}
+
+// Counts how many of the possible draws make each hand along with the `held`
+// cards, which should already be out of `deck`.
+pub fn enumerate_held(
+    deck: &[CardOrJoker],
+    held: &[CardOrJoker],
+    num_cards: usize,
+    counts: &[HandCount],
+) -> ExactCounts {
+    let space = CombinationSpace::new(deck, num_cards);
+    enumerate_range_par_held(&space, 0..space.len(), held, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinatorics::binomial_exact;
+
+    #[test]
+    fn test_enumerate() {
+        let deck = crate::standard_deck(1, 0);
+        let counts = crate::hand_counts(5).unwrap();
+        let exact = enumerate(&deck, 4, &counts);
+        assert_eq!(270725, exact.total);
+        let hits = |name: &str| exact.hits[counts.iter().position(|c| c.name == name).unwrap()];
+        // Every 4-card hand except those with four distinct ranks.
+        assert_eq!(270725 - 715 * 256, hits("Pair"));
+        assert_eq!(13 * 4 * 48 + 13, hits("3oak"));
+        assert_eq!(13, hits("4oak"));
+        assert_eq!(78 * 36 + 13, hits("2 pair"));
+        assert_eq!(0, hits("Full House"));
+    }
+
+    #[test]
+    fn test_enumerate_duplicates() {
+        // Grouping identical cards must give the same total as choosing
+        // among every physical card.
+        let deck = crate::standard_deck(2, 2);
+        let counts = crate::hand_counts(5).unwrap();
+        let exact = enumerate(&deck, 3, &counts);
+        assert_eq!(
+            binomial_exact(106, 3),
+            num_bigint::BigUint::from(exact.total)
+        );
+        let pair = exact.probability(0);
+        let p = num_traits::ToPrimitive::to_f64(&pair).unwrap();
+        assert!(p > 0.0 && p < 1.0);
+    }
+
+    #[test]
+    fn test_enumerate_held() {
+        let mut deck = crate::standard_deck(1, 0);
+        let held = crate::cards!("Ah Kh 7h 2h")
+            .into_iter()
+            .map(CardOrJoker::Card)
+            .collect::<Vec<_>>();
+        crate::deck::take_cards(&mut deck, &held).unwrap();
+        let counts = crate::hand_counts(5).unwrap();
+        let flush = counts.iter().position(|c| c.name == "Flush").unwrap();
+        let exact = enumerate_held(&deck, &held, 1, &counts);
+        assert_eq!((48, 9), (exact.total, exact.hits[flush]));
+        // All but the draws without a heart.
+        let exact = enumerate_held(&deck, &held, 2, &counts);
+        assert_eq!((1128, 1128 - 741), (exact.total, exact.hits[flush]));
+    }
+
+    #[test]
+    fn test_ranges() {
+        let deck = crate::standard_deck(2, 1);
+        let counts = crate::hand_counts(5).unwrap();
+        let space = CombinationSpace::new(&deck, 3);
+        let whole = enumerate_range(&space, 0..space.len(), &counts);
+        let split = space.len() / 3;
+        let a = enumerate_range(&space, 0..split, &counts);
+        let b = enumerate_range(&space, split..space.len(), &counts);
+        assert_eq!(whole.total, a.total + b.total);
+        for h in 0..counts.len() {
+            assert_eq!(whole.hits[h], a.hits[h] + b.hits[h]);
+        }
+        // A single-draw range sees exactly the unranked draw.
+        let drawn = space.unrank_combination(split);
+        let one = enumerate_range(&space, split..split + 1, &counts);
+        let (cards, num_jokers) = crate::split_jokers(&drawn);
+        for (h, c) in counts.iter().enumerate() {
+            assert_eq!(c.matches(&cards, num_jokers), one.hits[h] > 0);
+        }
+    }
+
+    #[test]
+    fn test_unrank() {
+        let deck = crate::standard_deck(1, 2);
+        let space = CombinationSpace::new(&deck, 2);
+        // Any two of 52 cards and a joker, or both jokers.
+        assert_eq!(53 * 52 / 2 + 1, space.len());
+        let mut seen = std::collections::HashSet::new();
+        for i in 0..space.len() {
+            let drawn = space.unrank_combination(i);
+            assert_eq!(2, drawn.len());
+            let (cards, num_jokers) = crate::split_jokers(&drawn);
+            assert!(seen.insert((cards, num_jokers)));
+            assert_eq!(Some(i), space.rank_combination(&drawn));
+        }
+        let jokers = [CardOrJoker::Joker; 2];
+        assert_eq!(1, space.weight(&jokers));
+        assert_eq!(None, space.rank_combination(&jokers[..1]));
+        assert_eq!(
+            None,
+            CombinationSpace::new(&deck[..52], 2).rank_combination(&jokers)
+        );
+    }
+
+    #[test]
+    fn test_weight() {
+        let deck = crate::standard_deck(3, 0);
+        let space = CombinationSpace::new(&deck, 3);
+        let total = (0..space.len())
+            .map(|i| space.weight(&space.unrank_combination(i)))
+            .sum::<u128>();
+        assert_eq!(binomial_u128(156, 3), total);
+    }
+
+    #[test]
+    fn test_num_draws() {
+        assert_eq!(Some(2598960), num_draws(52, 5));
+        assert_eq!(Some(0), num_draws(3, 5));
+        assert_eq!(Some(binomial_u128(13515, 7)), num_draws(13515, 7));
+        // 255 decks and jokers, 20 cards.
+        assert_eq!(None, num_draws(13515, 20));
+    }
+}
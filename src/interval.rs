@@ -0,0 +1,277 @@
+//! 99.73% confidence intervals for a hand's probability. The normal
+//! approximation is the default but is poor for hands seen only a handful of
+//! times, where it can dip below zero. Wilson and Clopper-Pearson intervals
+//! stay in [0, 1] and are asymmetric around the estimate.
+
+// Standard deviations for a 99.73% interval.
+const Z: f64 = 3.0;
+const ALPHA: f64 = 0.0027;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CiMethod {
+    #[default]
+    Normal,
+    Wilson,
+    ClopperPearson,
+}
+
+// Lanczos approximation, accurate to about 15 digits for x > 0.
+pub fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let sum = COEFFICIENTS[0]
+        + COEFFICIENTS[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, c)| c / (x + i as f64 + 1.0))
+            .sum::<f64>();
+    0.5 * std::f64::consts::TAU.ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// Continued fraction for the incomplete beta function, from Numerical
+// Recipes' betacf.
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..100_000 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let step = |num: f64, c: &mut f64, d: &mut f64| {
+            *d = 1.0 + num * *d;
+            if d.abs() < TINY {
+                *d = TINY;
+            }
+            *c = 1.0 + num / *c;
+            if c.abs() < TINY {
+                *c = TINY;
+            }
+            *d = 1.0 / *d;
+            *c * *d
+        };
+        h *= step(
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            &mut c,
+            &mut d,
+        );
+        let delta = step(
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+            &mut c,
+            &mut d,
+        );
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-14 {
+            break;
+        }
+    }
+    h
+}
+
+// Regularized incomplete beta function I_x(a, b), the CDF of Beta(a, b).
+pub fn beta_cdf(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges quickly on this side of the mean.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// The `q` quantile of Beta(a, b), by bisection.
+fn beta_quantile(a: f64, b: f64, q: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if beta_cdf(a, b, mid) < q {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+// The `q` quantile of the standard normal distribution, by Acklam's rational
+// approximation, accurate to about 1e-9.
+pub fn normal_quantile(q: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.024_25;
+    let poly = |coefficients: &[f64], x: f64| coefficients.iter().fold(0.0, |acc, c| acc * x + c);
+    // The tails.
+    let tail = |q: f64| {
+        let r = (-2.0 * q.ln()).sqrt();
+        poly(&C, r) / (poly(&D, r) * r + 1.0)
+    };
+    if q < LOW {
+        tail(q)
+    } else if q > 1.0 - LOW {
+        -tail(1.0 - q)
+    } else {
+        let r = (q - 0.5) * (q - 0.5);
+        (q - 0.5) * poly(&A, r) / (poly(&B, r) * r + 1.0)
+    }
+}
+
+// According to https://sigmazone.com/binomial-confidence-intervals/
+fn normal_half_width(p: f64, n: f64) -> f64 {
+    Z * (p * (1.0 - p) / n).sqrt()
+}
+
+// Lower and upper bounds of the interval for `num_true` hits in
+// `total_iters` deals.
+pub fn bounds_with(method: CiMethod, total_iters: u64, num_true: u64) -> (f64, f64) {
+    let n = total_iters as f64;
+    let k = num_true as f64;
+    let p = k / n;
+    match method {
+        CiMethod::Normal => {
+            let ci = normal_half_width(p, n);
+            (p - ci, p + ci)
+        }
+        CiMethod::Wilson => {
+            let z2 = Z * Z;
+            let denom = 1.0 + z2 / n;
+            let center = (p + z2 / (2.0 * n)) / denom;
+            let half = Z / denom * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+            ((center - half).max(0.0), (center + half).min(1.0))
+        }
+        CiMethod::ClopperPearson => {
+            let lo = if num_true == 0 {
+                0.0
+            } else {
+                beta_quantile(k, n - k + 1.0, ALPHA / 2.0)
+            };
+            let hi = if num_true == total_iters {
+                1.0
+            } else {
+                beta_quantile(k + 1.0, n - k, 1.0 - ALPHA / 2.0)
+            };
+            (lo, hi)
+        }
+    }
+}
+
+// The estimate and the larger of its distances to the bounds, so that
+// "p ± ci" covers the whole interval.
+pub fn interval_with(method: CiMethod, total_iters: u64, num_true: u64) -> (f64, f64) {
+    let p = num_true as f64 / total_iters as f64;
+    if method == CiMethod::Normal {
+        return (p, normal_half_width(p, total_iters as f64));
+    }
+    let (lo, hi) = bounds_with(method, total_iters, num_true);
+    (p, (p - lo).max(hi - p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 1e-6, "{expected} vs {actual}");
+    }
+
+    #[test]
+    fn test_beta_cdf() {
+        assert_close(0.5, beta_cdf(3.0, 3.0, 0.5));
+        // Beta(1, b) has CDF 1 - (1 - x)^b.
+        assert_close(1.0 - 0.9f64.powi(20), beta_cdf(1.0, 20.0, 0.1));
+        assert_close(0.1, beta_cdf(1.0, 20.0, beta_quantile(1.0, 20.0, 0.1)));
+    }
+
+    #[test]
+    fn test_normal_quantile() {
+        assert_close(0.0, normal_quantile(0.5));
+        assert_close(1.959_963_985, normal_quantile(0.975));
+        assert_close(-2.575_829_304, normal_quantile(0.005));
+        // Z is the 99.73% two-sided quantile.
+        assert!((Z - normal_quantile(1.0 - ALPHA / 2.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bounds() {
+        // p = 0.5 with a standard error of 0.05.
+        let (lo, hi) = bounds_with(CiMethod::Normal, 100, 50);
+        assert_close(0.35, lo);
+        assert_close(0.65, hi);
+        let (lo, hi) = bounds_with(CiMethod::Wilson, 100, 0);
+        assert_eq!(0.0, lo);
+        assert_close(9.0 / 109.0, hi);
+        // With no hits the upper bound solves (1 - p)^n = alpha / 2.
+        let (lo, hi) = bounds_with(CiMethod::ClopperPearson, 1000, 0);
+        assert_eq!(0.0, lo);
+        assert_close(1.0 - (ALPHA / 2.0).powf(1.0 / 1000.0), hi);
+        // Rare hands get a positive lower bound where the normal one is
+        // negative.
+        let (normal_lo, _) = bounds_with(CiMethod::Normal, 1_000_000, 3);
+        assert!(normal_lo < 0.0);
+        for method in [CiMethod::Wilson, CiMethod::ClopperPearson] {
+            let (lo, hi) = bounds_with(method, 1_000_000, 3);
+            assert!(lo > 0.0 && lo < 3e-6 && hi > 3e-6, "{method:?}: {lo} {hi}");
+        }
+        // Every method agrees for common hands.
+        let (normal_lo, normal_hi) = bounds_with(CiMethod::Normal, 1_000_000, 400_000);
+        for method in [CiMethod::Wilson, CiMethod::ClopperPearson] {
+            let (lo, hi) = bounds_with(method, 1_000_000, 400_000);
+            assert!((lo - normal_lo).abs() < 1e-5 && (hi - normal_hi).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_interval_with() {
+        let (p, ci) = interval_with(CiMethod::Wilson, 100, 0);
+        assert_eq!(0.0, p);
+        assert_close(9.0 / 109.0, ci);
+    }
+}
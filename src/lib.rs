@@ -0,0 +1,2792 @@
+pub mod badugi;
+pub mod bench;
+pub mod bitboard;
+pub mod boards;
+pub mod classes;
+pub mod combinatorics;
+pub mod coordinate;
+pub mod deck;
+#[cfg(any(test, feature = "differential"))]
+pub mod differential;
+pub mod editor;
+pub mod estimate;
+pub mod exact;
+pub mod interval;
+pub mod lookup;
+pub mod lowball;
+pub mod metadata;
+pub mod objective;
+pub mod observer;
+pub mod omaha;
+pub mod outs;
+pub mod plan;
+pub mod players;
+pub mod prob_table;
+pub mod profile;
+pub mod progress;
+pub mod quiz;
+pub mod ranking;
+pub mod results;
+pub mod rules;
+pub mod scenario;
+pub mod schema;
+pub mod scoring;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod sensitivity;
+pub mod sequential;
+pub mod serve;
+pub mod session;
+pub mod sink;
+pub mod strategy;
+pub mod table;
+#[cfg(any(test, feature = "test_util"))]
+pub mod test_util;
+pub mod trainer;
+pub mod tui;
+pub mod wild;
+
+use bitboard::{highest_straight, straight_mask, Bitboard};
+use interval::CiMethod;
+use lowball::Lowball;
+use omaha::OmahaHand;
+use plan::{Check, Facts, Needs};
+
+// Most cards in a deal. Hands live in fixed-capacity `ArrayVec`s to avoid
+// allocating per deal, so this bounds their size on the stack too.
+pub const MAX_CARDS: usize = 32;
+
+pub const NUM_RANKS: u8 = 13;
+
+type RankCounts = [u8; NUM_RANKS as usize];
+
+pub const NUM_SUITS: u8 = 4;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    // Every rank from deuce to ace.
+    pub const ALL: [Rank; NUM_RANKS as usize] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    pub fn iter() -> impl DoubleEndedIterator<Item = Rank> {
+        Rank::ALL.into_iter()
+    }
+
+    pub fn is_ace(self) -> bool {
+        self == Rank::Ace
+    }
+}
+
+impl TryFrom<u8> for Rank {
+    type Error = String;
+
+    fn try_from(rank: u8) -> Result<Self, Self::Error> {
+        Rank::ALL
+            .get(rank as usize)
+            .copied()
+            .ok_or_else(|| format!("invalid rank {rank}"))
+    }
+}
+
+// Formats as the rank's character in card names, like "T" or "A".
+impl std::fmt::Display for Rank {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", RANK_CHARS[*self as usize] as char)
+    }
+}
+
+impl std::str::FromStr for Rank {
+    type Err = String;
+
+    // Parses ranks like "A", "t" or "10".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rank = match s.to_ascii_uppercase().as_bytes() {
+            b"10" => b'T',
+            &[rank] => rank,
+            _ => return Err(format!("invalid rank '{s}'")),
+        };
+        RANK_CHARS
+            .iter()
+            .position(|&c| c == rank)
+            .map(|i| Rank::ALL[i])
+            .ok_or_else(|| format!("invalid rank '{s}'"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    pub const ALL: [Suit; NUM_SUITS as usize] =
+        [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    pub fn iter() -> impl DoubleEndedIterator<Item = Suit> {
+        Suit::ALL.into_iter()
+    }
+}
+
+impl TryFrom<u8> for Suit {
+    type Error = String;
+
+    fn try_from(suit: u8) -> Result<Self, Self::Error> {
+        Suit::ALL
+            .get(suit as usize)
+            .copied()
+            .ok_or_else(|| format!("invalid suit {suit}"))
+    }
+}
+
+// Cards are ordered by rank, then by suit (clubs, diamonds, hearts, spades),
+// with wild cards after plain ones.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Card {
+    // Field order matters for the derived ordering.
+    pub rank: Rank,
+    pub suit: Suit,
+    // Counts as every suit, like Balatro's Wild enhancement. Unlike a joker
+    // the rank stays fixed.
+    pub wild: bool,
+}
+
+impl Card {
+    pub const fn new(rank: Rank, suit: Suit) -> Self {
+        Self {
+            rank,
+            suit,
+            wild: false,
+        }
+    }
+
+    pub const fn made_wild(self) -> Self {
+        Self { wild: true, ..self }
+    }
+}
+
+impl From<(Rank, Suit)> for Card {
+    fn from((rank, suit): (Rank, Suit)) -> Self {
+        Card::new(rank, suit)
+    }
+}
+
+impl From<Card> for (Rank, Suit) {
+    fn from(card: Card) -> Self {
+        (card.rank, card.suit)
+    }
+}
+
+// Packed codes are `rank * 4 + suit`, as used by the serve protocol. They
+// have no room for wildness, which is dropped.
+impl TryFrom<u8> for Card {
+    type Error = String;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        if code >= NUM_RANKS * NUM_SUITS {
+            return Err(format!("invalid card code {code}"));
+        }
+        Ok(Card::new(
+            Rank::ALL[(code / NUM_SUITS) as usize],
+            Suit::ALL[(code % NUM_SUITS) as usize],
+        ))
+    }
+}
+
+impl From<Card> for u8 {
+    fn from(card: Card) -> Self {
+        card.rank as u8 * NUM_SUITS + card.suit as u8
+    }
+}
+
+pub(crate) const RANK_CHARS: &[u8; NUM_RANKS as usize] = b"23456789TJQKA";
+
+pub(crate) const SUIT_CHARS: &[u8; NUM_SUITS as usize] = b"cdhs";
+
+const SUIT_SYMBOLS: [char; NUM_SUITS as usize] = ['♣', '♦', '♥', '♠'];
+
+// Formats as "As", or "A♠" with the alternate flag (`{:#}`). Wild cards get
+// a trailing "*".
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let rank = RANK_CHARS[self.rank as usize] as char;
+        if f.alternate() {
+            write!(f, "{rank}{}", SUIT_SYMBOLS[self.suit as usize])?;
+        } else {
+            write!(f, "{rank}{}", SUIT_CHARS[self.suit as usize] as char)?;
+        }
+        if self.wild {
+            write!(f, "*")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Card({self})")
+    }
+}
+
+impl std::str::FromStr for Card {
+    type Err = String;
+
+    // Parses cards like "As", "Td", "10h" or "Kc*" for a wild card. Ranks and
+    // suits are case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (plain, wild) = match s.strip_suffix('*') {
+            Some(plain) => (plain, true),
+            None => (s, false),
+        };
+        let upper = plain.to_ascii_uppercase();
+        let (rank, suit) = match upper.as_bytes() {
+            [b'1', b'0', suit] => (b'T', *suit),
+            &[rank, suit] => (rank, suit),
+            _ => return Err(format!("invalid card '{s}'")),
+        };
+        let rank = RANK_CHARS
+            .iter()
+            .position(|&c| c == rank)
+            .ok_or_else(|| format!("invalid rank in card '{s}'"))?;
+        let suit = SUIT_CHARS
+            .iter()
+            .position(|&c| c.to_ascii_uppercase() == suit)
+            .ok_or_else(|| format!("invalid suit in card '{s}'"))?;
+        let card = Card::new(Rank::ALL[rank], Suit::ALL[suit]);
+        Ok(if wild { card.made_wild() } else { card })
+    }
+}
+
+// Parses a whitespace-separated list of cards, where "J" on its own is a joker.
+pub fn parse_hand(s: &str) -> Result<(Vec<Card>, u8), String> {
+    let mut cards = Vec::new();
+    let mut num_jokers = 0;
+    for word in s.split_whitespace() {
+        if word.eq_ignore_ascii_case("j") {
+            num_jokers += 1;
+        } else {
+            cards.push(word.parse()?);
+        }
+    }
+    Ok((cards, num_jokers))
+}
+
+// Inverse of `parse_hand`.
+pub fn format_hand(cards: &[Card], num_jokers: u8) -> String {
+    let mut words = cards.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    words.extend((0..num_jokers).map(|_| "J".to_string()));
+    words.join(" ")
+}
+
+// A card literal like `card!("Ah")`, checked at compile time.
+#[macro_export]
+macro_rules! card {
+    ($s:literal) => {{
+        const CARD: $crate::Card = $crate::parse_card_const($s);
+        CARD
+    }};
+}
+
+// An array of cards like `hand!("Ah Kh Qh Jh Th")`, checked at compile time.
+// Jokers aren't allowed since the result only holds `Card`s.
+#[macro_export]
+macro_rules! hand {
+    ($s:literal) => {{
+        const HAND: [$crate::Card; $crate::count_cards($s)] = $crate::parse_cards_const($s);
+        HAND
+    }};
+}
+
+// Parses the card in `bytes[start..end]`. Panics on invalid cards, which is a
+// compile error when evaluated in a const context.
+const fn parse_card_bytes(bytes: &[u8], start: usize, mut end: usize) -> Card {
+    let wild = end > start && bytes[end - 1] == b'*';
+    if wild {
+        end -= 1;
+    }
+    let (rank, suit) = match end - start {
+        2 => (bytes[start], bytes[start + 1]),
+        3 if bytes[start] == b'1' && bytes[start + 1] == b'0' => (b'T', bytes[start + 2]),
+        _ => panic!("invalid card"),
+    };
+    let mut found_rank = None;
+    let mut i = 0;
+    while i < RANK_CHARS.len() {
+        if RANK_CHARS[i] == rank.to_ascii_uppercase() {
+            found_rank = Some(Rank::ALL[i]);
+        }
+        i += 1;
+    }
+    let mut found_suit = None;
+    let mut i = 0;
+    while i < SUIT_CHARS.len() {
+        if SUIT_CHARS[i] == suit.to_ascii_lowercase() {
+            found_suit = Some(Suit::ALL[i]);
+        }
+        i += 1;
+    }
+    match (found_rank, found_suit) {
+        (Some(rank), Some(suit)) if wild => Card::new(rank, suit).made_wild(),
+        (Some(rank), Some(suit)) => Card::new(rank, suit),
+        (None, _) => panic!("invalid rank"),
+        (_, None) => panic!("invalid suit"),
+    }
+}
+
+// Const version of `Card::from_str`, for `card!`.
+#[doc(hidden)]
+pub const fn parse_card_const(s: &str) -> Card {
+    parse_card_bytes(s.as_bytes(), 0, s.len())
+}
+
+// Start and end of the first whitespace-separated word of `bytes` at or after
+// `from`, or None if there are no more.
+const fn next_word(bytes: &[u8], from: usize) -> Option<(usize, usize)> {
+    let mut start = from;
+    while start < bytes.len() && bytes[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    if start == bytes.len() {
+        return None;
+    }
+    let mut end = start;
+    while end < bytes.len() && !bytes[end].is_ascii_whitespace() {
+        end += 1;
+    }
+    Some((start, end))
+}
+
+#[doc(hidden)]
+pub const fn count_cards(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+    let mut from = 0;
+    while let Some((_, end)) = next_word(bytes, from) {
+        count += 1;
+        from = end;
+    }
+    count
+}
+
+// Const version of `parse_hand` without jokers, for `hand!`.
+#[doc(hidden)]
+pub const fn parse_cards_const<const N: usize>(s: &str) -> [Card; N] {
+    let bytes = s.as_bytes();
+    let mut cards = [Card::new(Rank::Two, Suit::Clubs); N];
+    let mut i = 0;
+    let mut from = 0;
+    while let Some((start, end)) = next_word(bytes, from) {
+        assert!(i < N, "too many cards");
+        cards[i] = parse_card_bytes(bytes, start, end);
+        i += 1;
+        from = end;
+    }
+    assert!(i == N, "too few cards");
+    cards
+}
+
+fn rank_counts(cards: &[Card]) -> RankCounts {
+    let mut ret = RankCounts::default();
+    for c in cards {
+        ret[c.rank as usize] += 1;
+    }
+    ret
+}
+
+// Bitmask of ranks which straights skip over, so that in a deck without 2-5
+// A-6-7-8-9 is a straight. Standard straights skip none.
+pub fn skipped_ranks(ranks: impl IntoIterator<Item = Rank>) -> u16 {
+    ranks.into_iter().fold(0, |mask, r| mask | 1 << r as u16)
+}
+
+// Which ranks, ace low first, make up straights when `skipped` are left out.
+fn straight_order(skipped: u16) -> arrayvec::ArrayVec<Rank, { NUM_RANKS as usize + 1 }> {
+    let kept = |r: &Rank| skipped & 1 << *r as u16 == 0;
+    let mut ret = arrayvec::ArrayVec::new();
+    if kept(&Rank::Ace) {
+        ret.push(Rank::Ace);
+    }
+    ret.extend(Rank::iter().filter(kept));
+    ret
+}
+
+// Wild cards count towards every suit.
+fn suit_counts(cards: &[Card]) -> RankCounts {
+    let mut ret = RankCounts::default();
+    let mut num_wild = 0;
+    for c in cards {
+        if c.wild {
+            num_wild += 1;
+        } else {
+            ret[c.suit as usize] += 1;
+        }
+    }
+    for count in ret.iter_mut().take(NUM_SUITS as usize) {
+        *count += num_wild;
+    }
+    ret
+}
+
+// The cards of each suit, with wild cards in all of them.
+fn cards_by_suit(cards: &[Card]) -> [arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize] {
+    let mut ret = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+    for &c in cards {
+        if c.wild {
+            for suit in &mut ret {
+                suit.push(c);
+            }
+        } else {
+            ret[c.suit as usize].push(c);
+        }
+    }
+    ret
+}
+
+// `is_n_of_a_kind` from counts already made by `rank_counts`.
+fn n_of_a_kind_in(rank_counts: &RankCounts, n: u8, num_jokers: u8) -> bool {
+    num_jokers >= n || rank_counts.iter().any(|&c| c + num_jokers >= n)
+}
+
+pub fn is_n_of_a_kind(cards: &[Card], n: u8, num_jokers: u8) -> bool {
+    let mut counts = <[u8; NUM_RANKS as usize]>::default();
+    for &c in cards {
+        let count = &mut counts[c.rank as usize];
+        *count += 1;
+        if *count + num_jokers >= n {
+            return true;
+        }
+    }
+    num_jokers >= n
+}
+
+pub fn is_n_and_m_of_a_kind(cards: &[Card], n: u8, m: u8, num_jokers: u8) -> bool {
+    n_and_m_in(rank_counts(cards), n, m, num_jokers)
+}
+
+fn n_and_m_in(mut rank_counts: RankCounts, n: u8, m: u8, mut num_jokers: u8) -> bool {
+    assert!(n >= m);
+    let mut fill_with_jokers = |val: &mut u8, fill_to: u8| -> bool {
+        if *val >= fill_to {
+            return true;
+        }
+        if *val + num_jokers < fill_to {
+            return false;
+        }
+        num_jokers -= fill_to - *val;
+        *val = fill_to;
+        true
+    };
+    // FIXME: no need to sort, just find two largest values
+    rank_counts.sort_by(|a, b| b.cmp(a));
+    if !fill_with_jokers(&mut rank_counts[0], n) {
+        return false;
+    }
+    rank_counts[0] -= n;
+    if fill_with_jokers(&mut rank_counts[0], m) {
+        return true;
+    }
+    fill_with_jokers(&mut rank_counts[1], m)
+}
+
+pub fn is_full_house(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_and_m_of_a_kind(cards, 3, 2, num_jokers)
+}
+
+pub fn is_two_triplet(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_and_m_of_a_kind(cards, 3, 3, num_jokers)
+}
+
+pub fn is_full_mansion(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_and_m_of_a_kind(cards, 4, 2, num_jokers)
+}
+
+pub fn is_flush_mansion(cards: &[Card], num_jokers: u8) -> bool {
+    cards_by_suit(cards)
+        .iter()
+        .any(|cards| is_full_mansion(cards, num_jokers))
+}
+
+pub fn is_flush_two_triplet(cards: &[Card], num_jokers: u8) -> bool {
+    cards_by_suit(cards)
+        .iter()
+        .any(|cards| is_two_triplet(cards, num_jokers))
+}
+
+pub fn is_flush_three_pair(cards: &[Card], num_jokers: u8) -> bool {
+    cards_by_suit(cards)
+        .iter()
+        .any(|cards| is_three_pair(cards, num_jokers))
+}
+
+pub fn is_n_pairs(cards: &[Card], n: u8, num_jokers: u8) -> bool {
+    n_pairs_in(&rank_counts(cards), n, num_jokers)
+}
+
+fn n_pairs_in(rank_counts: &RankCounts, n: u8, mut num_jokers: u8) -> bool {
+    let mut num_pairs = 0;
+    for &i in rank_counts {
+        if i % 2 == 1 && num_jokers > 0 {
+            num_jokers -= 1;
+            num_pairs += 1;
+        }
+        num_pairs += i / 2;
+    }
+    num_pairs + num_jokers / 2 >= n
+}
+// `is_n_pairs` with `n` fixed, usable as a `HandCount` detector.
+pub fn n_pairs<const N: u8>(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_pairs(cards, N, num_jokers)
+}
+
+pub fn is_two_pair(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_pairs(cards, 2, num_jokers)
+}
+
+pub fn is_three_pair(cards: &[Card], num_jokers: u8) -> bool {
+    is_n_pairs(cards, 3, num_jokers)
+}
+
+pub fn is_flush(cards: &[Card], num_jokers: u8, flush_size: u8) -> bool {
+    Bitboard::new(cards)
+        .suit_counts()
+        .unwrap_or_else(|| suit_counts(cards))
+        .iter()
+        .any(|&c| c + num_jokers >= flush_size)
+}
+
+pub fn is_straight(cards: &[Card], num_jokers: u8, straight_size: usize) -> bool {
+    straight_in(Bitboard::new(cards).ranks(), num_jokers, straight_size, 0)
+}
+
+// `is_straight` for a bitmask of the ranks present, skipping the `skipped`
+// ranks.
+fn straight_in(ranks: u16, num_jokers: u8, straight_size: usize, skipped: u16) -> bool {
+    let (mask, len) = straight_mask(ranks, skipped);
+    // A straight longer than the ranks can only be made with enough jokers.
+    if straight_size > len {
+        return mask.count_ones() as usize + num_jokers as usize >= straight_size;
+    }
+    straight_size == 0 || highest_straight(mask, len, num_jokers, straight_size).is_some()
+}
+
+pub fn is_straight_flush(cards: &[Card], num_jokers: u8, size: usize) -> bool {
+    Bitboard::new(cards)
+        .suits()
+        .into_iter()
+        .any(|suit| straight_in(suit, num_jokers, size, 0))
+}
+
+// Ten through ace of one suit.
+pub fn is_royal_flush(cards: &[Card], num_jokers: u8) -> bool {
+    Bitboard::new(cards)
+        .suits()
+        .into_iter()
+        .any(|suit| royal_in(suit, num_jokers))
+}
+
+// Whether the ranks of one suit make ten through ace.
+fn royal_in(suit: u16, num_jokers: u8) -> bool {
+    let royal = Rank::iter()
+        .filter(|&r| r >= Rank::Ten)
+        .fold(0u16, |mask, r| mask | 1 << r as u16);
+    (suit & royal).count_ones() as u8 + num_jokers >= 5
+}
+
+// The highest straight flush of `size` cards the deal can make in each suit,
+// as the suit and its high card, skipping the `skipped` ranks. Wheels are
+// five-high.
+pub fn straight_flushes(
+    cards: &[Card],
+    num_jokers: u8,
+    size: usize,
+    skipped: u16,
+) -> Vec<(Suit, Rank)> {
+    let order = straight_order(skipped);
+    let mut ret = Vec::new();
+    for (suit, ranks) in Suit::iter().zip(Bitboard::new(cards).suits()) {
+        let (mask, len) = straight_mask(ranks, skipped);
+        if let Some(start) = highest_straight(mask, len, num_jokers, size) {
+            ret.push((suit, order[start + size - 1]));
+        }
+    }
+    ret
+}
+
+pub fn is_flush_house(cards: &[Card], num_jokers: u8) -> bool {
+    cards_by_suit(cards)
+        .iter()
+        .any(|cards| is_full_house(cards, num_jokers))
+}
+
+pub fn is_flush_n(cards: &[Card], n: u8, num_jokers: u8) -> bool {
+    cards_by_suit(cards)
+        .iter()
+        .any(|cards| is_n_of_a_kind(cards, n, num_jokers))
+}
+
+// Ordered from weakest to strongest. Six-card categories slot in directly
+// above the five-card category they extend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandRank {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    ThreePair,
+    Straight,
+    Flush,
+    FullHouse,
+    TwoTriplet,
+    FourOfAKind,
+    FullMansion,
+    SixStraight,
+    SixFlush,
+    StraightFlush,
+    FiveOfAKind,
+    SixOfAKind,
+    SixStraightFlush,
+    FlushHouse,
+    FlushThreePair,
+    FlushTwoTriplet,
+    FlushMansion,
+    FlushFive,
+    FlushSix,
+}
+
+impl HandRank {
+    pub const ALL: [HandRank; 23] = [
+        HandRank::HighCard,
+        HandRank::Pair,
+        HandRank::TwoPair,
+        HandRank::ThreeOfAKind,
+        HandRank::ThreePair,
+        HandRank::Straight,
+        HandRank::Flush,
+        HandRank::FullHouse,
+        HandRank::TwoTriplet,
+        HandRank::FourOfAKind,
+        HandRank::FullMansion,
+        HandRank::SixStraight,
+        HandRank::SixFlush,
+        HandRank::StraightFlush,
+        HandRank::FiveOfAKind,
+        HandRank::SixOfAKind,
+        HandRank::SixStraightFlush,
+        HandRank::FlushHouse,
+        HandRank::FlushThreePair,
+        HandRank::FlushTwoTriplet,
+        HandRank::FlushMansion,
+        HandRank::FlushFive,
+        HandRank::FlushSix,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HandRank::HighCard => "High Card",
+            HandRank::Pair => "Pair",
+            HandRank::TwoPair => "2 pair",
+            HandRank::ThreeOfAKind => "3oak",
+            HandRank::ThreePair => "3 pair",
+            HandRank::Straight => "Straight",
+            HandRank::Flush => "Flush",
+            HandRank::FullHouse => "Full House",
+            HandRank::TwoTriplet => "2 triplet",
+            HandRank::FourOfAKind => "4oak",
+            HandRank::FullMansion => "Full Mansion",
+            HandRank::SixStraight => "Straight (6)",
+            HandRank::SixFlush => "Flush (6)",
+            HandRank::StraightFlush => "Strt Flush",
+            HandRank::FiveOfAKind => "5oak",
+            HandRank::SixOfAKind => "6oak",
+            HandRank::SixStraightFlush => "Strt Flush (6)",
+            HandRank::FlushHouse => "Flush House",
+            HandRank::FlushThreePair => "Flush 3 pair",
+            HandRank::FlushTwoTriplet => "Flush 2 triplet",
+            HandRank::FlushMansion => "Flush Mansion",
+            HandRank::FlushFive => "Flush 5",
+            HandRank::FlushSix => "Flush 6",
+        }
+    }
+
+    // Identifier used to select this hand on the command line.
+    pub fn id(self) -> &'static str {
+        match self {
+            HandRank::HighCard => "high_card",
+            HandRank::Pair => "pair",
+            HandRank::TwoPair => "two_pair",
+            HandRank::ThreeOfAKind => "3oak",
+            HandRank::ThreePair => "three_pair",
+            HandRank::Straight => "straight",
+            HandRank::Flush => "flush",
+            HandRank::FullHouse => "full_house",
+            HandRank::TwoTriplet => "two_triplet",
+            HandRank::FourOfAKind => "4oak",
+            HandRank::FullMansion => "full_mansion",
+            HandRank::SixStraight => "straight6",
+            HandRank::SixFlush => "flush6",
+            HandRank::StraightFlush => "straight_flush",
+            HandRank::FiveOfAKind => "5oak",
+            HandRank::SixOfAKind => "6oak",
+            HandRank::SixStraightFlush => "straight_flush6",
+            HandRank::FlushHouse => "flush_house",
+            HandRank::FlushThreePair => "flush_three_pair",
+            HandRank::FlushTwoTriplet => "flush_two_triplet",
+            HandRank::FlushMansion => "flush_mansion",
+            HandRank::FlushFive => "flush_five",
+            HandRank::FlushSix => "flush_six",
+        }
+    }
+
+    // Balatro's base (chips, mult) for the hand. Six-card hands aren't in the
+    // base game, so their values are placeholders which keep the score
+    // increasing with rank.
+    pub fn base_score(self) -> (u32, u32) {
+        match self {
+            HandRank::HighCard => (5, 1),
+            HandRank::Pair => (10, 2),
+            HandRank::TwoPair => (20, 2),
+            HandRank::ThreeOfAKind => (30, 3),
+            HandRank::ThreePair => (40, 3),
+            HandRank::Straight => (30, 4),
+            HandRank::Flush => (35, 4),
+            HandRank::FullHouse => (40, 4),
+            HandRank::TwoTriplet => (50, 5),
+            HandRank::FourOfAKind => (60, 7),
+            HandRank::FullMansion => (70, 7),
+            HandRank::SixStraight => (80, 7),
+            HandRank::SixFlush => (85, 7),
+            HandRank::StraightFlush => (100, 8),
+            HandRank::FiveOfAKind => (120, 12),
+            HandRank::SixOfAKind => (130, 12),
+            HandRank::SixStraightFlush => (135, 13),
+            HandRank::FlushHouse => (140, 14),
+            HandRank::FlushThreePair => (145, 14),
+            HandRank::FlushTwoTriplet => (150, 15),
+            HandRank::FlushMansion => (155, 15),
+            HandRank::FlushFive => (160, 16),
+            HandRank::FlushSix => (180, 18),
+        }
+    }
+
+    // Number of cards that must be played to make this hand.
+    pub fn num_cards(self) -> usize {
+        match self {
+            HandRank::HighCard => 1,
+            HandRank::Pair => 2,
+            HandRank::ThreeOfAKind => 3,
+            HandRank::TwoPair | HandRank::FourOfAKind => 4,
+            HandRank::Straight
+            | HandRank::Flush
+            | HandRank::FullHouse
+            | HandRank::StraightFlush
+            | HandRank::FiveOfAKind
+            | HandRank::FlushHouse
+            | HandRank::FlushFive => 5,
+            HandRank::ThreePair
+            | HandRank::TwoTriplet
+            | HandRank::FullMansion
+            | HandRank::SixStraight
+            | HandRank::SixFlush
+            | HandRank::SixOfAKind
+            | HandRank::SixStraightFlush
+            | HandRank::FlushThreePair
+            | HandRank::FlushTwoTriplet
+            | HandRank::FlushMansion
+            | HandRank::FlushSix => 6,
+        }
+    }
+
+    // The hand as a `Check`, for every hand but high card.
+    pub fn check(self) -> Option<Check> {
+        Some(match self {
+            HandRank::HighCard => return None,
+            HandRank::Pair => Check::OfAKind(2),
+            HandRank::TwoPair => Check::Pairs(2),
+            HandRank::ThreeOfAKind => Check::OfAKind(3),
+            HandRank::ThreePair => Check::Pairs(3),
+            HandRank::Straight => Check::Straight(5),
+            HandRank::Flush => Check::Flush(5),
+            HandRank::FullHouse => Check::Groups(3, 2),
+            HandRank::TwoTriplet => Check::Groups(3, 3),
+            HandRank::FourOfAKind => Check::OfAKind(4),
+            HandRank::FullMansion => Check::Groups(4, 2),
+            HandRank::SixStraight => Check::Straight(6),
+            HandRank::SixFlush => Check::Flush(6),
+            HandRank::StraightFlush => Check::StraightFlush(5),
+            HandRank::FiveOfAKind => Check::OfAKind(5),
+            HandRank::SixOfAKind => Check::OfAKind(6),
+            HandRank::SixStraightFlush => Check::StraightFlush(6),
+            HandRank::FlushHouse => Check::SuitedGroups(3, 2),
+            HandRank::FlushThreePair => Check::SuitedPairs(3),
+            HandRank::FlushTwoTriplet => Check::SuitedGroups(3, 3),
+            HandRank::FlushMansion => Check::SuitedGroups(4, 2),
+            HandRank::FlushFive => Check::SuitedOfAKind(5),
+            HandRank::FlushSix => Check::SuitedOfAKind(6),
+        })
+    }
+
+    // The hand's detector, as a plain function like `HandCount` takes.
+    pub fn detector(self) -> fn(&[Card], u8) -> bool {
+        match self {
+            HandRank::HighCard => |cards, num_jokers| !cards.is_empty() || num_jokers > 0,
+            HandRank::Pair => |cards, num_jokers| is_n_of_a_kind(cards, 2, num_jokers),
+            HandRank::TwoPair => is_two_pair,
+            HandRank::ThreeOfAKind => |cards, num_jokers| is_n_of_a_kind(cards, 3, num_jokers),
+            HandRank::ThreePair => is_three_pair,
+            HandRank::Straight => |cards, num_jokers| is_straight(cards, num_jokers, 5),
+            HandRank::Flush => |cards, num_jokers| is_flush(cards, num_jokers, 5),
+            HandRank::FullHouse => is_full_house,
+            HandRank::TwoTriplet => is_two_triplet,
+            HandRank::FourOfAKind => |cards, num_jokers| is_n_of_a_kind(cards, 4, num_jokers),
+            HandRank::FullMansion => is_full_mansion,
+            HandRank::SixStraight => |cards, num_jokers| is_straight(cards, num_jokers, 6),
+            HandRank::SixFlush => |cards, num_jokers| is_flush(cards, num_jokers, 6),
+            HandRank::StraightFlush => |cards, num_jokers| is_straight_flush(cards, num_jokers, 5),
+            HandRank::FiveOfAKind => |cards, num_jokers| is_n_of_a_kind(cards, 5, num_jokers),
+            HandRank::SixOfAKind => |cards, num_jokers| is_n_of_a_kind(cards, 6, num_jokers),
+            HandRank::SixStraightFlush => {
+                |cards, num_jokers| is_straight_flush(cards, num_jokers, 6)
+            }
+            HandRank::FlushHouse => is_flush_house,
+            HandRank::FlushThreePair => is_flush_three_pair,
+            HandRank::FlushTwoTriplet => is_flush_two_triplet,
+            HandRank::FlushMansion => is_flush_mansion,
+            HandRank::FlushFive => |cards, num_jokers| is_flush_n(cards, 5, num_jokers),
+            HandRank::FlushSix => |cards, num_jokers| is_flush_n(cards, 6, num_jokers),
+        }
+    }
+
+    pub fn matches(self, cards: &[Card], num_jokers: u8) -> bool {
+        self.detector()(cards, num_jokers)
+    }
+}
+
+impl std::fmt::Display for HandRank {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl std::str::FromStr for HandRank {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HandRank::ALL
+            .iter()
+            .copied()
+            .find(|r| r.id() == s)
+            .ok_or_else(|| {
+                let ids = HandRank::ALL.iter().map(|r| r.id()).collect::<Vec<_>>();
+                format!("unknown hand '{s}', expected one of: {}", ids.join(", "))
+            })
+    }
+}
+
+// Best hand that can be played with at most `hand_size` cards.
+pub fn evaluate_best(cards: &[Card], num_jokers: u8, hand_size: usize) -> HandRank {
+    evaluate_best_skipping(cards, num_jokers, hand_size, 0)
+}
+
+// `evaluate_best` with straights skipping the `skipped` ranks.
+pub fn evaluate_best_skipping(
+    cards: &[Card],
+    num_jokers: u8,
+    hand_size: usize,
+    skipped: u16,
+) -> HandRank {
+    // The lookup table only knows standard straights.
+    let looked_up = (skipped == 0)
+        .then(|| lookup::evaluate(cards, num_jokers, hand_size))
+        .flatten();
+    looked_up.unwrap_or_else(|| {
+        let facts = Facts::new(cards, Needs::ALL).skipping(skipped);
+        evaluate_best_with(&facts, num_jokers, hand_size)
+    })
+}
+
+// `evaluate_best` from facts with everything in `Needs::ALL`.
+fn evaluate_best_with(facts: &Facts, num_jokers: u8, hand_size: usize) -> HandRank {
+    HandRank::ALL
+        .iter()
+        .rev()
+        .filter(|r| r.num_cards() <= hand_size)
+        .find_map(|r| {
+            let check = r.check()?;
+            check.matches_facts(facts, num_jokers).then_some(*r)
+        })
+        .unwrap_or(HandRank::HighCard)
+}
+
+// `evaluate_best` for cards coming from an iterator, such as a deck iterator
+// or cards decoded from a buffer. Panics on more than `MAX_CARDS` cards.
+pub fn evaluate_best_iter(
+    cards: impl IntoIterator<Item = Card>,
+    num_jokers: u8,
+    hand_size: usize,
+) -> HandRank {
+    let cards = cards
+        .into_iter()
+        .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+    evaluate_best(&cards, num_jokers, hand_size)
+}
+
+// `evaluate_best_iter` for drawn cards which may include jokers.
+pub fn evaluate_drawn(drawn: impl IntoIterator<Item = CardOrJoker>, hand_size: usize) -> HandRank {
+    let (cards, num_jokers) = split_jokers_iter(drawn);
+    evaluate_best(&cards, num_jokers, hand_size)
+}
+
+// Minimum number of additional (or replacement) cards needed to make `target`.
+// Since a joker can stand in for whichever card is missing, this is the
+// number of jokers that would have to be added.
+pub fn cards_needed(cards: &[Card], num_jokers: u8, target: HandRank) -> u8 {
+    let max = target.num_cards() as u8;
+    (0..max)
+        .find(|&extra| target.matches(cards, num_jokers + extra))
+        .unwrap_or(max)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardOrJoker {
+    Card(Card),
+    Joker,
+}
+
+// Separates drawn cards from jokers.
+pub fn split_jokers(cards_or_jokers: &[CardOrJoker]) -> (arrayvec::ArrayVec<Card, MAX_CARDS>, u8) {
+    split_jokers_iter(cards_or_jokers.iter().copied())
+}
+
+// `split_jokers` for cards coming from an iterator. Panics on more than
+// `MAX_CARDS` cards.
+pub fn split_jokers_iter(
+    cards_or_jokers: impl IntoIterator<Item = CardOrJoker>,
+) -> (arrayvec::ArrayVec<Card, MAX_CARDS>, u8) {
+    let mut cards = arrayvec::ArrayVec::new();
+    let mut num_jokers = 0;
+    for coj in cards_or_jokers {
+        match coj {
+            CardOrJoker::Card(c) => cards.push(c),
+            CardOrJoker::Joker => num_jokers += 1,
+        }
+    }
+    (cards, num_jokers)
+}
+
+// Whether any two of `cards` are the same card, which takes more than one
+// deck.
+pub fn has_identical_cards(cards: &[Card]) -> bool {
+    cards
+        .iter()
+        .enumerate()
+        .any(|(i, c)| cards[i + 1..].contains(c))
+}
+
+// `cards` with identical copies removed, for rule sets where a hand must be
+// made from distinct cards.
+pub fn distinct_cards(cards: &[Card]) -> arrayvec::ArrayVec<Card, MAX_CARDS> {
+    let mut ret = arrayvec::ArrayVec::<Card, MAX_CARDS>::new();
+    for &c in cards {
+        if !ret.contains(&c) {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+// `decks` standard 52-card decks plus `jokers` jokers.
+pub fn standard_deck(decks: usize, jokers: u8) -> Vec<CardOrJoker> {
+    let mut deck = Vec::new();
+    for _ in 0..decks {
+        for suit in Suit::iter() {
+            for rank in Rank::iter() {
+                deck.push(CardOrJoker::Card(Card::new(rank, suit)));
+            }
+        }
+    }
+    for _ in 0..jokers {
+        deck.push(CardOrJoker::Joker);
+    }
+    deck
+}
+
+// Makes the first `n` cards of `deck` wild, which for a standard deck spreads
+// them over different ranks: 2c, 3c, 4c and so on.
+pub fn make_wild(deck: &mut [CardOrJoker], n: usize) -> Result<(), String> {
+    let mut cards = deck.iter_mut().filter_map(|c| match c {
+        CardOrJoker::Card(card) => Some(card),
+        CardOrJoker::Joker => None,
+    });
+    for _ in 0..n {
+        let card = cards
+            .next()
+            .ok_or_else(|| format!("can't make {n} cards wild, the deck is too small"))?;
+        *card = card.made_wild();
+    }
+    Ok(())
+}
+
+// An RNG seeded with `seed`, or with a fresh random seed if None, along with
+// the seed used so the run can be reproduced.
+pub fn seeded_rng(seed: Option<u64>) -> (rand::rngs::StdRng, u64) {
+    use rand::SeedableRng;
+    let seed = seed.unwrap_or_else(rand::random);
+    (rand::rngs::StdRng::seed_from_u64(seed), seed)
+}
+
+// The estimate of a probability and the half-width of its 99.73% confidence
+// interval by the normal approximation. `interval::interval_with` takes the
+// method.
+pub fn confidence_interval(total_iters: u64, num_true: u64) -> (f64, f64) {
+    interval::interval_with(CiMethod::Normal, total_iters, num_true)
+}
+
+// Expected number of deals between occurrences (1/p), along with the bounds
+// implied by the `method` confidence interval on p. The upper bound is
+// infinite when the interval includes 0.
+pub fn expected_wait(method: CiMethod, total_iters: u64, num_true: u64) -> Option<(f64, f64, f64)> {
+    if num_true == 0 {
+        return None;
+    }
+    let p = num_true as f64 / total_iters as f64;
+    let (lo, hi) = interval::bounds_with(method, total_iters, num_true);
+    let longest = if lo > 0.0 { 1.0 / lo } else { f64::INFINITY };
+    Some((1.0 / p, 1.0 / hi, longest))
+}
+
+// Picks how many iterations to run between stopping-rule checks. Automatic
+// sizing starts with a small batch and then aims for `target` between checks
+// at the throughput measured so far, so slow machines still report progress
+// often and fast ones don't spend their time checking.
+pub struct BatchSizer {
+    target: Option<std::time::Duration>,
+    next: u64,
+}
+
+impl BatchSizer {
+    const FIRST_BATCH: u64 = 10_000;
+    const MIN_BATCH: u64 = 1_000;
+    const MAX_BATCH: u64 = 100_000_000;
+    // Limits how quickly one unusually fast or slow batch can move the size.
+    const MAX_GROWTH: u64 = 10;
+
+    pub fn auto(target: std::time::Duration) -> Self {
+        Self {
+            target: Some(target),
+            next: Self::FIRST_BATCH,
+        }
+    }
+
+    pub fn fixed(size: u64) -> Self {
+        Self {
+            target: None,
+            next: size,
+        }
+    }
+
+    pub fn next(&self) -> u64 {
+        self.next
+    }
+
+    // Records that the last batch of `iters` iterations took `elapsed`.
+    pub fn record(&mut self, iters: u64, elapsed: std::time::Duration) {
+        let Some(target) = self.target else {
+            return;
+        };
+        let secs = elapsed.as_secs_f64().max(1e-6);
+        let size = (iters as f64 * target.as_secs_f64() / secs) as u64;
+        self.next = size
+            .clamp(self.next / Self::MAX_GROWTH, self.next * Self::MAX_GROWTH)
+            .clamp(Self::MIN_BATCH, Self::MAX_BATCH);
+    }
+}
+
+#[derive(Clone)]
+pub struct HandCount {
+    pub name: &'static str,
+    pub count: u64,
+    pub func: fn(&[Card], u8) -> bool,
+    // Only cards of at least this rank count towards the hand, as in "jacks
+    // or better".
+    pub min_rank: Option<Rank>,
+    // Instead of `func`, match deals whose best hand playing at most this
+    // many cards is exactly this rank, so that counts don't overlap.
+    pub best: Option<(HandRank, usize)>,
+    // Instead of `func`, a check which a `Plan` can share work between.
+    pub check: Option<Check>,
+    // Instead of `func`, an Omaha hand, see omaha.rs.
+    pub omaha: Option<OmahaHand>,
+    // Instead of `func`, a low of this rank or better, see lowball.rs.
+    pub lowball: Option<(Lowball, Rank)>,
+    // Instead of `func`, deals whose best badugi plays exactly this many
+    // cards, see badugi.rs.
+    pub badugi: Option<usize>,
+    // Ranks straights skip over, see `skip_in_straights`.
+    pub skipped_in_straights: u16,
+    // Iteration indices of the first and most recent occurrence.
+    pub first_seen: Option<u64>,
+    pub last_seen: u64,
+    pub max_gap: u64,
+}
+
+impl HandCount {
+    pub fn new(name: &'static str, func: fn(&[Card], u8) -> bool) -> Self {
+        Self {
+            name,
+            count: 0,
+            func,
+            min_rank: None,
+            best: None,
+            check: None,
+            omaha: None,
+            lowball: None,
+            badugi: None,
+            skipped_in_straights: 0,
+            first_seen: None,
+            last_seen: 0,
+            max_gap: 0,
+        }
+    }
+
+    pub fn checked(name: &'static str, check: Check) -> Self {
+        Self {
+            check: Some(check),
+            ..Self::new(name, |_, _| false)
+        }
+    }
+
+    // A count for `rank`, checked when it can be.
+    pub fn for_rank(name: &'static str, rank: HandRank) -> Self {
+        match rank.check() {
+            Some(check) => Self::checked(name, check),
+            None => Self::new(name, rank.detector()),
+        }
+    }
+
+    pub fn record(&mut self, iter: u64) {
+        self.count += 1;
+        if self.first_seen.is_none() {
+            self.first_seen = Some(iter);
+        } else {
+            self.max_gap = self.max_gap.max(iter - self.last_seen);
+        }
+        self.last_seen = iter;
+    }
+
+    pub fn mean_gap(&self) -> Option<f64> {
+        let first = self.first_seen?;
+        if self.count < 2 {
+            return None;
+        }
+        Some((self.last_seen - first) as f64 / (self.count - 1) as f64)
+    }
+
+    // Whether `cards` and `num_jokers` jokers make this hand.
+    pub fn matches(&self, cards: &[Card], num_jokers: u8) -> bool {
+        if let Some((rank, hand_size)) = self.best {
+            return evaluate_best_skipping(cards, num_jokers, hand_size, self.skipped_in_straights)
+                == rank;
+        }
+        if let Some(hand) = self.omaha {
+            return hand.matches(cards);
+        }
+        if let Some((rules, high)) = self.lowball {
+            return lowball::is_low_hand(cards, num_jokers, rules, high);
+        }
+        if let Some(size) = self.badugi {
+            return badugi::evaluate_badugi(cards, num_jokers).map(|b| b.size()) == Some(size);
+        }
+        let detect = |cards: &[Card]| match self.check {
+            Some(check) => {
+                let facts = Facts::new(cards, check.needs()).skipping(self.skipped_in_straights);
+                check.matches_facts(&facts, num_jokers)
+            }
+            None => (self.func)(cards, num_jokers),
+        };
+        match self.min_rank {
+            None => detect(cards),
+            Some(min_rank) => {
+                let qualifying = cards
+                    .iter()
+                    .copied()
+                    .filter(|c| c.rank >= min_rank)
+                    .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+                detect(&qualifying)
+            }
+        }
+    }
+
+    // Only counts the hand when it's made from cards of at least `rank`, and
+    // renames it to match, like "Pair (J+)".
+    pub fn set_min_rank(&mut self, rank: Rank) {
+        self.min_rank = Some(rank);
+        // Names live for the whole run, and only a few are ever renamed.
+        self.name = Box::leak(format!("{} ({rank}+)", self.name).into_boxed_str());
+    }
+
+    // Whether any deal of `num_cards` cards can make this hand. Jokers can be
+    // any card, so a hand is possible exactly when that many jokers make it.
+    pub fn possible_with(&self, num_cards: usize) -> bool {
+        // All jokers make the best hand rather than every hand.
+        if let Some((rank, _)) = self.best {
+            return rank.num_cards() <= num_cards;
+        }
+        if self.omaha.is_some() {
+            return num_cards >= omaha::HOLE_CARDS + omaha::MIN_BOARD;
+        }
+        if let Some(size) = self.badugi {
+            return size <= num_cards;
+        }
+        self.matches(&[], num_cards.min(u8::MAX as usize) as u8)
+    }
+
+    // TODO: write tests
+    pub fn overlap(&self, method: CiMethod, total_iters: u64, other: &HandCount) -> bool {
+        if self.count == 0 || other.count == 0 {
+            return false;
+        }
+        let (ci1_start, ci1_end) = interval::bounds_with(method, total_iters, self.count);
+        let (ci2_start, ci2_end) = interval::bounds_with(method, total_iters, other.count);
+        ci1_start <= ci2_end && ci2_start <= ci1_end
+    }
+}
+
+// One count per hand rank playable with `hand_size` cards, each matching the
+// deals whose best hand is that rank. Exactly one matches any deal.
+pub fn best_hand_counts(hand_size: usize) -> Option<Vec<HandCount>> {
+    if !(5..=MAX_CARDS).contains(&hand_size) {
+        return None;
+    }
+    let counts = HandRank::ALL
+        .iter()
+        .filter(|r| r.num_cards() <= hand_size)
+        .map(|&rank| HandCount {
+            best: Some((rank, hand_size)),
+            ..HandCount::new(rank.name(), |_, _| false)
+        })
+        .collect();
+    Some(counts)
+}
+
+// Counts for exactly `ranks`, as chosen on the command line, instead of the
+// usual list for a hand size.
+pub fn hand_counts_for(ranks: &[HandRank]) -> Vec<HandCount> {
+    ranks
+        .iter()
+        .map(|&rank| HandCount::for_rank(rank.name(), rank))
+        .collect()
+}
+
+// Makes straights take `straight_size` cards and flushes `flush_size`,
+// whatever the hand size, like Balatro mods where 6-card hands still count
+// 5-card straights. A straight flush needs enough consecutive cards of one
+// suit to be both. Resized hands are renamed to match, like "5-card
+// Straight", and the royal flush, a five card hand, is dropped when straight
+// flushes aren't five cards.
+pub fn resize_straights_and_flushes(
+    counts: &mut Vec<HandCount>,
+    straight_size: usize,
+    flush_size: usize,
+) {
+    let straight_flush_size = straight_size.max(flush_size);
+    counts.retain(|c| c.check != Some(Check::RoyalFlush) || straight_flush_size == 5);
+    for c in counts.iter_mut() {
+        let (old, new, check) = match c.check {
+            Some(Check::Straight(n)) => (n, straight_size, Check::Straight(straight_size)),
+            Some(Check::Flush(n)) => (n as usize, flush_size, Check::Flush(flush_size as u8)),
+            Some(Check::StraightFlush(n)) => (
+                n,
+                straight_flush_size,
+                Check::StraightFlush(straight_flush_size),
+            ),
+            _ => continue,
+        };
+        if old != new {
+            c.check = Some(check);
+            // Names live for the whole run, as with `set_min_rank`.
+            c.name = Box::leak(format!("{new}-card {}", c.name).into_boxed_str());
+        }
+    }
+}
+
+// Lets the straights in `counts`, including those in straight flushes and
+// best hands, skip the `skipped` ranks from `skipped_ranks`.
+pub fn skip_in_straights(counts: &mut [HandCount], skipped: u16) {
+    for c in counts {
+        c.skipped_in_straights = skipped;
+    }
+}
+
+// Parses "<hand>=<rank>", like "Pair=J", for `HandCount::set_min_rank`.
+pub fn parse_min_rank(s: &str) -> Result<(String, Rank), String> {
+    let Some((hand, rank)) = s.split_once('=') else {
+        return Err(format!("expected '<hand>=<rank>', got '{s}'"));
+    };
+    Ok((hand.trim().to_string(), rank.trim().parse()?))
+}
+
+// Most pairs that fit in a hand.
+const MAX_PAIRS: usize = MAX_CARDS / 2;
+
+// "2 pair", "3 pair" and so on, indexed by n.
+fn n_pairs_name(n: usize) -> &'static str {
+    static NAMES: std::sync::OnceLock<Vec<String>> = std::sync::OnceLock::new();
+    &NAMES.get_or_init(|| (0..=MAX_PAIRS).map(|n| format!("{n} pair")).collect())[n]
+}
+
+// Hands counted for each hand size, from 5 up to `MAX_CARDS`. Every size gets
+// n pair hands for as many pairs as fit.
+pub fn hand_counts(hand_size: usize) -> Option<Vec<HandCount>> {
+    if !(5..=MAX_CARDS).contains(&hand_size) {
+        return None;
+    }
+    let mut counts = vec![
+        HandCount::checked("Pair", Check::OfAKind(2)),
+        HandCount::checked("3oak", Check::OfAKind(3)),
+        HandCount::checked("4oak", Check::OfAKind(4)),
+        HandCount::checked("5oak", Check::OfAKind(5)),
+    ];
+    for n in 2..=hand_size / 2 {
+        counts.push(HandCount::checked(n_pairs_name(n), Check::Pairs(n as u8)));
+    }
+    counts.push(HandCount::checked("Full House", Check::Groups(3, 2)));
+
+    if hand_size == 5 {
+        counts.push(HandCount::checked("Flush House", Check::SuitedGroups(3, 2)));
+        counts.push(HandCount::checked("Strt Flush", Check::StraightFlush(5)));
+        counts.push(HandCount::checked("Flush 5", Check::SuitedOfAKind(5)));
+        // The rest of the standard poker ladder, after the hands above so
+        // that their positions don't change.
+        counts.push(HandCount::for_rank("High Card", HandRank::HighCard));
+        counts.push(HandCount::checked("Straight", Check::Straight(5)));
+        counts.push(HandCount::checked("Flush", Check::Flush(5)));
+        counts.push(HandCount::checked("Royal Flush", Check::RoyalFlush));
+    } else if hand_size == 6 {
+        counts.push(HandCount::checked("6oak", Check::OfAKind(6)));
+        counts.push(HandCount::checked("2 triplet", Check::Groups(3, 3)));
+        counts.push(HandCount::checked("Straight", Check::Straight(6)));
+        counts.push(HandCount::checked("Flush", Check::Flush(6)));
+        counts.push(HandCount::checked("Full Mansion", Check::Groups(4, 2)));
+        counts.push(HandCount::checked(
+            "Flush Mansion",
+            Check::SuitedGroups(4, 2),
+        ));
+        counts.push(HandCount::checked("Flush 3 pair", Check::SuitedPairs(3)));
+        counts.push(HandCount::checked(
+            "Flush 2 triplet",
+            Check::SuitedGroups(3, 3),
+        ));
+        counts.push(HandCount::checked("Strt Flush", Check::StraightFlush(6)));
+        counts.push(HandCount::checked("Flush 6", Check::SuitedOfAKind(6)));
+    } else {
+        // Bigger hands only get the n of a kind and n pair style hands.
+        counts.push(HandCount::checked("6oak", Check::OfAKind(6)));
+        counts.push(HandCount::checked("2 triplet", Check::Groups(3, 3)));
+    }
+    Some(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_counts() {
+        assert_eq!(RankCounts::default(), rank_counts(&[]));
+
+        {
+            let mut expected = RankCounts::default();
+            expected[1] = 2;
+            expected[3] = 1;
+            assert_eq!(
+                expected,
+                rank_counts(&[card!("3c"), card!("3c"), card!("5h")])
+            )
+        }
+    }
+
+    #[test]
+    fn test_skipping_ranks_in_straights() {
+        let short = 0b1111;
+        let is_straight = |hand: &str, skipped| {
+            let (cards, num_jokers) = parse_hand(hand).unwrap();
+            straight_in(Bitboard::new(&cards).ranks(), num_jokers, 5, skipped)
+        };
+        assert!(is_straight("Ah 6c 7d 8s 9h", short));
+        assert!(!is_straight("Ah 6c 7d 8s 9h", 0));
+        assert!(is_straight("Ah Kc Qd Js Th", short));
+        assert!(is_straight("Ah 6c 7d J 9h", short));
+        assert!(!is_straight("Ah 2c 3d 4s 5h", short));
+        // Without face cards, T is right below a high ace, but straights
+        // still don't wrap around.
+        let abandoned = 1 << Rank::Jack as u16 | 1 << Rank::Queen as u16 | 1 << Rank::King as u16;
+        assert!(is_straight("7h 8h 9c Td As", abandoned));
+        assert!(!is_straight("9h Tc Ad 2s 3h", abandoned));
+        assert_eq!(
+            vec![
+                Rank::Ace,
+                Rank::Seven,
+                Rank::Eight,
+                Rank::Nine,
+                Rank::Ten,
+                Rank::Jack,
+                Rank::Queen,
+                Rank::King,
+                Rank::Ace
+            ],
+            straight_order(0b11111).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_skip_in_straights() {
+        use crate::rules::RuleSet;
+        let (cards, _) = parse_hand("Ah 6h 7h 8h 9h").unwrap();
+        let short = skipped_ranks([Rank::Two, Rank::Three, Rank::Four, Rank::Five]);
+        assert_eq!(HandRank::Flush, evaluate_best(&cards, 0, 5));
+        assert_eq!(
+            HandRank::StraightFlush,
+            evaluate_best_skipping(&cards, 0, 5, short)
+        );
+        let mut counts = hand_counts(5).unwrap();
+        let straight = counts.iter().position(|c| c.name == "Straight").unwrap();
+        assert!(!counts[straight].matches(&cards, 0));
+        skip_in_straights(&mut counts, short);
+        assert!(counts[straight].matches(&cards, 0));
+        let mut best = RuleSet::Best.counts(5).unwrap();
+        skip_in_straights(&mut best, short);
+        let matched = best
+            .iter()
+            .filter(|c| c.matches(&cards, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["Strt Flush"],
+            matched.iter().map(|c| c.name).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_suit_counts() {
+        assert_eq!(RankCounts::default(), suit_counts(&[]));
+
+        {
+            let mut expected = RankCounts::default();
+            expected[1] = 2;
+            expected[3] = 1;
+            assert_eq!(
+                expected,
+                suit_counts(&[card!("2d"), card!("2d"), card!("4s")])
+            )
+        }
+    }
+
+    #[test]
+    fn test_is_n_of_a_kind() {
+        assert!(is_n_of_a_kind(&[], 0, 0));
+        assert!(!is_n_of_a_kind(&[], 1, 0));
+        assert!(is_n_of_a_kind(&[card!("3c")], 1, 0));
+
+        assert!(!is_n_of_a_kind(&[card!("2d"), card!("3c")], 2, 0));
+        assert!(is_n_of_a_kind(&[card!("3d"), card!("3d")], 2, 0));
+        assert!(is_n_of_a_kind(&[card!("3c"), card!("3d")], 2, 0));
+
+        assert!(!is_n_of_a_kind(&[card!("3c"), card!("3d")], 3, 0));
+        assert!(is_n_of_a_kind(
+            &[card!("3c"), card!("3c"), card!("3d"),],
+            3,
+            0
+        ));
+        assert!(is_n_of_a_kind(
+            &[card!("3c"), card!("3c"), card!("3d"), card!("4d"),],
+            3,
+            0
+        ));
+
+        assert!(!is_n_of_a_kind(&[], 2, 1));
+        assert!(is_n_of_a_kind(&[], 2, 2));
+        assert!(is_n_of_a_kind(&[card!("4d"),], 2, 1));
+        assert!(!is_n_of_a_kind(&[card!("4d"), card!("5h"),], 3, 1));
+        assert!(is_n_of_a_kind(&[card!("5d"), card!("5h"),], 3, 1));
+    }
+
+    #[test]
+    fn test_is_two_pair() {
+        assert!(!is_two_pair(&[], 0));
+        assert!(!is_two_pair(&[card!("2c")], 0));
+        assert!(!is_two_pair(&[card!("2c"), card!("2c"),], 0));
+        assert!(!is_two_pair(&[card!("2c"), card!("2c"), card!("2c"),], 0));
+        assert!(is_two_pair(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            0
+        ));
+        assert!(is_two_pair(
+            &[card!("2c"), card!("2h"), card!("2c"), card!("2h"),],
+            0
+        ));
+        assert!(is_two_pair(
+            &[card!("2c"), card!("2c"), card!("2c"), card!("2c"),],
+            0
+        ));
+
+        assert!(!is_two_pair(&[], 3));
+        assert!(is_two_pair(&[], 4));
+        assert!(is_two_pair(&[card!("2c"),], 3));
+        assert!(is_two_pair(&[card!("3d"), card!("2c"),], 2));
+        assert!(is_two_pair(&[card!("2c"), card!("2c"),], 2));
+        assert!(is_two_pair(&[card!("2c"), card!("2c"), card!("3d"),], 1));
+        assert!(!is_two_pair(&[card!("2c"), card!("2c"),], 1));
+    }
+
+    #[test]
+    fn test_is_full_house() {
+        assert!(!is_full_house(&[], 0));
+        assert!(!is_full_house(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            0
+        ));
+        assert!(!is_full_house(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+                card!("4s"),
+            ],
+            0
+        ));
+        assert!(is_full_house(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_full_house(
+            &[
+                card!("2c"),
+                card!("2d"),
+                card!("2h"),
+                card!("2s"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_full_house(&[], 5));
+        assert!(!is_full_house(&[], 4));
+        assert!(is_full_house(&[card!("2c"),], 4));
+        assert!(is_full_house(&[card!("2c"), card!("3d"), card!("3d"),], 2));
+        assert!(is_full_house(&[card!("2c"), card!("3d"),], 3));
+        assert!(is_full_house(
+            &[card!("2c"), card!("2c"), card!("3d"), card!("3d"),],
+            1
+        ));
+        assert!(is_full_house(
+            &[card!("2c"), card!("3d"), card!("3d"), card!("3d"),],
+            1
+        ));
+        assert!(is_full_house(
+            &[card!("3d"), card!("3d"), card!("3d"), card!("3d"),],
+            1
+        ));
+    }
+
+    #[test]
+    fn test_is_full_mansion() {
+        assert!(!is_full_mansion(&[], 0));
+        assert!(!is_full_mansion(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_full_mansion(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_full_mansion(
+            &[
+                card!("3c"),
+                card!("3c"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+                card!("4s"),
+            ],
+            0
+        ));
+        assert!(is_full_mansion(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+                card!("2s"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_full_mansion(
+            &[
+                card!("2c"),
+                card!("2d"),
+                card!("2h"),
+                card!("2s"),
+                card!("2s"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_full_mansion(&[], 6));
+        assert!(!is_full_mansion(&[], 5));
+        assert!(is_full_mansion(&[card!("2c"),], 5));
+        assert!(is_full_mansion(
+            &[card!("2c"), card!("2c"), card!("3d"), card!("3d"),],
+            2
+        ));
+        assert!(is_full_mansion(
+            &[card!("2c"), card!("3c"), card!("3d"), card!("3d"),],
+            2
+        ));
+        assert!(!is_full_mansion(
+            &[card!("2c"), card!("3d"), card!("4d"),],
+            3
+        ));
+        assert!(is_full_mansion(
+            &[card!("2c"), card!("3d"), card!("3d"),],
+            3
+        ));
+        assert!(!is_three_pair(&[], 5));
+        assert!(is_three_pair(&[], 6));
+    }
+
+    #[test]
+    fn test_is_two_triplet() {
+        assert!(!is_two_triplet(&[], 0));
+        assert!(!is_two_triplet(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            0
+        ));
+        assert!(!is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("3d"),
+                card!("3h"),
+                card!("3s"),
+            ],
+            0
+        ));
+        assert!(is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            1
+        ));
+        assert!(!is_two_triplet(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            1
+        ));
+        assert!(!is_two_triplet(
+            &[card!("3d"), card!("2h"), card!("2s"),],
+            2
+        ));
+        assert!(is_two_triplet(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            2
+        ));
+        assert!(!is_two_triplet(
+            &[card!("3c"), card!("4d"), card!("2h"), card!("2s"),],
+            2
+        ));
+        assert!(!is_two_triplet(&[], 5));
+        assert!(is_two_triplet(&[], 6));
+    }
+
+    #[test]
+    fn test_is_three_pair() {
+        assert!(!is_three_pair(&[], 0));
+        assert!(!is_three_pair(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            0
+        ));
+        assert!(!is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(!is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            0
+        ));
+        assert!(is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("3d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            1
+        ));
+        assert!(is_three_pair(
+            &[
+                card!("3c"),
+                card!("3d"),
+                card!("4d"),
+                card!("2h"),
+                card!("2s"),
+            ],
+            1
+        ));
+        assert!(!is_three_pair(&[card!("3d"), card!("2h"), card!("2s"),], 2));
+        assert!(is_three_pair(
+            &[card!("3c"), card!("3d"), card!("2h"), card!("2s"),],
+            2
+        ));
+        assert!(is_three_pair(
+            &[card!("3c"), card!("4d"), card!("2h"), card!("2s"),],
+            2
+        ));
+        assert!(!is_three_pair(&[], 5));
+        assert!(is_three_pair(&[], 6));
+    }
+
+    #[test]
+    fn test_is_flush() {
+        assert!(!is_flush(&[], 0, 5));
+        assert!(!is_flush(&[card!("2c"),], 0, 5));
+        assert!(!is_flush(
+            &[card!("2c"), card!("2c"), card!("2c"), card!("2c"),],
+            0,
+            5
+        ));
+        assert!(is_flush(
+            &[
+                card!("2c"),
+                card!("2c"),
+                card!("3c"),
+                card!("2c"),
+                card!("2c"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_flush(
+            &[
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("2d"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_flush(&[], 4, 5));
+        assert!(is_flush(&[], 5, 5));
+        assert!(is_flush(
+            &[
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("2d"),
+            ],
+            1,
+            5
+        ));
+        assert!(is_flush(
+            &[card!("2c"), card!("2c"), card!("2c"), card!("2d"),],
+            2,
+            5
+        ));
+        assert!(!is_flush(&[card!("2c"), card!("2c"), card!("2d"),], 2, 5));
+        assert!(!is_flush(
+            &[
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+            ],
+            0,
+            6
+        ));
+        assert!(is_flush(
+            &[
+                card!("2c"),
+                card!("2c"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+                card!("5c"),
+            ],
+            0,
+            6
+        ));
+    }
+
+    #[test]
+    fn test_is_straight() {
+        assert!(!is_straight(&[], 0, 5));
+        assert!(!is_straight(&[card!("2c"),], 0, 5));
+        // More jokers than a straight needs.
+        assert!(is_straight(&[], 7, 5));
+        assert!(is_straight(&hand!("2c 3d 4h 5s"), 2, 5));
+
+        assert!(!is_straight(
+            &[card!("4c"), card!("5c"), card!("6c"), card!("7c"),],
+            0,
+            5
+        ));
+        assert!(is_straight(
+            &[
+                card!("2d"),
+                card!("3d"),
+                card!("4d"),
+                card!("5d"),
+                card!("6d"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight(
+            &[
+                card!("2h"),
+                card!("3s"),
+                card!("4c"),
+                card!("5c"),
+                card!("6d"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight(
+            &[
+                card!("Tc"),
+                card!("Jc"),
+                card!("Qc"),
+                card!("Kc"),
+                card!("Ac"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight(
+            &[
+                card!("Ac"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+                card!("5c"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_straight(
+            &[
+                card!("Kc"),
+                card!("Ac"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_straight(&[], 4, 5));
+        assert!(is_straight(&[], 5, 5));
+        assert!(is_straight(
+            &[card!("Ac"), card!("2c"), card!("3c"), card!("4c"),],
+            1,
+            5
+        ));
+        assert!(is_straight(
+            &[card!("2c"), card!("3c"), card!("4c"), card!("5c"),],
+            1,
+            5
+        ));
+        assert!(is_straight(&[card!("2c"), card!("3c"), card!("4c"),], 2, 5));
+        assert!(is_straight(&[card!("2c"), card!("4c"), card!("5c"),], 2, 5));
+        assert!(is_straight(&[card!("2c"), card!("4c"), card!("6c"),], 2, 5));
+        assert!(is_straight(&[card!("2c"), card!("6c"),], 3, 5));
+        assert!(is_straight(&[card!("3c"), card!("6c"),], 3, 5));
+        assert!(!is_straight(&[card!("3c"), card!("4c"),], 2, 5));
+        assert!(is_straight(
+            &[card!("Tc"), card!("Jc"), card!("Kc"), card!("Ac"),],
+            1,
+            5
+        ));
+        assert!(is_straight(
+            &[card!("Tc"), card!("Jc"), card!("Qc"), card!("Kc"),],
+            1,
+            5
+        ));
+        assert!(!is_straight(
+            &[
+                card!("9c"),
+                card!("Tc"),
+                card!("Jc"),
+                card!("Qc"),
+                card!("Kc"),
+            ],
+            0,
+            6
+        ));
+        assert!(is_straight(
+            &[
+                card!("8c"),
+                card!("9c"),
+                card!("Tc"),
+                card!("Jc"),
+                card!("Qc"),
+                card!("Kc"),
+            ],
+            0,
+            6
+        ));
+    }
+
+    #[test]
+    fn test_is_straight_flush() {
+        assert!(!is_straight_flush(&[], 0, 5));
+        assert!(!is_straight_flush(&[card!("2c"),], 0, 5));
+
+        assert!(!is_straight_flush(
+            &[card!("4c"), card!("5c"), card!("6c"), card!("7c"),],
+            0,
+            5
+        ));
+        assert!(is_straight_flush(
+            &[
+                card!("2d"),
+                card!("3d"),
+                card!("4d"),
+                card!("5d"),
+                card!("6d"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_straight_flush(
+            &[
+                card!("2h"),
+                card!("3s"),
+                card!("4c"),
+                card!("5c"),
+                card!("6d"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight_flush(
+            &[
+                card!("Tc"),
+                card!("Jc"),
+                card!("Qc"),
+                card!("Kc"),
+                card!("Ac"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight_flush(
+            &[
+                card!("Ac"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+                card!("5c"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_straight_flush(
+            &[
+                card!("Kc"),
+                card!("Ac"),
+                card!("2c"),
+                card!("3c"),
+                card!("4c"),
+            ],
+            0,
+            5
+        ));
+        assert!(is_straight_flush(
+            &[
+                card!("4d"),
+                card!("5c"),
+                card!("6c"),
+                card!("7c"),
+                card!("8c"),
+                card!("9c"),
+            ],
+            0,
+            5
+        ));
+        assert!(!is_straight_flush(&[], 4, 5));
+        assert!(is_straight_flush(&[], 5, 5));
+        assert!(is_straight_flush(
+            &[
+                card!("5c"),
+                card!("6c"),
+                card!("7d"),
+                card!("8c"),
+                card!("9c"),
+            ],
+            1,
+            5
+        ));
+        assert!(!is_straight_flush(
+            &[
+                card!("5c"),
+                card!("6c"),
+                card!("7d"),
+                card!("8d"),
+                card!("9c"),
+            ],
+            1,
+            5
+        ));
+        assert!(!is_straight_flush(
+            &[card!("5c"), card!("6c"), card!("9d"),],
+            2,
+            5
+        ));
+        assert!(is_straight_flush(
+            &[card!("5c"), card!("6c"), card!("9c"),],
+            2,
+            5
+        ));
+        assert!(!is_straight_flush(
+            &[
+                card!("2d"),
+                card!("3d"),
+                card!("4d"),
+                card!("5d"),
+                card!("6d"),
+            ],
+            0,
+            6
+        ));
+        assert!(is_straight_flush(
+            &[
+                card!("2d"),
+                card!("3d"),
+                card!("4d"),
+                card!("5d"),
+                card!("6d"),
+                card!("7d"),
+            ],
+            0,
+            6
+        ));
+    }
+
+    #[test]
+    fn test_is_royal_flush() {
+        assert!(!is_royal_flush(&[], 4));
+        assert!(is_royal_flush(&[], 5));
+        assert!(is_royal_flush(&hand!("Ts Js Qs Ks As 2c"), 0));
+        assert!(!is_royal_flush(&hand!("9s Ts Js Qs Ks"), 0));
+        assert!(!is_royal_flush(&hand!("Ts Js Qs Ks Ah"), 0));
+        assert!(is_royal_flush(&hand!("Ts Js Ks As Ah"), 1));
+        assert!(!is_royal_flush(&hand!("Ts Ts Js Ks As"), 0));
+        assert!(is_royal_flush(&[card!("Th").made_wild()], 4));
+    }
+
+    #[test]
+    fn test_straight_flushes() {
+        assert_eq!(
+            Vec::<(Suit, Rank)>::new(),
+            straight_flushes(&hand!("2h 3h 4h 5h 7h"), 0, 5, 0)
+        );
+        assert_eq!(
+            vec![(Suit::Hearts, Rank::Six)],
+            straight_flushes(&hand!("Ah 2h 3h 4h 5h 6h"), 0, 5, 0)
+        );
+        assert_eq!(
+            vec![(Suit::Spades, Rank::Ace)],
+            straight_flushes(&hand!("Ts Js Qs Ks 2c"), 1, 5, 0)
+        );
+        // A wild card can complete a straight flush in any suit.
+        assert_eq!(
+            vec![(Suit::Clubs, Rank::Nine), (Suit::Diamonds, Rank::Nine)],
+            straight_flushes(&hand!("5c 6c 7c 8d* 9c 6d 7d 5d 9d"), 0, 5, 0)
+        );
+    }
+
+    #[test]
+    fn test_is_flush_house() {
+        assert!(!is_flush_house(&[], 0));
+        assert!(is_flush_house(
+            &[
+                card!("3c"),
+                card!("3c"),
+                card!("3c"),
+                card!("3c"),
+                card!("3c"),
+            ],
+            0
+        ));
+        assert!(is_flush_house(
+            &[
+                card!("3c"),
+                card!("3c"),
+                card!("4c"),
+                card!("4c"),
+                card!("4c"),
+            ],
+            0
+        ));
+        assert!(!is_flush_house(
+            &[
+                card!("3c"),
+                card!("3c"),
+                card!("4c"),
+                card!("4d"),
+                card!("4c"),
+            ],
+            0
+        ));
+
+        assert!(!is_flush_house(&[], 4));
+        assert!(is_flush_house(&[], 5));
+        assert!(is_flush_house(
+            &[card!("3c"), card!("3c"), card!("4c"), card!("4c"),],
+            1
+        ));
+        assert!(!is_flush_house(
+            &[card!("3d"), card!("3c"), card!("4c"), card!("4c"),],
+            1
+        ));
+        assert!(is_flush_house(&[card!("4c"), card!("4c"), card!("4c"),], 2));
+        assert!(is_flush_house(&[card!("3c"), card!("4c"), card!("4c"),], 2));
+    }
+
+    #[test]
+    fn test_is_flush_n() {
+        assert!(!is_flush_n(&[], 1, 0));
+        assert!(is_flush_n(&[card!("3c"),], 1, 0));
+        assert!(is_flush_n(
+            &[card!("3c"), card!("3c"), card!("3c"), card!("3c"),],
+            4,
+            0
+        ));
+        assert!(!is_flush_n(
+            &[card!("4c"), card!("3c"), card!("3c"), card!("3c"),],
+            4,
+            0
+        ));
+        assert!(!is_flush_n(
+            &[card!("3h"), card!("3c"), card!("3c"), card!("3c"),],
+            4,
+            0
+        ));
+        assert!(is_flush_n(&[card!("3c"), card!("3c"), card!("3c"),], 4, 1));
+        assert!(!is_flush_n(&[card!("4c"), card!("3c"), card!("3c"),], 4, 1));
+    }
+
+    #[test]
+    fn test_wild_cards() {
+        let mut expected = RankCounts::default();
+        expected[..4].copy_from_slice(&[1, 1, 2, 1]);
+        assert_eq!(expected, suit_counts(&hand!("2h 5s*")));
+
+        // Wild cards join any suit, but keep their rank.
+        assert!(is_flush(&hand!("2h 5h 9h Kh As*"), 0, 5));
+        assert!(is_flush(&hand!("2h 5h 9d* Kc* As*"), 0, 5));
+        assert!(!is_flush(&hand!("2h 5h 9d Kc* As*"), 0, 5));
+        assert!(is_straight_flush(&hand!("2h 3h 4c* 5d* As*"), 0, 5));
+        assert!(!is_straight_flush(&hand!("2h 3h 4c 5d* As*"), 0, 5));
+        assert!(is_flush_house(&hand!("2h 2h 3h 3s* 3c*"), 0));
+        assert!(!is_flush_house(&hand!("2h 2h 3h 3s 3c*"), 0));
+        assert!(is_flush_n(&hand!("7d 7d* 7s* 7c*"), 5, 1));
+        assert!(!is_flush_n(&hand!("7d 7d* 7s* 6c*"), 5, 1));
+        // Suitless hands don't care.
+        assert!(!is_n_of_a_kind(&hand!("2h 3h* 4h*"), 2, 0));
+        assert_eq!(
+            HandRank::StraightFlush,
+            evaluate_best(&hand!("9c Tc Jd* Qh* Ks* 2d"), 0, 5)
+        );
+
+        let mut deck = standard_deck(1, 1);
+        make_wild(&mut deck, 3).unwrap();
+        let wild = deck
+            .iter()
+            .filter_map(|c| match c {
+                CardOrJoker::Card(c) if c.wild => Some(*c),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(hand!("2c* 3c* 4c*").to_vec(), wild);
+        assert!(make_wild(&mut deck, 53).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_best() {
+        assert_eq!(HandRank::HighCard, evaluate_best(&[], 0, 5));
+        assert_eq!(
+            HandRank::HighCard,
+            evaluate_best(&[card!("2c"), card!("9d")], 0, 5)
+        );
+        assert_eq!(HandRank::FlushFive, evaluate_best(&[], 5, 5));
+        assert_eq!(HandRank::FlushSix, evaluate_best(&[], 6, 6));
+
+        let three_pair = [
+            card!("2c"),
+            card!("2d"),
+            card!("5c"),
+            card!("5d"),
+            card!("Kh"),
+            card!("Ks"),
+        ];
+        assert_eq!(HandRank::TwoPair, evaluate_best(&three_pair, 0, 5));
+        assert_eq!(HandRank::ThreePair, evaluate_best(&three_pair, 0, 6));
+
+        let straight = [
+            card!("2c"),
+            card!("3d"),
+            card!("4c"),
+            card!("5d"),
+            card!("6h"),
+            card!("6s"),
+        ];
+        assert_eq!(HandRank::Straight, evaluate_best(&straight, 0, 5));
+        assert_eq!(HandRank::Straight, evaluate_best(&straight, 0, 6));
+        assert_eq!(HandRank::SixStraight, evaluate_best(&straight, 1, 6));
+    }
+
+    #[test]
+    fn test_hand_count_record() {
+        let mut c = HandCount::new("Pair", |_, _| true);
+        assert_eq!(None, c.mean_gap());
+        c.record(3);
+        assert_eq!(Some(3), c.first_seen);
+        assert_eq!(None, c.mean_gap());
+        c.record(5);
+        c.record(13);
+        assert_eq!(3, c.count);
+        assert_eq!(Some(3), c.first_seen);
+        assert_eq!(8, c.max_gap);
+        assert_eq!(Some(5.0), c.mean_gap());
+    }
+
+    #[test]
+    fn test_n_pairs_counts() {
+        let names = |hand_size| {
+            hand_counts(hand_size)
+                .unwrap()
+                .iter()
+                .map(|c| c.name)
+                .filter(|name| name.ends_with(" pair") && !name.starts_with("Flush"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["2 pair"], names(5));
+        assert_eq!(vec!["2 pair", "3 pair"], names(6));
+        assert_eq!(vec!["2 pair", "3 pair", "4 pair"], names(8));
+        assert_eq!(
+            vec!["2 pair", "3 pair", "4 pair", "5 pair", "6 pair"],
+            names(12)
+        );
+        assert_eq!(MAX_CARDS / 2 - 1, names(MAX_CARDS).len());
+        assert!(hand_counts(4).is_none());
+        assert!(hand_counts(MAX_CARDS + 1).is_none());
+
+        let counts = hand_counts(8).unwrap();
+        let four_pair = counts.iter().find(|c| c.name == "4 pair").unwrap();
+        assert!(four_pair.matches(&hand!("2c 2d 3c 3d 4c 4d 5c 5d"), 0));
+        assert!(four_pair.matches(&hand!("2c 2d 3c 3d 4c 4d 5c"), 1));
+        assert!(!four_pair.matches(&hand!("2c 2d 3c 3d 4c 4d 5c 6d"), 0));
+    }
+
+    #[test]
+    fn test_min_rank() {
+        assert_eq!(Ok(Rank::Jack), "j".parse());
+        assert_eq!(Ok(Rank::Ten), "10".parse());
+        assert!("1".parse::<Rank>().is_err());
+        assert_eq!("Q", Rank::Queen.to_string());
+        assert_eq!(
+            Ok(("Pair".to_string(), Rank::Jack)),
+            parse_min_rank("Pair = J")
+        );
+        assert!(parse_min_rank("Pair").is_err());
+
+        let mut counts = hand_counts(5).unwrap();
+        let pair = &mut counts[0];
+        pair.set_min_rank(Rank::Jack);
+        assert_eq!("Pair (J+)", pair.name);
+        assert!(pair.matches(&hand!("Jc Jd 2c 3c 4c"), 0));
+        assert!(!pair.matches(&hand!("Tc Td 2c 3c 4c"), 0));
+        // A joker only helps pair up a qualifying card.
+        assert!(pair.matches(&hand!("Ac 2c 3c 4c"), 1));
+        assert!(!pair.matches(&hand!("Tc 2c 3c 4c"), 1));
+        assert!(pair.possible_with(2));
+    }
+
+    #[test]
+    fn test_possible_with() {
+        let counts = hand_counts(6).unwrap();
+        let possible = |n: usize| {
+            counts
+                .iter()
+                .filter(|c| c.possible_with(n))
+                .map(|c| c.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["Pair", "3oak", "4oak", "2 pair"], possible(4));
+        assert!(!possible(5).contains(&"6oak"));
+        assert!(!possible(5).contains(&"Flush"));
+        assert!(possible(5).contains(&"Full House"));
+        assert_eq!(
+            counts.iter().map(|c| c.name).collect::<Vec<_>>(),
+            possible(6)
+        );
+        assert_eq!(
+            counts.iter().map(|c| c.name).collect::<Vec<_>>(),
+            possible(7)
+        );
+        assert!(possible(0).is_empty());
+    }
+
+    #[test]
+    fn test_hand_counts_for() {
+        let counts = hand_counts_for(&[HandRank::Straight, HandRank::FiveOfAKind]);
+        assert_eq!(
+            vec!["Straight", "5oak"],
+            counts.iter().map(|c| c.name).collect::<Vec<_>>()
+        );
+        assert!(counts[0].matches(&hand!("9c Td Jh Qs Kc"), 0));
+        assert!(!counts[1].matches(&hand!("9c 9d 9h 9s"), 0));
+        assert!(counts[1].matches(&hand!("9c 9d 9h 9s"), 1));
+        for rank in HandRank::ALL {
+            let cards = hand!("Ah Kh Qh Jh Th Ad As");
+            assert_eq!(rank.matches(&cards, 0), (rank.detector())(&cards, 0));
+        }
+    }
+
+    #[test]
+    fn test_resize_straights_and_flushes() {
+        let mut counts = hand_counts(6).unwrap();
+        resize_straights_and_flushes(&mut counts, 5, 6);
+        let find = |name| counts.iter().find(|c| c.name == name).unwrap();
+        assert!(find("5-card Straight").matches(&hand!("9c Td Jh Qs Kc 2d"), 0));
+        assert!(!find("Flush").matches(&hand!("9c Tc Jc Qc Kc 2d"), 0));
+        // Still six cards, to be a flush too.
+        assert!(find("Strt Flush").matches(&hand!("8c 9c Tc Jc Qc Kc"), 0));
+
+        // Four fingers: 4-card straights and flushes in 5-card hands.
+        let mut counts = hand_counts(5).unwrap();
+        resize_straights_and_flushes(&mut counts, 4, 4);
+        let names = counts.iter().map(|c| c.name).collect::<Vec<_>>();
+        assert!(names.contains(&"4-card Flush") && names.contains(&"4-card Strt Flush"));
+        assert!(!names.contains(&"Royal Flush"));
+        let find = |name| counts.iter().find(|c| c.name == name).unwrap();
+        assert!(find("4-card Straight").matches(&hand!("9c Td Jh Qs 2c"), 0));
+        assert!(find("4-card Flush").matches(&hand!("9c 2c Jc Qc 2d"), 0));
+
+        // The sizes they already have change nothing.
+        let mut counts = hand_counts(5).unwrap();
+        resize_straights_and_flushes(&mut counts, 5, 5);
+        assert_eq!(
+            hand_counts(5)
+                .unwrap()
+                .iter()
+                .map(|c| c.name)
+                .collect::<Vec<_>>(),
+            counts.iter().map(|c| c.name).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_best_hand_counts() {
+        assert!(best_hand_counts(4).is_none());
+        let counts = best_hand_counts(5).unwrap();
+        assert_eq!("High Card", counts[0].name);
+        assert!(counts.iter().all(|c| c.best.unwrap().0.num_cards() <= 5));
+        // Every hand matches exactly one count.
+        for (cards, num_jokers, best) in [
+            (&hand!("Ac Kd 9h 4s 2c")[..], 0, "High Card"),
+            (&hand!("Ac Ad Ah Kd Ks 2c"), 0, "Full House"),
+            (&hand!("Ac Ad"), 3, "5oak"),
+        ] {
+            let matched = counts
+                .iter()
+                .filter(|c| c.matches(cards, num_jokers))
+                .map(|c| c.name)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![best], matched);
+        }
+        assert!(counts[0].possible_with(1));
+        assert!(!counts.last().unwrap().possible_with(4));
+    }
+
+    #[test]
+    fn test_seeded_rng() {
+        use rand::Rng;
+        let (mut a, seed) = seeded_rng(Some(42));
+        assert_eq!(42, seed);
+        let (mut b, _) = seeded_rng(Some(seed));
+        assert_eq!(a.gen::<u64>(), b.gen::<u64>());
+        // A picked seed reproduces the same stream when passed back in.
+        let (mut c, seed) = seeded_rng(None);
+        assert_eq!(c.gen::<u64>(), seeded_rng(Some(seed)).0.gen::<u64>());
+    }
+
+    #[test]
+    fn test_batch_sizer() {
+        use std::time::Duration;
+        let mut fixed = BatchSizer::fixed(123);
+        fixed.record(123, Duration::from_secs(100));
+        assert_eq!(123, fixed.next());
+
+        let mut auto = BatchSizer::auto(Duration::from_secs(1));
+        assert_eq!(10_000, auto.next());
+        // 2M iterations/sec, but growth is limited per batch.
+        auto.record(10_000, Duration::from_millis(5));
+        assert_eq!(100_000, auto.next());
+        auto.record(100_000, Duration::from_millis(50));
+        assert_eq!(1_000_000, auto.next());
+        auto.record(1_000_000, Duration::from_millis(500));
+        assert_eq!(2_000_000, auto.next());
+        // A slow machine gets small batches, but not arbitrarily small.
+        auto.record(2_000_000, Duration::from_secs(1000));
+        assert_eq!(200_000, auto.next());
+        for _ in 0..5 {
+            auto.record(auto.next(), Duration::from_secs(1000));
+        }
+        assert_eq!(1_000, auto.next());
+    }
+
+    #[test]
+    fn test_expected_wait() {
+        let normal = CiMethod::Normal;
+        assert_eq!(None, expected_wait(normal, 100, 0));
+        let (wait, lo, hi) = expected_wait(normal, 100, 100).unwrap();
+        assert_eq!(1.0, wait);
+        assert_eq!(1.0, lo);
+        assert_eq!(1.0, hi);
+        let (wait, lo, hi) = expected_wait(normal, 10000, 100).unwrap();
+        assert_eq!(100.0, wait);
+        assert!(lo < wait && wait < hi);
+        let (_, _, hi) = expected_wait(normal, 10, 1).unwrap();
+        assert_eq!(f64::INFINITY, hi);
+        // Wilson intervals stay above 0, so the wait is bounded.
+        let (_, _, hi) = expected_wait(CiMethod::Wilson, 10, 1).unwrap();
+        assert!(hi.is_finite());
+    }
+
+    #[test]
+    fn test_parse_card() {
+        assert_eq!(Ok(card!("As")), "As".parse());
+        assert_eq!(Ok(card!("Td")), "Td".parse());
+        assert_eq!(Ok(card!("Th")), "10h".parse());
+        assert_eq!(Ok(card!("2c")), "2C".parse());
+        assert!("Ax".parse::<Card>().is_err());
+        assert!("1s".parse::<Card>().is_err());
+        assert!("Ass".parse::<Card>().is_err());
+        assert_eq!(Ok(card!("Kc").made_wild()), "Kc*".parse());
+        assert!("Kc**".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn test_parse_hand() {
+        assert_eq!(Ok((vec![], 0)), parse_hand(""));
+        assert_eq!(
+            Ok((vec![card!("As"), card!("Kd")], 2)),
+            parse_hand("As J Kd j")
+        );
+        assert!(parse_hand("As Kx").is_err());
+    }
+
+    #[test]
+    fn test_format_hand() {
+        assert_eq!("", format_hand(&[], 0));
+        assert_eq!("As Td J J", format_hand(&[card!("As"), card!("Td")], 2));
+        assert_eq!(
+            Ok((vec![card!("2c")], 1)),
+            parse_hand(&format_hand(&[card!("2c")], 1))
+        );
+    }
+
+    #[test]
+    fn test_card_order() {
+        let mut cards = hand!("Ah 2s Kc 2c Ac").to_vec();
+        cards.sort();
+        assert_eq!(hand!("2c 2s Kc Ac Ah").to_vec(), cards);
+    }
+
+    #[test]
+    fn test_card_conversions() {
+        for code in 0..NUM_RANKS * NUM_SUITS {
+            let card = Card::try_from(code).unwrap();
+            assert_eq!(code, u8::from(card));
+            let (rank, suit) = card.into();
+            assert_eq!(card, Card::new(rank, suit));
+        }
+        assert!(Card::try_from(NUM_RANKS * NUM_SUITS).is_err());
+        assert_eq!(card!("Qd"), (Rank::Queen, Suit::Diamonds).into());
+        assert_eq!(Ok(Rank::Ace), Rank::try_from(12));
+        assert!(Suit::try_from(4).is_err());
+    }
+
+    #[test]
+    fn test_rank_suit_iter() {
+        assert_eq!(NUM_RANKS as usize, Rank::iter().count());
+        assert!(Rank::iter().zip(Rank::iter().skip(1)).all(|(a, b)| a < b));
+        assert_eq!(Some(Rank::Ace), Rank::iter().last());
+        assert!(Rank::Ace.is_ace());
+        assert!(!Rank::King.is_ace());
+        assert_eq!(
+            vec![Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades],
+            Suit::iter().collect::<Vec<_>>()
+        );
+        for (i, rank) in Rank::iter().enumerate() {
+            assert_eq!(i, rank as usize);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("As", card!("As").to_string());
+        assert_eq!("T♥", format!("{:#}", card!("Th")));
+        assert_eq!("Card(2c)", format!("{:?}", card!("2c")));
+        assert_eq!("7d*", card!("7d*").to_string());
+        assert_eq!("7♦*", format!("{:#}", card!("7d*")));
+        assert_eq!("Full House", HandRank::FullHouse.to_string());
+        assert_eq!("      Pair", format!("{:>10}", HandRank::Pair));
+    }
+
+    #[test]
+    fn test_card_macros() {
+        assert_eq!(card!("Ah"), card!("Ah"));
+        assert_eq!(card!("Tc"), card!("10c"));
+        assert_eq!(card!("Ks"), card!("ks"));
+        assert_eq!(
+            parse_hand(" Ah  Kh Qh Jh Th").unwrap().0,
+            hand!(" Ah  Kh Qh Jh Th")
+        );
+        assert_eq!(0, hand!("").len());
+    }
+
+    #[test]
+    fn test_cards_needed() {
+        assert_eq!(2, cards_needed(&[], 0, HandRank::Pair));
+        assert_eq!(5, cards_needed(&[], 0, HandRank::FlushFive));
+        assert_eq!(0, cards_needed(&[], 2, HandRank::Pair));
+        let cards = [
+            card!("2c"),
+            card!("3c"),
+            card!("4c"),
+            card!("4d"),
+            card!("9h"),
+        ];
+        assert_eq!(0, cards_needed(&cards, 0, HandRank::Pair));
+        assert_eq!(1, cards_needed(&cards, 0, HandRank::ThreeOfAKind));
+        assert_eq!(1, cards_needed(&cards, 0, HandRank::TwoPair));
+        assert_eq!(2, cards_needed(&cards, 0, HandRank::Straight));
+        assert_eq!(1, cards_needed(&cards, 1, HandRank::Straight));
+        assert_eq!(2, cards_needed(&cards, 0, HandRank::Flush));
+        assert_eq!(2, cards_needed(&cards, 0, HandRank::StraightFlush));
+        assert_eq!(2, cards_needed(&cards, 0, HandRank::FullHouse));
+    }
+
+    #[test]
+    fn test_hand_rank_ids() {
+        for r in HandRank::ALL {
+            assert_eq!(Ok(r), r.id().parse());
+        }
+        assert!("royal".parse::<HandRank>().is_err());
+    }
+
+    #[test]
+    fn test_identical_cards() {
+        assert!(!has_identical_cards(&[]));
+        assert!(!has_identical_cards(&hand!("As Ah Ks")));
+        assert!(has_identical_cards(&hand!("As Kh As")));
+        assert_eq!(
+            &hand!("As Kh Qd"),
+            distinct_cards(&hand!("As Kh As Qd Kh")).as_slice()
+        );
+        // Five identical cards only make a flush five when duplicates count.
+        let five = hand!("7h 7h 7h 7h 7h");
+        assert!(is_flush_n(&five, 5, 0));
+        assert!(!is_flush_n(&distinct_cards(&five), 5, 0));
+    }
+
+    #[test]
+    fn test_split_jokers() {
+        let (cards, num_jokers) = split_jokers(&[]);
+        assert!(cards.is_empty());
+        assert_eq!(0, num_jokers);
+        let (cards, num_jokers) = split_jokers(&[
+            CardOrJoker::Joker,
+            CardOrJoker::Card(card!("5d")),
+            CardOrJoker::Joker,
+        ]);
+        assert_eq!(&[card!("5d")], cards.as_slice());
+        assert_eq!(2, num_jokers);
+    }
+
+    #[test]
+    fn test_evaluate_iter() {
+        let hand = hand!("Ah Ad As 9c 9d");
+        assert_eq!(evaluate_best(&hand, 0, 5), evaluate_best_iter(hand, 0, 5));
+        // Cards decoded on the fly from packed codes.
+        let codes = hand.map(u8::from);
+        assert_eq!(
+            HandRank::FullHouse,
+            evaluate_best_iter(codes.iter().map(|&c| Card::try_from(c).unwrap()), 0, 5)
+        );
+        let drawn = hand!("Ah Ad As")
+            .map(CardOrJoker::Card)
+            .into_iter()
+            .chain([CardOrJoker::Joker]);
+        assert_eq!(HandRank::FourOfAKind, evaluate_drawn(drawn, 5));
+        let (cards, num_jokers) = split_jokers_iter(standard_deck(0, 3));
+        assert!(cards.is_empty());
+        assert_eq!(3, num_jokers);
+    }
+}
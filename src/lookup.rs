@@ -0,0 +1,162 @@
+//! Table-driven best-hand ranking for the standard game: at most 7 cards
+//! from one deck, no jokers or wild cards, 5-card hands and no skipped ranks
+//! in straights. Hold'em and `--mode best` runs spend almost all their time
+//! here, and exhaustive enumeration of them needs it to be cheap.
+//!
+//! Like the two-plus-two evaluator, ranks are fed one at a time through a
+//! state machine, except that states are multisets of ranks rather than of
+//! cards, since suits only matter for flushes. That keeps the table to 76,155
+//! states, 4MB of transitions, built on first use. With 7 cards from one deck a flush rules
+//! out quads and full houses, so a deal with 5 cards of a suit is either a
+//! flush or a straight flush, which the suit's ranks decide.
+
+use crate::bitboard::{highest_straight, straight_mask};
+use crate::{n_and_m_in, n_of_a_kind_in, n_pairs_in, Card, HandRank};
+use crate::{RankCounts, NUM_RANKS, NUM_SUITS};
+
+// The most cards the table handles.
+pub const MAX_LOOKUP_CARDS: usize = 7;
+
+const NONE: u32 = u32::MAX;
+
+struct Table {
+    // `next[state * NUM_RANKS + rank]` is the state after another card of
+    // `rank`, or `NONE` when it would be a fifth copy or an eighth card.
+    next: Vec<u32>,
+    // The best hand of each state's ranks, ignoring flushes.
+    rank: Vec<HandRank>,
+}
+
+// The best hand made by cards with these rank counts, ignoring suits.
+fn unsuited_rank(counts: &RankCounts) -> HandRank {
+    let present = (0..NUM_RANKS)
+        .filter(|&r| counts[r as usize] > 0)
+        .fold(0u16, |mask, r| mask | 1 << r);
+    let (mask, len) = straight_mask(present, 0);
+    if n_of_a_kind_in(counts, 4, 0) {
+        HandRank::FourOfAKind
+    } else if n_and_m_in(*counts, 3, 2, 0) {
+        HandRank::FullHouse
+    } else if highest_straight(mask, len, 0, 5).is_some() {
+        HandRank::Straight
+    } else if n_of_a_kind_in(counts, 3, 0) {
+        HandRank::ThreeOfAKind
+    } else if n_pairs_in(counts, 2, 0) {
+        HandRank::TwoPair
+    } else if n_of_a_kind_in(counts, 2, 0) {
+        HandRank::Pair
+    } else {
+        HandRank::HighCard
+    }
+}
+
+impl Table {
+    fn build() -> Self {
+        let mut states = vec![RankCounts::default()];
+        let mut ids = std::collections::HashMap::from([(RankCounts::default(), 0u32)]);
+        let mut next = Vec::new();
+        // States are numbered in the order found, so each one's successors
+        // are found after it.
+        let mut i = 0;
+        while i < states.len() {
+            let counts = states[i];
+            let size = counts.iter().map(|&c| c as usize).sum::<usize>();
+            for rank in 0..NUM_RANKS as usize {
+                if size == MAX_LOOKUP_CARDS || counts[rank] == NUM_SUITS {
+                    next.push(NONE);
+                    continue;
+                }
+                let mut child = counts;
+                child[rank] += 1;
+                let id = *ids.entry(child).or_insert_with(|| {
+                    states.push(child);
+                    (states.len() - 1) as u32
+                });
+                next.push(id);
+            }
+            i += 1;
+        }
+        Self {
+            next,
+            rank: states.iter().map(unsuited_rank).collect(),
+        }
+    }
+}
+
+fn table() -> &'static Table {
+    static TABLE: std::sync::OnceLock<Table> = std::sync::OnceLock::new();
+    TABLE.get_or_init(Table::build)
+}
+
+// `evaluate_best(cards, num_jokers, hand_size)` from the table, or None when
+// it doesn't apply.
+pub fn evaluate(cards: &[Card], num_jokers: u8, hand_size: usize) -> Option<HandRank> {
+    if num_jokers != 0 || hand_size != 5 || cards.len() > MAX_LOOKUP_CARDS {
+        return None;
+    }
+    let table = table();
+    let mut state = 0;
+    let mut seen = 0u64;
+    let mut suits = [0u16; NUM_SUITS as usize];
+    for c in cards {
+        let bit = 1 << (c.suit as u32 * 16 + c.rank as u32);
+        if c.wild || seen & bit != 0 {
+            return None;
+        }
+        seen |= bit;
+        suits[c.suit as usize] |= 1 << c.rank as u16;
+        state = table.next[state as usize * NUM_RANKS as usize + c.rank as usize];
+    }
+    if let Some(suit) = suits.into_iter().find(|s| s.count_ones() >= 5) {
+        let (mask, len) = straight_mask(suit, 0);
+        return Some(match highest_straight(mask, len, 0, 5) {
+            Some(_) => HandRank::StraightFlush,
+            None => HandRank::Flush,
+        });
+    }
+    Some(table.rank[state as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Dealer;
+    use crate::{cards, evaluate_best_with, plan::Facts, plan::Needs};
+
+    #[test]
+    fn test_table() {
+        let table = table();
+        // Multisets of up to 7 of the 13 ranks with at most 4 of each.
+        assert_eq!(table.rank.len() * NUM_RANKS as usize, table.next.len());
+        assert_eq!(HandRank::HighCard, table.rank[0]);
+        assert_eq!(76_155, table.rank.len());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(
+            Some(HandRank::StraightFlush),
+            evaluate(&cards!("Ah 2h 3h 4h 5h Kd Kc"), 0, 5)
+        );
+        assert_eq!(
+            Some(HandRank::FullHouse),
+            evaluate(&cards!("Ah Ad As Kd Kc Kh 2c"), 0, 5)
+        );
+        assert_eq!(Some(HandRank::HighCard), evaluate(&[], 0, 5));
+        assert_eq!(None, evaluate(&cards!("Ah Ah 2c"), 0, 5));
+        assert_eq!(None, evaluate(&cards!("Ah 2c"), 1, 5));
+        assert_eq!(None, evaluate(&cards!("Ah 2c"), 0, 6));
+        assert_eq!(None, evaluate(&cards!("Ah 2c 3c 4c 5c 6c 7c 8c"), 0, 5));
+        assert_eq!(None, evaluate(&[cards!("Ah")[0].made_wild()], 0, 5));
+    }
+
+    #[test]
+    fn test_matches_evaluate_best() {
+        let mut dealer = Dealer::new(1, 0, 0);
+        for i in 0..20_000 {
+            let (cards, _) = dealer.deal(i % 8);
+            let expected = evaluate_best_with(&Facts::new(&cards, Needs::ALL), 0, 5);
+            assert_eq!(Some(expected), evaluate(&cards, 0, 5), "{cards:?}");
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Lowball, where the lowest five cards win.
+//!
+//! - Ace-to-five, as in Razz: aces are low and straights and flushes don't
+//!   count, so A-2-3-4-5 is the best hand. Only pairs spoil a low.
+//! - Deuce-to-seven, as in 2-7 Triple Draw: aces are high and straights and
+//!   flushes count against the hand, so 7-5-4-3-2 of mixed suits is the best.
+//!
+//! Either way a low is named for its highest card, so an "8 low" is five
+//! unpaired cards topped by an eight, and beats every 9 low. Jokers can be
+//! any card, so they never make a flush, but wild cards count as every suit,
+//! as they do for high hands, so they can't avoid one.
+
+use crate::{Card, HandCount, Rank, NUM_RANKS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lowball {
+    AceToFive,
+    DeuceToSeven,
+}
+
+// A five-card low. Lower compares as better.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LowHand {
+    // 0 for an unpaired hand, then pairs, two pair and so on, including
+    // straights and flushes for deuce-to-seven.
+    category: u8,
+    // Card values grouped by how many of each, most first, then highest
+    // first, so that a pair of threes with 8-5-2 is [3, 3, 8, 5, 2].
+    values: [u8; 5],
+}
+
+impl Lowball {
+    // A rank's value, where aces are 1 for ace-to-five and 14 otherwise.
+    fn value(self, rank: Rank) -> u8 {
+        match (self, rank) {
+            (Lowball::AceToFive, Rank::Ace) => 1,
+            _ => rank as u8 + 2,
+        }
+    }
+
+    // The values from lowest to highest.
+    fn values(self) -> std::ops::RangeInclusive<u8> {
+        match self {
+            Lowball::AceToFive => 1..=NUM_RANKS,
+            Lowball::DeuceToSeven => 2..=NUM_RANKS + 1,
+        }
+    }
+
+    // The highest card of the best possible low.
+    fn nut_high(self) -> Rank {
+        match self {
+            Lowball::AceToFive => Rank::Five,
+            Lowball::DeuceToSeven => Rank::Seven,
+        }
+    }
+
+    // The lows worth counting, from the best up to any unpaired hand, with
+    // aces last for deuce-to-seven and left out of ace-to-five, where they're
+    // the lowest card.
+    fn highs(self) -> impl Iterator<Item = Rank> {
+        let last = match self {
+            Lowball::AceToFive => Rank::King,
+            Lowball::DeuceToSeven => Rank::Ace,
+        };
+        Rank::ALL
+            .into_iter()
+            .filter(move |&r| r >= self.nut_high() && r <= last)
+    }
+}
+
+impl LowHand {
+    // `values` in any order, `flush` when they're forced to share a suit.
+    fn new(rules: Lowball, values: [u8; 5], flush: bool) -> Self {
+        let mut groups = Vec::<(u8, u8)>::with_capacity(5);
+        for v in values {
+            match groups.iter_mut().find(|(value, _)| *value == v) {
+                Some((_, n)) => *n += 1,
+                None => groups.push((v, 1)),
+            }
+        }
+        groups.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+        let mut sorted = [0; 5];
+        let flat = groups
+            .iter()
+            .flat_map(|&(v, n)| std::iter::repeat_n(v, n as usize));
+        for (slot, v) in sorted.iter_mut().zip(flat) {
+            *slot = v;
+        }
+        let shape = groups.iter().map(|&(_, n)| n).collect::<Vec<_>>();
+        let paired = match shape[..] {
+            [1, 1, 1, 1, 1] => 0,
+            [2, 1, 1, 1] => 1,
+            [2, 2, 1] => 2,
+            [3, 1, 1] => 3,
+            [3, 2] => 4,
+            [4, 1] => 5,
+            _ => 6,
+        };
+        let category = match rules {
+            Lowball::AceToFive => paired,
+            Lowball::DeuceToSeven => {
+                let straight = paired == 0 && sorted[0] - sorted[4] == 4;
+                match (straight, flush) {
+                    (true, true) => 8,
+                    (true, false) => 4,
+                    (false, true) if paired < 4 => 5,
+                    // Full houses and up rank above flushes.
+                    _ if paired >= 4 => paired + 2,
+                    _ => paired,
+                }
+            }
+        };
+        Self {
+            category,
+            values: sorted,
+        }
+    }
+
+    // The highest card of an unpaired low, or None for anything worse.
+    pub fn high_card(self) -> Option<u8> {
+        (self.category == 0).then_some(self.values[0])
+    }
+
+    // Whether this is a low of `high` or better.
+    pub fn is_at_most(self, rules: Lowball, high: Rank) -> bool {
+        self.high_card().is_some_and(|v| v <= rules.value(high))
+    }
+}
+
+// The best low five of `cards` and `num_jokers` jokers make, or None with
+// fewer than five cards.
+pub fn evaluate_low(cards: &[Card], num_jokers: u8, rules: Lowball) -> Option<LowHand> {
+    if cards.len() + (num_jokers as usize) < 5 {
+        return None;
+    }
+    let max_value = *rules.values().end() as usize;
+    let mut available = vec![0u8; max_value + 1];
+    // Suits every card of each value has, so that picking any of them keeps
+    // that suit. Wild cards have them all.
+    let mut suits = vec![0b1111u8; max_value + 1];
+    for c in cards {
+        let v = rules.value(c.rank) as usize;
+        available[v] = available[v].saturating_add(1);
+        if !c.wild {
+            suits[v] &= 1 << c.suit as u8;
+        }
+    }
+    let mut best = None;
+    let mut picked = Vec::with_capacity(5);
+    pick(
+        rules,
+        *rules.values().start(),
+        num_jokers,
+        &available,
+        &suits,
+        &mut picked,
+        &mut best,
+    );
+    best
+}
+
+// Tries every multiset of five values from `value` up, using jokers once a
+// value's cards run out, keeping the best low in `best`.
+fn pick(
+    rules: Lowball,
+    value: u8,
+    jokers: u8,
+    available: &[u8],
+    suits: &[u8],
+    picked: &mut Vec<(u8, bool)>,
+    best: &mut Option<LowHand>,
+) {
+    if picked.len() == 5 {
+        let values = std::array::from_fn(|i| picked[i].0);
+        // A flush is forced when no jokers were used and every value's
+        // cards share a suit. This misses the rare flush which wild cards
+        // force even though some value has plain cards of two suits.
+        let common = picked
+            .iter()
+            .try_fold(0b1111, |common, &(v, joker)| {
+                (!joker).then(|| common & suits[v as usize])
+            })
+            .unwrap_or(0);
+        let hand = LowHand::new(rules, values, common != 0);
+        if best.is_none_or(|b| hand < b) {
+            *best = Some(hand);
+        }
+        return;
+    }
+    if value > *rules.values().end() {
+        return;
+    }
+    let room = 5 - picked.len();
+    let real = available[value as usize] as usize;
+    for n in 0..=room.min(real + jokers as usize) {
+        let from_jokers = n.saturating_sub(real);
+        for i in 0..n {
+            picked.push((value, i >= n - from_jokers));
+        }
+        pick(
+            rules,
+            value + 1,
+            jokers - from_jokers as u8,
+            available,
+            suits,
+            picked,
+            best,
+        );
+        picked.truncate(picked.len() - n);
+    }
+}
+
+// Whether the deal makes a low of `high` or better.
+pub fn is_low_hand(cards: &[Card], num_jokers: u8, rules: Lowball, high: Rank) -> bool {
+    evaluate_low(cards, num_jokers, rules).is_some_and(|l| l.is_at_most(rules, high))
+}
+
+// The name for a low of `high` or better, like "8 Low".
+fn low_name(high: Rank) -> &'static str {
+    match high {
+        Rank::Five => "5 Low",
+        Rank::Six => "6 Low",
+        Rank::Seven => "7 Low",
+        Rank::Eight => "8 Low",
+        Rank::Nine => "9 Low",
+        Rank::Ten => "10 Low",
+        Rank::Jack => "J Low",
+        Rank::Queen => "Q Low",
+        Rank::King => "K Low",
+        _ => "A Low",
+    }
+}
+
+// "N Low" counts, each counting the deals with that low or better, so that
+// they overlap like the default hands do.
+pub fn counts(rules: Lowball) -> Vec<HandCount> {
+    rules
+        .highs()
+        .map(|high| HandCount {
+            lowball: Some((rules, high)),
+            ..HandCount::new(low_name(high), |_, _| false)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, cards};
+
+    fn low(hand: &str, num_jokers: u8, rules: Lowball) -> LowHand {
+        evaluate_low(&cards!(hand), num_jokers, rules).unwrap()
+    }
+
+    #[test]
+    fn test_ace_to_five() {
+        let a5 = Lowball::AceToFive;
+        // Straights and flushes don't count.
+        assert_eq!(Some(5), low("As 2s 3s 4s 5s", 0, a5).high_card());
+        assert_eq!(Some(8), low("Kd 8c 2h 5s Ah 3d 2c", 0, a5).high_card());
+        // 8-5-4-3-2 beats 8-6-3-2-A.
+        assert!(low("8c 5d 4h 3s 2c", 0, a5) < low("8c 6d 3h 2s Ac", 0, a5));
+        // Any unpaired hand beats a pair, and a low pair beats a high one.
+        assert!(low("Kc Qd Jh 9s 8c", 0, a5) < low("Ac Ad 2h 3s 4c", 0, a5));
+        assert!(low("Ac Ad 2h 3s 4c", 0, a5) < low("2c 2d Ah 3s 4c", 0, a5));
+        assert!(low("2c 2d 3h 3s 4c", 0, a5) < low("2c 2d 2h 3s 4c", 0, a5));
+        assert_eq!(None, low("Ac Ad 2h 3s 4c 4d", 0, a5).high_card());
+        // Jokers fill the lowest missing ranks.
+        assert_eq!(Some(5), low("Ac 5d 3h Kd 4c", 1, a5).high_card());
+        assert_eq!(Some(13), low("Ac 5d 3h Kd", 1, a5).high_card());
+        assert_eq!(Some(6), low("6c 6d 6h 6s", 4, a5).high_card());
+        assert_eq!(None, evaluate_low(&cards!("Ac 2d 3h"), 1, a5));
+    }
+
+    #[test]
+    fn test_deuce_to_seven() {
+        let d27 = Lowball::DeuceToSeven;
+        assert_eq!(Some(7), low("7c 5d 4h 3s 2c", 0, d27).high_card());
+        // Aces are high, so A-2-3-4-5 is an ace-high hand, not a straight.
+        assert_eq!(Some(14), low("Ac 2d 3h 4s 5c", 0, d27).high_card());
+        // Straights and flushes count against the hand.
+        assert_eq!(None, low("6c 5d 4h 3s 2c", 0, d27).high_card());
+        assert_eq!(None, low("7c 5c 4c 3c 2c", 0, d27).high_card());
+        assert_eq!(Some(8), low("6c 5d 4h 3s 2c 8d", 0, d27).high_card());
+        // A wild card makes the flush, unless there's another card to play.
+        let mut wild = cards!("7c 5c 4c 3c 2c");
+        wild[0] = card!("7c").made_wild();
+        assert_eq!(None, evaluate_low(&wild, 0, d27).unwrap().high_card());
+        wild.push(card!("7d"));
+        assert_eq!(Some(7), evaluate_low(&wild, 0, d27).unwrap().high_card());
+        // A pair beats a straight, which beats a flush.
+        let pair = low("Kc Kd 4h 3s 2c", 0, d27);
+        let straight = low("6c 5d 4h 3s 2c", 0, d27);
+        let flush = low("9c 5c 4c 3c 2c", 0, d27);
+        let full_house = low("2c 2d 2h 3s 3c", 0, d27);
+        assert!(pair < straight && straight < flush && flush < full_house);
+        assert_eq!(Some(7), low("2c 3d", 3, d27).high_card());
+    }
+
+    #[test]
+    fn test_counts() {
+        let counts = counts(Lowball::AceToFive);
+        let deal = cards!("Kd 8c 2h 5s Ah 3d 2c");
+        let made = counts
+            .iter()
+            .filter(|c| c.matches(&deal, 0))
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["8 Low", "9 Low", "10 Low", "J Low", "Q Low", "K Low"],
+            made
+        );
+        assert!(counts.iter().all(|c| c.possible_with(5)));
+        assert!(counts.iter().all(|c| !c.possible_with(4)));
+        assert!(is_low_hand(&deal, 0, Lowball::DeuceToSeven, Rank::King));
+        assert!(!is_low_hand(&deal, 0, Lowball::DeuceToSeven, Rank::Queen));
+    }
+}
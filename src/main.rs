@@ -1,218 +1,771 @@
 use clap::Parser;
+use poker::boards::{BombPot, MultiBoard};
+use poker::classes::{best_strength, Strength};
+use poker::deck::Preset;
+use poker::estimate::{estimate, iterations_to_separate};
+use poker::interval::{interval_with, CiMethod};
+use poker::objective::{parse_objective, Objective, ObjectiveStats, Outcome};
+use poker::observer::{
+    parse_derived, Conditional, Correlations, Derived, ExpectedScore, FeatureWriter, History,
+    Observer, Overlaps, SampleDump, ScoreDistribution, Snapshot, Snapshots, StraightFlushBreakdown,
+};
+use poker::omaha::{self, OmahaHand};
+use poker::plan::Plan;
+use poker::players::Players;
+use poker::profile::{Profile, Stage};
+use poker::progress::ProgressFile;
+use poker::results::{RunResult, Warning};
+use poker::rules::{HandInfo, RuleSet};
+use poker::scoring::{play_round, CardEffects, RoundResult, ScoreTable};
+use poker::sensitivity::{Perturbation, RandomDeckSpec, Sensitivity, ThinOrder};
+use poker::session::Session;
+use poker::sink::{OutputSink, ReportFormat, SinkSpec};
+use poker::strategy::{discard_and_draw, parse_strategy, DiscardStrategy, MAX_DISCARD_CARDS};
+use poker::table::ColumnType;
+use poker::*;
+
+// Tracks how often being allowed to play 6 cards instead of 5 improves the
+// best available hand.
+struct HandSizeComparison {
+    // Indexed by the best 5-card hand: (deals, deals improved by a 6th card).
+    by_best5: [(u64, u64); HandRank::ALL.len()],
+    // Ranks straights skip over, see `skip_in_straights`.
+    skipped: u16,
+}
 
-const MAX_CARDS: usize = 12;
-
-const NUM_RANKS: u8 = 13;
+impl HandSizeComparison {
+    fn new(skipped: u16) -> Self {
+        Self {
+            by_best5: [(0, 0); HandRank::ALL.len()],
+            skipped,
+        }
+    }
 
-type RankCounts = [u8; NUM_RANKS as usize];
+    fn record(&mut self, cards: &[Card], num_jokers: u8) {
+        let best5 = evaluate_best_skipping(cards, num_jokers, 5, self.skipped);
+        let best6 = evaluate_best_skipping(cards, num_jokers, 6, self.skipped);
+        let entry = &mut self.by_best5[best5 as usize];
+        entry.0 += 1;
+        if best6 > best5 {
+            entry.1 += 1;
+        }
+    }
 
-const NUM_SUITS: u8 = 4;
+    fn print(&self, num_iters: u64) {
+        let improved = self.by_best5.iter().map(|e| e.1).sum::<u64>();
+        println!(
+            "6-card play improves the best hand in {p:.6} of deals ({improved})",
+            p = improved as f64 / num_iters as f64,
+        );
+        let max_str_len = HandRank::ALL.iter().map(|r| r.name().len()).max().unwrap();
+        for (rank, &(deals, improved)) in HandRank::ALL.iter().zip(&self.by_best5) {
+            if deals == 0 {
+                continue;
+            }
+            println!(
+                "{name: >width$}: {p:.6} of {deals} deals improved",
+                name = rank.name(),
+                width = max_str_len,
+                p = improved as f64 / deals as f64,
+            );
+        }
+    }
+}
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
-struct Card {
-    suit: u8,
-    rank: u8,
+// Tracks how often a deal holds two literally identical cards, which can
+// only come from different decks.
+#[derive(Default)]
+struct IdenticalCards {
+    deals: u64,
 }
 
-fn rank_counts(cards: &[Card]) -> RankCounts {
-    let mut ret = RankCounts::default();
-    for c in cards {
-        ret[c.rank as usize] += 1;
+impl IdenticalCards {
+    fn record(&mut self, cards: &[Card]) {
+        if has_identical_cards(cards) {
+            self.deals += 1;
+        }
+    }
+
+    fn print(&self, num_iters: u64, method: CiMethod) {
+        let (p, ci) = interval_with(method, num_iters, self.deals);
+        println!(
+            "identical cards: {p:.6} ± {ci:.6} of deals ({})",
+            self.deals
+        );
     }
-    ret
 }
 
-type Ranks = [u8; (NUM_RANKS + 1) as usize];
+// Compares results with and without peeking at upcoming cards on the same
+// deals.
+struct PeekLift {
+    baseline_counts: Vec<u64>,
+    baseline_score: f64,
+    peek_score: f64,
+    // Ranks straights skip over, see `skip_in_straights`.
+    skipped: u16,
+}
 
-fn ranks_for_straight(cards: &[Card]) -> Ranks {
-    let mut ret = Ranks::default();
-    for c in cards {
-        ret[c.rank as usize + 1] = 1;
+impl PeekLift {
+    fn new(num_hands: usize, skipped: u16) -> Self {
+        Self {
+            baseline_counts: vec![0; num_hands],
+            baseline_score: 0.0,
+            peek_score: 0.0,
+            skipped,
+        }
     }
-    ret[0] = *ret.last().unwrap();
-    ret
-}
 
-fn suit_counts(cards: &[Card]) -> RankCounts {
-    let mut ret = RankCounts::default();
-    for c in cards {
-        ret[c.suit as usize] += 1;
+    fn record(
+        &mut self,
+        counts: &[HandCount],
+        hand_size: usize,
+        baseline: (&[Card], u8),
+        peek: (&[Card], u8),
+    ) {
+        for (c, baseline_count) in counts.iter().zip(&mut self.baseline_counts) {
+            if c.matches(baseline.0, baseline.1) {
+                *baseline_count += 1;
+            }
+        }
+        let score = |(cards, num_jokers): (&[Card], u8)| {
+            let best = evaluate_best_skipping(cards, num_jokers, hand_size, self.skipped);
+            let (chips, mult) = best.base_score();
+            (chips * mult) as f64
+        };
+        self.baseline_score += score(baseline);
+        self.peek_score += score(peek);
     }
-    ret
-}
 
-fn is_n_of_a_kind(cards: &[Card], n: u8, num_jokers: u8) -> bool {
-    let mut counts = <[u8; NUM_RANKS as usize]>::default();
-    for &c in cards {
-        let count = &mut counts[c.rank as usize];
-        *count += 1;
-        if *count + num_jokers >= n {
-            return true;
+    fn print(&self, counts: &[HandCount], num_iters: u64) {
+        let n = num_iters as f64;
+        println!(
+            "expected base score: {:.2} without peeking, {:.2} with peeking ({:+.2})",
+            self.baseline_score / n,
+            self.peek_score / n,
+            (self.peek_score - self.baseline_score) / n,
+        );
+        let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+        for (c, &baseline_count) in counts.iter().zip(&self.baseline_counts) {
+            let baseline = baseline_count as f64 / n;
+            let peek = c.count as f64 / n;
+            println!(
+                "{name: >width$}: {baseline:.6} -> {peek:.6} ({lift:+.6})",
+                name = c.name,
+                width = max_str_len,
+                lift = peek - baseline,
+            );
         }
     }
-    num_jokers >= n
 }
 
-fn is_n_and_m_of_a_kind(cards: &[Card], n: u8, m: u8, mut num_jokers: u8) -> bool {
-    assert!(n >= m);
-    let mut fill_with_jokers = |val: &mut u8, fill_to: u8| -> bool {
-        if *val >= fill_to {
-            return true;
+// How often a single play, and a round of plays, reaches a target score.
+struct ScoreTarget {
+    target: u32,
+    single_hits: u64,
+    round_hits: u64,
+    money: u64,
+}
+
+impl ScoreTarget {
+    fn new(target: u32) -> Self {
+        Self {
+            target,
+            single_hits: 0,
+            round_hits: 0,
+            money: 0,
         }
-        if *val + num_jokers < fill_to {
-            return false;
+    }
+
+    fn record(&mut self, round: &RoundResult) {
+        if round.scores.first().is_some_and(|&s| s >= self.target) {
+            self.single_hits += 1;
         }
-        num_jokers -= fill_to - *val;
-        *val = fill_to;
-        true
-    };
-    let mut rank_counts = rank_counts(cards);
-    // FIXME: no need to sort, just find two largest values
-    rank_counts.sort_by(|a, b| b.cmp(a));
-    if !fill_with_jokers(&mut rank_counts[0], n) {
-        return false;
+        if round.scores.iter().sum::<u32>() >= self.target {
+            self.round_hits += 1;
+        }
+        self.money += round.gold_money as u64;
     }
-    rank_counts[0] -= n;
-    if fill_with_jokers(&mut rank_counts[0], m) {
-        return true;
+
+    fn print(&self, num_iters: u64, plays: usize, method: CiMethod) {
+        let (p, ci) = interval_with(method, num_iters, self.single_hits);
+        println!("P(one hand scores >= {}): {p:.6} ± {ci:.6}", self.target);
+        if plays > 1 {
+            let (p, ci) = interval_with(method, num_iters, self.round_hits);
+            println!(
+                "P({plays} hands score >= {} in total): {p:.6} ± {ci:.6}",
+                self.target
+            );
+        }
+        if self.money > 0 {
+            println!(
+                "expected money from held gold cards: ${:.3}",
+                self.money as f64 / num_iters as f64
+            );
+        }
     }
-    fill_with_jokers(&mut rank_counts[1], m)
 }
 
-fn is_full_house(cards: &[Card], num_jokers: u8) -> bool {
-    is_n_and_m_of_a_kind(cards, 3, 2, num_jokers)
+#[derive(Parser)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    sim: SimArgs,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Evaluate specified hands instead of simulating random draws
+    Eval(EvalArgs),
+    /// Answer evaluation requests over a socket, see serve.rs for the protocol
+    Serve(ServeArgs),
+    /// Estimate how hand probabilities change as the deck is trimmed
+    Sensitivity(SensitivityArgs),
+    /// Distribution of deals needed to make a hand a number of times
+    Until(UntilArgs),
+    /// Deal until each comparison like "flush_house>straight_flush" is
+    /// settled at the requested confidence, see sequential.rs for the test
+    Test(TestArgs),
+    /// Hand probabilities answered instantly from a precomputed table for
+    /// common configurations, simulating anything else
+    Prob(ProbArgs),
+    /// Exact hypergeometric probabilities for simple draw questions
+    Hyper(HyperArgs),
+    /// Chance that drawing to a held hand makes --target, both enumerated
+    /// and sampled, and which cards are outs
+    Outs(OutsArgs),
+    /// Iterations needed to estimate a probability to a given precision, or
+    /// the precision a number of iterations buys
+    Samplesize(SamplesizeArgs),
+    /// Time every hand detector on a fixed seeded workload, to catch
+    /// evaluators getting slower
+    Bench(BenchArgs),
+    /// Split a simulation or exact enumeration across `serve` workers
+    Coordinate(CoordinateArgs),
+    /// Combine result files written by --output
+    Merge(MergeArgs),
+    /// Print the version, command, seed and so on recorded in an output file
+    Info(InfoArgs),
+    /// Re-run a short simulation whenever a scenario file changes, see
+    /// scenario.rs for the format
+    Watch(WatchArgs),
+    /// Edit a deck card by card, re-simulating it after every change, see
+    /// editor.rs for the commands
+    Edit(EditArgs),
+    /// Guess hand probabilities, then see the answers and how calibrated you are
+    Quiz(QuizArgs),
+    /// Name the best hand in random draws, for practice reading hands
+    Trainer(TrainerArgs),
+    /// Expected money earned per round from the deck's gold cards and seals
+    Economy(EconomyArgs),
+    /// Write every canonical 5-card hand class with its rank, strongest first
+    Classes(ClassesArgs),
+    /// Run an experiment written as a Rhai script, see script.rs for the API
+    #[cfg(feature = "scripting")]
+    Run(RunArgs),
 }
 
-fn is_two_triplet(cards: &[Card], num_jokers: u8) -> bool {
-    is_n_and_m_of_a_kind(cards, 3, 3, num_jokers)
+#[derive(clap::Args)]
+struct EconomyArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Hands played per round
+    #[arg(long, default_value_t = 4)]
+    plays: usize,
+
+    /// Rounds of discarding before the first play
+    #[arg(long, default_value_t = 0)]
+    discards: usize,
+
+    /// How to choose discards: keep-best, greedy (keep the best made hand) or
+    /// target:<hand>
+    #[arg(long, default_value = "keep-best")]
+    discard_strategy: String,
+
+    /// Blind score which ends the round early, paying for each unused hand
+    #[arg(long)]
+    score_target: Option<u32>,
+
+    /// Gold cards, which earn $3 if held at the end of the round
+    #[arg(long, num_args = 1..)]
+    gold: Vec<Card>,
+
+    /// Cards with gold seals, which earn $3 when they score
+    #[arg(long, num_args = 1..)]
+    gold_seal: Vec<Card>,
+
+    /// Money held at the start of the round, for interest
+    #[arg(long, default_value_t = 0)]
+    money: u32,
+
+    #[arg(long, default_value_t = 1000000)]
+    iters: u64,
 }
 
-fn is_full_mansion(cards: &[Card], num_jokers: u8) -> bool {
-    is_n_and_m_of_a_kind(cards, 4, 2, num_jokers)
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ClassesFormat {
+    /// rank,category,ranks,example rows
+    Csv,
+    /// Fixed-size records, see classes.rs for the layout
+    Binary,
 }
 
-fn is_flush_mansion(cards: &[Card], num_jokers: u8) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct ClassesArgs {
+    /// Also include hands which need more than one deck, like flush houses
+    #[arg(long)]
+    extended: bool,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    #[arg(long, value_enum, default_value_t = ClassesFormat::Csv)]
+    format: ClassesFormat,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_full_mansion(cards, num_jokers))
+    /// Write here instead of to stdout
+    #[arg(short, long)]
+    output: Option<std::path::PathBuf>,
 }
 
-fn is_flush_two_triplet(cards: &[Card], num_jokers: u8) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct QuizArgs {
+    /// Questions to ask [default: until q or end of input]
+    #[arg(long)]
+    rounds: Option<u64>,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    /// Where to keep every guess for tracking calibration [default:
+    /// ~/.poker-quiz.json]
+    #[arg(long)]
+    stats: Option<std::path::PathBuf>,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_two_triplet(cards, num_jokers))
+    /// Deals simulated for each answer
+    #[arg(long, default_value_t = 200_000, value_parser = clap::value_parser!(u64).range(1..))]
+    iters: u64,
+
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
-fn is_flush_three_pair(cards: &[Card], num_jokers: u8) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct TrainerArgs {
+    /// Hands to show [default: until q or end of input]
+    #[arg(long)]
+    rounds: Option<u64>,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    #[arg(long, default_value_t = 5)]
+    min_cards: usize,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_three_pair(cards, num_jokers))
+    #[arg(long, default_value_t = 8)]
+    max_cards: usize,
+
+    #[arg(long, default_value_t = 1)]
+    decks: usize,
+
+    #[arg(long, default_value_t = 0)]
+    jokers: u8,
+
+    /// Make N cards wild, as for simulations
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    wild: usize,
+
+    #[arg(long, default_value_t = 5)]
+    hand_size: usize,
+
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
-fn is_n_pairs(cards: &[Card], n: u8, mut num_jokers: u8) -> bool {
-    let mut num_pairs = 0;
-    for i in rank_counts(cards) {
-        if i % 2 == 1 && num_jokers > 0 {
-            num_jokers -= 1;
-            num_pairs += 1;
-        }
-        num_pairs += i / 2;
-    }
-    num_pairs + num_jokers / 2 >= n
+#[derive(clap::Args)]
+struct InfoArgs {
+    /// A result file from --output, or a table from --history or --features
+    file: std::path::PathBuf,
 }
-fn is_two_pair(cards: &[Card], num_jokers: u8) -> bool {
-    is_n_pairs(cards, 2, num_jokers)
+
+#[derive(clap::Args)]
+struct WatchArgs {
+    /// Simulation options as "key = value" lines, like "cards = 8"
+    file: std::path::PathBuf,
+
+    /// Iterations per run, unless the scenario sets max_iters
+    #[arg(long, default_value_t = 200000)]
+    iters: u64,
+
+    /// Seed every run the same, unless the scenario sets one, so that
+    /// changes between runs come from edits rather than sampling noise
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
-fn is_three_pair(cards: &[Card], num_jokers: u8) -> bool {
-    is_n_pairs(cards, 3, num_jokers)
+#[derive(clap::Args)]
+struct EditArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Deals to simulate for each version of the deck
+    #[arg(long, default_value_t = 200000)]
+    iters: u64,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Which hands to count, instead of the session's
+    #[arg(long, value_enum)]
+    rules: Option<RuleSet>,
+
+    /// Keep the deck, rules and recent results in this named session under
+    /// ~/.poker-sessions, restoring them on the next launch. The session's
+    /// deck is used unless --deck-file is given
+    #[arg(long, value_name = "NAME", default_value = "default")]
+    session: String,
+
+    /// Don't restore or save a session
+    #[arg(long)]
+    no_session: bool,
 }
 
-fn is_flush(cards: &[Card], num_jokers: u8, flush_size: u8) -> bool {
-    suit_counts(cards)
-        .iter()
-        .any(|&c| c + num_jokers >= flush_size)
+#[derive(clap::Args)]
+struct MergeArgs {
+    /// Result files to combine, which must share a configuration
+    #[arg(required = true)]
+    inputs: Vec<std::path::PathBuf>,
+
+    /// Write the combined result here instead of printing it
+    #[arg(short, long)]
+    output: Option<std::path::PathBuf>,
+
+    /// How to print the combined probabilities
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+
+    /// Show the change in each hand's probability from a result file saved
+    /// by an earlier run, starred when significant
+    #[arg(long, value_name = "FILE", conflicts_with = "output")]
+    baseline: Option<std::path::PathBuf>,
 }
 
-fn is_straight(cards: &[Card], num_jokers: u8, straight_size: usize) -> bool {
-    let ranks = ranks_for_straight(cards);
-    let mut window_sum = ranks.iter().take(straight_size).sum::<u8>();
-    if window_sum + num_jokers == straight_size as u8 {
-        return true;
-    }
-    for i in straight_size..ranks.len() {
-        window_sum -= ranks[i - straight_size];
-        window_sum += ranks[i];
-        if window_sum + num_jokers == straight_size as u8 {
-            return true;
-        }
-    }
-    false
+#[cfg(feature = "scripting")]
+#[derive(clap::Args)]
+struct RunArgs {
+    /// Script to run
+    script: std::path::PathBuf,
 }
 
-fn is_straight_flush(cards: &[Card], num_jokers: u8, size: usize) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct CoordinateArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    /// Comma-separated worker addresses, e.g. host1:7777,host2:7777
+    #[arg(long, value_delimiter = ',', required = true)]
+    workers: Vec<String>,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_straight(cards, num_jokers, size))
+    /// Total deals to simulate
+    #[arg(long, default_value_t = 100000000)]
+    iters: u64,
+
+    /// Deals per request sent to a worker
+    #[arg(long, default_value_t = 10000000)]
+    chunk: u64,
+
+    /// Enumerate every possible draw instead of sampling
+    #[arg(long)]
+    exact: bool,
+
+    /// With --exact, also print each probability as an exact fraction
+    #[arg(long, requires = "exact")]
+    fractions: bool,
+
+    /// Write the final counts to this JSON file, see `merge`
+    #[arg(long, conflicts_with = "exact")]
+    output: Option<std::path::PathBuf>,
 }
 
-fn is_flush_house(cards: &[Card], num_jokers: u8) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct ProbArgs {
+    #[arg(long, default_value_t = 7)]
+    cards: u8,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    #[arg(long, default_value_t = 1)]
+    decks: u8,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_full_house(cards, num_jokers))
+    #[arg(long, default_value_t = 0)]
+    jokers: u8,
+
+    #[arg(long, default_value_t = 5)]
+    hand_size: u8,
+
+    /// Iterations to simulate configurations outside the table
+    #[arg(long, default_value_t = 10_000_000)]
+    iters: u64,
+
+    /// Regenerate the table as Rust source at PATH (src/prob_table_data.rs)
+    #[arg(long, value_name = "PATH")]
+    write_table: Option<std::path::PathBuf>,
+
+    /// Iterations per configuration for --write-table
+    #[arg(long, default_value_t = 10_000_000, requires = "write_table")]
+    table_iters: u64,
 }
 
-fn is_flush_n(cards: &[Card], n: u8, num_jokers: u8) -> bool {
-    let mut cards_by_suit = <[arrayvec::ArrayVec<Card, MAX_CARDS>; NUM_SUITS as usize]>::default();
+#[derive(clap::Args)]
+struct BenchArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
 
-    for &c in cards {
-        cards_by_suit[c.suit as usize].push(c);
-    }
+    /// Which hands to time, as for simulating
+    #[arg(long, visible_alias = "rules", value_enum, default_value_t = RuleSet::Independent)]
+    mode: RuleSet,
 
-    cards_by_suit
-        .iter()
-        .any(|cards| is_n_of_a_kind(cards, n, num_jokers))
+    /// Timed deals, split across the samples, for all hands together and
+    /// again for each hand
+    #[arg(long, default_value_t = 10_000_000,
+          value_parser = clap::value_parser!(u64).range(1..))]
+    iters: u64,
+
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    samples: u64,
+
+    /// Untimed deals before each measurement
+    #[arg(long, default_value_t = 1_000_000)]
+    warmup: u64,
+
+    /// Seed for the deals, which are the same for the same seed
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum CardOrJoker {
-    Card(Card),
-    Joker,
+#[derive(clap::Args)]
+struct SamplesizeArgs {
+    /// Probability to plan for, which can be repeated. Without it, every
+    /// hand of --cards, --decks, --jokers and --hand-size whose probability
+    /// is known without simulating
+    #[arg(long)]
+    p: Vec<f64>,
+
+    #[arg(long, default_value_t = 7)]
+    cards: u8,
+
+    #[arg(long, default_value_t = 1)]
+    decks: u8,
+
+    #[arg(long, default_value_t = 0)]
+    jokers: u8,
+
+    #[arg(long, default_value_t = 5)]
+    hand_size: u8,
+
+    /// Target half-width of the confidence interval, as a fraction of the
+    /// probability
+    #[arg(long, required_unless_present = "iters", conflicts_with = "iters")]
+    rel_error: Option<f64>,
+
+    /// Instead, report the precision this many iterations buy
+    #[arg(long)]
+    iters: Option<u64>,
+
+    /// Confidence level of the interval
+    #[arg(long, default_value_t = 0.99)]
+    confidence: f64,
 }
 
-#[derive(Parser)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[derive(clap::Args)]
+struct HyperArgs {
+    /// Cards in the deck
+    #[arg(long, default_value_t = 52)]
+    deck: u64,
+
+    /// Cards in the deck that count as a success
+    #[arg(long)]
+    successes: u64,
+
+    /// Cards drawn
+    #[arg(long)]
+    draw: u64,
+
+    /// Number of successes wanted
+    #[arg(long)]
+    need: Option<u64>,
+}
+
+// The deck comes from the deck options, but the cards come from --hand and
+// --draw rather than --cards.
+#[derive(clap::Args)]
+struct OutsArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// The cards held, e.g. "Ah Kh 7h 2h" (J is a joker)
+    #[arg(long)]
+    hand: String,
+
+    /// Hand to make
+    #[arg(long)]
+    target: HandRank,
+
+    /// Replacement cards drawn to the held ones
+    #[arg(long, default_value_t = 1)]
+    draw: usize,
+
+    /// Random draws for the sampled estimate
+    #[arg(long, default_value_t = 100_000)]
+    iters: u64,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(clap::Args)]
+struct UntilArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Hand to wait for
+    #[arg(long)]
+    hand: HandRank,
+
+    /// Number of times the hand must be made
+    #[arg(long, default_value_t = 1)]
+    successes: u64,
+
+    /// Number of times to repeat the experiment
+    #[arg(long, default_value_t = 10000)]
+    trials: u64,
+
+    /// Give up on a trial after this many deals
+    #[arg(long, default_value_t = 10000000)]
+    max_deals: u64,
+}
+
+#[derive(clap::Args)]
+struct TestArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Which hands to count
+    #[arg(long, value_enum, default_value_t = RuleSet::Independent)]
+    rules: RuleSet,
+
+    /// Hypothesis that one hand is more likely than another, like
+    /// "flush_house>straight_flush", naming hands by id or by name. Either
+    /// side can instead be a probability objective, prob:<hand> or
+    /// score>=<target>, scoring one play with no card effects. Can be
+    /// repeated
+    #[arg(long, value_name = "A>B", required = true)]
+    greater: Vec<String>,
+
+    /// Chance that every verdict is right, split evenly between the
+    /// comparisons
+    #[arg(long, default_value_t = 0.99)]
+    confidence: f64,
+
+    /// Give up on comparisons still undecided after this many deals
+    #[arg(long, default_value_t = 100000000)]
+    max_deals: u64,
+}
+
+#[derive(clap::Args)]
+struct SensitivityArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Which cards to trim first
+    #[arg(long, value_enum, default_value_t = ThinOrder::LowRanks)]
+    order: ThinOrder,
+
+    /// Cards removed per level
+    #[arg(long, default_value_t = 4)]
+    step: usize,
+
+    /// Number of trimmed deck sizes to compare against the full deck
+    #[arg(long, default_value_t = 3)]
+    levels: usize,
+
+    #[arg(long, default_value_t = 1000000)]
+    iters: u64,
+
+    /// Also sweep the mean of an objective: prob:<hand>, score,
+    /// score>=<target> or gold, scoring one play with no card effects
+    #[arg(long)]
+    objective: Vec<String>,
+
+    /// Also write a row per deck size of every hand's probability and
+    /// objective's mean to this file, as CSV or, with the arrow feature,
+    /// .arrow or .parquet by extension
+    #[arg(long, value_name = "PATH")]
+    table: Option<std::path::PathBuf>,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Protocol {
+    /// Length-prefixed binary frames
+    Binary,
+}
+
+#[derive(clap::Args)]
+struct ServeArgs {
+    #[arg(long, value_enum, default_value_t = Protocol::Binary)]
+    proto: Protocol,
+
+    /// TCP address to listen on
+    #[arg(long, default_value = "127.0.0.1:7777")]
+    listen: String,
+
+    /// Listen on a Unix socket at this path instead of TCP
+    #[cfg(unix)]
+    #[arg(long)]
+    unix: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct EvalArgs {
+    /// Hands to evaluate, e.g. "As Kd Th 2c J" (J is a joker)
+    hands: Vec<String>,
+
+    /// Read one hand per line from stdin
+    #[arg(long)]
+    stdin: bool,
+
+    /// Read one hand per line from a file
+    #[arg(long)]
+    file: Option<std::path::PathBuf>,
+
+    /// Worker threads for --stdin/--file (defaults to available parallelism)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Report throughput on stderr after each chunk of hands
+    #[arg(long)]
+    progress: bool,
+
+    #[arg(long, default_value_t = 5)]
+    hand_size: usize,
+
+    /// Also report the best hand that can be played
+    #[arg(long)]
+    best: bool,
+}
+
+// Deck and hand configuration shared by every simulating command.
+#[derive(clap::Args)]
+struct DeckArgs {
     #[arg(long, default_value_t = 7)]
     cards: usize,
 
@@ -224,1361 +777,3084 @@ struct Args {
 
     #[arg(long, default_value_t = 5)]
     hand_size: usize,
-}
-
-fn confidence_interval(total_iters: u64, num_true: u64) -> (f64, f64) {
-    let p = num_true as f64 / total_iters as f64;
-    // 99.73% confidence interval according to https://sigmazone.com/binomial-confidence-intervals/
-    let ci = 3.0 * (p * (1.0 - p) / total_iters as f64).sqrt();
-    (p, ci)
-}
 
-struct HandCount {
-    name: &'static str,
-    count: u64,
-    func: fn(&[Card], u8) -> bool,
+    /// Read the deck from a file of card counts like "As x3", "Kh x0" and
+    /// "2 jokers", instead of using --decks and --jokers
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["decks", "jokers"])]
+    deck_file: Option<std::path::PathBuf>,
+
+    /// Which cards each deck has: standard, short36 (6 and up), short32 (7
+    /// and up), pinochle (two of each card from 9 up) or abandoned (no face
+    /// cards)
+    #[arg(
+        long,
+        value_name = "PRESET",
+        default_value = "standard",
+        conflicts_with = "deck_file"
+    )]
+    deck_preset: Preset,
+
+    /// Let straights skip ranks missing from the deck, so that A-6-7-8-9 is
+    /// a straight in a short deck
+    #[arg(long)]
+    short_straights: bool,
+
+    /// Make N cards wild, counting as every suit: the first N of the deck,
+    /// 2c, 3c, 4c and so on
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    wild: usize,
 }
 
-impl HandCount {
-    fn new(name: &'static str, func: fn(&[Card], u8) -> bool) -> Self {
-        Self {
-            name,
-            count: 0,
-            func,
+impl DeckArgs {
+    fn deck(&self) -> Vec<CardOrJoker> {
+        let mut deck = match &self.deck_file {
+            Some(path) => poker::deck::read(path).unwrap_or_else(|e| {
+                println!("{e}");
+                std::process::exit(1);
+            }),
+            None => self.deck_preset.deck(self.decks, self.jokers),
+        };
+        if let Err(e) = make_wild(&mut deck, self.wild) {
+            println!("{e}");
+            std::process::exit(1);
         }
+        deck
     }
 
-    // TODO: write tests
-    fn overlap(&self, total_iters: u64, other: &HandCount) -> bool {
-        if self.count == 0 || other.count == 0 {
-            return false;
+    // Ranks straights skip over, for `skip_in_straights`.
+    fn skipped_in_straights(&self) -> u16 {
+        match self.short_straights {
+            true => skipped_ranks(poker::deck::missing_ranks(&self.deck())),
+            false => 0,
         }
-        let ci1 = confidence_interval(total_iters, self.count);
-        let ci2 = confidence_interval(total_iters, other.count);
-        let ci1_start = ci1.0 - ci1.1;
-        let ci1_end = ci1.0 + ci1.1;
-        let ci2_start = ci2.0 - ci2.1;
-        let ci2_end = ci2.0 + ci2.1;
-        ci1_start <= ci2_end && ci2_start <= ci1_end
     }
 }
 
-fn print_counts(counts: &[HandCount], num_iters: u64) {
-    let mut counts = counts.iter().collect::<Vec<_>>();
-    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
-    counts.sort_by_key(|c| (c.count, c.name));
-    counts.reverse();
-    for c in counts {
-        println!(
-            "{name: >width$}: {p:.6} ({count})",
-            name = c.name,
-            width = max_str_len,
-            p = (c.count as f64 / num_iters as f64),
-            count = c.count,
-        );
-    }
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ImpossibleHands {
+    /// Leave them out of the results with a warning
+    Warn,
+    /// Exit with an error
+    Error,
 }
 
-fn main() {
-    use rand::seq::SliceRandom;
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ScoreMode {
+    /// Balatro's chips times mult for each hand
+    Balatro,
+}
 
-    let args = Args::parse();
+#[derive(clap::Args)]
+struct SimArgs {
+    #[command(flatten)]
+    deck: DeckArgs,
+
+    /// Only track these hands, e.g. "straight,flush,full_house,5oak"
+    #[arg(long, value_name = "HANDS", value_delimiter = ',')]
+    hands: Vec<HandRank>,
+
+    /// Which hands a deal counts towards
+    #[arg(long, visible_alias = "rules", value_enum, default_value_t = RuleSet::Independent,
+          conflicts_with_all = ["min_rank", "output"])]
+    mode: RuleSet,
+
+    /// Only count a hand when made from cards of at least a rank, e.g.
+    /// "Pair=J" for jacks or better
+    #[arg(long, value_name = "HAND=RANK", value_parser = parse_min_rank)]
+    min_rank: Vec<(String, Rank)>,
+
+    /// Cards in a straight [default: --hand-size], e.g. 5 to still count
+    /// 5-card straights in 6-card hands
+    #[arg(long, value_name = "N", conflicts_with = "mode")]
+    straight_size: Option<usize>,
+
+    /// Cards in a flush [default: --hand-size]
+    #[arg(long, value_name = "N", conflicts_with = "mode")]
+    flush_size: Option<usize>,
+
+    /// What to do with hands that need more cards than --cards
+    #[arg(long, value_enum, default_value_t = ImpossibleHands::Warn)]
+    impossible_hands: ImpossibleHands,
+
+    /// Also report how often playing 6 cards beats the best 5-card hand
+    #[arg(long)]
+    compare_hand_sizes: bool,
+
+    /// Also report how often a deal holds two identical cards from different decks
+    #[arg(long)]
+    identical_cards: bool,
+
+    /// Make hands from distinct cards only, so identical cards from different
+    /// decks count once
+    #[arg(long, conflicts_with = "exact")]
+    distinct_cards: bool,
+
+    /// Cards in every draw, e.g. "Kh J" for the king of hearts and a joker,
+    /// with the rest of the draw random
+    #[arg(long, value_name = "CARDS",
+          conflicts_with_all = ["exact", "perturb", "random_deck", "boards", "hand"])]
+    guarantee: Option<String>,
+
+    /// Report first occurrence and gaps between occurrences for the N rarest hands
+    #[arg(long, value_name = "N")]
+    occurrences: Option<usize>,
+
+    /// Write a row of hand probabilities to this file after each batch, as
+    /// CSV or, with the arrow feature, .arrow or .parquet by extension
+    #[arg(long)]
+    history: Option<std::path::PathBuf>,
+
+    /// Write a row of every hand's count, probability and interval so far
+    /// to --snapshot-file every N iterations, for plotting convergence.
+    /// Appends to CSV files; .arrow or .parquet files, with the arrow
+    /// feature, are replaced
+    #[arg(long, value_name = "N", requires = "snapshot_file",
+          value_parser = clap::value_parser!(u64).range(1..))]
+    snapshot_every: Option<u64>,
+
+    #[arg(long, requires = "snapshot_every")]
+    snapshot_file: Option<std::path::PathBuf>,
+
+    /// Print the first N deals and the hands they satisfy, as text. Use
+    /// --features for a table of deals
+    #[arg(long, value_name = "N")]
+    dump_samples: Option<u64>,
+
+    /// Write per-deal features (rank and suit counts, straight window,
+    /// jokers) and 0/1 hand labels to this file, for training models. CSV,
+    /// or with the arrow feature, .arrow or .parquet by extension
+    #[arg(long, value_name = "PATH")]
+    features: Option<std::path::PathBuf>,
+
+    /// Only write features for the first N deals
+    #[arg(long, value_name = "N", requires = "features")]
+    feature_deals: Option<u64>,
+
+    /// Write the first N deals and the hands they satisfy to --log-file, as
+    /// text. Use --features for a table of deals
+    #[arg(long, value_name = "N", requires = "log_file")]
+    log_deals: Option<u64>,
+
+    #[arg(long, requires = "log_deals")]
+    log_file: Option<std::path::PathBuf>,
+
+    /// Seed for the random number generator, making runs reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Worker threads for simulating. Seeded runs give the same results with
+    /// any number [default: available parallelism, or 1 with options that
+    /// need every deal in order]
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Iterations between stopping-rule checks [default: about 1.5 seconds'
+    /// worth, or 1000000 with --seed so seeded runs stop at the same point]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    batch_size: Option<u64>,
+
+    /// Stop after N iterations even if the stopping rule hasn't been met
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_iters: Option<u64>,
+
+    /// Stop once every hand's 99.73% confidence interval is narrower than
+    /// ± WIDTH, instead of once no intervals overlap
+    #[arg(long, value_name = "WIDTH", value_parser = parse_ci_width)]
+    target_ci: Option<f64>,
+
+    /// How to compute confidence intervals, for reporting and for deciding
+    /// when to stop. Wilson and Clopper-Pearson stay accurate for hands seen
+    /// only a few times
+    #[arg(long, value_enum, default_value_t = CiMethod::Normal)]
+    ci_method: CiMethod,
+
+    /// Redraw a live table of probabilities and confidence intervals in place
+    /// after each batch, instead of printing the counts every time
+    #[arg(long)]
+    tui: bool,
+
+    /// Rewrite a fixed-layout binary snapshot of the run's progress to PATH
+    /// after each batch, for GUI frontends to mmap and poll (layout in
+    /// src/progress.rs)
+    #[arg(long, value_name = "PATH")]
+    progress_file: Option<std::path::PathBuf>,
+
+    /// After each batch, print the most likely order of the hands so far and
+    /// the chance it's right, from posterior samples of the counts
+    #[arg(long, conflicts_with = "tui")]
+    ranking: bool,
+
+    /// Posterior samples per --ranking estimate
+    #[arg(long, default_value_t = 1000, requires = "ranking")]
+    ranking_samples: usize,
+
+    /// Report correlations between each pair of hands
+    #[arg(long)]
+    correlations: bool,
+
+    /// Break straight flushes down by suit and high card, including royal
+    /// flushes
+    #[arg(long)]
+    sf_breakdown: bool,
+
+    /// Also report each hand's probability among only the deals making HAND,
+    /// e.g. how often a flush is also a straight flush
+    #[arg(long, value_name = "HAND")]
+    condition: Option<String>,
+
+    /// Report how two hands overlap, P(A and B), P(A | B) and P(B | A), e.g.
+    /// "Strt Flush,Flush". Repeatable
+    #[arg(long, value_name = "A,B")]
+    overlap: Vec<String>,
+
+    /// Rounds of discarding (up to 5 cards each) and redrawing before evaluating
+    #[arg(long, default_value_t = 0)]
+    discards: usize,
 
-    if args.cards > MAX_CARDS {
-        println!("Does not support more than {} cards", MAX_CARDS);
+    /// How to choose discards: keep-best, greedy (keep the best made hand) or
+    /// target:<hand>
+    #[arg(long, default_value = "keep-best")]
+    discard_strategy: String,
+
+    /// Let the discard strategy see the next N cards in the deck, and report
+    /// the improvement over not peeking
+    #[arg(long, default_value_t = 0)]
+    peek: usize,
+
+    /// Enumerate every possible draw instead of sampling
+    #[arg(long, conflicts_with_all = [
+        "compare_hand_sizes",
+        "identical_cards",
+        "occurrences",
+        "history",
+        "snapshot_every",
+        "dump_samples",
+        "log_deals",
+        "features",
+        "seed",
+        "batch_size",
+        "max_iters",
+        "target_ci",
+        "tui",
+        "progress_file",
+        "ranking",
+        "correlations",
+        "sf_breakdown",
+        "condition",
+        "overlap",
+        "discards",
+        "peek",
+        "derive",
+        "scores",
+        "score",
+        "score_target",
+        "objective",
+    ])]
+    exact: bool,
+
+    /// With --exact, also print each probability as an exact fraction
+    #[arg(long, requires = "exact")]
+    fractions: bool,
+
+    /// Instead, simulate N random variations of the deck, each with K random
+    /// cards removed or added, and report the spread of each probability
+    #[arg(long, value_name = "K:N", value_parser = parse_perturb,
+          conflicts_with_all = ["exact", "discards", "peek"])]
+    perturb: Option<(usize, usize)>,
+
+    /// Iterations per --perturb variation
+    #[arg(long, default_value_t = 100000, requires = "perturb")]
+    perturb_iters: u64,
+
+    /// Write a row per --perturb variation of every hand's probability to
+    /// this file, as CSV or, with the arrow feature, .arrow or .parquet by
+    /// extension
+    #[arg(long, value_name = "PATH", requires = "perturb")]
+    perturb_table: Option<std::path::PathBuf>,
+
+    /// Instead, generate random decks like "size=45,bias=flush" and report
+    /// the spread of each probability across them. Keys are size, bias
+    /// (none, flush, straight or pairs), weight of the favored cards and
+    /// jokers
+    #[arg(long, value_name = "SPEC",
+          conflicts_with_all = ["exact", "perturb", "discards", "peek", "deck_file"])]
+    random_deck: Option<RandomDeckSpec>,
+
+    /// Number of --random-deck decks
+    #[arg(long, default_value_t = 100, requires = "random_deck")]
+    random_decks: usize,
+
+    /// Iterations per --random-deck deck
+    #[arg(long, default_value_t = 100000, requires = "random_deck")]
+    random_deck_iters: u64,
+
+    /// Instead, deal N boards of --cards from the same deck each iteration
+    /// and report how often a hand is made on some or every board. With
+    /// --players, deal N boards of --board-size, as in double-board bomb
+    /// pots, and report each player's share of the pot, breaking ties by
+    /// kickers
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(2..),
+          conflicts_with_all = ["exact", "perturb", "random_deck", "discards", "peek"])]
+    boards: Option<u64>,
+
+    /// Iterations for --boards
+    #[arg(long, default_value_t = 1000000, requires = "boards")]
+    board_iters: u64,
+
+    /// Instead, deal N players --cards each from the same deck each
+    /// iteration and report how often at least one of them makes a hand
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(2..),
+          conflicts_with_all = ["exact", "perturb", "random_deck", "discards", "peek"])]
+    players: Option<u64>,
+
+    /// Also report how often player K of --players makes a hand, counting
+    /// from 1
+    #[arg(long, value_name = "K", value_parser = clap::value_parser!(u64).range(1..),
+          requires = "players", conflicts_with = "boards")]
+    player: Option<u64>,
+
+    /// Iterations for --players
+    #[arg(long, default_value_t = 1000000, requires = "players")]
+    player_iters: u64,
+
+    /// Also deal K community cards, which make hands together with each
+    /// player's --cards, as in Hold'em with --cards 2 --board-size 5
+    #[arg(long, value_name = "K", default_value_t = 0, conflicts_with = "hand")]
+    board_size: usize,
+
+    /// Instead, print which hands these cards make, e.g. "As Kd Th 2c J"
+    /// (J is a joker)
+    #[arg(long, conflicts_with_all = ["exact", "perturb", "random_deck", "boards", "players"])]
+    hand: Option<String>,
+
+    /// Write the final counts to this JSON file, see `merge`
+    #[arg(long, conflicts_with = "exact")]
+    output: Option<std::path::PathBuf>,
+
+    /// How to print the final probabilities
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text, conflicts_with = "exact")]
+    format: ReportFormat,
+
+    /// Also send the results to KIND:PATH, repeatable. json, csv and
+    /// markdown files are written at the end; ndjson appends a result line
+    /// after every batch (ndjson:- for stdout) and prometheus rewrites a
+    /// textfile collector file after every batch
+    #[arg(long, value_name = "KIND:PATH", conflicts_with = "exact")]
+    out: Vec<SinkSpec>,
+
+    /// Rewrite a result file like --output's after every batch, replacing it
+    /// whole so that scripts tailing a long run always read a complete one
+    #[arg(long, value_name = "PATH", conflicts_with = "exact")]
+    live_file: Option<std::path::PathBuf>,
+
+    /// After the report, sum up one hand in a sentence for pasting elsewhere,
+    /// e.g. "Pair in 7 cards from 1 deck: 73.8% ± 0.1% (1 in 1.4)"
+    #[arg(long, value_name = "HAND", conflicts_with = "exact")]
+    headline: Option<String>,
+
+    /// Show the change in each hand's probability from a result file saved
+    /// with --output by an earlier run. One star marks a change at least 2
+    /// standard errors from zero, two stars at least 3
+    #[arg(long, value_name = "FILE", conflicts_with = "exact")]
+    baseline: Option<std::path::PathBuf>,
+
+    /// Report a quantity derived from two hands with its confidence
+    /// interval, e.g. "sf_ratio = straight_flush / flush"
+    #[arg(long, value_parser = parse_derived)]
+    derive: Vec<Derived>,
+
+    /// Report the distribution of each deal's best hand base score
+    #[arg(long)]
+    scores: bool,
+
+    /// Report the expected score of playing each deal's highest scoring hand
+    #[arg(long, value_enum)]
+    score: Option<ScoreMode>,
+
+    /// TOML file of chips and mult for leveled up hands, for --score, e.g.
+    /// "[flush]" then "chips = 50" and "mult = 6"
+    #[arg(long, value_name = "PATH", requires = "score")]
+    score_table: Option<std::path::PathBuf>,
+
+    /// Report how often playing the best hand scores at least this many
+    /// chips times mult
+    #[arg(long)]
+    score_target: Option<u32>,
+
+    /// Hands played per round for --score-target and --objective,
+    /// refilling from the deck after each. With --score-target, also report
+    /// how often the round's total reaches it
+    #[arg(long, default_value_t = 1)]
+    plays: usize,
+
+    /// Steel cards, e.g. "--steel Ks Kh", which multiply mult by 1.5 while
+    /// held in hand
+    #[arg(long, num_args = 1..)]
+    steel: Vec<Card>,
+
+    /// Gold cards which earn $3 if held at the end of the round
+    #[arg(long, num_args = 1..)]
+    gold: Vec<Card>,
+
+    /// Report the mean of an objective: prob:<hand>, score, score>=<target>
+    /// or gold
+    #[arg(long)]
+    objective: Vec<String>,
+}
+
+// Options which affect simulated probabilities, recorded in result files.
+fn run_config(
+    deck: &DeckArgs,
+    discards: usize,
+    discard_strategy: &str,
+    peek: usize,
+) -> serde_json::Value {
+    let mut config = serde_json::json!({
+        "cards": deck.cards,
+        "decks": deck.decks,
+        "jokers": deck.jokers,
+        "hand_size": deck.hand_size,
+        "discards": discards,
+        "discard_strategy": discard_strategy,
+        "peek": peek,
+    });
+    // Only when used, so results saved before --wild existed still merge.
+    if deck.wild > 0 {
+        config["wild"] = deck.wild.into();
+    }
+    if deck.deck_preset != Preset::Standard {
+        config["deck_preset"] = deck.deck_preset.id().into();
+    }
+    if deck.short_straights {
+        config["short_straights"] = true.into();
+    }
+    if deck.deck_file.is_some() {
+        let config = config.as_object_mut().unwrap();
+        config.remove("decks");
+        config.remove("jokers");
+        config.insert("deck".into(), poker::deck::describe(&deck.deck()).into());
+    }
+    config
+}
+
+// Creates a table file for an observer, starting it with `metadata`.
+fn create_table(
+    path: &std::path::Path,
+    metadata: &poker::metadata::Metadata,
+) -> Box<dyn poker::table::TableSink> {
+    let mut table = poker::table::create(path).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    if let Err(e) = table.write_metadata(metadata) {
+        println!("Could not write {}: {e}", path.display());
         std::process::exit(1);
     }
+    table
+}
 
-    let mut rng = rand::thread_rng();
-    let mut deck = Vec::new();
-    for _ in 0..args.decks {
-        for suit in 0..NUM_SUITS {
-            for rank in 0..NUM_RANKS {
-                deck.push(CardOrJoker::Card(Card { suit, rank }));
-            }
+// Writes a whole table at once, for results that aren't built up row by row.
+fn write_table(
+    path: &std::path::Path,
+    metadata: &poker::metadata::Metadata,
+    columns: &[(String, ColumnType)],
+    rows: impl Iterator<Item = Vec<f64>>,
+) {
+    let mut table = create_table(path, metadata);
+    let result = table.write_header(columns).and_then(|()| {
+        for row in rows {
+            table.write_row(&row)?;
         }
+        table.finish()
+    });
+    if let Err(e) = result {
+        println!("Could not write {}: {e}", path.display());
+        std::process::exit(1);
     }
-    for _ in 0..args.jokers {
-        deck.push(CardOrJoker::Joker);
-    }
-
-    let mut counts = Vec::new();
-    counts.push(HandCount::new("Pair", |cards, num_jokers| {
-        is_n_of_a_kind(cards, 2, num_jokers)
-    }));
-    counts.push(HandCount::new("3oak", |cards, num_jokers| {
-        is_n_of_a_kind(cards, 3, num_jokers)
-    }));
-    counts.push(HandCount::new("4oak", |cards, num_jokers| {
-        is_n_of_a_kind(cards, 4, num_jokers)
-    }));
-    counts.push(HandCount::new("5oak", |cards, num_jokers| {
-        is_n_of_a_kind(cards, 5, num_jokers)
-    }));
-    counts.push(HandCount::new("2 pair", is_two_pair));
-    counts.push(HandCount::new("Full House", is_full_house));
+}
 
-    if args.hand_size == 5 {
-        counts.push(HandCount::new("Flush House", |cards, num_jokers| {
-            is_flush_house(cards, num_jokers)
-        }));
-        counts.push(HandCount::new("Strt Flush", |cards, num_jokers| {
-            is_straight_flush(cards, num_jokers, 5)
-        }));
-        counts.push(HandCount::new("Flush 5", |cards, num_jokers| {
-            is_flush_n(cards, 5, num_jokers)
-        }));
-    } else if args.hand_size == 6 {
-        counts.push(HandCount::new("3 pair", is_three_pair));
-        counts.push(HandCount::new("6oak", |cards, num_jokers| {
-            is_n_of_a_kind(cards, 6, num_jokers)
-        }));
-        counts.push(HandCount::new("2 triplet", is_two_triplet));
-        counts.push(HandCount::new("Straight", |cards, num_jokers| {
-            is_straight(cards, num_jokers, 6)
-        }));
-        counts.push(HandCount::new("Flush", |cards, num_jokers| {
-            is_flush(cards, num_jokers, 6)
-        }));
-        counts.push(HandCount::new("Full Mansion", is_full_mansion));
-        counts.push(HandCount::new("Flush Mansion", is_flush_mansion));
-        counts.push(HandCount::new("Flush 3 pair", is_flush_three_pair));
-        counts.push(HandCount::new("Flush 2 triplet", is_flush_two_triplet));
-        counts.push(HandCount::new("Strt Flush", |cards, num_jokers| {
-            is_straight_flush(cards, num_jokers, 6)
-        }));
-        counts.push(HandCount::new("Flush 6", |cards, num_jokers| {
-            is_flush_n(cards, 6, num_jokers)
-        }));
-    } else {
-        println!("--hand-size must be 5 or 6");
+fn write_result(path: &std::path::Path, result: &RunResult) {
+    if let Err(e) = result.write(path) {
+        println!("Could not write {e}");
         std::process::exit(1);
     }
+}
 
-    let mut num_iters: u64 = 0;
+// Applies --min-rank options to the hands they name.
+fn apply_min_ranks(counts: &mut [HandCount], min_ranks: &[(String, Rank)]) {
+    for (name, rank) in min_ranks {
+        let Some(c) = counts
+            .iter_mut()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+        else {
+            let names = counts.iter().map(|c| c.name).collect::<Vec<_>>();
+            println!(
+                "Unknown hand '{name}', expected one of: {}",
+                names.join(", ")
+            );
+            std::process::exit(1);
+        };
+        c.set_min_rank(*rank);
+    }
+}
 
-    loop {
-        for _ in 0..1000000 {
-            let cards_or_jokers = deck
-                .choose_multiple(&mut rng, args.cards)
-                .copied()
-                .collect::<arrayvec::ArrayVec<CardOrJoker, MAX_CARDS>>();
-            let num_jokers = cards_or_jokers
-                .iter()
-                .filter(|&&coj| coj == CardOrJoker::Joker)
-                .count() as u8;
-            let cards = cards_or_jokers
-                .iter()
-                .filter_map(|coj| match coj {
-                    CardOrJoker::Card(c) => Some(*c),
-                    CardOrJoker::Joker => None,
-                })
-                .collect::<arrayvec::ArrayVec<Card, MAX_CARDS>>();
-            for c in &mut counts {
-                if (c.func)(&cards, num_jokers) {
-                    c.count += 1;
-                }
-            }
-            num_iters += 1;
-        }
-        let mut has_overlap = false;
-        'outer: for (idx, c1) in counts.iter().enumerate() {
-            for c2 in counts.iter().skip(idx + 1) {
-                has_overlap = c1.overlap(num_iters, c2);
-                if has_overlap {
-                    break 'outer;
-                }
+// Drops hands that can't be made from `num_cards` cards, which would
+// otherwise be reported as never happening, returning the warning to record.
+fn remove_impossible_hands(
+    counts: &mut Vec<HandCount>,
+    num_cards: usize,
+    mode: ImpossibleHands,
+) -> Option<Warning> {
+    let impossible = counts
+        .iter()
+        .filter(|c| !c.possible_with(num_cards))
+        .map(|c| c.name)
+        .collect::<Vec<_>>();
+    if impossible.is_empty() {
+        return None;
+    }
+    let list = impossible.join(", ");
+    match mode {
+        ImpossibleHands::Warn => {
+            println!("warning: skipping {list}, which need more than {num_cards} cards");
+            counts.retain(|c| c.possible_with(num_cards));
+            // Reports are laid out by hand, so there'd be nothing to show.
+            if counts.is_empty() {
+                println!("No hands left to track with {num_cards} cards");
+                std::process::exit(1);
             }
+            Some(Warning::ImpossibleHands {
+                hands: impossible.iter().map(|h| h.to_string()).collect(),
+                cards: num_cards as u64,
+            })
         }
-        println!("{num_iters} iterations...");
-        if !has_overlap {
-            break;
+        ImpossibleHands::Error => {
+            println!("{list} need more than {num_cards} cards");
+            std::process::exit(1);
         }
-        print_counts(&counts, num_iters);
-        println!("--------------");
     }
-    println!("(no overlapping 99% confidence intervals)");
-    println!("total iterations: {num_iters}");
-    print_counts(&counts, num_iters);
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    const R2: u8 = 0;
-    const R3: u8 = 1;
-    const R4: u8 = 2;
-    const R5: u8 = 3;
-    const R6: u8 = 4;
-    const R7: u8 = 5;
-    const R8: u8 = 6;
-    const R9: u8 = 7;
-    const R10: u8 = 8;
-    const RJ: u8 = 9;
-    const RQ: u8 = 10;
-    const RK: u8 = 11;
-    const RA: u8 = 12;
-
-    #[test]
-    fn test_rank_counts() {
-        assert_eq!(RankCounts::default(), rank_counts(&[]));
+}
 
-        {
-            let mut expected = RankCounts::default();
-            expected[1] = 2;
-            expected[3] = 1;
-            assert_eq!(
-                expected,
-                rank_counts(&[
-                    Card { suit: 0, rank: 1 },
-                    Card { suit: 0, rank: 1 },
-                    Card { suit: 2, rank: 3 }
-                ])
-            )
-        }
+fn print_counts(counts: &[HandCount], num_iters: u64, method: CiMethod) {
+    let mut result = RunResult::new(serde_json::Value::Null, counts, num_iters);
+    result.set_ci_method(method);
+    print!("{}", result.to_text(None));
+}
+
+// Reads a result file to compare a run with `config` against, warning about
+// settings which differ between them.
+fn read_baseline(path: &std::path::Path, config: &serde_json::Value) -> RunResult {
+    let baseline = RunResult::read(path).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    let differences = baseline.config_differences(config);
+    if !differences.is_empty() {
+        println!("note: baseline settings differ: {}", differences.join(", "));
     }
+    baseline
+}
 
-    #[test]
-    fn test_ranks_for_straight() {
-        assert_eq!(Ranks::default(), ranks_for_straight(&[]));
+// Prints analytic estimates, most likely first, with hands that have none at
+// the end.
+fn print_estimates(counts: &[HandCount], estimates: &[Option<f64>]) {
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut rows = counts.iter().zip(estimates).collect::<Vec<_>>();
+    rows.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
+    for (c, estimate) in rows {
+        let p = match estimate {
+            Some(p) => format!("{p:.6}"),
+            None => "?".to_string(),
+        };
+        println!("{name: >width$}: {p}", name = c.name, width = max_str_len);
+    }
+}
 
-        {
-            let mut expected = Ranks::default();
-            expected[2] = 1;
-            expected[4] = 1;
-            assert_eq!(
-                expected,
-                ranks_for_straight(&[
-                    Card { suit: 0, rank: 1 },
-                    Card { suit: 0, rank: 1 },
-                    Card { suit: 2, rank: 3 }
-                ])
-            )
+// Prints when each of the `n` rarest hands was first seen and the spacing
+// between its occurrences.
+fn print_occurrences(counts: &[HandCount], n: usize) {
+    let mut counts = counts.iter().collect::<Vec<_>>();
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    counts.sort_by_key(|c| (c.count, c.name));
+    for c in counts.into_iter().take(n) {
+        let Some(first_seen) = c.first_seen else {
+            println!(
+                "{name: >width$}: never seen",
+                name = c.name,
+                width = max_str_len
+            );
+            continue;
+        };
+        let mean_gap = c
+            .mean_gap()
+            .map_or_else(|| "-".to_string(), |g| format!("{g:.1}"));
+        println!(
+            "{name: >width$}: first at {first_seen}, mean gap {mean_gap}, max gap {max_gap}",
+            name = c.name,
+            width = max_str_len,
+            max_gap = c.max_gap,
+        );
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    match args.command {
+        Some(Command::Eval(eval_args)) => eval(eval_args),
+        Some(Command::Serve(serve_args)) => serve(serve_args),
+        Some(Command::Sensitivity(sensitivity_args)) => sensitivity(sensitivity_args),
+        Some(Command::Until(until_args)) => until(until_args),
+        Some(Command::Test(test_args)) => test(test_args),
+        Some(Command::Prob(prob_args)) => prob(prob_args),
+        Some(Command::Hyper(hyper_args)) => hyper(hyper_args),
+        Some(Command::Outs(outs_args)) => outs(outs_args),
+        Some(Command::Samplesize(samplesize_args)) => samplesize(samplesize_args),
+        Some(Command::Bench(bench_args)) => bench(bench_args),
+        Some(Command::Coordinate(coordinate_args)) => coordinate(coordinate_args),
+        Some(Command::Merge(merge_args)) => merge(merge_args),
+        Some(Command::Info(info_args)) => info(info_args),
+        Some(Command::Watch(watch_args)) => watch(watch_args),
+        Some(Command::Edit(edit_args)) => edit(edit_args),
+        Some(Command::Quiz(quiz_args)) => quiz(quiz_args),
+        Some(Command::Trainer(trainer_args)) => trainer(trainer_args),
+        Some(Command::Economy(economy_args)) => economy(economy_args),
+        Some(Command::Classes(classes_args)) => classes(classes_args),
+        #[cfg(feature = "scripting")]
+        Some(Command::Run(run_args)) => run(run_args),
+        None => simulate(args.sim),
+    }
+}
+
+fn eval_hand(line: &str, counts: &[HandCount], hand_size: usize, best: bool) -> serde_json::Value {
+    let (cards, num_jokers) = match parse_hand(line) {
+        Ok(hand) => hand,
+        Err(e) => return serde_json::json!({ "hand": line, "error": e }),
+    };
+    if cards.len() + num_jokers as usize > MAX_CARDS {
+        return serde_json::json!({
+            "hand": line,
+            "error": format!("does not support more than {MAX_CARDS} cards"),
+        });
+    }
+    let categories = counts
+        .iter()
+        .filter(|c| c.matches(&cards, num_jokers))
+        .map(|c| c.name)
+        .collect::<Vec<_>>();
+    let mut result = serde_json::json!({ "hand": line, "categories": categories });
+    if best {
+        result["best"] = evaluate_best(&cards, num_jokers, hand_size).name().into();
+    }
+    result
+}
+
+const EVAL_CHUNK_LINES: usize = 1 << 16;
+
+// Evaluates one hand per line, reading in chunks which are split across
+// `threads` workers. Output order matches input order.
+fn eval_lines(
+    reader: impl std::io::BufRead,
+    out: &mut impl std::io::Write,
+    args: &EvalArgs,
+    counts: &[HandCount],
+) -> std::io::Result<()> {
+    let threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .max(1);
+    let start = std::time::Instant::now();
+    let mut num_evaluated = 0;
+    let mut lines = reader.lines();
+    loop {
+        let chunk = lines
+            .by_ref()
+            .take(EVAL_CHUNK_LINES)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let per_thread = chunk.len().div_ceil(threads);
+        let results = std::thread::scope(|s| {
+            let handles = chunk
+                .chunks(per_thread)
+                .map(|lines| {
+                    s.spawn(move || {
+                        lines
+                            .iter()
+                            .map(|l| l.trim())
+                            .filter(|l| !l.is_empty())
+                            .map(|l| eval_hand(l, counts, args.hand_size, args.best).to_string())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        for result in results.iter().flatten() {
+            writeln!(out, "{result}")?;
+            num_evaluated += 1;
+        }
+        if args.progress {
+            let secs = start.elapsed().as_secs_f64();
+            eprintln!(
+                "{num_evaluated} hands evaluated ({rate:.0} hands/s)",
+                rate = num_evaluated as f64 / secs
+            );
         }
+    }
+    out.flush()
+}
 
-        {
-            let mut expected = Ranks::default();
-            expected[0] = 1;
-            expected[1] = 1;
-            expected[3] = 1;
-            expected[13] = 1;
-            assert_eq!(
-                expected,
-                ranks_for_straight(&[
-                    Card { suit: 0, rank: R2 },
-                    Card { suit: 0, rank: RA },
-                    Card { suit: 2, rank: R4 }
-                ])
+fn eval(args: EvalArgs) {
+    let Some(counts) = hand_counts(args.hand_size) else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let result = (|| {
+        use std::io::Write;
+        for hand in &args.hands {
+            writeln!(
+                out,
+                "{}",
+                eval_hand(hand, &counts, args.hand_size, args.best)
+            )?;
+        }
+        if let Some(path) = &args.file {
+            let file = std::fs::File::open(path)?;
+            eval_lines(std::io::BufReader::new(file), &mut out, &args, &counts)?;
+        }
+        if args.stdin {
+            eval_lines(std::io::stdin().lock(), &mut out, &args, &counts)?;
+        }
+        out.flush()
+    })();
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn serve(args: ServeArgs) {
+    let Protocol::Binary = args.proto;
+    #[cfg(unix)]
+    let result = match &args.unix {
+        Some(path) => serve::serve_unix(path),
+        None => serve::serve_tcp(&args.listen),
+    };
+    #[cfg(not(unix))]
+    let result = serve::serve_tcp(&args.listen);
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+// RNG for a run and its seed, printing the seed when it was picked
+// automatically so the run can be repeated with --seed.
+fn run_rng(seed: Option<u64>) -> (rand::rngs::StdRng, u64) {
+    let (rng, used) = seeded_rng(seed);
+    if seed.is_none() {
+        println!("seed: {used}");
+    }
+    (rng, used)
+}
+
+fn parse_objectives(names: &[String]) -> Vec<Box<dyn Objective>> {
+    names
+        .iter()
+        .map(|name| {
+            parse_objective(name).unwrap_or_else(|e| {
+                println!("{e}");
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+fn sensitivity(args: SensitivityArgs) {
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    let deck = args.deck.deck();
+    if args.deck.cards + args.step * args.levels > deck.len() {
+        println!("Trimming would leave fewer than {} cards", args.deck.cards);
+        std::process::exit(1);
+    }
+    let Some(mut counts) = hand_counts(args.deck.hand_size) else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+    skip_in_straights(&mut counts, args.deck.skipped_in_straights());
+    let objectives = parse_objectives(&args.objective);
+
+    let mut sensitivity = Sensitivity::new(
+        counts.len(),
+        objectives.len(),
+        args.order,
+        args.levels,
+        args.step,
+        args.deck.hand_size,
+    );
+    let (mut rng, seed) = run_rng(args.seed);
+    sensitivity.run(
+        &deck,
+        args.deck.cards,
+        &counts,
+        &objectives,
+        args.iters,
+        &mut rng,
+    );
+
+    let names = objectives.iter().map(|o| o.name()).collect::<Vec<_>>();
+    let max_str_len = counts
+        .iter()
+        .map(|c| c.name.len())
+        .chain(names.iter().map(String::len))
+        .max()
+        .unwrap();
+    print!("{:>width$}", "deck size", width = max_str_len);
+    for level in 0..=args.levels {
+        print!(" {:>9}", deck.len() - level * args.step);
+    }
+    println!("  dP/d(size)");
+    for (h, c) in counts.iter().enumerate() {
+        print!("{name: >width$}", name = c.name, width = max_str_len);
+        for level in 0..=args.levels {
+            print!(" {:>9.6}", sensitivity.probability(level, h));
+        }
+        let (derivative, ci) = sensitivity.derivative(h);
+        println!("  {derivative:+.6} ± {ci:.6}");
+    }
+    for (o, name) in names.iter().enumerate() {
+        print!("{name: >width$}", width = max_str_len);
+        for level in 0..=args.levels {
+            print!(" {:>9.6}", sensitivity.objective_mean(level, o));
+        }
+        let (derivative, ci) = sensitivity.objective_derivative(o);
+        println!("  {derivative:+.6} ± {ci:.6}");
+    }
+
+    if let Some(path) = &args.table {
+        let mut config = run_config(&args.deck, 0, "keep-best", 0);
+        config["order"] = clap::ValueEnum::to_possible_value(&args.order)
+            .unwrap()
+            .get_name()
+            .into();
+        config["step"] = args.step.into();
+        let metadata = poker::metadata::collect(&config, Some(seed));
+        let columns = std::iter::once(("deck size".to_string(), ColumnType::U64))
+            .chain(
+                counts
+                    .iter()
+                    .map(|c| c.name.to_string())
+                    .chain(names.iter().cloned())
+                    .map(|name| (name, ColumnType::F64)),
             )
+            .collect::<Vec<_>>();
+        let rows = (0..=args.levels).map(|level| {
+            std::iter::once((deck.len() - level * args.step) as f64)
+                .chain((0..counts.len()).map(|h| sensitivity.probability(level, h)))
+                .chain((0..names.len()).map(|o| sensitivity.objective_mean(level, o)))
+                .collect()
+        });
+        write_table(path, &metadata, &columns, rows);
+    }
+}
+
+fn test(args: TestArgs) {
+    use poker::sequential::{parse_comparison, PairedTest, Verdict};
+    use rand::seq::SliceRandom;
+
+    // Deals between checks, which cost far more than a deal.
+    const BATCH: u64 = 1000;
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    if !(args.confidence > 0.0 && args.confidence < 1.0) {
+        println!("--confidence must be between 0 and 1");
+        std::process::exit(1);
+    }
+    let Some(counts) = args.rules.counts(args.deck.hand_size) else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+    let hands = args.rules.hands_for(args.deck.hand_size).unwrap();
+    // A side of a comparison: a hand's index, or a probability objective.
+    enum Side {
+        Hand(usize),
+        Objective(Box<dyn Objective>),
+    }
+    let find = |side: &str| {
+        if let Some(h) = hands
+            .iter()
+            .position(|h| h.id == side || h.name.eq_ignore_ascii_case(side))
+        {
+            return Side::Hand(h);
+        }
+        match parse_objective(side) {
+            Ok(objective) if objective.is_probability() => Side::Objective(objective),
+            Ok(objective) => {
+                println!("{} isn't a probability to compare", objective.name());
+                std::process::exit(1);
+            }
+            Err(_) => {
+                let ids = hands.iter().map(|h| h.id.as_str()).collect::<Vec<_>>();
+                println!(
+                    "unknown hand '{side}', expected one of {} or prob:<hand> or score>=<target>",
+                    ids.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    };
+    let mut sides = Vec::new();
+    let mut tests = args
+        .greater
+        .iter()
+        .map(|s| {
+            let (a, b) = parse_comparison(s).unwrap_or_else(|e| {
+                println!("{e}");
+                std::process::exit(1);
+            });
+            sides.extend([find(&a), find(&b)]);
+            PairedTest::new(sides.len() - 2, sides.len() - 1)
+        })
+        .collect::<Vec<_>>();
+    let scored = sides.iter().any(|s| matches!(s, Side::Objective(_)));
+    let alpha = (1.0 - args.confidence) / tests.len() as f64;
+
+    let mut deck = args.deck.deck();
+    let num_cards = args.deck.cards.min(deck.len());
+    let plan = Plan::new(&counts);
+    let (mut rng, _) = run_rng(args.seed);
+    let mut verdicts = vec![None; tests.len()];
+    let mut deals = 0;
+    while deals < args.max_deals && verdicts.iter().any(Option::is_none) {
+        for _ in 0..BATCH.min(args.max_deals - deals) {
+            let (drawn, _) = deck.partial_shuffle(&mut rng, num_cards);
+            let (cards, num_jokers) = split_jokers(drawn);
+            let mask = plan.evaluate(&counts, &cards, num_jokers);
+            let round = scored.then(|| {
+                play_round(
+                    &mut cards.clone(),
+                    &mut num_jokers.clone(),
+                    &[],
+                    args.deck.hand_size,
+                    1,
+                    None,
+                    &CardEffects::default(),
+                )
+            });
+            let made = |side: &Side| match side {
+                Side::Hand(h) => mask >> h & 1 != 0,
+                Side::Objective(objective) => {
+                    let outcome = Outcome {
+                        cards: &cards,
+                        num_jokers,
+                        round: round.as_ref().unwrap(),
+                    };
+                    objective.value(&outcome) == 1.0
+                }
+            };
+            for (test, verdict) in tests.iter_mut().zip(&verdicts) {
+                if verdict.is_none() {
+                    test.record_sides(made(&sides[test.a]), made(&sides[test.b]));
+                }
+            }
+            deals += 1;
+        }
+        for (test, verdict) in tests.iter().zip(&mut verdicts) {
+            if verdict.is_none() {
+                *verdict = test.verdict(alpha);
+            }
+        }
+    }
+
+    let name = |side: &Side| match side {
+        Side::Hand(h) => format!("P({})", hands[*h].name),
+        Side::Objective(objective) => objective.name(),
+    };
+    for (test, verdict) in tests.iter().zip(&verdicts) {
+        let (a, b) = (name(&sides[test.a]), name(&sides[test.b]));
+        let outcome = match verdict {
+            Some(Verdict::Greater) => format!("accepted after {} deals", test.deals),
+            Some(Verdict::Less) => {
+                format!("rejected after {} deals, {b} is greater", test.deals)
+            }
+            None => format!("undecided after {} deals", test.deals),
+        };
+        let (gap, ci) = test.gap();
+        println!("{a} > {b}: {outcome}, {a} - {b} = {gap:.6} ± {ci:.6}");
+    }
+}
+
+fn until(args: UntilArgs) {
+    use rand::seq::SliceRandom;
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    if args.deck.cards < args.hand.num_cards() {
+        println!(
+            "{} needs {} cards but only {} are drawn",
+            args.hand.name(),
+            args.hand.num_cards(),
+            args.deck.cards
+        );
+        std::process::exit(1);
+    }
+    let mut deck = args.deck.deck();
+    let num_cards = args.deck.cards.min(deck.len());
+    // Otherwise every trial deals --max-deals times without a word.
+    let mut hand = HandCount::for_rank(args.hand.name(), args.hand);
+    hand.skipped_in_straights = args.deck.skipped_in_straights();
+    let info = HandInfo::new(hand.clone());
+    if !info.possible_in(&deck, num_cards) {
+        println!(
+            "No deal of {num_cards} cards from this deck makes {}",
+            args.hand.name()
+        );
+        std::process::exit(1);
+    }
+    let (mut rng, _) = run_rng(args.seed);
+
+    let mut deals_needed = Vec::new();
+    let mut num_truncated = 0;
+    for _ in 0..args.trials {
+        let mut successes = 0;
+        let mut deals = 0;
+        while successes < args.successes && deals < args.max_deals {
+            let (drawn, _) = deck.partial_shuffle(&mut rng, num_cards);
+            let (cards, num_jokers) = split_jokers(drawn);
+            if hand.matches(&cards, num_jokers) {
+                successes += 1;
+            }
+            deals += 1;
+        }
+        if successes < args.successes {
+            num_truncated += 1;
+        } else {
+            deals_needed.push(deals);
+        }
+    }
+
+    println!(
+        "deals needed to make {} {} times over {} trials:",
+        args.hand.name(),
+        args.successes,
+        args.trials
+    );
+    if num_truncated > 0 {
+        println!(
+            "({num_truncated} trials gave up after {} deals)",
+            args.max_deals
+        );
+    }
+    if deals_needed.is_empty() {
+        return;
+    }
+    deals_needed.sort_unstable();
+    let mean = deals_needed.iter().sum::<u64>() as f64 / deals_needed.len() as f64;
+    let quantile = |q: f64| deals_needed[((deals_needed.len() - 1) as f64 * q).round() as usize];
+    println!("  mean: {mean:.1}");
+    for (name, q) in [("p10", 0.1), ("p50", 0.5), ("p90", 0.9), ("p99", 0.99)] {
+        println!("   {name}: {}", quantile(q));
+    }
+    println!("   max: {}", deals_needed.last().unwrap());
+}
+
+fn prob(args: ProbArgs) {
+    use poker::serve::DeckConfig;
+
+    if let Some(path) = &args.write_table {
+        let file = std::fs::File::create(path).unwrap_or_else(|e| {
+            println!("Could not create {}: {e}", path.display());
+            std::process::exit(1);
+        });
+        poker::prob_table::write_table(args.table_iters, std::io::BufWriter::new(file)).unwrap();
+        return;
+    }
+    let config = DeckConfig {
+        cards: args.cards,
+        decks: args.decks,
+        jokers: args.jokers,
+        hand_size: args.hand_size,
+    };
+    if let Err(e) = config.validate() {
+        println!("{e}");
+        std::process::exit(1);
+    }
+    let mut counts = config.counts().unwrap();
+    let (iterations, hits) = match poker::prob_table::lookup(config) {
+        Some(entry) => {
+            println!("(from the precomputed table)");
+            (entry.iterations, entry.hits.to_vec())
+        }
+        None => {
+            println!("(not in the precomputed table, simulating)");
+            (args.iters, config.simulate(args.iters).unwrap())
+        }
+    };
+    for (c, hits) in counts.iter_mut().zip(hits) {
+        c.count = hits;
+    }
+    print_counts(&counts, iterations, CiMethod::Normal);
+}
+
+fn bench(args: BenchArgs) {
+    use poker::bench::{Config, Stats};
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    let Some(counts) = args.mode.counts(args.deck.hand_size) else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+    let deck = args.deck.deck();
+    let num_cards = args.deck.cards.min(deck.len());
+    let config = Config {
+        iters: args.iters,
+        samples: args.samples,
+        warmup: args.warmup,
+    };
+    println!(
+        "{} deals of {num_cards} cards, seed {}, in {} samples after {} warmup deals",
+        args.iters, args.seed, args.samples, args.warmup
+    );
+    let deals = poker::bench::deals(&deck, num_cards, args.seed);
+    let report = poker::bench::run(&counts, &deals, &config);
+    let spread = |s: &Stats| format!("{:.1}ns ({:.1}-{:.1})", s.median, s.min, s.max);
+    println!(
+        "all hands: {:.0} deals/s, {}/deal",
+        1e9 / report.all.median,
+        spread(&report.all)
+    );
+    let width = counts.iter().map(|c| c.name.len()).max().unwrap();
+    println!("each hand, slowest first:");
+    for (name, stats) in &report.detectors {
+        println!("  {name:>width$}: {}/call", spread(stats));
+    }
+}
+
+fn samplesize(args: SamplesizeArgs) {
+    use poker::estimate::{error_after, iterations_for_error};
+    use poker::serve::DeckConfig;
+
+    if !(args.confidence > 0.0 && args.confidence < 1.0) {
+        println!("--confidence must be between 0 and 1");
+        std::process::exit(1);
+    }
+    if args.rel_error.is_some_and(|e| e <= 0.0) || args.iters == Some(0) {
+        println!("--rel-error and --iters must be positive");
+        std::process::exit(1);
+    }
+    let rows = if args.p.is_empty() {
+        let config = DeckConfig {
+            cards: args.cards,
+            decks: args.decks,
+            jokers: args.jokers,
+            hand_size: args.hand_size,
+        };
+        if let Err(e) = config.validate() {
+            println!("{e}");
+            std::process::exit(1);
+        }
+        let counts = config.counts().unwrap();
+        // The table covers every hand; otherwise only the analytic estimates
+        // are known.
+        let probabilities = match poker::prob_table::lookup(config) {
+            Some(entry) => entry
+                .hits
+                .iter()
+                .map(|&hits| Some(hits as f64 / entry.iterations as f64))
+                .collect(),
+            None => estimate(
+                args.decks as usize,
+                args.jokers,
+                args.cards as usize,
+                &counts,
+            ),
+        };
+        counts
+            .iter()
+            .zip(probabilities)
+            .map(|(c, p)| (c.name.to_string(), p))
+            .collect::<Vec<_>>()
+    } else {
+        if let Some(p) = args.p.iter().find(|p| !(**p > 0.0 && **p < 1.0)) {
+            println!("--p must be between 0 and 1, not {p}");
+            std::process::exit(1);
+        }
+        args.p.iter().map(|&p| (format!("{p}"), Some(p))).collect()
+    };
+
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let confidence = 100.0 * args.confidence;
+    match (args.rel_error, args.iters) {
+        (Some(rel_error), _) => println!(
+            "iterations for an interval of ± {}% of p at {confidence}% confidence:",
+            100.0 * rel_error
+        ),
+        (None, Some(iters)) => {
+            println!("interval after {iters} iterations at {confidence}% confidence:")
         }
+        (None, None) => unreachable!(),
+    }
+    for (name, p) in rows {
+        let Some(p) = p else {
+            println!("{name: >width$}: unknown without simulating");
+            continue;
+        };
+        // Rows for --p are already named by their probability.
+        let known = match args.p.is_empty() {
+            true => format!("p = {p:.6}, "),
+            false => String::new(),
+        };
+        let line = match (args.rel_error, args.iters) {
+            _ if p <= 0.0 || p >= 1.0 => "nothing to estimate".to_string(),
+            (Some(rel_error), _) => format!(
+                "{} iterations",
+                iterations_for_error(p, rel_error, args.confidence)
+            ),
+            (None, Some(iters)) => {
+                let error = error_after(p, iters, args.confidence);
+                format!("± {:.6} (± {:.2}%)", p * error, 100.0 * error)
+            }
+            (None, None) => unreachable!(),
+        };
+        println!("{name: >width$}: {known}{line}");
     }
+}
 
-    #[test]
-    fn test_suit_counts() {
-        assert_eq!(RankCounts::default(), suit_counts(&[]));
+fn hyper(args: HyperArgs) {
+    use poker::combinatorics::{hypergeometric_exact, to_f64};
 
+    if args.successes > args.deck || args.draw > args.deck {
+        println!("--successes and --draw can't be larger than --deck");
+        std::process::exit(1);
+    }
+    let max_k = args.successes.min(args.draw);
+    let exactly = (0..=max_k)
+        .map(|k| {
+            to_f64(&hypergeometric_exact(
+                args.deck,
+                args.successes,
+                args.draw,
+                k,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let at_least = |k: u64| exactly.iter().skip(k as usize).sum::<f64>();
+
+    println!(" k   exactly  at least");
+    for (k, p) in exactly.iter().enumerate() {
+        println!("{k:>2}  {p:.6}  {:.6}", at_least(k as u64));
+    }
+    if let Some(need) = args.need {
+        let p_exactly = exactly.get(need as usize).copied().unwrap_or(0.0);
+        println!(
+            "P(exactly {need}) = {p_exactly:.6}, P(at least {need}) = {:.6}",
+            at_least(need)
+        );
+    }
+}
+
+// The hands a simulation counts: overlapping hands from `hand_counts`, or
+// with --mode best, the best hand of each deal.
+fn sim_counts(args: &SimArgs) -> Vec<HandCount> {
+    let counts = args.mode.counts(args.deck.hand_size);
+    let Some(mut counts) = counts else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+    if !args.hands.is_empty() {
+        let mut ranks = args.hands.clone();
+        ranks.sort();
+        ranks.dedup();
+        match args.mode {
+            RuleSet::Independent => counts = hand_counts_for(&ranks),
+            RuleSet::Best => {
+                if let Some(rank) = ranks.iter().find(|r| r.num_cards() > args.deck.hand_size) {
+                    println!(
+                        "{} takes {} cards, more than --hand-size",
+                        rank.id(),
+                        rank.num_cards()
+                    );
+                    std::process::exit(1);
+                }
+                counts.retain(|c| ranks.contains(&c.best.unwrap().0));
+            }
+            // The low isn't a rank, so it stays.
+            RuleSet::Omaha => counts.retain(|c| match c.omaha.unwrap() {
+                OmahaHand::High(rank) => ranks.contains(&rank),
+                OmahaHand::Low => true,
+            }),
+            RuleSet::Razz | RuleSet::DeuceToSeven | RuleSet::Badugi => {
+                println!("--hands only takes high hands, not lows");
+                std::process::exit(1);
+            }
+        }
+    }
+    apply_min_ranks(&mut counts, &args.min_rank);
+    if args.straight_size.is_some() || args.flush_size.is_some() {
+        let hand_size = args.deck.hand_size;
+        let straight_size = args.straight_size.unwrap_or(hand_size);
+        let flush_size = args.flush_size.unwrap_or(hand_size);
+        if ![straight_size, flush_size]
+            .iter()
+            .all(|n| (2..=hand_size).contains(n))
         {
-            let mut expected = RankCounts::default();
-            expected[1] = 2;
-            expected[3] = 1;
-            assert_eq!(
-                expected,
-                suit_counts(&[
-                    Card { suit: 1, rank: 0 },
-                    Card { suit: 1, rank: 0 },
-                    Card { suit: 3, rank: 2 }
-                ])
-            )
+            println!("--straight-size and --flush-size must be between 2 and --hand-size");
+            std::process::exit(1);
         }
+        resize_straights_and_flushes(&mut counts, straight_size, flush_size);
     }
+    skip_in_straights(&mut counts, args.deck.skipped_in_straights());
+    counts
+}
 
-    #[test]
-    fn test_is_n_of_a_kind() {
-        assert!(is_n_of_a_kind(&[], 0, 0));
-        assert!(!is_n_of_a_kind(&[], 1, 0));
-        assert!(is_n_of_a_kind(&[Card { suit: 0, rank: 1 }], 1, 0));
+// Options which only make sense when sampling are rejected alongside --exact
+// by clap.
+fn exact(args: SimArgs) {
+    let deck = args.deck.deck();
+    let mut counts = sim_counts(&args);
+    let num_cards = args.deck.cards.min(deck.len());
+    if poker::exact::num_draws(deck.len(), num_cards).is_none() {
+        println!("Too many possible draws to enumerate, simulate without --exact instead");
+        std::process::exit(1);
+    }
+    remove_impossible_hands(&mut counts, num_cards, args.impossible_hands);
 
-        assert!(!is_n_of_a_kind(
-            &[Card { suit: 1, rank: 0 }, Card { suit: 0, rank: 1 }],
-            2,
-            0
-        ));
-        assert!(is_n_of_a_kind(
-            &[Card { suit: 1, rank: 1 }, Card { suit: 1, rank: 1 }],
-            2,
-            0
-        ));
-        assert!(is_n_of_a_kind(
-            &[Card { suit: 0, rank: 1 }, Card { suit: 1, rank: 1 }],
-            2,
-            0
-        ));
+    let result = poker::exact::enumerate(&deck, num_cards, &counts);
+    print_exact(&counts, &result, args.fractions);
+}
 
-        assert!(!is_n_of_a_kind(
-            &[Card { suit: 0, rank: 1 }, Card { suit: 1, rank: 1 }],
-            3,
-            0
-        ));
-        assert!(is_n_of_a_kind(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            3,
-            0
-        ));
-        assert!(is_n_of_a_kind(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-            ],
-            3,
-            0
-        ));
-
-        assert!(!is_n_of_a_kind(&[], 2, 1));
-        assert!(is_n_of_a_kind(&[], 2, 2));
-        assert!(is_n_of_a_kind(&[Card { suit: 1, rank: 2 },], 2, 1));
-        assert!(!is_n_of_a_kind(
-            &[Card { suit: 1, rank: 2 }, Card { suit: 2, rank: 3 },],
-            3,
-            1
-        ));
-        assert!(is_n_of_a_kind(
-            &[Card { suit: 1, rank: 3 }, Card { suit: 2, rank: 3 },],
-            3,
-            1
-        ));
-    }
-
-    #[test]
-    fn test_is_two_pair() {
-        assert!(!is_two_pair(&[], 0));
-        assert!(!is_two_pair(&[Card { suit: 0, rank: 0 }], 0));
-        assert!(!is_two_pair(
-            &[Card { suit: 0, rank: 0 }, Card { suit: 0, rank: 0 },],
-            0
-        ));
-        assert!(!is_two_pair(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_two_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_two_pair(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 2, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_two_pair(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-            ],
-            0
-        ));
-
-        assert!(!is_two_pair(&[], 3));
-        assert!(is_two_pair(&[], 4));
-        assert!(is_two_pair(&[Card { suit: 0, rank: 0 },], 3));
-        assert!(is_two_pair(
-            &[Card { suit: 1, rank: 1 }, Card { suit: 0, rank: 0 },],
-            2
-        ));
-        assert!(is_two_pair(
-            &[Card { suit: 0, rank: 0 }, Card { suit: 0, rank: 0 },],
-            2
-        ));
-        assert!(is_two_pair(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-            ],
-            1
-        ));
-        assert!(!is_two_pair(
-            &[Card { suit: 0, rank: 0 }, Card { suit: 0, rank: 0 },],
-            1
-        ));
-    }
-
-    #[test]
-    fn test_is_full_house() {
-        assert!(!is_full_house(&[], 0));
-        assert!(!is_full_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_full_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 2 },
-            ],
-            0
-        ));
-        assert!(is_full_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_full_house(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_full_house(&[], 5));
-        assert!(!is_full_house(&[], 4));
-        assert!(is_full_house(&[Card { suit: 0, rank: 0 },], 4));
-        assert!(is_full_house(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            2
-        ));
-        assert!(is_full_house(
-            &[Card { suit: 0, rank: 0 }, Card { suit: 1, rank: 1 },],
-            3
-        ));
-        assert!(is_full_house(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            1
-        ));
-        assert!(is_full_house(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            1
-        ));
-        assert!(is_full_house(
-            &[
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            1
-        ));
-    }
-
-    #[test]
-    fn test_is_full_mansion() {
-        assert!(!is_full_mansion(&[], 0));
-        assert!(!is_full_mansion(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_full_mansion(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_full_mansion(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 2 },
-            ],
-            0
-        ));
-        assert!(is_full_mansion(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_full_mansion(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_full_mansion(&[], 6));
-        assert!(!is_full_mansion(&[], 5));
-        assert!(is_full_mansion(&[Card { suit: 0, rank: 0 },], 5));
-        assert!(is_full_mansion(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            2
-        ));
-        assert!(is_full_mansion(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            2
-        ));
-        assert!(!is_full_mansion(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-            ],
-            3
-        ));
-        assert!(is_full_mansion(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-            ],
-            3
-        ));
-        assert!(!is_three_pair(&[], 5));
-        assert!(is_three_pair(&[], 6));
-    }
-
-    #[test]
-    fn test_is_two_triplet() {
-        assert!(!is_two_triplet(&[], 0));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 0 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 1 },
-                Card { suit: 3, rank: 1 },
-            ],
-            0
-        ));
-        assert!(is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            1
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            1
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(!is_two_triplet(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(!is_two_triplet(&[], 5));
-        assert!(is_two_triplet(&[], 6));
-    }
-
-    #[test]
-    fn test_is_three_pair() {
-        assert!(!is_three_pair(&[], 0));
-        assert!(!is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(!is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            0
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            1
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            1
-        ));
-        assert!(!is_three_pair(
-            &[
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 1 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(is_three_pair(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 2, rank: 0 },
-                Card { suit: 3, rank: 0 },
-            ],
-            2
-        ));
-        assert!(!is_three_pair(&[], 5));
-        assert!(is_three_pair(&[], 6));
-    }
-
-    #[test]
-    fn test_is_flush() {
-        assert!(!is_flush(&[], 0, 5));
-        assert!(!is_flush(&[Card { suit: 0, rank: 0 },], 0, 5));
-        assert!(!is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_flush(&[], 4, 5));
-        assert!(is_flush(&[], 5, 5));
-        assert!(is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-            ],
-            1,
-            5
-        ));
-        assert!(is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-            ],
-            2,
-            5
-        ));
-        assert!(!is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 1, rank: 0 },
-            ],
-            2,
-            5
-        ));
-        assert!(!is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-            ],
-            0,
-            6
-        ));
-        assert!(is_flush(
-            &[
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 0 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 3 },
-            ],
-            0,
-            6
-        ));
-    }
-
-    #[test]
-    fn test_is_straight() {
-        assert!(!is_straight(&[], 0, 5));
-        assert!(!is_straight(&[Card { suit: 0, rank: 0 },], 0, 5));
-
-        assert!(!is_straight(
-            &[
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 3 },
-                Card { suit: 0, rank: 4 },
-                Card { suit: 0, rank: 5 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 1, rank: R2 },
-                Card { suit: 1, rank: R3 },
-                Card { suit: 1, rank: R4 },
-                Card { suit: 1, rank: R5 },
-                Card { suit: 1, rank: R6 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 2, rank: R2 },
-                Card { suit: 3, rank: R3 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-                Card { suit: 1, rank: R6 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RQ },
-                Card { suit: 0, rank: RK },
-                Card { suit: 0, rank: RA },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: RA },
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_straight(
-            &[
-                Card { suit: 0, rank: RK },
-                Card { suit: 0, rank: RA },
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_straight(&[], 4, 5));
-        assert!(is_straight(&[], 5, 5));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: RA },
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-            ],
-            1,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-            ],
-            1,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-            ],
-            2,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-            ],
-            2,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R6 },
-            ],
-            2,
-            5
-        ));
-        assert!(is_straight(
-            &[Card { suit: 0, rank: R2 }, Card { suit: 0, rank: R6 },],
-            3,
-            5
-        ));
-        assert!(is_straight(
-            &[Card { suit: 0, rank: R3 }, Card { suit: 0, rank: R6 },],
-            3,
-            5
-        ));
-        assert!(!is_straight(
-            &[Card { suit: 0, rank: R3 }, Card { suit: 0, rank: R4 },],
-            2,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RK },
-                Card { suit: 0, rank: RA },
-            ],
-            1,
-            5
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RQ },
-                Card { suit: 0, rank: RK },
-            ],
-            1,
-            5
-        ));
-        assert!(!is_straight(
-            &[
-                Card { suit: 0, rank: R9 },
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RQ },
-                Card { suit: 0, rank: RK },
-            ],
-            0,
-            6
-        ));
-        assert!(is_straight(
-            &[
-                Card { suit: 0, rank: R8 },
-                Card { suit: 0, rank: R9 },
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RQ },
-                Card { suit: 0, rank: RK },
-            ],
-            0,
-            6
-        ));
-    }
-
-    #[test]
-    fn test_is_straight_flush() {
-        assert!(!is_straight_flush(&[], 0, 5));
-        assert!(!is_straight_flush(&[Card { suit: 0, rank: 0 },], 0, 5));
-
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 3 },
-                Card { suit: 0, rank: 4 },
-                Card { suit: 0, rank: 5 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 1, rank: R2 },
-                Card { suit: 1, rank: R3 },
-                Card { suit: 1, rank: R4 },
-                Card { suit: 1, rank: R5 },
-                Card { suit: 1, rank: R6 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 2, rank: R2 },
-                Card { suit: 3, rank: R3 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-                Card { suit: 1, rank: R6 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 0, rank: R10 },
-                Card { suit: 0, rank: RJ },
-                Card { suit: 0, rank: RQ },
-                Card { suit: 0, rank: RK },
-                Card { suit: 0, rank: RA },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 0, rank: RA },
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-                Card { suit: 0, rank: R5 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 0, rank: RK },
-                Card { suit: 0, rank: RA },
-                Card { suit: 0, rank: R2 },
-                Card { suit: 0, rank: R3 },
-                Card { suit: 0, rank: R4 },
-            ],
-            0,
-            5
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 1, rank: R4 },
-                Card { suit: 0, rank: R5 },
-                Card { suit: 0, rank: R6 },
-                Card { suit: 0, rank: R7 },
-                Card { suit: 0, rank: R8 },
-                Card { suit: 0, rank: R9 },
-            ],
-            0,
-            5
-        ));
-        assert!(!is_straight_flush(&[], 4, 5));
-        assert!(is_straight_flush(&[], 5, 5));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 0, rank: R5 },
-                Card { suit: 0, rank: R6 },
-                Card { suit: 1, rank: R7 },
-                Card { suit: 0, rank: R8 },
-                Card { suit: 0, rank: R9 },
-            ],
-            1,
-            5
-        ));
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 0, rank: R5 },
-                Card { suit: 0, rank: R6 },
-                Card { suit: 1, rank: R7 },
-                Card { suit: 1, rank: R8 },
-                Card { suit: 0, rank: R9 },
-            ],
-            1,
-            5
-        ));
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 0, rank: R5 },
-                Card { suit: 0, rank: R6 },
-                Card { suit: 1, rank: R9 },
-            ],
-            2,
-            5
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 0, rank: R5 },
-                Card { suit: 0, rank: R6 },
-                Card { suit: 0, rank: R9 },
-            ],
-            2,
-            5
-        ));
-        assert!(!is_straight_flush(
-            &[
-                Card { suit: 1, rank: R2 },
-                Card { suit: 1, rank: R3 },
-                Card { suit: 1, rank: R4 },
-                Card { suit: 1, rank: R5 },
-                Card { suit: 1, rank: R6 },
-            ],
-            0,
-            6
-        ));
-        assert!(is_straight_flush(
-            &[
-                Card { suit: 1, rank: R2 },
-                Card { suit: 1, rank: R3 },
-                Card { suit: 1, rank: R4 },
-                Card { suit: 1, rank: R5 },
-                Card { suit: 1, rank: R6 },
-                Card { suit: 1, rank: R7 },
-            ],
-            0,
-            6
-        ));
-    }
-
-    #[test]
-    fn test_is_flush_house() {
-        assert!(!is_flush_house(&[], 0));
-        assert!(is_flush_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            0
-        ));
-        assert!(is_flush_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            0
-        ));
-        assert!(!is_flush_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 1, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            0
-        ));
-
-        assert!(!is_flush_house(&[], 4));
-        assert!(is_flush_house(&[], 5));
-        assert!(is_flush_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            1
-        ));
-        assert!(!is_flush_house(
-            &[
-                Card { suit: 1, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            1
-        ));
-        assert!(is_flush_house(
-            &[
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            2
-        ));
-        assert!(is_flush_house(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 2 },
-            ],
-            2
-        ));
-    }
-
-    #[test]
-    fn test_is_flush_n() {
-        assert!(!is_flush_n(&[], 1, 0));
-        assert!(is_flush_n(&[Card { suit: 0, rank: 1 },], 1, 0));
-        assert!(is_flush_n(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            4,
-            0
-        ));
-        assert!(!is_flush_n(
-            &[
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            4,
-            0
-        ));
-        assert!(!is_flush_n(
-            &[
-                Card { suit: 2, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            4,
-            0
-        ));
-        assert!(is_flush_n(
-            &[
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            4,
-            1
-        ));
-        assert!(!is_flush_n(
-            &[
-                Card { suit: 0, rank: 2 },
-                Card { suit: 0, rank: 1 },
-                Card { suit: 0, rank: 1 },
-            ],
-            4,
-            1
-        ));
+fn print_exact(counts: &[HandCount], result: &poker::exact::ExactCounts, fractions: bool) {
+    println!("total draws: {}", result.total);
+    let mut order = (0..counts.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&h| (result.hits[h], counts[h].name));
+    order.reverse();
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    for h in order {
+        let p = result.probability(h);
+        let fraction = if fractions {
+            format!(" = {p}")
+        } else {
+            String::new()
+        };
+        println!(
+            "{name: >width$}: {p:.6} ({count}){fraction}",
+            name = counts[h].name,
+            width = max_str_len,
+            p = poker::combinatorics::to_f64(&p),
+            count = result.hits[h],
+        );
+    }
+}
+
+// Draws beyond this are too many to enumerate in reasonable time, so only
+// the sampled estimate is shown.
+const MAX_OUTS_DRAWS: u128 = 100_000_000;
+
+fn outs(args: OutsArgs) {
+    let (cards, num_jokers) = parse_hand(&args.hand).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    let mut held = cards
+        .iter()
+        .copied()
+        .map(CardOrJoker::Card)
+        .collect::<Vec<_>>();
+    held.extend((0..num_jokers).map(|_| CardOrJoker::Joker));
+    let draw = poker::outs::Draw::new(&args.deck.deck(), &held, args.draw).unwrap_or_else(|e| {
+        println!("--hand: {e}");
+        std::process::exit(1);
+    });
+    let target = HandCount::for_rank(args.target.name(), args.target);
+    let hand = format_hand(&cards, num_jokers);
+    if draw.made(&target) {
+        println!("{hand} already makes {}", target.name);
+        return;
+    }
+    println!(
+        "{hand}, drawing {} of {} cards, for {}:",
+        draw.draw,
+        draw.deck.len(),
+        target.name
+    );
+
+    let outs = draw.outs(&target);
+    let num_outs = outs.iter().map(|&(_, copies)| copies).sum::<u64>();
+    let names = outs
+        .iter()
+        .map(|&(coj, copies)| {
+            let name = match coj {
+                CardOrJoker::Card(card) => card.to_string(),
+                CardOrJoker::Joker => "J".to_string(),
+            };
+            match copies {
+                1 => name,
+                _ => format!("{name} x{copies}"),
+            }
+        })
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        println!("outs: none, no one card makes {}", target.name);
+    } else {
+        println!("outs ({num_outs}): {}", names.join(" "));
+    }
+
+    let num_draws = draw.num_draws();
+    if num_draws <= MAX_OUTS_DRAWS {
+        let exact = draw.exact(&target);
+        println!(
+            "  exact: {:.6} ({} of {} draws)",
+            poker::combinatorics::to_f64(&exact.probability(0)),
+            exact.hits[0],
+            exact.total
+        );
+    } else {
+        println!("  exact: skipped, {num_draws} distinct draws are too many to enumerate");
+    }
+    let (mut rng, _) = run_rng(args.seed);
+    let hits = draw.sample(&target, args.iters, &mut rng);
+    let (p, ci) = confidence_interval(args.iters, hits);
+    println!("sampled: {p:.6} ± {ci:.6} ({hits} of {} draws)", args.iters);
+}
+
+fn coordinate(args: CoordinateArgs) {
+    use poker::serve::DeckConfig;
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    if args.deck.wild > 0
+        || args.deck.deck_file.is_some()
+        || args.deck.deck_preset != Preset::Standard
+        || args.deck.short_straights
+    {
+        println!(
+            "--wild, --deck-file, --deck-preset and --short-straights can't be sent to workers"
+        );
+        std::process::exit(1);
+    }
+    let byte = |value: usize, flag: &str| {
+        u8::try_from(value).unwrap_or_else(|_| {
+            println!("--{flag} is too large to send to workers");
+            std::process::exit(1);
+        })
+    };
+    let config = DeckConfig {
+        cards: byte(args.deck.cards, "cards"),
+        decks: byte(args.deck.decks, "decks"),
+        jokers: args.deck.jokers,
+        hand_size: byte(args.deck.hand_size, "hand-size"),
+    };
+    let Ok(mut counts) = config.counts() else {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    };
+    if args.chunk == 0 || args.chunk > poker::serve::MAX_ITERATIONS {
+        println!(
+            "--chunk must be between 1 and {}",
+            poker::serve::MAX_ITERATIONS
+        );
+        std::process::exit(1);
+    }
+
+    let fail = |e: String| -> ! {
+        println!("{e}");
+        std::process::exit(1);
+    };
+    if args.exact {
+        // Enough ranges that a slow worker doesn't hold everything up.
+        let num_chunks = 64 * args.workers.len() as u128;
+        let result = poker::coordinate::enumerate(&args.workers, config, num_chunks)
+            .unwrap_or_else(|e| fail(e));
+        print_exact(&counts, &result, args.fractions);
+    } else {
+        let hits = poker::coordinate::simulate(&args.workers, config, args.iters, args.chunk)
+            .unwrap_or_else(|e| fail(e));
+        for (c, h) in counts.iter_mut().zip(hits) {
+            c.count = h;
+        }
+        println!("total iterations: {}", args.iters);
+        print_counts(&counts, args.iters, CiMethod::Normal);
+        if let Some(path) = &args.output {
+            let config = run_config(&args.deck, 0, "keep-best", 0);
+            let mut result = RunResult::new(config, &counts, args.iters);
+            result.metadata = Some(poker::metadata::collect(&result.config, None));
+            write_result(path, &result);
+        }
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn run(args: RunArgs) {
+    if let Err(e) = poker::script::run_file(&args.script) {
+        println!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn classes(args: ClassesArgs) {
+    let classes = poker::classes::hand_classes(args.extended);
+    let result = (|| {
+        use std::io::Write;
+        let mut out: Box<dyn Write> = match &args.output {
+            Some(path) => Box::new(std::fs::File::create(path)?),
+            None => Box::new(std::io::stdout().lock()),
+        };
+        let mut out = std::io::BufWriter::new(&mut out);
+        match args.format {
+            ClassesFormat::Csv => poker::classes::write_csv(&classes, &mut out)?,
+            ClassesFormat::Binary => poker::classes::write_binary(&classes, &mut out)?,
+        }
+        out.flush()
+    })();
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn merge(args: MergeArgs) {
+    let mut inputs = args.inputs.iter().map(|path| {
+        RunResult::read(path).unwrap_or_else(|e| {
+            println!("{e}");
+            std::process::exit(1);
+        })
+    });
+    let mut merged = inputs.next().unwrap();
+    for result in inputs {
+        if let Err(e) = merged.merge(&result) {
+            println!("{e}");
+            std::process::exit(1);
+        }
+    }
+    merged.metadata = Some(poker::metadata::collect(&merged.config, None));
+    if let Some(path) = &args.output {
+        write_result(path, &merged);
+        return;
+    }
+    let baseline = args
+        .baseline
+        .as_ref()
+        .map(|path| read_baseline(path, &merged.config));
+    print!("{}", args.format.render(&merged, baseline.as_ref()));
+}
+
+fn info(args: InfoArgs) {
+    let metadata = poker::metadata::read(&args.file).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    print!("{}", poker::metadata::format(&metadata));
+}
+
+// Arguments for one run of a `watch` scenario.
+fn watch_run_args(args: &WatchArgs) -> Result<Vec<String>, String> {
+    let mut run_args = poker::scenario::read(&args.file)?;
+    if !run_args.iter().any(|a| a == "--max-iters") {
+        run_args.extend(["--max-iters".to_string(), args.iters.to_string()]);
+    }
+    if let Some(seed) = args
+        .seed
+        .filter(|_| !run_args.iter().any(|a| a == "--seed"))
+    {
+        run_args.extend(["--seed".to_string(), seed.to_string()]);
+    }
+    Ok(run_args)
+}
+
+// Runs the simulation in a child process so that a scenario which fails,
+// or is edited mid-run, doesn't end the watch.
+fn watch(args: WatchArgs) {
+    let exe = std::env::current_exe().unwrap_or_else(|e| {
+        println!("Could not find the poker binary: {e}");
+        std::process::exit(1);
+    });
+    let modified = |path: &std::path::Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified = None;
+    let mut child: Option<std::process::Child> = None;
+    loop {
+        let now_modified = modified(&args.file);
+        if now_modified != last_modified {
+            last_modified = now_modified;
+            if let Some(mut running) = child.take() {
+                let _ = running.kill();
+                let _ = running.wait();
+            }
+            print!("{}", poker::tui::CLEAR);
+            println!("watching {} (Ctrl-C to stop)", args.file.display());
+            match watch_run_args(&args) {
+                Ok(run_args) => {
+                    println!("poker {}", run_args.join(" "));
+                    match std::process::Command::new(&exe).args(&run_args).spawn() {
+                        Ok(spawned) => child = Some(spawned),
+                        Err(e) => println!("Could not run the simulation: {e}"),
+                    }
+                }
+                Err(e) => println!("{e}"),
+            }
+        }
+        if let Some(running) = &mut child {
+            if !matches!(running.try_wait(), Ok(None)) {
+                child = None;
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(300));
+    }
+}
+
+// What the deck editor shows, shared between the thread reading commands and
+// the one simulating.
+struct EditorScreen {
+    live: poker::editor::Live,
+    start: std::time::Instant,
+    last_draw: std::time::Instant,
+    message: String,
+}
+
+impl EditorScreen {
+    // Waits for the first batch of a new deck, which the simulating thread
+    // draws, since until then there are no probabilities to show.
+    fn draw(&mut self, max_iters: u64) {
+        if self.live.num_iters == 0 {
+            return;
+        }
+        print!(
+            "{}{}",
+            poker::tui::CLEAR,
+            poker::tui::editor_frame(&self.live, self.start.elapsed(), max_iters, &self.message)
+        );
+        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+        self.last_draw = std::time::Instant::now();
+    }
+}
+
+fn edit(args: EditArgs) {
+    use poker::editor::{Command, Editor, Live};
+    use std::io::BufRead;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    // Small enough that an edit never waits long for the simulation.
+    const BATCH: u64 = 2000;
+    const REDRAW: Duration = Duration::from_millis(100);
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {MAX_CARDS} cards");
+        std::process::exit(1);
+    }
+    if hand_counts(args.deck.hand_size).is_none() {
+        println!("--hand-size must be between 5 and {MAX_CARDS}");
+        std::process::exit(1);
+    }
+    let session_path = match std::env::var_os("HOME") {
+        Some(home) if !args.no_session => {
+            let dir = std::path::Path::new(&home).join(".poker-sessions");
+            Some(
+                poker::session::path(&dir, &args.session).unwrap_or_else(|e| {
+                    println!("{e}");
+                    std::process::exit(1);
+                }),
+            )
+        }
+        _ => None,
+    };
+    let saved = session_path.as_deref().and_then(|path| {
+        Session::read(path).unwrap_or_else(|e| {
+            println!("{e}");
+            std::process::exit(1);
+        })
+    });
+    let mut message = String::new();
+    let mut session = match saved {
+        Some(mut saved) => {
+            message = format!(
+                "restored session '{}' with {} results",
+                args.session,
+                saved.results.len()
+            );
+            if args.deck.deck_file.is_some() {
+                saved.deck = args.deck.deck();
+            }
+            saved.rules = args.rules.unwrap_or(saved.rules);
+            saved
+        }
+        None => Session::new(args.deck.deck(), args.rules.unwrap_or_default()),
+    };
+    let counts = session.rules.counts(args.deck.hand_size).unwrap();
+    let mut editor = Editor::new(session.deck.clone());
+    let (mut rng, _) = run_rng(args.seed);
+    let screen = Arc::new(Mutex::new(EditorScreen {
+        live: Live::new(editor.deck.clone(), args.deck.cards, counts),
+        start: Instant::now(),
+        last_draw: Instant::now() - REDRAW,
+        message,
+    }));
+    // Saves the simulation so far among the session's recent results.
+    let save_session = |session: &mut Session, live: &Live| -> Result<(), String> {
+        if live.num_iters > 0 {
+            session.push_result(session_result(session.rules, &args.deck, live));
+        }
+        match &session_path {
+            Some(path) => session.write(path),
+            None => Ok(()),
+        }
+    };
+    {
+        let screen = Arc::clone(&screen);
+        std::thread::spawn(move || loop {
+            let mut screen = screen.lock().unwrap();
+            let remaining = args.iters.saturating_sub(screen.live.num_iters);
+            if remaining == 0 {
+                drop(screen);
+                std::thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+            screen.live.run(remaining.min(BATCH), &mut rng);
+            if remaining <= BATCH || screen.last_draw.elapsed() >= REDRAW {
+                screen.draw(args.iters);
+            }
+        });
+    }
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let mut screen = screen.lock().unwrap();
+        screen.message.clear();
+        match line.parse::<Command>() {
+            _ if line.trim().is_empty() => {}
+            Ok(Command::Quit) => break,
+            Ok(Command::Results(hand)) => {
+                let current = session_result(session.rules, &args.deck, &screen.live);
+                let lines = session
+                    .results
+                    .iter()
+                    .chain(std::iter::once(&current))
+                    .filter(|r| r.iterations > 0)
+                    .filter_map(|r| {
+                        let rules = r.config.get("rules").and_then(|r| r.as_str())?;
+                        Some(format!("{} ({rules} rules)", r.headline(&hand)?))
+                    })
+                    .collect::<Vec<_>>();
+                screen.message = if lines.is_empty() {
+                    format!("no results count {hand}")
+                } else {
+                    lines.join("\n")
+                };
+            }
+            Ok(Command::Rules(rules)) => {
+                if let Err(e) = save_session(&mut session, &screen.live) {
+                    screen.message = e;
+                }
+                session.rules = rules;
+                let counts = rules.counts(args.deck.hand_size).unwrap();
+                screen.live = Live::new(editor.deck.clone(), args.deck.cards, counts);
+                screen.start = Instant::now();
+                screen.last_draw = screen.start - REDRAW;
+            }
+            Ok(Command::Save(path)) => {
+                screen.message = match std::fs::write(&path, poker::deck::to_text(&editor.deck)) {
+                    Ok(()) => format!("saved {}", path.display()),
+                    Err(e) => format!("{}: {e}", path.display()),
+                }
+            }
+            Ok(command) => match editor.apply(&command) {
+                Ok(()) => {
+                    if let Err(e) = save_session(&mut session, &screen.live) {
+                        screen.message = e;
+                    }
+                    session.deck = editor.deck.clone();
+                    let counts = std::mem::take(&mut screen.live.counts);
+                    screen.live = Live::new(editor.deck.clone(), args.deck.cards, counts);
+                    screen.start = Instant::now();
+                    screen.last_draw = screen.start - REDRAW;
+                }
+                Err(e) => screen.message = e,
+            },
+            Err(e) => screen.message = e,
+        }
+        screen.draw(args.iters);
+    }
+    let screen = screen.lock().unwrap();
+    if let Err(e) = save_session(&mut session, &screen.live) {
+        println!("{e}");
+        std::process::exit(1);
+    }
+}
+
+// A result for the session's history, configured like `run_config` so that
+// `RunResult::headline` can describe it.
+fn session_result(rules: RuleSet, deck_args: &DeckArgs, live: &poker::editor::Live) -> RunResult {
+    let mut config = serde_json::json!({
+        "cards": deck_args.cards.min(live.deck.len()),
+        "hand_size": deck_args.hand_size,
+        "rules": clap::ValueEnum::to_possible_value(&rules).unwrap().get_name(),
+    });
+    match poker::deck::describe(&live.deck).as_str() {
+        "standard" => config["decks"] = 1.into(),
+        edits => config["deck"] = edits.into(),
+    }
+    RunResult::new(config, &live.counts, live.num_iters)
+}
+
+fn quiz(args: QuizArgs) {
+    use poker::quiz::{parse_guess, random_question, Round, Stats};
+    use std::io::BufRead;
+
+    let stats_path = args.stats.clone().or_else(|| {
+        std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".poker-quiz.json"))
+    });
+    let mut stats = match &stats_path {
+        Some(path) => Stats::read(path).unwrap_or_else(|e| {
+            println!("{e}");
+            std::process::exit(1);
+        }),
+        None => Stats::default(),
+    };
+    let (mut rng, _) = run_rng(args.seed);
+    let mut lines = std::io::stdin().lock().lines();
+    let mut played = 0;
+    'rounds: while args.rounds.is_none_or(|n| played < n) {
+        let (question, answer) = random_question(args.iters, &mut rng);
+        println!("{}", question.prompt());
+        let guess = loop {
+            let Some(Ok(line)) = lines.next() else {
+                break 'rounds;
+            };
+            if line.trim() == "q" {
+                break 'rounds;
+            }
+            match parse_guess(&line) {
+                Ok(guess) => break guess,
+                Err(e) => println!("{e}"),
+            }
+        };
+        let round = Round {
+            question: question.prompt(),
+            guess,
+            answer,
+        };
+        let ratio = round.ratio();
+        let verdict = if ratio >= 1.0 {
+            format!("{ratio:.2}x too high")
+        } else {
+            format!("{:.2}x too low", 1.0 / ratio)
+        };
+        println!(
+            "answer: {answer:.4} (1 in {:.1}), your guess was {verdict}",
+            1.0 / answer
+        );
+        stats.rounds.push(round);
+        played += 1;
+        if let Some(path) = &stats_path {
+            if let Err(e) = stats.write(path) {
+                println!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if played > 0 {
+        println!("--------------");
+        println!("{}", stats.summary(played as usize).unwrap());
+        if stats.rounds.len() > played as usize {
+            println!("{}", stats.summary(stats.rounds.len()).unwrap());
+        }
+    }
+}
+
+fn trainer(args: TrainerArgs) {
+    use poker::trainer::{parse_answer, Attempt, Draw, Tally};
+    use rand::Rng;
+    use std::io::BufRead;
+
+    let mut deck = standard_deck(args.decks, args.jokers);
+    if args.min_cards == 0
+        || args.min_cards > args.max_cards
+        || args.max_cards > MAX_CARDS.min(deck.len())
+    {
+        println!(
+            "need 0 < --min-cards <= --max-cards <= {MAX_CARDS}, and enough cards in the deck"
+        );
+        std::process::exit(1);
+    }
+    if let Err(e) = make_wild(&mut deck, args.wild) {
+        println!("{e}");
+        std::process::exit(1);
+    }
+    let (mut rng, _) = run_rng(args.seed);
+    let choices = HandRank::ALL
+        .iter()
+        .filter(|r| r.num_cards() <= args.hand_size)
+        .map(|r| r.name())
+        .collect::<Vec<_>>();
+    println!("name the best hand: {}", choices.join(", "));
+    let mut lines = std::io::stdin().lock().lines();
+    let mut tally = Tally::default();
+    'rounds: while args
+        .rounds
+        .is_none_or(|n| (tally.attempts.len() as u64) < n)
+    {
+        let num_cards = rng.gen_range(args.min_cards..=args.max_cards);
+        let draw = Draw::random(&mut deck, num_cards, args.hand_size, &mut rng);
+        println!("{draw}");
+        let start = std::time::Instant::now();
+        let answer = loop {
+            let Some(Ok(line)) = lines.next() else {
+                break 'rounds;
+            };
+            if line.trim() == "q" {
+                break 'rounds;
+            }
+            match parse_answer(&line) {
+                Ok(answer) => break answer,
+                Err(e) => println!("{e}"),
+            }
+        };
+        if answer == draw.best {
+            println!("right");
+        } else {
+            println!("no, it's {}", draw.best);
+        }
+        tally.attempts.push(Attempt {
+            best: draw.best,
+            answer,
+            time: start.elapsed(),
+        });
+    }
+    println!("--------------");
+    println!("{}", tally.summary());
+}
+
+fn economy(args: EconomyArgs) {
+    use poker::scoring::{interest, UNUSED_HAND_MONEY};
+    use rand::seq::SliceRandom;
+
+    if args.deck.cards > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    let strategy = parse_strategy(&args.discard_strategy).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    let mut deck = args.deck.deck();
+    let num_drawn = (args.deck.cards
+        + args.discards * MAX_DISCARD_CARDS
+        + args.plays.saturating_sub(1) * args.deck.hand_size)
+        .min(deck.len());
+    let effects = CardEffects {
+        steel: Vec::new(),
+        gold: args.gold,
+        gold_seal: args.gold_seal,
+    };
+    let (mut rng, _) = run_rng(args.seed);
+
+    // Totals over every round of: held gold, gold seals, unused hands and
+    // interest.
+    let mut totals = [0u64; 4];
+    for _ in 0..args.iters {
+        let (drawn, _) = deck.partial_shuffle(&mut rng, num_drawn);
+        let (hand, pile) = drawn.split_at(args.deck.cards.min(drawn.len()));
+        let (mut cards, mut num_jokers) = split_jokers(hand);
+        let num_discard_drawn = discard_and_draw(
+            &mut cards,
+            &mut num_jokers,
+            pile,
+            strategy.as_ref(),
+            args.deck.hand_size,
+            args.discards,
+            0,
+        );
+        let round = play_round(
+            &mut cards,
+            &mut num_jokers,
+            &pile[num_discard_drawn..],
+            args.deck.hand_size,
+            args.plays,
+            args.score_target,
+            &effects,
+        );
+        let beaten = args
+            .score_target
+            .is_some_and(|target| round.scores.iter().sum::<u32>() >= target);
+        let unused = if beaten {
+            (args.plays - round.scores.len()) as u32 * UNUSED_HAND_MONEY
+        } else {
+            0
+        };
+        // Seals pay out during the round, so they count toward interest.
+        let interest = interest(args.money + round.seal_money);
+        for (total, money) in
+            totals
+                .iter_mut()
+                .zip([round.gold_money, round.seal_money, unused, interest])
+        {
+            *total += money as u64;
+        }
+    }
+
+    let n = args.iters as f64;
+    println!("expected money per round over {} rounds:", args.iters);
+    let names = ["held gold cards", "gold seals", "unused hands", "interest"];
+    for (name, total) in names.iter().zip(totals) {
+        println!("{name: >15}: ${:.3}", total as f64 / n);
+    }
+    println!(
+        "{: >15}: ${:.3}",
+        "total",
+        totals.iter().sum::<u64>() as f64 / n
+    );
+}
+
+// Iterations before the stopping rule is first checked, unless the user
+// picked a batch size.
+const MIN_STOPPING_ITERS: u64 = 1_000_000;
+
+// Most iterations analytic estimates can delay the first stopping check by.
+const MAX_WARM_START_ITERS: u64 = 100_000_000;
+
+// The posterior samples use their own generator so that --ranking doesn't
+// change the deals of seeded runs.
+fn ranking_report(counts: &[HandCount], num_iters: u64, samples: usize) -> String {
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(num_iters);
+    poker::ranking::Ranking::new(counts, num_iters, samples, &mut rng).report(counts)
+}
+
+// Options which need to see every deal in order, so run on a single thread.
+fn serial_only_options(args: &SimArgs) -> Vec<&'static str> {
+    let mut ret = Vec::new();
+    for (enabled, name) in [
+        (args.compare_hand_sizes, "--compare-hand-sizes"),
+        (args.identical_cards, "--identical-cards"),
+        (args.occurrences.is_some(), "--occurrences"),
+        (args.history.is_some(), "--history"),
+        (args.snapshot_every.is_some(), "--snapshot-every"),
+        (args.dump_samples.is_some(), "--dump-samples"),
+        (args.log_deals.is_some(), "--log-deals"),
+        (args.features.is_some(), "--features"),
+        (args.correlations, "--correlations"),
+        (args.sf_breakdown, "--sf-breakdown"),
+        (args.condition.is_some(), "--condition"),
+        (!args.overlap.is_empty(), "--overlap"),
+        (args.peek > 0, "--peek"),
+        (!args.derive.is_empty(), "--derive"),
+        (args.scores, "--scores"),
+        (args.score.is_some(), "--score"),
+        (args.score_target.is_some(), "--score-target"),
+        (!args.objective.is_empty(), "--objective"),
+        (Profile::enabled(), "the profiling feature"),
+    ] {
+        if enabled {
+            ret.push(name);
+        }
+    }
+    ret
+}
+
+// Cards dealt from the deck per iteration, besides guaranteed ones: the
+// random part of the hand, then what discards and later plays draw.
+fn num_drawn(args: &SimArgs, num_random: usize, deck_len: usize) -> usize {
+    // Each play after the first refills at most a full hand.
+    (num_random
+        + args.discards * MAX_DISCARD_CARDS
+        + args.plays.saturating_sub(1) * args.deck.hand_size)
+        .min(deck_len)
+}
+
+// Deals per unit of work handed to a thread.
+const DEAL_CHUNK: u64 = 1 << 13;
+
+// Deals `iters` hands in chunks spread across the current rayon pool, each
+// chunk with its own RNG seeded from `rng`, and returns how many deals made
+// each hand. The seeds don't depend on the number of threads, so seeded runs
+// give the same results with any --threads.
+fn deal_parallel(
+    args: &SimArgs,
+    deck: &[CardOrJoker],
+    guaranteed: &[CardOrJoker],
+    counts: &[HandCount],
+    strategy: &(dyn DiscardStrategy + Sync),
+    iters: u64,
+    rng: &mut rand::rngs::StdRng,
+) -> Vec<u64> {
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use rayon::prelude::*;
+
+    let num_random = args.deck.cards - guaranteed.len();
+    let num_drawn = num_drawn(args, num_random, deck.len());
+    let seeds = (0..iters.div_ceil(DEAL_CHUNK))
+        .map(|_| rng.gen::<u64>())
+        .collect::<Vec<_>>();
+    seeds
+        .into_par_iter()
+        .enumerate()
+        .map(|(chunk, seed)| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let mut deck = deck.to_vec();
+            let plan = Plan::new(counts);
+            let mut hits = vec![0u64; counts.len()];
+            let start = chunk as u64 * DEAL_CHUNK;
+            for _ in start..(start + DEAL_CHUNK).min(iters) {
+                let (drawn, _) = deck.partial_shuffle(&mut rng, num_drawn);
+                let (cards_or_jokers, pile) = drawn.split_at(num_random.min(drawn.len()));
+                let (mut cards, mut num_jokers) =
+                    split_jokers_iter(guaranteed.iter().chain(cards_or_jokers).copied());
+                discard_and_draw(
+                    &mut cards,
+                    &mut num_jokers,
+                    pile,
+                    strategy,
+                    args.deck.hand_size,
+                    args.discards,
+                    0,
+                );
+                let distinct;
+                let detected: &[Card] = if args.distinct_cards {
+                    distinct = distinct_cards(&cards);
+                    &distinct
+                } else {
+                    &cards
+                };
+                let mask = plan.evaluate(counts, detected, num_jokers);
+                for (h, hit) in hits.iter_mut().enumerate() {
+                    *hit += mask >> h & 1;
+                }
+            }
+            hits
+        })
+        .reduce(
+            || vec![0; counts.len()],
+            |mut a, b| {
+                for (x, y) in a.iter_mut().zip(b) {
+                    *x += y;
+                }
+                a
+            },
+        )
+}
+
+// Parses "K:N" for --perturb.
+fn parse_perturb(s: &str) -> Result<(usize, usize), String> {
+    let parse = |n: &str| n.trim().parse::<usize>().map_err(|e| format!("'{n}': {e}"));
+    let Some((edits, variations)) = s.split_once(':') else {
+        return Err(format!("expected K:N, got '{s}'"));
+    };
+    let variations = parse(variations)?;
+    if variations == 0 {
+        return Err("need at least one variation".to_string());
+    }
+    Ok((parse(edits)?, variations))
+}
+
+// Parses a confidence interval half-width for --target-ci.
+fn parse_ci_width(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(w) if w > 0.0 && w.is_finite() => Ok(w),
+        Ok(_) => Err("must be positive".to_string()),
+        Err(e) => Err(format!("'{s}': {e}")),
+    }
+}
+
+// Lists the hands `hand` makes, with the same --hand-size and --min-rank
+// treatment as a simulation.
+fn print_hand(args: &SimArgs, hand: &str) {
+    let (cards, num_jokers) = parse_hand(hand).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    if cards.len() + num_jokers as usize > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    let counts = sim_counts(args);
+    println!("{}", format_hand(&cards, num_jokers));
+    for c in counts.iter().filter(|c| c.matches(&cards, num_jokers)) {
+        println!("  {}", c.name);
+    }
+    let best = evaluate_best_skipping(
+        &cards,
+        num_jokers,
+        args.deck.hand_size,
+        args.deck.skipped_in_straights(),
+    );
+    println!("best: {}", best.name());
+}
+
+fn perturb(args: SimArgs, edits: usize, variations: usize) {
+    let deck = args.deck.deck();
+    let mut counts = sim_counts(&args);
+    remove_impossible_hands(
+        &mut counts,
+        args.deck.cards.min(deck.len()),
+        args.impossible_hands,
+    );
+    let (mut rng, seed) = run_rng(args.seed);
+    let perturbation = Perturbation::run(
+        &deck,
+        args.deck.cards,
+        &counts,
+        edits,
+        variations,
+        args.perturb_iters,
+        &mut rng,
+    );
+
+    println!("{variations} decks with {edits} random cards removed or added:");
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    println!(
+        "{:>width$}     mean  std dev    noise      min      max",
+        "",
+        width = max_str_len
+    );
+    for (h, c) in counts.iter().enumerate() {
+        let (lo, hi) = perturbation.range(h);
+        println!(
+            "{name: >width$} {:.6} {:.6} {:.6} {lo:.6} {hi:.6}",
+            perturbation.mean(h),
+            perturbation.std_dev(h),
+            perturbation.sampling_noise(h),
+            name = c.name,
+            width = max_str_len,
+        );
+    }
+    println!("(noise is the std dev expected from sampling alone)");
+
+    if let Some(path) = &args.perturb_table {
+        let config = run_config(&args.deck, 0, "keep-best", 0);
+        let metadata = poker::metadata::collect(&config, Some(seed));
+        let columns = std::iter::once(("variation".to_string(), ColumnType::U64))
+            .chain(counts.iter().map(|c| (c.name.to_string(), ColumnType::F64)))
+            .collect::<Vec<_>>();
+        let rows = perturbation
+            .probabilities
+            .iter()
+            .enumerate()
+            .map(|(v, p)| std::iter::once(v as f64).chain(p.iter().copied()).collect());
+        write_table(path, &metadata, &columns, rows);
+    }
+}
+
+fn random_decks(args: &SimArgs, spec: &RandomDeckSpec) {
+    let mut counts = sim_counts(args);
+    remove_impossible_hands(
+        &mut counts,
+        args.deck.cards.min(spec.size + spec.jokers as usize),
+        args.impossible_hands,
+    );
+    let (mut rng, _) = run_rng(args.seed);
+    let population = Perturbation::run_decks(
+        args.deck.cards,
+        &counts,
+        args.random_decks,
+        args.random_deck_iters,
+        &mut rng,
+        |rng| spec.generate(rng),
+    );
+
+    println!("{} random decks of {} cards:", args.random_decks, spec.size);
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    println!(
+        "{:>width$}     mean  std dev    noise       p5      p50      p95      min      max",
+        "",
+        width = max_str_len
+    );
+    for (h, c) in counts.iter().enumerate() {
+        let (lo, hi) = population.range(h);
+        println!(
+            "{name: >width$} {:.6} {:.6} {:.6} {:.6} {:.6} {:.6} {lo:.6} {hi:.6}",
+            population.mean(h),
+            population.std_dev(h),
+            population.sampling_noise(h),
+            population.quantile(h, 0.05),
+            population.quantile(h, 0.5),
+            population.quantile(h, 0.95),
+            name = c.name,
+            width = max_str_len,
+        );
+    }
+    println!("(noise is the std dev expected from sampling alone)");
+}
+
+fn multi_board(args: SimArgs, boards: usize) {
+    let deck = args.deck.deck();
+    let mut counts = sim_counts(&args);
+    remove_impossible_hands(
+        &mut counts,
+        args.deck.cards.min(deck.len()),
+        args.impossible_hands,
+    );
+    let (mut rng, _) = run_rng(args.seed);
+    let result = MultiBoard::run(
+        &deck,
+        args.deck.cards,
+        &counts,
+        boards,
+        args.board_iters,
+        &mut rng,
+    )
+    .unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+
+    println!("{boards} boards of {} cards:", args.deck.cards);
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    println!(
+        "{:>width$}    board      any     some    every",
+        "",
+        width = max_str_len
+    );
+    for (h, c) in counts.iter().enumerate() {
+        println!(
+            "{name: >width$} {:.6} {:.6} {:.6} {:.6}",
+            result.per_board(h),
+            result.any(h),
+            result.some(h),
+            result.every(h),
+            name = c.name,
+            width = max_str_len,
+        );
+    }
+    println!("(some is some boards but not all)");
+}
+
+fn bomb_pot(args: SimArgs, players: usize, boards: usize) {
+    if args.board_size == 0 {
+        println!("--boards with --players needs --board-size");
+        std::process::exit(1);
+    }
+    // Kickers are only defined for plain 5-card hands.
+    let deck = args.deck.deck();
+    let plain = |c: &CardOrJoker| matches!(c, CardOrJoker::Card(card) if !card.wild);
+    if args.deck.hand_size != 5 || args.deck.short_straights || !deck.iter().all(plain) {
+        println!(
+            "--boards with --players breaks ties between plain 5-card hands, so it can't take --hand-size, --short-straights, jokers or wild cards"
+        );
+        std::process::exit(1);
+    }
+    let rank: fn(&[Card]) -> Option<Strength> = match args.mode {
+        RuleSet::Independent | RuleSet::Best => best_strength,
+        RuleSet::Omaha => omaha::high_strength,
+        RuleSet::Razz | RuleSet::DeuceToSeven | RuleSet::Badugi => {
+            println!("--boards with --players only splits pots by high hands, not lows");
+            std::process::exit(1);
+        }
+    };
+    let table = poker::boards::Table {
+        players,
+        hole_cards: args.deck.cards,
+        boards,
+        board_size: args.board_size,
+    };
+    let (mut rng, _) = run_rng(args.seed);
+    let result = BombPot::run(
+        &deck,
+        &table,
+        |cards, _| rank(cards),
+        args.board_iters,
+        &mut rng,
+    )
+    .unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+
+    println!(
+        "{players} players of {} cards, {boards} boards of {} cards:",
+        args.deck.cards, args.board_size
+    );
+    println!("player   equity    scoop    split");
+    for p in 0..players {
+        println!(
+            "{:>6} {:.6} {:.6} {:.6}",
+            p + 1,
+            result.equity(p),
+            result.scoop(p),
+            result.split(p)
+        );
+    }
+    println!("(equity is the average share of the pot, scoop is winning every board alone,");
+    println!(" split is winning some of the pot but not all)");
+}
+
+fn multi_player(args: SimArgs, players: usize) {
+    let player = args.player.map(|p| p as usize - 1);
+    if player.is_some_and(|p| p >= players) {
+        println!("--player must be at most --players ({players})");
+        std::process::exit(1);
+    }
+    let deck = args.deck.deck();
+    let mut counts = sim_counts(&args);
+    remove_impossible_hands(
+        &mut counts,
+        (args.deck.cards + args.board_size).min(deck.len()),
+        args.impossible_hands,
+    );
+    let (mut rng, _) = run_rng(args.seed);
+    let result = Players::run(
+        &deck,
+        args.deck.cards,
+        args.board_size,
+        &counts,
+        players,
+        args.player_iters,
+        &mut rng,
+    )
+    .unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+
+    match args.board_size {
+        0 => println!("{players} players of {} cards:", args.deck.cards),
+        board => println!(
+            "{players} players of {} cards with {board} board cards:",
+            args.deck.cards
+        ),
+    }
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap();
+    let header = match player {
+        Some(p) => format!(" {:>8}", format!("player {}", p + 1)),
+        None => String::new(),
+    };
+    println!(
+        "{:>width$}     each      any{header}",
+        "",
+        width = max_str_len
+    );
+    for (h, c) in counts.iter().enumerate() {
+        let seat = match player {
+            Some(p) => format!(" {:.6}", result.player(h, p)),
+            None => String::new(),
+        };
+        println!(
+            "{name: >width$} {:.6} {:.6}{seat}",
+            result.each(h),
+            result.any(h),
+            name = c.name,
+            width = max_str_len,
+        );
+    }
+    println!("(each is one player, any is at least one player)");
+}
+
+// Omaha counts take the first cards of each deal as the hole cards, so the
+// deal has to keep its order and its jokers' places.
+fn check_omaha(args: &SimArgs) {
+    let error = if args.deck.cards != omaha::HOLE_CARDS {
+        format!(
+            "--mode omaha deals {} hole cards, use --cards {0}",
+            omaha::HOLE_CARDS
+        )
+    } else if args.board_size < omaha::MIN_BOARD {
+        format!(
+            "--mode omaha needs --board-size of at least {}",
+            omaha::MIN_BOARD
+        )
+    } else if args.exact {
+        "--mode omaha does not support --exact, which doesn't keep hole and board cards apart"
+            .to_string()
+    } else if args.discards > 0 {
+        "--mode omaha does not support --discards".to_string()
+    } else if args.deck.deck().contains(&CardOrJoker::Joker) {
+        "--mode omaha does not support jokers".to_string()
+    } else {
+        return;
+    };
+    println!("{error}");
+    std::process::exit(1);
+}
+
+fn simulate(mut args: SimArgs) {
+    use rand::seq::SliceRandom;
+
+    if args.deck.cards + args.board_size > MAX_CARDS {
+        println!("Does not support more than {} cards", MAX_CARDS);
+        std::process::exit(1);
+    }
+    match args.mode {
+        RuleSet::Omaha => check_omaha(&args),
+        // Discard strategies chase high hands, so they'd throw lows away.
+        RuleSet::Razz | RuleSet::DeuceToSeven | RuleSet::Badugi if args.discards > 0 => {
+            println!("--mode {} does not support --discards, fix the kept cards with --guarantee instead",
+                clap::ValueEnum::to_possible_value(&args.mode).unwrap().get_name());
+            std::process::exit(1);
+        }
+        _ => {}
+    }
+    if let Some(players) = args.players {
+        match args.boards {
+            Some(boards) => bomb_pot(args, players as usize, boards as usize),
+            None => multi_player(args, players as usize),
+        }
+        return;
+    }
+    if args.boards.is_some() && args.board_size > 0 {
+        println!("--boards only deals --board-size with --players");
+        std::process::exit(1);
+    }
+    // A lone player's cards and the board are just a bigger draw.
+    args.deck.cards += args.board_size;
+    if args.exact {
+        exact(args);
+        return;
+    }
+    if let Some((edits, variations)) = args.perturb {
+        perturb(args, edits, variations);
+        return;
+    }
+    if let Some(spec) = &args.random_deck {
+        random_decks(&args, spec);
+        return;
+    }
+    if let Some(boards) = args.boards {
+        multi_board(args, boards as usize);
+        return;
+    }
+    if let Some(hand) = &args.hand {
+        print_hand(&args, hand);
+        return;
+    }
+
+    let mut config = run_config(&args.deck, args.discards, &args.discard_strategy, args.peek);
+    let guaranteed = match &args.guarantee {
+        Some(cards) => {
+            let (cards, num_jokers) = parse_hand(cards).unwrap_or_else(|e| {
+                println!("{e}");
+                std::process::exit(1);
+            });
+            if cards.len() + num_jokers as usize > args.deck.cards {
+                println!("--guarantee has more cards than --cards");
+                std::process::exit(1);
+            }
+            config["guarantee"] = format_hand(&cards, num_jokers).into();
+            let mut guaranteed = cards.into_iter().map(CardOrJoker::Card).collect::<Vec<_>>();
+            guaranteed.extend((0..num_jokers).map(|_| CardOrJoker::Joker));
+            guaranteed
+        }
+        None => Vec::new(),
+    };
+    // Only the rest of each draw is random.
+    let num_random = args.deck.cards - guaranteed.len();
+    // Read before simulating so a bad file doesn't waste the run.
+    let baseline = args
+        .baseline
+        .as_ref()
+        .map(|path| read_baseline(path, &config));
+    // Opened before simulating too, for the same reason.
+    let mut sinks: Vec<Box<dyn OutputSink>> = vec![Box::new(poker::sink::Terminal {
+        format: args.format,
+        baseline,
+    })];
+    if let Some(path) = &args.output {
+        sinks.push(Box::new(poker::sink::JsonFile(path.clone())));
+    }
+    if let Some(path) = &args.live_file {
+        sinks.push(Box::new(poker::sink::LiveJson(path.clone())));
+    }
+    for spec in &args.out {
+        sinks.push(spec.open().unwrap_or_else(|e| {
+            println!("Could not open {e}");
+            std::process::exit(1);
+        }));
+    }
+    let sink_error = |e: String| -> ! {
+        println!("Could not write {e}");
+        std::process::exit(1);
+    };
+    let (mut rng, seed) = run_rng(args.seed);
+    let metadata = poker::metadata::collect(&config, Some(seed));
+    let mut deck = args.deck.deck();
+    if let Err(e) = poker::deck::take_cards(&mut deck, &guaranteed) {
+        println!("--guarantee: {e}");
+        std::process::exit(1);
+    }
+
+    let mut counts = sim_counts(&args);
+    let warnings = Vec::from_iter(remove_impossible_hands(
+        &mut counts,
+        num_random.min(deck.len()) + guaranteed.len(),
+        args.impossible_hands,
+    ));
+    let find_hand = |hand: &str| {
+        counts
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(hand))
+            .unwrap_or_else(|| {
+                let names = counts.iter().map(|c| c.name).collect::<Vec<_>>();
+                println!(
+                    "Unknown hand '{hand}', expected one of: {}",
+                    names.join(", ")
+                );
+                std::process::exit(1);
+            })
+    };
+    if let Some(hand) = &args.headline {
+        find_hand(hand);
+    }
+    let condition = args.condition.as_deref().map(find_hand);
+    let overlaps = args
+        .overlap
+        .iter()
+        .map(|pair| {
+            let Some((a, b)) = pair.split_once(',') else {
+                println!("--overlap takes two hands like 'Strt Flush,Flush', not '{pair}'");
+                std::process::exit(1);
+            };
+            (find_hand(a.trim()), find_hand(b.trim()))
+        })
+        .collect::<Vec<_>>();
+
+    let serial_only = serial_only_options(&args);
+    let threads = match args.threads {
+        Some(threads) if threads > 1 && !serial_only.is_empty() => {
+            println!("--threads does not support {}", serial_only.join(", "));
+            std::process::exit(1);
+        }
+        Some(threads) => threads.max(1),
+        None if !serial_only.is_empty() => 1,
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    // Even one thread deals through `deal_parallel`, so that seeded runs
+    // don't depend on the thread count. Only options which need every deal
+    // in order use the serial loop below.
+    let pool = serial_only.is_empty().then(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+    });
+
+    let strategy = parse_strategy(&args.discard_strategy).unwrap_or_else(|e| {
+        println!("{e}");
+        std::process::exit(1);
+    });
+    let num_drawn = num_drawn(&args, num_random, deck.len());
+
+    let skipped = args.deck.skipped_in_straights();
+    let mut peek_lift = (args.peek > 0).then(|| PeekLift::new(counts.len(), skipped));
+
+    let mut hand_size_comparison = args
+        .compare_hand_sizes
+        .then(|| HandSizeComparison::new(skipped));
+    let mut identical_cards = args.identical_cards.then(IdenticalCards::default);
+
+    let mut score_target = args.score_target.map(ScoreTarget::new);
+    let mut objectives = parse_objectives(&args.objective)
+        .into_iter()
+        .map(|objective| (objective, ObjectiveStats::default()))
+        .collect::<Vec<_>>();
+    let card_effects = CardEffects {
+        steel: args.steel.clone(),
+        gold: args.gold.clone(),
+        gold_seal: Vec::new(),
+    };
+
+    let mut observers: Vec<Box<dyn Observer>> = Vec::new();
+    if let Some(path) = &args.history {
+        let table = create_table(path, &metadata);
+        observers.push(Box::new(History::new(table)));
+    }
+    if let (Some(every), Some(path)) = (args.snapshot_every, &args.snapshot_file) {
+        let (mut table, is_new) = poker::table::append(path).unwrap_or_else(|e| {
+            println!("{e}");
+            std::process::exit(1);
+        });
+        // Appended runs keep the first run's metadata.
+        if is_new {
+            if let Err(e) = table.write_metadata(&metadata) {
+                println!("Could not write {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+        observers.push(Box::new(Snapshots::new(
+            table,
+            &counts,
+            every,
+            args.ci_method,
+            is_new,
+        )));
+    }
+    if let (Some(limit), Some(path)) = (args.log_deals, &args.log_file) {
+        let file = std::fs::File::create(path).unwrap_or_else(|e| {
+            println!("Could not create {}: {e}", path.display());
+            std::process::exit(1);
+        });
+        let mut file = std::io::BufWriter::new(file);
+        // Replaying with the same seed and options reproduces these deals.
+        poker::metadata::write_comments(&metadata, &mut file).unwrap();
+        observers.push(Box::new(SampleDump::new(file, &counts, limit)));
+    }
+    if let Some(path) = &args.features {
+        let table = create_table(path, &metadata);
+        observers.push(Box::new(FeatureWriter::new(
+            table,
+            &counts,
+            args.deck.hand_size.min(6),
+            args.feature_deals.unwrap_or(u64::MAX),
+        )));
+    }
+    if let Some(limit) = args.dump_samples {
+        observers.push(Box::new(SampleDump::new(std::io::stdout(), &counts, limit)));
+    }
+    if args.correlations {
+        observers.push(Box::new(Correlations::new(counts.len())));
+    }
+    if args.sf_breakdown {
+        observers.push(Box::new(StraightFlushBreakdown::new(
+            args.deck.skipped_in_straights(),
+        )));
+    }
+    if let Some(condition) = condition {
+        observers.push(Box::new(Conditional::new(condition, counts.len())));
+    }
+    if !overlaps.is_empty() {
+        observers.push(Box::new(Overlaps::new(overlaps, counts.len())));
+    }
+    for derived in &args.derive {
+        observers.push(Box::new(derived.clone()));
+    }
+    if args.scores {
+        observers.push(Box::new(ScoreDistribution::new(
+            args.deck.hand_size,
+            args.deck.skipped_in_straights(),
+        )));
+    }
+    if let Some(ScoreMode::Balatro) = args.score {
+        let table = match &args.score_table {
+            Some(path) => ScoreTable::read(path).unwrap_or_else(|e| {
+                println!("{e}");
+                std::process::exit(1);
+            }),
+            None => ScoreTable::default(),
+        };
+        observers.push(Box::new(ExpectedScore::new(args.deck.hand_size, table)));
+    }
+
+    let mut profile = Profile::new(counts.iter().map(|c| c.name));
+    let min_stopping_iters = if args.batch_size.is_none() {
+        MIN_STOPPING_ITERS
+    } else {
+        0
+    };
+    // Only for telling hands apart; --target-ci asks its own question.
+    let mut min_separation_iters = 0;
+    // Analytic estimates give an informative first report, and tell the
+    // stopping rule roughly when the hands will be told apart. They assume
+    // the initial deal is evaluated as is, with no wild cards.
+    if args.discards == 0
+        && !args.distinct_cards
+        && args.deck.wild == 0
+        && args.deck.deck_file.is_none()
+        && args.deck.deck_preset == Preset::Standard
+        && guaranteed.is_empty()
+    {
+        let estimates = estimate(args.deck.decks, args.deck.jokers, args.deck.cards, &counts);
+        println!("analytic estimates:");
+        print_estimates(&counts, &estimates);
+        if let Some(n) = iterations_to_separate(&estimates) {
+            println!("estimated hands separate after about {n} iterations");
+            if args.batch_size.is_none() {
+                min_separation_iters = n.min(MAX_WARM_START_ITERS);
+            }
+        }
+        println!("--------------");
+    }
+
+    let mut batch_sizer = match (args.batch_size, args.seed) {
+        (Some(size), _) => BatchSizer::fixed(size),
+        (None, Some(_)) => BatchSizer::fixed(MIN_STOPPING_ITERS),
+        (None, None) => BatchSizer::auto(std::time::Duration::from_millis(1500)),
+    };
+    let mut num_iters: u64 = 0;
+
+    let max_iters = args.max_iters.unwrap_or(u64::MAX);
+    let run_start = std::time::Instant::now();
+    let mut progress_file = args.progress_file.as_ref().map(|path| {
+        ProgressFile::create(path).unwrap_or_else(|e| {
+            println!("Could not create {}: {e}", path.display());
+            std::process::exit(1);
+        })
+    });
+    let mut update_progress_file = |counts: &[HandCount], num_iters, done| {
+        if let Some(progress_file) = &mut progress_file {
+            progress_file
+                .update(counts, num_iters, args.max_iters, run_start.elapsed(), done)
+                .unwrap();
+        }
+    };
+    let plan = Plan::new(&counts);
+    let stop_reason = loop {
+        let batch_start = std::time::Instant::now();
+        let batch_size = batch_sizer.next().min(max_iters - num_iters);
+        if let Some(pool) = &pool {
+            let hits = pool.install(|| {
+                deal_parallel(
+                    &args,
+                    &deck,
+                    &guaranteed,
+                    &counts,
+                    strategy.as_ref(),
+                    batch_size,
+                    &mut rng,
+                )
+            });
+            for (c, hits) in counts.iter_mut().zip(hits) {
+                c.count += hits;
+            }
+            num_iters += batch_size;
+        }
+        // With serial-only options, the batch is dealt here one deal at a
+        // time.
+        let serial_iters = if pool.is_some() { 0 } else { batch_size };
+        for _ in 0..serial_iters {
+            let start = Profile::start();
+            let (drawn, _) = deck.partial_shuffle(&mut rng, num_drawn);
+            let (cards_or_jokers, pile) = drawn.split_at(num_random.min(drawn.len()));
+            let (mut cards, mut num_jokers) =
+                split_jokers_iter(guaranteed.iter().chain(cards_or_jokers).copied());
+            profile.add_stage(Stage::Deal, start);
+            let start = Profile::start();
+            // Without peeking, for comparison against the same deal.
+            let baseline = peek_lift.is_some().then(|| {
+                let mut baseline_cards = cards.clone();
+                let mut baseline_jokers = num_jokers;
+                discard_and_draw(
+                    &mut baseline_cards,
+                    &mut baseline_jokers,
+                    pile,
+                    strategy.as_ref(),
+                    args.deck.hand_size,
+                    args.discards,
+                    0,
+                );
+                (baseline_cards, baseline_jokers)
+            });
+            let num_discard_drawn = discard_and_draw(
+                &mut cards,
+                &mut num_jokers,
+                pile,
+                strategy.as_ref(),
+                args.deck.hand_size,
+                args.discards,
+                args.peek,
+            );
+            if let (Some(lift), Some((baseline_cards, baseline_jokers))) =
+                (&mut peek_lift, &baseline)
+            {
+                lift.record(
+                    &counts,
+                    args.deck.hand_size,
+                    (baseline_cards, *baseline_jokers),
+                    (&cards, num_jokers),
+                );
+            }
+            profile.add_stage(Stage::Discard, start);
+            if let Some(identical) = &mut identical_cards {
+                identical.record(&cards);
+            }
+            let distinct;
+            let detected: &[Card] = if args.distinct_cards {
+                distinct = distinct_cards(&cards);
+                &distinct
+            } else {
+                &cards
+            };
+            let start = Profile::start();
+            // Timing each detector needs them run one at a time.
+            let category_mask = if Profile::enabled() {
+                let mut category_mask = 0;
+                for (i, c) in counts.iter().enumerate() {
+                    let detector_start = Profile::start();
+                    let hit = c.matches(detected, num_jokers);
+                    profile.add_detector(i, detector_start);
+                    category_mask |= u64::from(hit) << i;
+                }
+                category_mask
+            } else {
+                plan.evaluate(&counts, detected, num_jokers)
+            };
+            for (i, c) in counts.iter_mut().enumerate() {
+                if category_mask & 1 << i != 0 {
+                    c.record(num_iters);
+                }
+            }
+            profile.add_stage(Stage::Detectors, start);
+            let start = Profile::start();
+            for o in &mut observers {
+                o.on_deal(detected, num_jokers, category_mask);
+            }
+            profile.add_deal(!observers.is_empty());
+            if let Some(comparison) = &mut hand_size_comparison {
+                comparison.record(detected, num_jokers);
+            }
+            profile.add_stage(Stage::Observers, start);
+            let start = Profile::start();
+            if score_target.is_some() || !objectives.is_empty() {
+                let round = play_round(
+                    &mut cards.clone(),
+                    &mut num_jokers.clone(),
+                    &pile[num_discard_drawn..],
+                    args.deck.hand_size,
+                    args.plays,
+                    None,
+                    &card_effects,
+                );
+                if let Some(score_target) = &mut score_target {
+                    score_target.record(&round);
+                }
+                let outcome = Outcome {
+                    cards: detected,
+                    num_jokers,
+                    round: &round,
+                };
+                for (objective, stats) in &mut objectives {
+                    stats.record(objective.value(&outcome));
+                }
+            }
+            profile.add_stage(Stage::Scoring, start);
+            num_iters += 1;
+        }
+        batch_sizer.record(batch_size, batch_start.elapsed());
+        let snapshot = Snapshot {
+            num_iters,
+            counts: &counts,
+            ci_method: args.ci_method,
+        };
+        for o in &mut observers {
+            o.on_batch_end(&snapshot);
+        }
+        update_progress_file(&counts, num_iters, false);
+        let mut result = RunResult::new(config.clone(), &counts, num_iters);
+        result.set_ci_method(args.ci_method);
+        result.warnings.splice(0..0, warnings.iter().cloned());
+        poker::sink::on_batch(&mut sinks, &result).unwrap_or_else(|e| sink_error(e));
+        if args.tui {
+            print!(
+                "{}{}",
+                poker::tui::CLEAR,
+                poker::tui::frame(
+                    &counts,
+                    num_iters,
+                    args.ci_method,
+                    run_start.elapsed(),
+                    args.max_iters,
+                )
+            );
+            std::io::Write::flush(&mut std::io::stdout()).unwrap();
+        } else {
+            println!("{num_iters} iterations...");
+        }
+        let done = match args.target_ci {
+            Some(width) => counts
+                .iter()
+                .all(|c| interval_with(args.ci_method, num_iters, c.count).1 < width)
+                .then(|| format!("all 99.73% confidence intervals narrower than ± {width}")),
+            None => {
+                let has_overlap = counts.iter().enumerate().any(|(idx, c1)| {
+                    counts
+                        .iter()
+                        .skip(idx + 1)
+                        .any(|c2| c1.overlap(args.ci_method, num_iters, c2))
+                });
+                (!has_overlap && num_iters >= min_separation_iters)
+                    .then(|| "no overlapping 99% confidence intervals".to_string())
+            }
+        };
+        // Small automatic batches are for feedback; too few deals have been
+        // seen for the confidence intervals to mean much yet.
+        if let Some(done) = done.filter(|_| num_iters >= min_stopping_iters) {
+            break done;
+        }
+        if num_iters >= max_iters {
+            break "reached --max-iters".to_string();
+        }
+        if !args.tui {
+            print_counts(&counts, num_iters, args.ci_method);
+            if args.ranking {
+                println!(
+                    "{}",
+                    ranking_report(&counts, num_iters, args.ranking_samples)
+                );
+            }
+            println!("--------------");
+        }
+    };
+    update_progress_file(&counts, num_iters, true);
+    println!("({stop_reason})");
+    if args.ranking {
+        println!(
+            "{}",
+            ranking_report(&counts, num_iters, args.ranking_samples)
+        );
+    }
+    let mut result = RunResult::new(config, &counts, num_iters);
+    result.set_ci_method(args.ci_method);
+    result.metadata = Some(metadata);
+    result.warnings.splice(0..0, warnings);
+    poker::sink::on_finish(&mut sinks, &result).unwrap_or_else(|e| sink_error(e));
+    if let Some(hand) = &args.headline {
+        println!("{}", result.headline(hand).unwrap());
+    }
+    if let Some(n) = args.occurrences {
+        println!("--------------");
+        print_occurrences(&counts, n);
+    }
+    if let Some(comparison) = &hand_size_comparison {
+        println!("--------------");
+        comparison.print(num_iters);
+    }
+    if let Some(identical) = &identical_cards {
+        println!("--------------");
+        identical.print(num_iters, args.ci_method);
+    }
+    if let Some(lift) = &peek_lift {
+        println!("--------------");
+        lift.print(&counts, num_iters);
+    }
+    if let Some(score_target) = &score_target {
+        println!("--------------");
+        score_target.print(num_iters, args.plays, args.ci_method);
+    }
+    if !objectives.is_empty() {
+        println!("--------------");
+        for (objective, stats) in &objectives {
+            let (mean, ci) = stats.mean(args.ci_method);
+            println!("{}: {mean:.6} ± {ci:.6}", objective.name());
+        }
+    }
+    let snapshot = Snapshot {
+        num_iters,
+        counts: &counts,
+        ci_method: args.ci_method,
+    };
+    for o in &mut observers {
+        o.on_finish(&snapshot);
+    }
+    if Profile::enabled() {
+        println!("--------------");
+        print!("{}", profile.report());
     }
 }
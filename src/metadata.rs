@@ -0,0 +1,220 @@
+//! Where an output file came from, so it can still be interpreted months
+//! later: the crate version and git commit, the command line, the resolved
+//! configuration, the seed, the host and the time.
+//!
+//! JSON result files hold it as a "metadata" object, CSV files start with
+//! "# key: value" comment lines and Arrow and Parquet files carry it as schema
+//! metadata. `poker info` prints it from any of them. SQLite output, and so
+//! metadata in it, is still a follow-up request.
+
+use serde_json::{Map, Value};
+use std::io::Write;
+
+pub type Metadata = Map<String, Value>;
+
+// Metadata for a run with `config`. `seed` is None when the run isn't random
+// or had no single seed.
+pub fn collect(config: &Value, seed: Option<u64>) -> Metadata {
+    let mut metadata = Metadata::new();
+    metadata.insert("version".into(), env!("CARGO_PKG_VERSION").into());
+    metadata.insert(
+        "git".into(),
+        option_env!("POKER_GIT_HASH").unwrap_or("unknown").into(),
+    );
+    metadata.insert(
+        "command".into(),
+        std::env::args().collect::<Vec<_>>().into(),
+    );
+    metadata.insert("config".into(), config.clone());
+    if let Some(seed) = seed {
+        metadata.insert("seed".into(), seed.into());
+    }
+    metadata.insert("host".into(), hostname().into());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    metadata.insert("time".into(), utc_timestamp(now).into());
+    metadata
+}
+
+fn hostname() -> String {
+    if let Ok(host) = std::env::var("HOSTNAME") {
+        return host;
+    }
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map_or("unknown".to_string(), |host| host.trim().to_string())
+}
+
+// Seconds since the Unix epoch as "2024-01-31T12:00:00Z".
+fn utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+    // Howard Hinnant's days_from_civil, inverted.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+// Strings as they are, anything else as compact JSON.
+fn flat_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+// Key and value pairs for formats which only hold strings.
+pub fn to_strings(metadata: &Metadata) -> Vec<(String, String)> {
+    metadata
+        .iter()
+        .map(|(k, v)| (k.clone(), flat_value(v)))
+        .collect()
+}
+
+// The inverse of `to_strings`, except that numbers come back as strings.
+pub fn from_strings<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Metadata {
+    pairs
+        .into_iter()
+        .map(|(k, v)| {
+            // A git hash can look like a number, so only objects and arrays
+            // are parsed.
+            let value = match serde_json::from_str::<Value>(v) {
+                Ok(v) if v.is_object() || v.is_array() => v,
+                _ => Value::String(v.to_string()),
+            };
+            (k.to_string(), value)
+        })
+        .collect()
+}
+
+// "# key: value" lines to start a CSV file with.
+pub fn write_comments(metadata: &Metadata, mut w: impl Write) -> std::io::Result<()> {
+    for (k, v) in to_strings(metadata) {
+        writeln!(w, "# {k}: {v}")?;
+    }
+    Ok(())
+}
+
+// Metadata from the comment lines at the start of a CSV file.
+pub fn from_comments(text: &str) -> Metadata {
+    from_strings(
+        text.lines()
+            .map_while(|line| line.strip_prefix("# "))
+            .filter_map(|line| line.split_once(": ")),
+    )
+}
+
+// Reads the metadata of a JSON result file, or a table written by
+// `table::create`.
+pub fn read(path: &std::path::Path) -> Result<Metadata, String> {
+    let error = |e: String| format!("{}: {e}", path.display());
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let metadata = if ext.eq_ignore_ascii_case("json") {
+        let text = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let value = serde_json::from_str::<Value>(&text).map_err(|e| error(e.to_string()))?;
+        match value.get("metadata") {
+            Some(Value::Object(metadata)) => metadata.clone(),
+            _ => Metadata::new(),
+        }
+    } else {
+        crate::table::read_metadata(path)?
+    };
+    if metadata.is_empty() {
+        return Err(error(
+            "no metadata, it may predate metadata being recorded".to_string(),
+        ));
+    }
+    Ok(metadata)
+}
+
+// One "key: value" line per entry, with objects like the configuration
+// spread over indented lines.
+pub fn format(metadata: &Metadata) -> String {
+    fn write_map(out: &mut String, map: &Map<String, Value>, indent: usize) {
+        let width = map.keys().map(|k| k.len() + 1).max().unwrap_or(0);
+        for (k, v) in map {
+            let label = format!("{k}:");
+            let value = match v {
+                Value::Object(inner) => {
+                    *out += &format!("{:indent$}{label}\n", "");
+                    write_map(out, inner, indent + 2);
+                    continue;
+                }
+                Value::Array(items) => items.iter().map(flat_value).collect::<Vec<_>>().join(" "),
+                v => flat_value(v),
+            };
+            *out += &format!("{:indent$}{label: <width$} {value}\n", "");
+        }
+    }
+
+    let mut out = String::new();
+    write_map(&mut out, metadata, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!("1970-01-01T00:00:00Z", utc_timestamp(0));
+        assert_eq!("2000-02-29T23:59:59Z", utc_timestamp(951868799));
+        assert_eq!("2024-12-31T12:34:56Z", utc_timestamp(1735648496));
+    }
+
+    #[test]
+    fn test_collect() {
+        let metadata = collect(&json!({ "cards": 7 }), Some(3));
+        let keys = metadata.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["version", "git", "command", "config", "seed", "host", "time"],
+            keys
+        );
+        assert_eq!(json!({ "cards": 7 }), metadata["config"]);
+        assert!(!collect(&json!({}), None).contains_key("seed"));
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut metadata = Metadata::new();
+        metadata.insert("version".into(), "0.1.0".into());
+        metadata.insert("config".into(), json!({ "cards": 7, "strategy": "a b" }));
+        metadata.insert("seed".into(), "12".into());
+        let mut out = Vec::new();
+        write_comments(&metadata, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap() + "iterations,Pair\n# not: metadata\n";
+        assert!(text.starts_with(
+            "# version: 0.1.0\n# config: {\"cards\":7,\"strategy\":\"a b\"}\n# seed: 12\n"
+        ));
+        assert_eq!(metadata, from_comments(&text));
+    }
+
+    #[test]
+    fn test_format() {
+        let mut metadata = Metadata::new();
+        metadata.insert("git".into(), "abc".into());
+        metadata.insert("command".into(), json!(["poker", "--cards", "8"]));
+        metadata.insert("config".into(), json!({ "cards": 8, "jokers": 0 }));
+        assert_eq!(
+            "git:     abc\ncommand: poker --cards 8\nconfig:\n  cards:  8\n  jokers: 0\n",
+            format(&metadata)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::interval::{interval_with, CiMethod};
+use crate::scoring::RoundResult;
+use crate::{Card, HandRank};
+
+// Everything that happened in one deal which an objective might care about.
+pub struct Outcome<'a> {
+    // The final hand, after any discards.
+    pub cards: &'a [Card],
+    pub num_jokers: u8,
+    // The plays made from that hand.
+    pub round: &'a RoundResult,
+}
+
+// A per-deal quantity whose mean analysis tools estimate, so they can all
+// share the same choice of what to optimize or report. Simulating, the
+// `sensitivity` sweep and the `test` comparisons take one with --objective.
+pub trait Objective {
+    fn name(&self) -> String;
+
+    fn value(&self, outcome: &Outcome) -> f64;
+
+    // Whether every value is 0 or 1, making the mean a probability.
+    fn is_probability(&self) -> bool {
+        false
+    }
+}
+
+// Probability of making a hand.
+pub struct Probability(pub HandRank);
+
+impl Objective for Probability {
+    fn name(&self) -> String {
+        format!("P({})", self.0.name())
+    }
+
+    fn value(&self, outcome: &Outcome) -> f64 {
+        self.0.matches(outcome.cards, outcome.num_jokers) as u8 as f64
+    }
+
+    fn is_probability(&self) -> bool {
+        true
+    }
+}
+
+// Expected score of the first play.
+pub struct ExpectedScore;
+
+impl Objective for ExpectedScore {
+    fn name(&self) -> String {
+        "E(score)".to_string()
+    }
+
+    fn value(&self, outcome: &Outcome) -> f64 {
+        outcome.round.scores.first().copied().unwrap_or(0) as f64
+    }
+}
+
+// Probability of every play together scoring at least a target.
+pub struct ScoreAtLeast(pub u32);
+
+impl Objective for ScoreAtLeast {
+    fn name(&self) -> String {
+        format!("P(score >= {})", self.0)
+    }
+
+    fn value(&self, outcome: &Outcome) -> f64 {
+        (outcome.round.scores.iter().sum::<u32>() >= self.0) as u8 as f64
+    }
+
+    fn is_probability(&self) -> bool {
+        true
+    }
+}
+
+// Expected money from gold cards and gold seals.
+pub struct ExpectedGold;
+
+impl Objective for ExpectedGold {
+    fn name(&self) -> String {
+        "E(gold)".to_string()
+    }
+
+    fn value(&self, outcome: &Outcome) -> f64 {
+        (outcome.round.gold_money + outcome.round.seal_money) as f64
+    }
+}
+
+// Parses "prob:<hand id>", "score", "score>=<target>" or "gold".
+pub fn parse_objective(s: &str) -> Result<Box<dyn Objective>, String> {
+    if let Some(hand) = s.strip_prefix("prob:") {
+        return Ok(Box::new(Probability(hand.parse()?)));
+    }
+    if let Some(target) = s.strip_prefix("score>=") {
+        let target = target
+            .parse()
+            .map_err(|_| format!("invalid score target '{target}'"))?;
+        return Ok(Box::new(ScoreAtLeast(target)));
+    }
+    match s {
+        "score" => Ok(Box::new(ExpectedScore)),
+        "gold" => Ok(Box::new(ExpectedGold)),
+        _ => Err(format!(
+            "unknown objective '{s}', expected prob:<hand>, score, score>=<target> or gold"
+        )),
+    }
+}
+
+// Running mean of an objective over deals.
+#[derive(Clone, Default)]
+pub struct ObjectiveStats {
+    n: u64,
+    sum: f64,
+    sum_sq: f64,
+    // Whether any value wasn't 0 or 1.
+    non_binary: bool,
+}
+
+impl ObjectiveStats {
+    pub fn record(&mut self, value: f64) {
+        self.n += 1;
+        self.sum += value;
+        self.sum_sq += value * value;
+        self.non_binary |= value != 0.0 && value != 1.0;
+    }
+
+    // Mean and the half-width of its confidence interval. Probabilities use
+    // `method` like hand counts do; other means use the same 3 standard
+    // errors as `confidence_interval`.
+    pub fn mean(&self, method: CiMethod) -> (f64, f64) {
+        if !self.non_binary {
+            return interval_with(method, self.n, self.sum as u64);
+        }
+        let n = self.n as f64;
+        let mean = self.sum / n;
+        let var = (self.sum_sq / n - mean * mean).max(0.0);
+        (mean, 3.0 * (var / n).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objectives() {
+        let (cards, num_jokers) = crate::parse_hand("Ah Ad 2c").unwrap();
+        let round = RoundResult {
+            scores: vec![40, 30],
+            gold_money: 3,
+            seal_money: 6,
+        };
+        let outcome = Outcome {
+            cards: &cards,
+            num_jokers,
+            round: &round,
+        };
+        let value = |s: &str| parse_objective(s).unwrap().value(&outcome);
+        assert_eq!(1.0, value("prob:pair"));
+        assert_eq!(0.0, value("prob:3oak"));
+        assert_eq!(40.0, value("score"));
+        assert_eq!(1.0, value("score>=70"));
+        assert_eq!(0.0, value("score>=71"));
+        assert_eq!(9.0, value("gold"));
+        assert!(parse_objective("prob:nope").is_err());
+        assert!(parse_objective("score>=x").is_err());
+        assert!(parse_objective("luck").is_err());
+        assert!(parse_objective("prob:pair").unwrap().is_probability());
+        assert!(parse_objective("score>=70").unwrap().is_probability());
+        assert!(!parse_objective("score").unwrap().is_probability());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut stats = ObjectiveStats::default();
+        for v in [1.0, 0.0, 1.0, 0.0] {
+            stats.record(v);
+        }
+        let (mean, ci) = stats.mean(CiMethod::Normal);
+        assert_eq!(0.5, mean);
+        assert_eq!(crate::confidence_interval(4, 2).1, ci);
+        assert_eq!(
+            interval_with(CiMethod::Wilson, 4, 2),
+            stats.mean(CiMethod::Wilson)
+        );
+
+        let mut scores = ObjectiveStats::default();
+        for v in [10.0, 20.0] {
+            scores.record(v);
+        }
+        let (mean, ci) = scores.mean(CiMethod::Wilson);
+        assert_eq!(15.0, mean);
+        assert_eq!(3.0 * (25.0f64 / 2.0).sqrt(), ci);
+    }
+}
@@ -0,0 +1,1125 @@
+use crate::interval::{interval_with, CiMethod};
+use crate::scoring::ScoreTable;
+use crate::table::{ColumnType, TableSink};
+use crate::{
+    evaluate_best_skipping, format_hand, straight_flushes, Card, HandCount, HandRank, Rank,
+    NUM_RANKS, NUM_SUITS, SUIT_CHARS,
+};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// Simulation state handed to observers at the end of each batch.
+pub struct Snapshot<'a> {
+    pub num_iters: u64,
+    pub counts: &'a [HandCount],
+    // How to compute confidence intervals in reports.
+    pub ci_method: CiMethod,
+}
+
+// Hooks into the simulation loop for collecting custom statistics. Bit `i` of
+// `category_mask` is set when the deal satisfies `counts[i]`.
+pub trait Observer {
+    fn on_deal(&mut self, _cards: &[Card], _num_jokers: u8, _category_mask: u64) {}
+
+    fn on_batch_end(&mut self, _snapshot: &Snapshot) {}
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {}
+}
+
+// Writes a row of every hand's probability after each batch.
+pub struct History<S: TableSink> {
+    out: S,
+    wrote_header: bool,
+}
+
+impl<S: TableSink> History<S> {
+    pub fn new(out: S) -> Self {
+        Self {
+            out,
+            wrote_header: false,
+        }
+    }
+}
+
+impl<S: TableSink> Observer for History<S> {
+    fn on_batch_end(&mut self, snapshot: &Snapshot) {
+        if !self.wrote_header {
+            let columns = std::iter::once(("iterations".to_string(), ColumnType::U64))
+                .chain(
+                    snapshot
+                        .counts
+                        .iter()
+                        .map(|c| (c.name.to_string(), ColumnType::F64)),
+                )
+                .collect::<Vec<_>>();
+            self.out.write_header(&columns).unwrap();
+            self.wrote_header = true;
+        }
+        let row = std::iter::once(snapshot.num_iters as f64)
+            .chain(
+                snapshot
+                    .counts
+                    .iter()
+                    .map(|c| c.count as f64 / snapshot.num_iters as f64),
+            )
+            .collect::<Vec<_>>();
+        self.out.write_row(&row).unwrap();
+        self.out.flush().unwrap();
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        self.out.finish().unwrap();
+    }
+}
+
+// Every `every` deals, writes a row of the iterations so far and each hand's
+// count, probability and confidence interval half-width.
+pub struct Snapshots<S: TableSink> {
+    out: S,
+    counts: Vec<u64>,
+    every: u64,
+    num_deals: u64,
+    ci_method: CiMethod,
+}
+
+impl<S: TableSink> Snapshots<S> {
+    // Skips the header when appending to an existing file.
+    pub fn new(
+        mut out: S,
+        counts: &[HandCount],
+        every: u64,
+        ci_method: CiMethod,
+        write_header: bool,
+    ) -> Self {
+        if write_header {
+            let columns = std::iter::once(("iterations".to_string(), ColumnType::U64))
+                .chain(counts.iter().flat_map(|c| {
+                    [
+                        (format!("{} count", c.name), ColumnType::U64),
+                        (c.name.to_string(), ColumnType::F64),
+                        (format!("{} ci", c.name), ColumnType::F64),
+                    ]
+                }))
+                .collect::<Vec<_>>();
+            out.write_header(&columns).unwrap();
+        }
+        Self {
+            out,
+            counts: vec![0; counts.len()],
+            every,
+            num_deals: 0,
+            ci_method,
+        }
+    }
+}
+
+impl<S: TableSink> Observer for Snapshots<S> {
+    fn on_deal(&mut self, _cards: &[Card], _num_jokers: u8, category_mask: u64) {
+        for (i, count) in self.counts.iter_mut().enumerate() {
+            *count += (category_mask >> i) & 1;
+        }
+        self.num_deals += 1;
+        if !self.num_deals.is_multiple_of(self.every) {
+            return;
+        }
+        let mut row = vec![self.num_deals as f64];
+        for &count in &self.counts {
+            let (p, ci) = interval_with(self.ci_method, self.num_deals, count);
+            row.extend([count as f64, p, ci]);
+        }
+        self.out.write_row(&row).unwrap();
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        self.out.finish().unwrap();
+    }
+}
+
+// Writes the first `limit` deals along with the hands they satisfy.
+pub struct SampleDump<W: Write> {
+    out: W,
+    names: Vec<&'static str>,
+    remaining: u64,
+}
+
+impl<W: Write> SampleDump<W> {
+    pub fn new(out: W, counts: &[HandCount], limit: u64) -> Self {
+        Self {
+            out,
+            names: counts.iter().map(|c| c.name).collect(),
+            remaining: limit,
+        }
+    }
+}
+
+impl<W: Write> Observer for SampleDump<W> {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, category_mask: u64) {
+        if self.remaining == 0 {
+            return;
+        }
+        self.remaining -= 1;
+        let hands = self
+            .names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| category_mask & (1 << i) != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        writeln!(
+            self.out,
+            "{}: {}",
+            format_hand(cards, num_jokers),
+            hands.join(", ")
+        )
+        .unwrap();
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        self.out.flush().unwrap();
+    }
+}
+
+// Most distinct ranks within any run of `size` consecutive ranks, with aces
+// also counting as low.
+fn straight_window(cards: &[Card], size: usize) -> usize {
+    // Ace low, then deuce through ace.
+    let mut present = [false; NUM_RANKS as usize + 1];
+    for c in cards {
+        present[c.rank as usize + 1] = true;
+        if c.rank.is_ace() {
+            present[0] = true;
+        }
+    }
+    present
+        .windows(size.min(present.len()))
+        .map(|w| w.iter().filter(|&&p| p).count())
+        .max()
+        .unwrap_or(0)
+}
+
+// Writes numeric features of the first `limit` deals, one row per deal:
+// cards of each rank, cards of each suit, the most distinct ranks in any
+// straight-sized window, jokers, and then a 0/1 label for each hand.
+pub struct FeatureWriter<S: TableSink> {
+    out: S,
+    straight_size: usize,
+    num_hands: usize,
+    remaining: u64,
+}
+
+impl<S: TableSink> FeatureWriter<S> {
+    pub fn new(mut out: S, counts: &[HandCount], straight_size: usize, limit: u64) -> Self {
+        let columns = Rank::iter()
+            .map(|r| format!("rank_{r}"))
+            .chain(SUIT_CHARS.iter().map(|&s| format!("suit_{}", s as char)))
+            .chain(["straight_window".to_string(), "jokers".to_string()])
+            .chain(counts.iter().map(|c| c.name.to_string()))
+            .map(|name| (name, ColumnType::U8))
+            .collect::<Vec<_>>();
+        out.write_header(&columns).unwrap();
+        Self {
+            out,
+            straight_size,
+            num_hands: counts.len(),
+            remaining: limit,
+        }
+    }
+}
+
+impl<S: TableSink> Observer for FeatureWriter<S> {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, category_mask: u64) {
+        if self.remaining == 0 {
+            return;
+        }
+        self.remaining -= 1;
+        let mut ranks = [0u8; NUM_RANKS as usize];
+        let mut suits = [0u8; NUM_SUITS as usize];
+        for c in cards {
+            ranks[c.rank as usize] += 1;
+            suits[c.suit as usize] += 1;
+        }
+        let row = ranks
+            .iter()
+            .chain(&suits)
+            .map(|&n| n as f64)
+            .chain([
+                straight_window(cards, self.straight_size) as f64,
+                num_jokers as f64,
+            ])
+            .chain((0..self.num_hands).map(|i| ((category_mask >> i) & 1) as f64))
+            .collect::<Vec<_>>();
+        self.out.write_row(&row).unwrap();
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        self.out.finish().unwrap();
+    }
+}
+
+// Tracks how often each pair of hands occur in the same deal.
+pub struct Correlations {
+    // Row-major `n * n` matrix of joint counts.
+    joint: Vec<u64>,
+    n: usize,
+}
+
+impl Correlations {
+    pub fn new(num_hands: usize) -> Self {
+        Self {
+            joint: vec![0; num_hands * num_hands],
+            n: num_hands,
+        }
+    }
+
+    pub fn joint_count(&self, a: usize, b: usize) -> u64 {
+        self.joint[a * self.n + b]
+    }
+
+    // Phi coefficient between the indicator variables of hands `a` and `b`,
+    // or None if either hand always or never occurs.
+    pub fn correlation(&self, snapshot: &Snapshot, a: usize, b: usize) -> Option<f64> {
+        let n = snapshot.num_iters as f64;
+        let na = snapshot.counts[a].count as f64;
+        let nb = snapshot.counts[b].count as f64;
+        let denom = (na * (n - na) * nb * (n - nb)).sqrt();
+        if denom == 0.0 {
+            return None;
+        }
+        Some((self.joint_count(a, b) as f64 * n - na * nb) / denom)
+    }
+}
+
+impl Observer for Correlations {
+    fn on_deal(&mut self, _cards: &[Card], _num_jokers: u8, category_mask: u64) {
+        let mut a_bits = category_mask;
+        while a_bits != 0 {
+            let a = a_bits.trailing_zeros() as usize;
+            a_bits &= a_bits - 1;
+            let mut b_bits = category_mask;
+            while b_bits != 0 {
+                let b = b_bits.trailing_zeros() as usize;
+                b_bits &= b_bits - 1;
+                self.joint[a * self.n + b] += 1;
+            }
+        }
+    }
+
+    fn on_finish(&mut self, snapshot: &Snapshot) {
+        let max_str_len = snapshot.counts.iter().map(|c| c.name.len()).max().unwrap();
+        println!("correlations:");
+        print!("{:width$}", "", width = max_str_len);
+        for c in snapshot.counts {
+            print!(
+                " {name: >width$}",
+                name = c.name,
+                width = c.name.len().max(6)
+            );
+        }
+        println!();
+        for (a, ca) in snapshot.counts.iter().enumerate() {
+            print!("{name: >width$}", name = ca.name, width = max_str_len);
+            for (b, cb) in snapshot.counts.iter().enumerate() {
+                let corr = self
+                    .correlation(snapshot, a, b)
+                    .map_or_else(|| "-".to_string(), |c| format!("{c:.3}"));
+                print!(" {corr: >width$}", width = cb.name.len().max(6));
+            }
+            println!();
+        }
+    }
+}
+
+// Probability of each hand among only the deals which satisfy one condition
+// hand, like how often a flush is also a straight flush.
+pub struct Conditional {
+    condition: usize,
+    // `joint[i]` deals satisfying both the condition and `counts[i]`.
+    joint: Vec<u64>,
+}
+
+impl Conditional {
+    pub fn new(condition: usize, num_hands: usize) -> Self {
+        Self {
+            condition,
+            joint: vec![0; num_hands],
+        }
+    }
+
+    // P(hand | condition) and its confidence interval, or None if the
+    // condition never occurred.
+    pub fn probability(&self, snapshot: &Snapshot, hand: usize) -> Option<(f64, f64)> {
+        let given = snapshot.counts[self.condition].count;
+        (given > 0).then(|| interval_with(snapshot.ci_method, given, self.joint[hand]))
+    }
+
+    pub fn report(&self, snapshot: &Snapshot) -> String {
+        let given = snapshot.counts[self.condition].count;
+        let mut ret = format!(
+            "given {} ({given} of {} deals):\n",
+            snapshot.counts[self.condition].name, snapshot.num_iters
+        );
+        let max_str_len = snapshot.counts.iter().map(|c| c.name.len()).max().unwrap();
+        for (i, c) in snapshot.counts.iter().enumerate() {
+            if i == self.condition {
+                continue;
+            }
+            let p = self
+                .probability(snapshot, i)
+                .map_or_else(|| "-".to_string(), |(p, ci)| format!("{p:.6} ± {ci:.6}"));
+            ret += &format!(
+                "{name: >width$}: {p} ({})\n",
+                self.joint[i],
+                name = c.name,
+                width = max_str_len
+            );
+        }
+        ret
+    }
+}
+
+impl Observer for Conditional {
+    fn on_deal(&mut self, _cards: &[Card], _num_jokers: u8, category_mask: u64) {
+        if category_mask >> self.condition & 1 == 0 {
+            return;
+        }
+        let mut bits = category_mask;
+        while bits != 0 {
+            self.joint[bits.trailing_zeros() as usize] += 1;
+            bits &= bits - 1;
+        }
+    }
+
+    fn on_finish(&mut self, snapshot: &Snapshot) {
+        println!("--------------");
+        print!("{}", self.report(snapshot));
+    }
+}
+
+// How pairs of hands overlap: P(A and B) and each conditioned on the other,
+// like P(straight flush | flush), from the same joint counts as
+// `Correlations`.
+pub struct Overlaps {
+    pairs: Vec<(usize, usize)>,
+    correlations: Correlations,
+}
+
+// Probabilities with their confidence intervals. The conditional ones are
+// None until the hand conditioned on occurs.
+#[derive(Debug, PartialEq)]
+pub struct Overlap {
+    pub both: (f64, f64),
+    pub a_given_b: Option<(f64, f64)>,
+    pub b_given_a: Option<(f64, f64)>,
+}
+
+impl Overlaps {
+    pub fn new(pairs: Vec<(usize, usize)>, num_hands: usize) -> Self {
+        Self {
+            pairs,
+            correlations: Correlations::new(num_hands),
+        }
+    }
+
+    pub fn overlap(&self, snapshot: &Snapshot, a: usize, b: usize) -> Overlap {
+        let both = self.correlations.joint_count(a, b);
+        let given = |hand: usize| {
+            let count = snapshot.counts[hand].count;
+            (count > 0).then(|| interval_with(snapshot.ci_method, count, both))
+        };
+        Overlap {
+            both: interval_with(snapshot.ci_method, snapshot.num_iters, both),
+            a_given_b: given(b),
+            b_given_a: given(a),
+        }
+    }
+
+    pub fn report(&self, snapshot: &Snapshot) -> String {
+        let format = |p: Option<(f64, f64)>| {
+            p.map_or_else(|| "-".to_string(), |(p, ci)| format!("{p:.6} ± {ci:.6}"))
+        };
+        let mut ret = String::new();
+        for &(a, b) in &self.pairs {
+            let (name_a, name_b) = (snapshot.counts[a].name, snapshot.counts[b].name);
+            let (count_a, count_b) = (snapshot.counts[a].count, snapshot.counts[b].count);
+            let both = self.correlations.joint_count(a, b);
+            let overlap = self.overlap(snapshot, a, b);
+            ret += &format!(
+                "P({name_a} and {name_b}) = {} ({both})\n",
+                format(Some(overlap.both))
+            );
+            ret += &format!("P({name_a} | {name_b}) = {}\n", format(overlap.a_given_b));
+            ret += &format!("P({name_b} | {name_a}) = {}\n", format(overlap.b_given_a));
+            // Only what the deals so far show, not a proof.
+            if both > 0 && both == count_a && both == count_b {
+                ret += &format!("{name_a} and {name_b} always occurred together\n");
+            } else if both > 0 && both == count_a {
+                ret += &format!("every {name_a} was also a {name_b}\n");
+            } else if both > 0 && both == count_b {
+                ret += &format!("every {name_b} was also a {name_a}\n");
+            } else if both == 0 && count_a > 0 && count_b > 0 {
+                ret += &format!("{name_a} and {name_b} never occurred together\n");
+            }
+        }
+        ret
+    }
+}
+
+impl Observer for Overlaps {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, category_mask: u64) {
+        self.correlations.on_deal(cards, num_jokers, category_mask);
+    }
+
+    fn on_finish(&mut self, snapshot: &Snapshot) {
+        println!("--------------");
+        print!("{}", self.report(snapshot));
+    }
+}
+
+// Distribution of the base score (chips times mult) of the best hand in each
+// deal.
+pub struct ScoreDistribution {
+    hand_size: usize,
+    // Ranks straights skip over, see `skip_in_straights`.
+    skipped: u16,
+    // Number of deals with each score. There are only a handful of distinct
+    // base scores, so this stays tiny.
+    histogram: BTreeMap<u32, u64>,
+}
+
+impl ScoreDistribution {
+    pub fn new(hand_size: usize, skipped: u16) -> Self {
+        Self {
+            hand_size,
+            skipped,
+            histogram: BTreeMap::new(),
+        }
+    }
+
+    fn num_deals(&self) -> u64 {
+        self.histogram.values().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total = self
+            .histogram
+            .iter()
+            .map(|(&score, &n)| score as f64 * n as f64)
+            .sum::<f64>();
+        total / self.num_deals() as f64
+    }
+
+    // Smallest score at least a fraction `q` of deals don't exceed.
+    pub fn quantile(&self, q: f64) -> Option<u32> {
+        let target = (q * self.num_deals() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (&score, &n) in &self.histogram {
+            seen += n;
+            if seen >= target {
+                return Some(score);
+            }
+        }
+        None
+    }
+
+    pub fn max(&self) -> Option<u32> {
+        self.histogram.keys().next_back().copied()
+    }
+}
+
+impl Observer for ScoreDistribution {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, _category_mask: u64) {
+        let (chips, mult) =
+            evaluate_best_skipping(cards, num_jokers, self.hand_size, self.skipped).base_score();
+        *self.histogram.entry(chips * mult).or_default() += 1;
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        if self.histogram.is_empty() {
+            return;
+        }
+        println!("best hand base score:");
+        println!("  mean: {:.2}", self.mean());
+        for (name, q) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99)] {
+            println!("   {name}: {}", self.quantile(q).unwrap());
+        }
+        println!("   max: {}", self.max().unwrap());
+    }
+}
+
+// Expected chips times mult from playing the highest scoring hand of each
+// deal, with a configurable score for each hand.
+pub struct ExpectedScore {
+    hand_size: usize,
+    table: ScoreTable,
+    // Deals where each hand was the one played.
+    played: [u64; HandRank::ALL.len()],
+    sum_sq: f64,
+}
+
+impl ExpectedScore {
+    pub fn new(hand_size: usize, table: ScoreTable) -> Self {
+        Self {
+            hand_size,
+            table,
+            played: [0; HandRank::ALL.len()],
+            sum_sq: 0.0,
+        }
+    }
+
+    fn num_deals(&self) -> u64 {
+        self.played.iter().sum()
+    }
+
+    // Mean score and its 99.73% confidence interval.
+    pub fn mean(&self) -> (f64, f64) {
+        let n = self.num_deals() as f64;
+        let sum = HandRank::ALL
+            .iter()
+            .map(|&r| self.table.score(r) as f64 * self.played[r as usize] as f64)
+            .sum::<f64>();
+        let mean = sum / n;
+        let variance = (self.sum_sq / n - mean * mean).max(0.0);
+        (mean, 3.0 * (variance / n).sqrt())
+    }
+
+    // How often each hand is played and how much of the mean it accounts
+    // for, best hands first.
+    pub fn report(&self) -> String {
+        let n = self.num_deals() as f64;
+        let (mean, ci) = self.mean();
+        let mut ret = format!("expected score: {mean:.2} ± {ci:.2}\n");
+        let max_str_len = HandRank::ALL.iter().map(|r| r.name().len()).max().unwrap();
+        for &rank in HandRank::ALL.iter().rev() {
+            let played = self.played[rank as usize];
+            if played == 0 {
+                continue;
+            }
+            let (chips, mult) = self.table.chips_and_mult(rank);
+            ret += &format!(
+                "{name: >width$}: {chips} x {mult}, played {:.6}, adds {:.2}\n",
+                played as f64 / n,
+                self.table.score(rank) as f64 * played as f64 / n,
+                name = rank.name(),
+                width = max_str_len
+            );
+        }
+        ret
+    }
+}
+
+impl Observer for ExpectedScore {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, _category_mask: u64) {
+        let rank = self.table.best(cards, num_jokers, self.hand_size);
+        self.played[rank as usize] += 1;
+        self.sum_sq += (self.table.score(rank) as f64).powi(2);
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        if self.num_deals() > 0 {
+            println!("--------------");
+            print!("{}", self.report());
+        }
+    }
+}
+
+// Which suit and high card straight flushes are made of. Edited decks can
+// have far more of one suit than another, which a single straight flush
+// probability hides. Royal flushes are the ace-high ones.
+#[derive(Default)]
+pub struct StraightFlushBreakdown {
+    // Deals with a straight flush of each suit and high card. A deal counts
+    // once per suit, for that suit's highest straight flush.
+    counts: [[u64; NUM_RANKS as usize]; NUM_SUITS as usize],
+    // Deals with a straight flush of each suit, and with a straight flush
+    // of any suit topped by each rank, so deals with several are only
+    // counted once.
+    by_suit: [u64; NUM_SUITS as usize],
+    by_high: [u64; NUM_RANKS as usize],
+    any: u64,
+    num_deals: u64,
+    // Ranks straights skip over, see `skip_in_straights`.
+    skipped: u16,
+}
+
+impl StraightFlushBreakdown {
+    pub fn new(skipped: u16) -> Self {
+        Self {
+            skipped,
+            ..Self::default()
+        }
+    }
+
+    pub fn count(&self, suit: usize, high: Rank) -> u64 {
+        self.counts[suit][high as usize]
+    }
+
+    // Per-million rates with suits as rows and high cards as columns, then
+    // the royal flush rate overall and by suit.
+    pub fn report(&self) -> String {
+        let rate = |n: u64| format!("{:.1}", n as f64 * 1e6 / self.num_deals.max(1) as f64);
+        // The lowest straight flush is 5-high.
+        let highs = &Rank::ALL[Rank::Five as usize..];
+        let rows = (0..NUM_SUITS as usize)
+            .map(|suit| {
+                let mut row = vec![(SUIT_CHARS[suit] as char).to_string()];
+                row.extend(highs.iter().map(|&high| rate(self.count(suit, high))));
+                row.push(rate(self.by_suit[suit]));
+                row
+            })
+            .chain(std::iter::once({
+                let mut row = vec!["any".to_string()];
+                row.extend(highs.iter().map(|&high| rate(self.by_high[high as usize])));
+                row.push(rate(self.any));
+                row
+            }))
+            .collect::<Vec<_>>();
+        let mut header = vec![String::new()];
+        header.extend(highs.iter().map(Rank::to_string));
+        header.push("any".to_string());
+        let widths = (0..header.len())
+            .map(|i| {
+                rows.iter()
+                    .chain(std::iter::once(&header))
+                    .map(|row| row[i].len())
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let mut ret = "straight flushes per million deals, by suit and high card:\n".to_string();
+        for row in std::iter::once(&header).chain(&rows) {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell: >width$}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            ret += &line;
+            ret += "\n";
+        }
+        let royals = (0..NUM_SUITS as usize)
+            .map(|suit| {
+                format!(
+                    "{}: {}",
+                    SUIT_CHARS[suit] as char,
+                    rate(self.count(suit, Rank::Ace))
+                )
+            })
+            .collect::<Vec<_>>();
+        ret += &format!(
+            "royal flushes per million deals: {} ({})\n",
+            rate(self.by_high[Rank::Ace as usize]),
+            royals.join(", ")
+        );
+        ret
+    }
+}
+
+impl Observer for StraightFlushBreakdown {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, _category_mask: u64) {
+        self.num_deals += 1;
+        let found = straight_flushes(cards, num_jokers, 5, self.skipped);
+        if found.is_empty() {
+            return;
+        }
+        self.any += 1;
+        let mut highs = 0u16;
+        for (suit, high) in found {
+            self.counts[suit as usize][high as usize] += 1;
+            self.by_suit[suit as usize] += 1;
+            highs |= 1 << high as usize;
+        }
+        for (high, n) in self.by_high.iter_mut().enumerate() {
+            *n += u64::from(highs >> high & 1);
+        }
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        print!("{}", self.report());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeriveOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+// A quantity computed from the probabilities of two hands, like
+// "sf_ratio = straight_flush / flush". Both hands are estimated from the same
+// deals, so the uncertainty accounts for their covariance.
+#[derive(Clone)]
+pub struct Derived {
+    pub name: String,
+    pub a: HandRank,
+    pub op: DeriveOp,
+    pub b: HandRank,
+    num_deals: u64,
+    num_a: u64,
+    num_b: u64,
+    num_both: u64,
+}
+
+impl Derived {
+    fn new(name: String, a: HandRank, op: DeriveOp, b: HandRank) -> Self {
+        Self {
+            name,
+            a,
+            op,
+            b,
+            num_deals: 0,
+            num_a: 0,
+            num_b: 0,
+            num_both: 0,
+        }
+    }
+
+    // The estimate and its confidence interval half-width, using the delta
+    // method with the same 3 standard errors as `confidence_interval`. None
+    // before any deals or when dividing by a hand that hasn't been seen.
+    pub fn value(&self) -> Option<(f64, f64)> {
+        if self.num_deals == 0 {
+            return None;
+        }
+        let n = self.num_deals as f64;
+        let pa = self.num_a as f64 / n;
+        let pb = self.num_b as f64 / n;
+        let pab = self.num_both as f64 / n;
+        let (value, da, db) = match self.op {
+            DeriveOp::Add => (pa + pb, 1.0, 1.0),
+            DeriveOp::Sub => (pa - pb, 1.0, -1.0),
+            DeriveOp::Mul => (pa * pb, pb, pa),
+            DeriveOp::Div if pb == 0.0 => return None,
+            DeriveOp::Div => (pa / pb, 1.0 / pb, -pa / (pb * pb)),
+        };
+        let var_a = pa * (1.0 - pa) / n;
+        let var_b = pb * (1.0 - pb) / n;
+        let cov = (pab - pa * pb) / n;
+        let var = da * da * var_a + db * db * var_b + 2.0 * da * db * cov;
+        Some((value, 3.0 * var.max(0.0).sqrt()))
+    }
+}
+
+// Parses "<name> = <hand> <op> <hand>" with hand ids like `straight_flush`
+// and op one of + - * /.
+pub fn parse_derived(s: &str) -> Result<Derived, String> {
+    let Some((name, expr)) = s.split_once('=') else {
+        return Err(format!("expected '<name> = <hand> <op> <hand>', got '{s}'"));
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing name in '{s}'"));
+    }
+    let Some((i, op)) = expr.char_indices().find_map(|(i, c)| {
+        let op = match c {
+            '+' => DeriveOp::Add,
+            '-' => DeriveOp::Sub,
+            '*' => DeriveOp::Mul,
+            '/' => DeriveOp::Div,
+            _ => return None,
+        };
+        Some((i, op))
+    }) else {
+        return Err(format!("missing one of + - * / in '{s}'"));
+    };
+    let a = expr[..i].trim().parse()?;
+    let b = expr[i + 1..].trim().parse()?;
+    Ok(Derived::new(name.to_string(), a, op, b))
+}
+
+impl Observer for Derived {
+    fn on_deal(&mut self, cards: &[Card], num_jokers: u8, _category_mask: u64) {
+        let a = self.a.matches(cards, num_jokers);
+        let b = self.b.matches(cards, num_jokers);
+        self.num_deals += 1;
+        self.num_a += a as u64;
+        self.num_b += b as u64;
+        self.num_both += (a && b) as u64;
+    }
+
+    fn on_finish(&mut self, _snapshot: &Snapshot) {
+        match self.value() {
+            Some((value, ci)) => println!("{}: {value:.6} ± {ci:.6}", self.name),
+            None => println!("{}: undefined, {} never seen", self.name, self.b.id()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlations() {
+        let mut counts = vec![
+            HandCount::new("a", |_, _| true),
+            HandCount::new("b", |_, _| true),
+            HandCount::new("c", |_, _| true),
+        ];
+        let mut correlations = Correlations::new(counts.len());
+        // a and b always occur together, c only occurs without them.
+        for (i, mask) in [0b011u64, 0b100, 0b011, 0b100].into_iter().enumerate() {
+            for (bit, c) in counts.iter_mut().enumerate() {
+                if mask & (1 << bit) != 0 {
+                    c.record(i as u64);
+                }
+            }
+            correlations.on_deal(&[], 0, mask);
+        }
+        let snapshot = Snapshot {
+            num_iters: 4,
+            counts: &counts,
+            ci_method: CiMethod::Normal,
+        };
+        assert_eq!(2, correlations.joint_count(0, 1));
+        assert_eq!(0, correlations.joint_count(0, 2));
+        assert_eq!(Some(1.0), correlations.correlation(&snapshot, 0, 1));
+        assert_eq!(Some(-1.0), correlations.correlation(&snapshot, 0, 2));
+    }
+
+    #[test]
+    fn test_conditional() {
+        let mut counts = vec![
+            HandCount::new("Flush", |_, _| true),
+            HandCount::new("Straight Flush", |_, _| true),
+            HandCount::new("Pair", |_, _| true),
+        ];
+        let mut conditional = Conditional::new(0, counts.len());
+        for (i, mask) in [0b011u64, 0b001, 0b100, 0b101].into_iter().enumerate() {
+            for (bit, c) in counts.iter_mut().enumerate() {
+                if mask & (1 << bit) != 0 {
+                    c.record(i as u64);
+                }
+            }
+            conditional.on_deal(&[], 0, mask);
+        }
+        let snapshot = Snapshot {
+            num_iters: 4,
+            counts: &counts,
+            ci_method: CiMethod::Normal,
+        };
+        assert_eq!(Some((1.0, 0.0)), conditional.probability(&snapshot, 0));
+        assert_eq!(1.0 / 3.0, conditional.probability(&snapshot, 1).unwrap().0);
+        assert_eq!(1.0 / 3.0, conditional.probability(&snapshot, 2).unwrap().0);
+        let report = conditional.report(&snapshot);
+        assert!(report.starts_with("given Flush (3 of 4 deals):\n"));
+        assert!(!report.contains("         Flush:"));
+
+        let never = Conditional::new(1, counts.len());
+        let counts = vec![
+            HandCount::new("Flush", |_, _| true),
+            HandCount::new("Straight Flush", |_, _| true),
+        ];
+        let snapshot = Snapshot {
+            num_iters: 4,
+            counts: &counts,
+            ci_method: CiMethod::Normal,
+        };
+        assert_eq!(None, never.probability(&snapshot, 0));
+    }
+
+    #[test]
+    fn test_sample_dump() {
+        let counts = vec![
+            HandCount::new("Pair", |_, _| true),
+            HandCount::new("3oak", |_, _| true),
+        ];
+        let mut out = Vec::new();
+        let mut dump = SampleDump::new(&mut out, &counts, 1);
+        let cards = crate::hand!("2c 2d");
+        dump.on_deal(&cards, 1, 0b11);
+        dump.on_deal(&cards, 0, 0b01);
+        assert_eq!("2c 2d J: Pair, 3oak\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let counts = vec![
+            HandCount::new("Pair", |_, _| true),
+            HandCount::new("3oak", |_, _| true),
+        ];
+        let mut out = Vec::new();
+        let table = crate::table::CsvSink::new(&mut out);
+        let mut snapshots = Snapshots::new(table, &counts, 2, CiMethod::Normal, true);
+        for mask in [0b01, 0b11, 0b00] {
+            snapshots.on_deal(&[], 0, mask);
+        }
+        drop(snapshots);
+        assert_eq!(
+            "iterations,Pair count,Pair,Pair ci,3oak count,3oak,3oak ci\n2,2,1,0,1,0.5,1.0606601717798214\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_feature_writer() {
+        let counts = vec![
+            HandCount::new("Pair", |_, _| true),
+            HandCount::new("Straight", |_, _| true),
+        ];
+        let mut out = Vec::new();
+        let mut features = FeatureWriter::new(crate::table::CsvSink::new(&mut out), &counts, 5, 1);
+        features.on_deal(&crate::hand!("Ah 2c 3d 3s 5h"), 1, 0b01);
+        features.on_deal(&crate::hand!("Kh"), 0, 0);
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("rank_2,rank_3,"));
+        assert!(lines[0]
+            .ends_with(",rank_A,suit_c,suit_d,suit_h,suit_s,straight_window,jokers,Pair,Straight"));
+        // A-2-3-5 is four ranks of the wheel.
+        assert_eq!("1,2,0,1,0,0,0,0,0,0,0,0,1,1,1,2,1,4,1,1,0", lines[1]);
+    }
+
+    #[test]
+    fn test_straight_flush_breakdown() {
+        let mut breakdown = StraightFlushBreakdown::new(0);
+        for hand in [
+            "Ah Kh Qh Jh Th 9h",
+            "5c 4c 3c 2c J",
+            "9s 8s 7s 6s 5s 9d 8d 7d 6d 5d",
+            "Ah Kh Qh Jh",
+        ] {
+            let (cards, num_jokers) = crate::parse_hand(hand).unwrap();
+            breakdown.on_deal(&cards, num_jokers, 0);
+        }
+        assert_eq!(1, breakdown.count(2, Rank::Ace));
+        assert_eq!(1, breakdown.count(0, Rank::Six));
+        assert_eq!(1, breakdown.count(1, Rank::Nine));
+        assert_eq!(1, breakdown.count(3, Rank::Nine));
+        assert_eq!([0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 1], breakdown.by_high);
+        assert_eq!(3, breakdown.any);
+        let report = breakdown.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(
+            "      5        6   7   8        9   T   J   Q   K        A      any",
+            lines[1]
+        );
+        assert_eq!(
+            "any 0.0 250000.0 0.0 0.0 250000.0 0.0 0.0 0.0 0.0 250000.0 750000.0",
+            lines[6].split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+        assert_eq!(
+            "royal flushes per million deals: 250000.0 (c: 0.0, d: 0.0, h: 250000.0, s: 0.0)",
+            lines[7]
+        );
+    }
+
+    #[test]
+    fn test_parse_derived() {
+        let d = parse_derived("sf_ratio = straight_flush / flush").unwrap();
+        assert_eq!("sf_ratio", d.name);
+        assert_eq!(HandRank::StraightFlush, d.a);
+        assert_eq!(DeriveOp::Div, d.op);
+        assert_eq!(HandRank::Flush, d.b);
+        assert_eq!(
+            DeriveOp::Sub,
+            parse_derived("x=full_house-flush").unwrap().op
+        );
+        assert!(parse_derived("straight_flush / flush").is_err());
+        assert!(parse_derived("x = straight_flush").is_err());
+        assert!(parse_derived("x = nope / flush").is_err());
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let mut counts = vec![
+            HandCount::new("Flush", |_, _| true),
+            HandCount::new("Straight Flush", |_, _| true),
+            HandCount::new("Pair", |_, _| true),
+        ];
+        let mut overlaps = Overlaps::new(vec![(1, 0), (0, 2)], counts.len());
+        for (i, mask) in [0b011u64, 0b001, 0b100, 0b001].into_iter().enumerate() {
+            for (bit, c) in counts.iter_mut().enumerate() {
+                if mask & (1 << bit) != 0 {
+                    c.record(i as u64);
+                }
+            }
+            overlaps.on_deal(&[], 0, mask);
+        }
+        let snapshot = Snapshot {
+            num_iters: 4,
+            counts: &counts,
+            ci_method: CiMethod::Normal,
+        };
+        let overlap = overlaps.overlap(&snapshot, 1, 0);
+        assert_eq!(0.25, overlap.both.0);
+        assert_eq!(1.0 / 3.0, overlap.a_given_b.unwrap().0);
+        assert_eq!(Some((1.0, 0.0)), overlap.b_given_a);
+        let report = overlaps.report(&snapshot);
+        assert!(report.starts_with("P(Straight Flush and Flush) = 0.250000 ± "));
+        assert!(report.contains("\nevery Straight Flush was also a Flush\n"));
+        assert!(report.contains("\nFlush and Pair never occurred together\n"));
+
+        let never = Overlaps::new(vec![(0, 1)], 2);
+        let counts = vec![
+            HandCount::new("Flush", |_, _| true),
+            HandCount::new("Straight Flush", |_, _| true),
+        ];
+        let snapshot = Snapshot {
+            num_iters: 4,
+            counts: &counts,
+            ci_method: CiMethod::Normal,
+        };
+        let overlap = never.overlap(&snapshot, 0, 1);
+        assert_eq!(None, overlap.a_given_b);
+        assert_eq!(None, overlap.b_given_a);
+    }
+
+    #[test]
+    fn test_derived() {
+        let mut d = parse_derived("x = pair / 3oak").unwrap();
+        assert_eq!(None, d.value());
+        let pair = crate::hand!("Ah Ad");
+        let trips = crate::hand!("Ah Ad Ac");
+        let nothing = crate::hand!("Ah Kd");
+        for cards in [&pair[..], &trips, &trips, &nothing] {
+            d.on_deal(cards, 0, 0);
+        }
+        // Every trips deal is also a pair deal.
+        let (value, ci) = d.value().unwrap();
+        assert_eq!(1.5, value);
+        assert!(ci > 0.0);
+        // A hand minus itself is exactly zero.
+        let mut d = parse_derived("x = pair - pair").unwrap();
+        for cards in [&pair[..], &nothing] {
+            d.on_deal(cards, 0, 0);
+        }
+        assert_eq!(Some((0.0, 0.0)), d.value());
+    }
+
+    #[test]
+    fn test_score_distribution() {
+        let mut scores = ScoreDistribution::new(5, 0);
+        assert_eq!(None, scores.quantile(0.5));
+        let high_card = crate::hand!("Ah Kd");
+        let pair = crate::hand!("Ah Ad");
+        for _ in 0..9 {
+            scores.on_deal(&high_card, 0, 0);
+        }
+        scores.on_deal(&pair, 0, 0);
+        let score = |r: HandRank| r.base_score().0 * r.base_score().1;
+        assert_eq!(Some(score(HandRank::HighCard)), scores.quantile(0.5));
+        assert_eq!(Some(score(HandRank::HighCard)), scores.quantile(0.9));
+        assert_eq!(Some(score(HandRank::Pair)), scores.quantile(0.99));
+        assert_eq!(Some(score(HandRank::Pair)), scores.max());
+        let mean = (9 * score(HandRank::HighCard) + score(HandRank::Pair)) as f64 / 10.0;
+        assert_eq!(mean, scores.mean());
+    }
+
+    #[test]
+    fn test_expected_score() {
+        let table = ScoreTable::parse("[pair]\nchips = 15\nmult = 1").unwrap();
+        let mut expected = ExpectedScore::new(5, table);
+        expected.on_deal(&crate::hand!("Ah Ad"), 0, 0);
+        expected.on_deal(&crate::hand!("Ah Kd"), 0, 0);
+        // A 15 and a 5.
+        let (mean, ci) = expected.mean();
+        assert_eq!(10.0, mean);
+        assert_eq!(3.0 * (25.0f64 / 2.0).sqrt(), ci);
+        let report = expected.report();
+        assert!(report.starts_with("expected score: 10.00 ± 10.61\n"));
+        assert!(report.contains("Pair: 15 x 1, played 0.500000, adds 7.50\n"));
+    }
+}
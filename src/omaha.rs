@@ -0,0 +1,193 @@
+//! Omaha, where a hand is exactly two of a player's four hole cards and
+//! exactly three of the board, so four hearts on the board are no flush
+//! without two more in hand. The hole cards are the first four of a deal, so
+//! `--guarantee` fixes hole cards, and the board is the rest.
+//!
+//! Hi/lo games split the pot with the best low: five distinct ranks of eight
+//! or under, aces low, made under the same two-and-three rule. Straights and
+//! flushes don't spoil a low, and the lowest highest card wins, so 8-5-4-3-2
+//! beats 8-6-3-2-A.
+//!
+//! A joker's place in the deal is lost once the cards are split from the
+//! jokers, so Omaha counts leave decks with jokers to the caller to reject.
+
+use crate::classes::{strength, Strength};
+use crate::{evaluate_best, Card, HandCount, HandRank, Rank};
+
+pub const HOLE_CARDS: usize = 4;
+// Each hand plays three board cards.
+pub const MIN_BOARD: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OmahaHand {
+    // The deal's best high hand is exactly this rank.
+    High(HandRank),
+    // The deal makes an 8-or-better low.
+    Low,
+}
+
+// Every five-card hand taking two hole cards and three board cards, or none
+// if the deal is too small to make one.
+fn hands(cards: &[Card]) -> impl Iterator<Item = [Card; 5]> + '_ {
+    let (hole, board) = cards.split_at(HOLE_CARDS.min(cards.len()));
+    let pairs = (0..hole.len()).flat_map(move |i| (i + 1..hole.len()).map(move |j| (i, j)));
+    pairs.flat_map(move |(i, j)| {
+        let n = board.len();
+        (0..n).flat_map(move |a| {
+            (a + 1..n).flat_map(move |b| {
+                (b + 1..n).map(move |c| [hole[i], hole[j], board[a], board[b], board[c]])
+            })
+        })
+    })
+}
+
+// The best high hand, or None if the deal has too few hole or board cards.
+pub fn evaluate_high(cards: &[Card]) -> Option<HandRank> {
+    hands(cards).map(|hand| evaluate_best(&hand, 0, 5)).max()
+}
+
+// `evaluate_high` with ties broken by rank, see `classes::strength`.
+pub fn high_strength(cards: &[Card]) -> Option<Strength> {
+    hands(cards).map(|hand| strength(&hand)).max()
+}
+
+// A rank's value in a low hand, with aces low, or None above eight.
+fn low_value(rank: Rank) -> Option<u8> {
+    match rank {
+        Rank::Ace => Some(1),
+        rank if rank <= Rank::Eight => Some(rank as u8 + 2),
+        _ => None,
+    }
+}
+
+// The best 8-or-better low as its ranks from highest to lowest, so that a
+// lower array is a better low, or None if the deal doesn't make one.
+pub fn evaluate_low(cards: &[Card]) -> Option<[u8; 5]> {
+    hands(cards)
+        .filter_map(|hand| {
+            let mut low = [0; 5];
+            for (value, card) in low.iter_mut().zip(hand) {
+                *value = low_value(card.rank)?;
+            }
+            low.sort_unstable_by(|a, b| b.cmp(a));
+            low.windows(2).all(|w| w[0] != w[1]).then_some(low)
+        })
+        .min()
+}
+
+impl OmahaHand {
+    pub fn matches(self, cards: &[Card]) -> bool {
+        match self {
+            OmahaHand::High(rank) => evaluate_high(cards) == Some(rank),
+            OmahaHand::Low => evaluate_low(cards).is_some(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OmahaHand::High(rank) => rank.name(),
+            OmahaHand::Low => "8 Low",
+        }
+    }
+}
+
+// One count per five-card high hand, which partition the deals as with
+// `best_hand_counts(5)`, then the low.
+pub fn counts() -> Vec<HandCount> {
+    HandRank::ALL
+        .iter()
+        .filter(|r| r.num_cards() <= 5)
+        .map(|&rank| OmahaHand::High(rank))
+        .chain([OmahaHand::Low])
+        .map(|hand| HandCount {
+            omaha: Some(hand),
+            ..HandCount::new(hand.name(), |_, _| false)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards;
+
+    #[test]
+    fn test_hands() {
+        assert_eq!(60, hands(&cards!("As Ks Qs Js 2c 3c 4c 5c 6c")).count());
+        assert_eq!(6, hands(&cards!("As Ks Qs Js 2c 3c 4c")).count());
+        assert_eq!(0, hands(&cards!("As Ks Qs Js 2c 3c")).count());
+        for hand in hands(&cards!("As Ks Qs Js 2c 3c 4c 5c")) {
+            let hole = hand.iter().filter(|c| c.rank >= Rank::Jack).count();
+            assert_eq!(2, hole);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_high() {
+        // Four spades on the board and one in hand make no flush.
+        assert_eq!(
+            Some(HandRank::Pair),
+            evaluate_high(&cards!("As 9d 9c 2h 3s 7s Ts Qs Kd"))
+        );
+        // Two in hand do.
+        assert_eq!(
+            Some(HandRank::Flush),
+            evaluate_high(&cards!("As 9s 9c 2h 3s 7s Ts Qd Kd"))
+        );
+        // Quads in hand only play as a pair.
+        assert_eq!(
+            Some(HandRank::TwoPair),
+            evaluate_high(&cards!("9s 9h 9c 9d 2s 2h 7c Td Kd"))
+        );
+        assert_eq!(
+            Some(HandRank::Straight),
+            evaluate_high(&cards!("Ac 2d Kh Kd 3s 4h 5c"))
+        );
+        assert_eq!(None, evaluate_high(&cards!("Ac 2d Kh Kd 3s 4h")));
+    }
+
+    #[test]
+    fn test_high_strength() {
+        // Both pair aces, and the king in hand outkicks the queen.
+        let board = "9c 7s 2h 3d Ad";
+        let strength = |hole: &str| high_strength(&cards!(&format!("{hole} {board}")));
+        assert!(strength("As Kh Jc Tc") > strength("Ah Qh Jd Td"));
+        assert_eq!(strength("As Kh Jc Tc"), strength("Ac Kd Js Ts"));
+        assert_eq!(None, high_strength(&cards!("Ac 2d Kh Kd 3s 4h")));
+    }
+
+    #[test]
+    fn test_evaluate_low() {
+        assert_eq!(
+            Some([5, 4, 3, 2, 1]),
+            evaluate_low(&cards!("Ac 2d Kh Kd 3s 4h 5c 9s 9h"))
+        );
+        // The board's low cards need two low hole cards of other ranks.
+        assert_eq!(None, evaluate_low(&cards!("Ac Ad Kh Kd 3s 4h 5c 9s 9h")));
+        assert_eq!(None, evaluate_low(&cards!("Ac 2d Kh Kd 3s 9h 5c 9s 9d")));
+        // 8-5-4-3-2 beats 8-6-3-2-A.
+        assert_eq!(
+            Some([8, 5, 4, 3, 2]),
+            evaluate_low(&cards!("2c 5d Kh Kd 3s 4h 8c"))
+                .min(evaluate_low(&cards!("Ac 6d Kh Kd 3s 2h 8c")))
+        );
+        assert_eq!(
+            Some([8, 6, 4, 2, 1]),
+            evaluate_low(&cards!("Ac 2d 8h 7d 6s 4h 8c Tc"))
+        );
+    }
+
+    #[test]
+    fn test_counts() {
+        let counts = counts();
+        assert_eq!(Some(OmahaHand::Low), counts.last().unwrap().omaha);
+        assert_eq!("8 Low", counts.last().unwrap().name);
+        let deal = cards!("As 2s 9c 9h 3s 7s 5s Qd Kd");
+        let made = counts
+            .iter()
+            .filter(|c| c.matches(&deal, 0))
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Flush", "8 Low"], made);
+    }
+}
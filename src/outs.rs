@@ -0,0 +1,150 @@
+//! `poker outs`: the chance that drawing more cards to a held hand makes a
+//! target hand, and which cards are outs, the ones that make it on their
+//! own. The chance is found both by enumerating every draw and by sampling,
+//! so each checks the other.
+
+use crate::exact::{enumerate_held, CombinationSpace, ExactCounts};
+use crate::plan::Plan;
+use crate::{split_jokers_iter, CardOrJoker, HandCount, MAX_CARDS};
+
+pub struct Draw {
+    // The deck without the held cards.
+    pub deck: Vec<CardOrJoker>,
+    pub held: Vec<CardOrJoker>,
+    // Cards drawn to the held ones.
+    pub draw: usize,
+}
+
+impl Draw {
+    pub fn new(deck: &[CardOrJoker], held: &[CardOrJoker], draw: usize) -> Result<Self, String> {
+        let mut deck = deck.to_vec();
+        crate::deck::take_cards(&mut deck, held)?;
+        if held.len() + draw > MAX_CARDS {
+            return Err(format!("does not support more than {MAX_CARDS} cards"));
+        }
+        if draw > deck.len() {
+            return Err(format!(
+                "can't draw {draw} of the {} cards left in the deck",
+                deck.len()
+            ));
+        }
+        Ok(Self {
+            deck,
+            held: held.to_vec(),
+            draw,
+        })
+    }
+
+    fn matches(&self, target: &HandCount, drawn: &[CardOrJoker]) -> bool {
+        let (cards, num_jokers) = split_jokers_iter(self.held.iter().chain(drawn).copied());
+        target.matches(&cards, num_jokers)
+    }
+
+    // Whether the held cards already make `target`.
+    pub fn made(&self, target: &HandCount) -> bool {
+        self.matches(target, &[])
+    }
+
+    // Each distinct card left in the deck which makes `target` with the held
+    // cards, and how many copies of it there are, in deck order.
+    pub fn outs(&self, target: &HandCount) -> Vec<(CardOrJoker, u64)> {
+        let mut outs: Vec<(CardOrJoker, u64)> = Vec::new();
+        for &coj in &self.deck {
+            if let Some((_, n)) = outs.iter_mut().find(|(o, _)| *o == coj) {
+                *n += 1;
+            } else if self.matches(target, &[coj]) {
+                outs.push((coj, 1));
+            }
+        }
+        outs
+    }
+
+    // How many distinct draws `exact` enumerates.
+    pub fn num_draws(&self) -> u128 {
+        CombinationSpace::new(&self.deck, self.draw).len()
+    }
+
+    // Every draw, and those which make `target`.
+    pub fn exact(&self, target: &HandCount) -> ExactCounts {
+        enumerate_held(
+            &self.deck,
+            &self.held,
+            self.draw,
+            std::slice::from_ref(target),
+        )
+    }
+
+    // Draws which make `target` out of `iters` random ones.
+    pub fn sample(&self, target: &HandCount, iters: u64, rng: &mut impl rand::Rng) -> u64 {
+        use rand::seq::SliceRandom;
+
+        let targets = std::slice::from_ref(target);
+        let plan = Plan::new(targets);
+        let mut deck = self.deck.clone();
+        let mut hits = 0;
+        for _ in 0..iters {
+            let (drawn, _) = deck.partial_shuffle(rng, self.draw);
+            let (cards, num_jokers) = split_jokers_iter(self.held.iter().chain(&*drawn).copied());
+            hits += plan.evaluate(targets, &cards, num_jokers);
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, cards, standard_deck, HandRank};
+    use rand::SeedableRng;
+
+    fn held(hand: &str) -> Vec<CardOrJoker> {
+        cards!(hand).into_iter().map(CardOrJoker::Card).collect()
+    }
+
+    #[test]
+    fn test_outs() {
+        let flush = HandCount::for_rank("Flush", HandRank::Flush);
+        let draw = Draw::new(&standard_deck(1, 1), &held("Ah Kh 7h 2h"), 1).unwrap();
+        assert!(!draw.made(&flush));
+        let outs = draw.outs(&flush);
+        // Nine hearts and the joker.
+        assert_eq!(10, outs.len());
+        assert!(outs.contains(&(CardOrJoker::Card(card!("3h")), 1)));
+        assert!(outs.contains(&(CardOrJoker::Joker, 1)));
+        let exact = draw.exact(&flush);
+        assert_eq!((49, 10), (exact.total, exact.hits[0]));
+
+        let draw = Draw::new(&standard_deck(2, 0), &held("5c 6d 7h 8s"), 1).unwrap();
+        let straight = HandCount::for_rank("Straight", HandRank::Straight);
+        let outs = draw.outs(&straight);
+        assert_eq!(8, outs.len());
+        assert!(outs.iter().all(|&(_, copies)| copies == 2));
+    }
+
+    #[test]
+    fn test_sample() {
+        let flush = HandCount::for_rank("Flush", HandRank::Flush);
+        let draw = Draw::new(&standard_deck(1, 0), &held("Ah Kh 7h 2h"), 2).unwrap();
+        let exact = draw.exact(&flush);
+        let p = exact.hits[0] as f64 / exact.total as f64;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let iters = 100_000;
+        let hits = draw.sample(&flush, iters, &mut rng);
+        let (sampled, ci) = crate::confidence_interval(iters, hits);
+        assert!((sampled - p).abs() < ci, "{sampled} vs {p}");
+    }
+
+    #[test]
+    fn test_new() {
+        let deck = standard_deck(1, 0);
+        assert!(Draw::new(&deck, &held("Ah Ah"), 1).is_err());
+        assert!(Draw::new(&deck, &held("Ah"), 52).is_err());
+        assert_eq!(
+            48,
+            Draw::new(&deck, &held("Ah Kh 7h 2h"), 1)
+                .unwrap()
+                .deck
+                .len()
+        );
+    }
+}
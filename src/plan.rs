@@ -0,0 +1,418 @@
+//! Compiles the enabled hands into the least work per deal.
+//!
+//! Standalone detectors each recount the cards they look at, so a run
+//! counting pairs and flushes sorts the deal into suits once per flush-family
+//! detector. Hands described by a `Check` instead say which facts about the
+//! deal they need: a `Bitboard`, rank counts, suit counts or the rank counts
+//! of each suit. A
+//! `Plan` computes only the facts its hands need, once per deal, then answers
+//! every check from them. Best-hand counts share one `evaluate_best` per
+//! deal, lows share one `evaluate_low` and badugi hands one
+//! `evaluate_badugi`, and anything else falls back to
+//! `HandCount::matches`.
+
+use crate::badugi::{self, BadugiHand};
+use crate::bitboard::Bitboard;
+use crate::lookup;
+use crate::lowball::{self, Lowball};
+use crate::omaha::{self, OmahaHand};
+use crate::{
+    evaluate_best_with, n_and_m_in, n_of_a_kind_in, n_pairs_in, rank_counts, royal_in, straight_in,
+    suit_counts, Card, HandCount, HandRank, Rank, RankCounts, MAX_CARDS, NUM_SUITS,
+};
+
+// A hand defined in terms of shared facts about the deal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    // At least n cards of one rank.
+    OfAKind(u8),
+    // At least n pairs of distinct ranks.
+    Pairs(u8),
+    // n of one rank and m of another, like 3 and 2 for a full house.
+    Groups(u8, u8),
+    // At least n cards of one suit.
+    Flush(u8),
+    // n cards of consecutive ranks.
+    Straight(usize),
+    // The same checks within a single suit.
+    StraightFlush(usize),
+    RoyalFlush,
+    SuitedOfAKind(u8),
+    SuitedPairs(u8),
+    SuitedGroups(u8, u8),
+}
+
+// Which facts a set of checks reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Needs {
+    pub bits: bool,
+    pub ranks: bool,
+    pub suits: bool,
+    pub suit_ranks: bool,
+}
+
+impl Needs {
+    pub const ALL: Needs = Needs {
+        bits: true,
+        ranks: true,
+        suits: true,
+        suit_ranks: true,
+    };
+
+    fn union(self, other: Needs) -> Needs {
+        Needs {
+            bits: self.bits || other.bits,
+            ranks: self.ranks || other.ranks,
+            suits: self.suits || other.suits,
+            suit_ranks: self.suit_ranks || other.suit_ranks,
+        }
+    }
+}
+
+impl Check {
+    pub fn needs(self) -> Needs {
+        match self {
+            Check::OfAKind(_) | Check::Pairs(_) | Check::Groups(..) => Needs {
+                ranks: true,
+                ..Needs::default()
+            },
+            Check::Flush(_) => Needs {
+                suits: true,
+                ..Needs::default()
+            },
+            Check::Straight(_) | Check::StraightFlush(_) | Check::RoyalFlush => Needs {
+                bits: true,
+                ..Needs::default()
+            },
+            Check::SuitedOfAKind(_) | Check::SuitedPairs(_) | Check::SuitedGroups(..) => Needs {
+                suit_ranks: true,
+                ..Needs::default()
+            },
+        }
+    }
+
+    pub fn matches(self, cards: &[Card], num_jokers: u8) -> bool {
+        self.matches_facts(&Facts::new(cards, self.needs()), num_jokers)
+    }
+
+    // Panics if `facts` lacks something in `self.needs()`.
+    pub fn matches_facts(self, facts: &Facts, num_jokers: u8) -> bool {
+        let suit_ranks = || facts.suit_ranks.as_ref().unwrap().iter();
+        match self {
+            Check::OfAKind(n) => n_of_a_kind_in(facts.ranks.as_ref().unwrap(), n, num_jokers),
+            Check::Pairs(n) => n_pairs_in(facts.ranks.as_ref().unwrap(), n, num_jokers),
+            Check::Groups(n, m) => n_and_m_in(facts.ranks.unwrap(), n, m, num_jokers),
+            Check::Flush(n) => facts.suits.unwrap().iter().any(|&c| c + num_jokers >= n),
+            Check::Straight(size) => {
+                straight_in(facts.bits.unwrap().ranks(), num_jokers, size, facts.skipped)
+            }
+            Check::StraightFlush(size) => facts
+                .bits
+                .unwrap()
+                .suits()
+                .into_iter()
+                .any(|suit| straight_in(suit, num_jokers, size, facts.skipped)),
+            Check::RoyalFlush => facts
+                .bits
+                .unwrap()
+                .suits()
+                .into_iter()
+                .any(|suit| royal_in(suit, num_jokers)),
+            Check::SuitedOfAKind(n) => suit_ranks().any(|r| n_of_a_kind_in(r, n, num_jokers)),
+            Check::SuitedPairs(n) => suit_ranks().any(|r| n_pairs_in(r, n, num_jokers)),
+            Check::SuitedGroups(n, m) => suit_ranks().any(|&r| n_and_m_in(r, n, m, num_jokers)),
+        }
+    }
+}
+
+// The suits `card` counts towards, all of them for wild cards.
+fn suits_of(card: &Card) -> impl Iterator<Item = usize> + '_ {
+    (0..NUM_SUITS as usize).filter(|&suit| card.wild || card.suit as usize == suit)
+}
+
+// Facts about one deal, each computed only if needed.
+pub struct Facts {
+    bits: Option<Bitboard>,
+    ranks: Option<RankCounts>,
+    suits: Option<RankCounts>,
+    suit_ranks: Option<[RankCounts; NUM_SUITS as usize]>,
+    // Ranks straights skip over.
+    skipped: u16,
+}
+
+impl Facts {
+    pub fn new(cards: &[Card], needs: Needs) -> Self {
+        // Flushes count suits from the bits unless duplicates get in the way.
+        let bits = (needs.bits || needs.suits).then(|| Bitboard::new(cards));
+        let suit_ranks = needs.suit_ranks.then(|| {
+            let mut ret = [RankCounts::default(); NUM_SUITS as usize];
+            for c in cards {
+                for suit in suits_of(c) {
+                    ret[suit][c.rank as usize] += 1;
+                }
+            }
+            ret
+        });
+        let suits = needs.suits.then(|| {
+            bits.and_then(Bitboard::suit_counts)
+                .unwrap_or_else(|| suit_counts(cards))
+        });
+        Self {
+            bits,
+            ranks: needs.ranks.then(|| rank_counts(cards)),
+            suits,
+            suit_ranks,
+            skipped: 0,
+        }
+    }
+
+    // Lets straights skip the `skipped` ranks, see `skip_in_straights`.
+    pub fn skipping(self, skipped: u16) -> Self {
+        Self { skipped, ..self }
+    }
+}
+
+impl Facts {
+    // Facts about a whole deck rather than a deal, for asking whether any deal
+    // from it could make a hand. No hand uses more than `MAX_CARDS` cards, so
+    // counts stop there.
+    pub fn for_deck(cards: &[Card]) -> Self {
+        let limit = MAX_CARDS as u8;
+        let mut ranks = RankCounts::default();
+        let mut suits = RankCounts::default();
+        let mut suit_ranks = [RankCounts::default(); NUM_SUITS as usize];
+        for c in cards {
+            let rank = c.rank as usize;
+            ranks[rank] = (ranks[rank] + 1).min(limit);
+            for suit in suits_of(c) {
+                suits[suit] = (suits[suit] + 1).min(limit);
+                let count = &mut suit_ranks[suit][rank];
+                *count = (*count + 1).min(limit);
+            }
+        }
+        Self {
+            bits: Some(Bitboard::new(cards)),
+            ranks: Some(ranks),
+            suits: Some(suits),
+            suit_ranks: Some(suit_ranks),
+            skipped: 0,
+        }
+    }
+}
+
+enum Step {
+    Check(Check),
+    // The deal's best hand playing at most this many cards is this rank.
+    Best(HandRank, usize),
+    Omaha(OmahaHand),
+    // The deal's best low is this rank or better.
+    Lowball(Lowball, Rank),
+    // The deal's best badugi plays exactly this many cards.
+    Badugi(usize),
+    Generic,
+}
+
+pub struct Plan {
+    steps: Vec<Step>,
+    needs: Needs,
+    // Ranks straights skip over, shared by every count.
+    skipped: u16,
+}
+
+impl Plan {
+    pub fn new(counts: &[HandCount]) -> Self {
+        let steps = counts
+            .iter()
+            .map(|c| match (c.best, c.omaha, c.lowball, c.badugi, c.check) {
+                (Some((rank, hand_size)), ..) => Step::Best(rank, hand_size),
+                (None, Some(hand), ..) => Step::Omaha(hand),
+                (None, None, Some((rules, high)), ..) => Step::Lowball(rules, high),
+                (None, None, None, Some(size), _) => Step::Badugi(size),
+                // Hands with a minimum rank only see some of the cards.
+                (None, None, None, None, Some(check)) if c.min_rank.is_none() => Step::Check(check),
+                _ => Step::Generic,
+            })
+            .collect::<Vec<_>>();
+        let needs = steps
+            .iter()
+            .map(|s| match s {
+                Step::Check(check) => check.needs(),
+                Step::Best(..) => Needs::ALL,
+                Step::Omaha(_) | Step::Lowball(..) | Step::Badugi(_) | Step::Generic => {
+                    Needs::default()
+                }
+            })
+            .fold(Needs::default(), Needs::union);
+        let skipped = counts.first().map_or(0, |c| c.skipped_in_straights);
+        Self {
+            steps,
+            needs,
+            skipped,
+        }
+    }
+
+    // Bit i is set when `counts[i]` matches, for the `counts` the plan was
+    // made from.
+    pub fn evaluate(&self, counts: &[HandCount], cards: &[Card], num_jokers: u8) -> u64 {
+        // Best-hand counts usually come from the lookup table instead.
+        let facts = std::cell::OnceCell::new();
+        let facts = || facts.get_or_init(|| Facts::new(cards, self.needs).skipping(self.skipped));
+        // Almost always every best-hand count shares one hand size.
+        let mut best = None;
+        // Omaha counts share the deal's best high hand and whether it has a
+        // low.
+        let omaha_high = std::cell::OnceCell::new();
+        let omaha_low = std::cell::OnceCell::new();
+        // Low counts share the deal's best low.
+        let mut low = None;
+        let badugi = std::cell::OnceCell::new();
+        let mut ret = 0;
+        for (i, (step, c)) in self.steps.iter().zip(counts).enumerate() {
+            let hit = match *step {
+                Step::Check(check) => check.matches_facts(facts(), num_jokers),
+                Step::Best(rank, hand_size) => {
+                    let best_rank = match best {
+                        Some((size, best_rank)) if size == hand_size => best_rank,
+                        _ => {
+                            // The lookup table only knows standard straights.
+                            let looked_up = (self.skipped == 0)
+                                .then(|| lookup::evaluate(cards, num_jokers, hand_size))
+                                .flatten();
+                            let best_rank = looked_up.unwrap_or_else(|| {
+                                evaluate_best_with(facts(), num_jokers, hand_size)
+                            });
+                            best = Some((hand_size, best_rank));
+                            best_rank
+                        }
+                    };
+                    best_rank == rank
+                }
+                Step::Omaha(OmahaHand::High(rank)) => {
+                    *omaha_high.get_or_init(|| omaha::evaluate_high(cards)) == Some(rank)
+                }
+                Step::Omaha(OmahaHand::Low) => {
+                    *omaha_low.get_or_init(|| omaha::evaluate_low(cards).is_some())
+                }
+                Step::Lowball(rules, high) => {
+                    let best_low = match low {
+                        Some((low_rules, best_low)) if low_rules == rules => best_low,
+                        _ => {
+                            let best_low = lowball::evaluate_low(cards, num_jokers, rules);
+                            low = Some((rules, best_low));
+                            best_low
+                        }
+                    };
+                    best_low.is_some_and(|l| l.is_at_most(rules, high))
+                }
+                Step::Badugi(size) => {
+                    let hand = badugi.get_or_init(|| badugi::evaluate_badugi(cards, num_jokers));
+                    hand.map(BadugiHand::size) == Some(size)
+                }
+                Step::Generic => c.matches(cards, num_jokers),
+            };
+            ret |= u64::from(hit) << i;
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Dealer;
+    use crate::{best_hand_counts, hand_counts};
+
+    #[test]
+    fn test_needs() {
+        let plan = Plan::new(&hand_counts(5).unwrap()[..4]);
+        assert_eq!(
+            Needs {
+                ranks: true,
+                ..Needs::default()
+            },
+            plan.needs
+        );
+        assert_eq!(Needs::ALL, Plan::new(&hand_counts(6).unwrap()).needs);
+    }
+
+    #[test]
+    fn test_checks_match_detectors() {
+        let mut dealer = Dealer::new(2, 3, 0);
+        let mut wild_dealer = Dealer::new(1, 0, 1);
+        for i in 0..5_000 {
+            let (mut cards, num_jokers) = if i % 2 == 0 {
+                dealer.deal(5 + i % 7)
+            } else {
+                wild_dealer.deal(5 + i % 7)
+            };
+            if i % 2 == 1 {
+                cards[0] = cards[0].made_wild();
+            }
+            for rank in HandRank::ALL {
+                if let Some(check) = rank.check() {
+                    assert_eq!(
+                        rank.detector()(&cards, num_jokers),
+                        check.matches(&cards, num_jokers),
+                        "{rank:?} {cards:?} {num_jokers}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut dealer = Dealer::new(1, 2, 0);
+        for hand_size in [5, 6, 8] {
+            let mut counts = hand_counts(hand_size).unwrap();
+            counts.extend(best_hand_counts(hand_size).unwrap());
+            counts[0].set_min_rank(crate::Rank::Jack);
+            let plan = Plan::new(&counts);
+            for _ in 0..2_000 {
+                let (cards, num_jokers) = dealer.deal(hand_size);
+                let expected = counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.matches(&cards, num_jokers))
+                    .fold(0, |mask, (i, _)| mask | 1 << i);
+                assert_eq!(expected, plan.evaluate(&counts, &cards, num_jokers));
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_omaha() {
+        let mut dealer = Dealer::new(1, 0, 0);
+        let counts = crate::omaha::counts();
+        let plan = Plan::new(&counts);
+        for i in 0..2_000 {
+            let (cards, _) = dealer.deal(7 + i % 3);
+            let expected = counts
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.matches(&cards, 0))
+                .fold(0, |mask, (i, _)| mask | 1 << i);
+            assert_eq!(expected, plan.evaluate(&counts, &cards, 0));
+            // One high hand, maybe with a low.
+            assert_eq!(1, (expected & !(1 << (counts.len() - 1))).count_ones());
+        }
+    }
+
+    #[test]
+    fn test_evaluate_lows() {
+        let mut dealer = Dealer::new(1, 2, 0);
+        let mut counts = crate::lowball::counts(Lowball::AceToFive);
+        counts.extend(crate::lowball::counts(Lowball::DeuceToSeven));
+        counts.extend(crate::badugi::counts());
+        let plan = Plan::new(&counts);
+        for i in 0..2_000 {
+            let (cards, num_jokers) = dealer.deal(5 + i % 3);
+            let expected = counts
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.matches(&cards, num_jokers))
+                .fold(0, |mask, (i, _)| mask | 1 << i);
+            assert_eq!(expected, plan.evaluate(&counts, &cards, num_jokers));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Several players dealt from one deck without replacement, for multiplayer
+//! odds where the cards in other hands can't be in yours. Each player gets
+//! their own `num_cards`, like `boards::MultiBoard`, but the question is
+//! whether anyone at the table makes a hand rather than how the pot splits,
+//! which `boards::BombPot` answers.
+//!
+//! With a board, as in Hold'em, `board_size` community cards are dealt first
+//! and every player's hand is their own cards followed by the board, the
+//! order Omaha counts expect.
+
+use crate::plan::Plan;
+use crate::{split_jokers_iter, CardOrJoker, HandCount};
+use rand::seq::SliceRandom;
+
+pub struct Players {
+    pub iters: u64,
+    pub players: usize,
+    // `made[hand][p]` deals where player `p` made the hand.
+    pub made: Vec<Vec<u64>>,
+    // `any[hand]` deals where at least one player made the hand.
+    pub any: Vec<u64>,
+}
+
+impl Players {
+    pub fn run(
+        deck: &[CardOrJoker],
+        num_cards: usize,
+        board_size: usize,
+        counts: &[HandCount],
+        players: usize,
+        iters: u64,
+        rng: &mut impl rand::Rng,
+    ) -> Result<Self, String> {
+        if num_cards == 0 {
+            return Err("players need at least one card each".to_string());
+        }
+        if num_cards * players + board_size > deck.len() {
+            return Err(format!(
+                "{players} players of {num_cards} cards{} need more than the {} cards in the deck",
+                match board_size {
+                    0 => String::new(),
+                    _ => format!(" and {board_size} board cards"),
+                },
+                deck.len()
+            ));
+        }
+        let plan = Plan::new(counts);
+        let mut deck = deck.to_vec();
+        let mut made = vec![vec![0; players]; counts.len()];
+        let mut any = vec![0; counts.len()];
+        for _ in 0..iters {
+            let (drawn, _) = deck.partial_shuffle(rng, num_cards * players + board_size);
+            let (board, hands) = drawn.split_at(board_size);
+            let mut any_mask = 0;
+            for (p, hand) in hands.chunks(num_cards).enumerate() {
+                let (cards, num_jokers) = split_jokers_iter(hand.iter().chain(board).copied());
+                let mask = plan.evaluate(counts, &cards, num_jokers);
+                any_mask |= mask;
+                for (h, made) in made.iter_mut().enumerate() {
+                    made[p] += mask >> h & 1;
+                }
+            }
+            for (h, any) in any.iter_mut().enumerate() {
+                *any += any_mask >> h & 1;
+            }
+        }
+        Ok(Self {
+            iters,
+            players,
+            made,
+            any,
+        })
+    }
+
+    fn fraction(&self, n: u64) -> f64 {
+        n as f64 / self.iters as f64
+    }
+
+    // Chance that `player`, counting from 0, makes the hand.
+    pub fn player(&self, hand: usize, player: usize) -> f64 {
+        self.fraction(self.made[hand][player])
+    }
+
+    // Chance that a given player makes the hand, averaged over the seats,
+    // which are all alike.
+    pub fn each(&self, hand: usize) -> f64 {
+        self.fraction(self.made[hand].iter().sum()) / self.players as f64
+    }
+
+    pub fn any(&self, hand: usize) -> f64 {
+        self.fraction(self.any[hand])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_players() {
+        let deck = crate::standard_deck(1, 0);
+        let counts = vec![HandCount::new("Pair", |cards, num_jokers| {
+            crate::is_n_of_a_kind(cards, 2, num_jokers)
+        })];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let players = Players::run(&deck, 2, 0, &counts, 3, 20000, &mut rng).unwrap();
+        assert_eq!(3, players.made[0].len());
+        // Each player is dealt a pair 3/51 of the time.
+        assert!((players.each(0) - 3.0 / 51.0).abs() < 0.005);
+        assert!((players.player(0, 2) - 3.0 / 51.0).abs() < 0.01);
+        assert!(players.any(0) > players.each(0));
+        assert!(players.any(0) <= 3.0 * players.each(0));
+
+        assert!(Players::run(&deck, 7, 0, &counts, 8, 1, &mut rng).is_err());
+        assert!(Players::run(&deck, 2, 5, &counts, 24, 1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_card_removal() {
+        // Two aces and two kings: if one player holds a pair the other must too.
+        let deck = crate::cards!("As Ah Ks Kh")
+            .into_iter()
+            .map(CardOrJoker::Card)
+            .collect::<Vec<_>>();
+        let counts = crate::hand_counts(5).unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let players = Players::run(&deck, 2, 0, &counts, 2, 3000, &mut rng).unwrap();
+        assert_eq!(players.made[0][0], players.made[0][1]);
+        assert_eq!(players.made[0][0], players.any[0]);
+        assert!((players.any(0) - 1.0 / 3.0).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_board() {
+        // Any 5 of these 6 cards hold two pair, but never a full house.
+        let deck = crate::cards!("As Ah Ks Kh Qs Qh")
+            .into_iter()
+            .map(CardOrJoker::Card)
+            .collect::<Vec<_>>();
+        let counts = crate::hand_counts(5).unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let players = Players::run(&deck, 1, 4, &counts, 2, 100, &mut rng).unwrap();
+        let two_pair = counts.iter().position(|c| c.name == "2 pair").unwrap();
+        let full_house = counts.iter().position(|c| c.name == "Full House").unwrap();
+        assert_eq!(vec![100, 100], players.made[two_pair]);
+        assert_eq!(0, players.any[full_house]);
+        assert!(Players::run(&deck, 0, 4, &counts, 2, 1, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Precomputed probabilities for the most common configurations, so `poker
+//! prob` can answer them instantly: 1 deck, 0 to 4 jokers, 5 to 10 cards and
+//! hand sizes 5 and 6. Anything else is simulated.
+//!
+//! The counts live in prob_table_data.rs, which `poker prob --write-table`
+//! regenerates.
+
+use crate::serve::DeckConfig;
+use std::io::Write;
+
+pub const JOKERS: std::ops::RangeInclusive<u8> = 0..=4;
+pub const CARDS: std::ops::RangeInclusive<u8> = 5..=10;
+pub const HAND_SIZES: std::ops::RangeInclusive<u8> = 5..=6;
+
+pub struct Entry {
+    pub cards: u8,
+    pub jokers: u8,
+    pub hand_size: u8,
+    pub iterations: u64,
+    // Deals which made each entry of `hand_counts(hand_size)`.
+    pub hits: &'static [u64],
+}
+
+include!("prob_table_data.rs");
+
+pub fn lookup(config: DeckConfig) -> Option<&'static Entry> {
+    if config.decks != 1 {
+        return None;
+    }
+    TABLE.iter().find(|e| {
+        (e.cards, e.jokers, e.hand_size) == (config.cards, config.jokers, config.hand_size)
+    })
+}
+
+// Every configuration in the grid.
+pub fn grid() -> impl Iterator<Item = DeckConfig> {
+    JOKERS.flat_map(|jokers| {
+        CARDS.flat_map(move |cards| {
+            HAND_SIZES.map(move |hand_size| DeckConfig {
+                cards,
+                decks: 1,
+                jokers,
+                hand_size,
+            })
+        })
+    })
+}
+
+// Simulates every configuration in the grid for `iterations` deals and writes
+// the table as Rust source.
+pub fn write_table(iterations: u64, mut w: impl Write) -> std::io::Result<()> {
+    writeln!(
+        w,
+        "// Generated by `poker prob --write-table --table-iters {iterations}`."
+    )?;
+    writeln!(w, "pub const TABLE: &[Entry] = &[")?;
+    for config in grid() {
+        let hits = config.simulate(iterations).unwrap();
+        let hits = hits.iter().map(u64::to_string).collect::<Vec<_>>();
+        writeln!(w, "    Entry {{")?;
+        writeln!(w, "        cards: {},", config.cards)?;
+        writeln!(w, "        jokers: {},", config.jokers)?;
+        writeln!(w, "        hand_size: {},", config.hand_size)?;
+        writeln!(w, "        iterations: {iterations},")?;
+        writeln!(w, "        hits: &[{}],", hits.join(", "))?;
+        writeln!(w, "    }},")?;
+    }
+    writeln!(w, "];")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confidence_interval;
+
+    #[test]
+    fn test_table_covers_grid() {
+        assert_eq!(grid().count(), TABLE.len());
+        for config in grid() {
+            let entry = lookup(config).unwrap();
+            assert_eq!(config.counts().unwrap().len(), entry.hits.len());
+            assert!(entry.hits.iter().all(|&h| h <= entry.iterations));
+        }
+        let outside = DeckConfig {
+            cards: 7,
+            decks: 2,
+            jokers: 0,
+            hand_size: 5,
+        };
+        assert!(lookup(outside).is_none());
+        assert!(lookup(DeckConfig {
+            decks: 1,
+            ..outside
+        })
+        .is_some());
+        assert!(lookup(DeckConfig {
+            cards: 11,
+            decks: 1,
+            ..outside
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_table_values() {
+        // 1 - 1317888/2598960 of 5-card hands have at least a pair.
+        let entry = lookup(DeckConfig {
+            cards: 5,
+            decks: 1,
+            jokers: 0,
+            hand_size: 5,
+        })
+        .unwrap();
+        let (p, ci) = confidence_interval(entry.iterations, entry.hits[0]);
+        assert!((p - (1.0 - 1317888.0 / 2598960.0)).abs() < ci, "{p} ± {ci}");
+    }
+}
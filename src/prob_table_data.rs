@@ -0,0 +1,423 @@
+// Generated by `poker prob --write-table --table-iters 10000000`.
+pub const TABLE: &[Entry] = &[
+    Entry {
+        cards: 5,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[4928454, 228606, 2401, 0, 491382, 14328, 0, 180, 0, 10000000, 39694, 20134, 22],
+    },
+    Entry {
+        cards: 5,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[4929387, 228205, 2431, 0, 492591, 0, 14407, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    Entry {
+        cards: 6,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[6549240, 448527, 7150, 0, 1332932, 81967, 0, 944, 0, 10000000, 180613, 102381, 89],
+    },
+    Entry {
+        cards: 6,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[6547687, 448714, 7294, 0, 1332993, 31103, 82201, 0, 643, 18229, 3245, 451, 0, 0, 0, 13, 0],
+    },
+    Entry {
+        cards: 7,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[7898481, 768862, 16907, 0, 2677579, 262421, 0, 3081, 0, 10000000, 482707, 304674, 306],
+    },
+    Entry {
+        cards: 7,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[7898580, 768306, 16730, 0, 2676754, 197428, 262081, 0, 4166, 92197, 20289, 3046, 0, 0, 0, 128, 0],
+    },
+    Entry {
+        cards: 8,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[8879477, 1201245, 33620, 0, 4394439, 619652, 0, 8279, 0, 10000000, 984426, 696310, 906],
+    },
+    Entry {
+        cards: 8,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[8880150, 1201415, 33712, 0, 4396285, 682000, 619091, 0, 16220, 266694, 71354, 11671, 0, 0, 0, 494, 0],
+    },
+    Entry {
+        cards: 9,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9490868, 1747162, 60577, 0, 6204204, 1183489, 0, 17936, 0, 10000000, 1684514, 1335797, 1948],
+    },
+    Entry {
+        cards: 9,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9490561, 1747596, 61011, 0, 6202730, 1669789, 1183672, 0, 46218, 584053, 185585, 32041, 0, 0, 0, 1462, 0],
+    },
+    Entry {
+        cards: 10,
+        jokers: 0,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9810465, 2410662, 100928, 0, 7785883, 1952641, 0, 35052, 0, 10000000, 2559034, 2263690, 3956],
+    },
+    Entry {
+        cards: 10,
+        jokers: 0,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9810137, 2409649, 100702, 0, 7783673, 3206820, 1951426, 0, 111163, 1063982, 399713, 69697, 0, 0, 0, 3475, 0],
+    },
+    Entry {
+        cards: 5,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[5408295, 511604, 10931, 45, 751623, 31420, 0, 701, 0, 10000000, 71773, 27801, 88],
+    },
+    Entry {
+        cards: 5,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[5408600, 513706, 10935, 41, 753464, 0, 31780, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    Entry {
+        cards: 6,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[6939901, 955499, 32235, 297, 1741136, 152623, 0, 4046, 0, 10000000, 315378, 141484, 495],
+    },
+    Entry {
+        cards: 6,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[6940118, 957027, 32296, 283, 1741457, 83334, 152880, 0, 2213, 36435, 5260, 2325, 0, 0, 0, 102, 0],
+    },
+    Entry {
+        cards: 7,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[8176273, 1531669, 74185, 946, 3188533, 451824, 0, 13800, 0, 10000000, 797400, 416202, 1720],
+    },
+    Entry {
+        cards: 7,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[8173728, 1530913, 73313, 968, 3184993, 346084, 449542, 0, 14303, 176047, 31328, 14193, 0, 0, 0, 618, 0],
+    },
+    Entry {
+        cards: 8,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9047649, 2213664, 144541, 2468, 4925206, 1005007, 0, 35159, 0, 10000000, 1538833, 932175, 4468],
+    },
+    Entry {
+        cards: 8,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9046697, 2208563, 144296, 2511, 4923994, 982678, 1002512, 0, 53312, 484452, 107108, 51750, 0, 0, 0, 2528, 0],
+    },
+    Entry {
+        cards: 9,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9577372, 2958239, 254744, 5767, 6661052, 1825073, 0, 74963, 0, 10000000, 2481634, 1750151, 9732],
+    },
+    Entry {
+        cards: 9,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9576628, 2962994, 255038, 5662, 6660227, 2134731, 1829218, 0, 143924, 999316, 271966, 135679, 0, 0, 0, 7027, 0],
+    },
+    Entry {
+        cards: 10,
+        jokers: 1,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9846097, 3744842, 411223, 11397, 8105998, 2854148, 0, 142526, 0, 10000000, 3537998, 2886210, 19298],
+    },
+    Entry {
+        cards: 10,
+        jokers: 1,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9846657, 3744472, 411386, 11572, 8106361, 3775183, 2854745, 0, 313893, 1713851, 576280, 285065, 0, 0, 0, 16813, 0],
+    },
+    Entry {
+        cards: 5,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[5831734, 811810, 29869, 229, 1029122, 57286, 0, 1915, 0, 10000000, 111797, 37583, 277],
+    },
+    Entry {
+        cards: 5,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[5833695, 810587, 29768, 216, 1028987, 0, 57193, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    Entry {
+        cards: 6,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[7279421, 1451924, 85551, 1436, 2148391, 240250, 0, 11142, 0, 10000000, 467516, 189472, 1588],
+    },
+    Entry {
+        cards: 6,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[7277400, 1450988, 85445, 1458, 2145549, 157502, 240633, 6, 5407, 60128, 7751, 5817, 0, 0, 0, 316, 0],
+    },
+    Entry {
+        cards: 7,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[8411110, 2230553, 187380, 5109, 3673447, 659304, 0, 36576, 0, 10000000, 1134899, 547731, 5288],
+    },
+    Entry {
+        cards: 7,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[8410584, 2233231, 188169, 4957, 3675257, 526847, 659239, 51, 32121, 277468, 45714, 32905, 0, 0, 0, 2018, 0],
+    },
+    Entry {
+        cards: 8,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9188786, 3092646, 349209, 13034, 5402653, 1390108, 0, 90181, 0, 10000000, 2086166, 1203205, 13428],
+    },
+    Entry {
+        cards: 8,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9188703, 3094733, 350597, 13100, 5406609, 1309271, 1389328, 149, 111890, 732035, 152178, 112506, 0, 0, 0, 7309, 0],
+    },
+    Entry {
+        cards: 9,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9647347, 3974029, 580218, 29051, 7056759, 2420256, 0, 187051, 0, 10000000, 3213020, 2200739, 29027],
+    },
+    Entry {
+        cards: 9,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9647764, 3976168, 581292, 28884, 7058493, 2598950, 2422439, 417, 288013, 1440598, 382318, 284291, 0, 0, 0, 20657, 0],
+    },
+    Entry {
+        cards: 10,
+        jokers: 2,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9874618, 4824810, 883098, 56371, 8378053, 3638470, 0, 343697, 0, 10000000, 4383628, 3510448, 55818],
+    },
+    Entry {
+        cards: 10,
+        jokers: 2,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9874262, 4826118, 882761, 56300, 8379121, 4307380, 3642459, 1084, 593207, 2360088, 793573, 574157, 0, 0, 0, 48476, 0],
+    },
+    Entry {
+        cards: 5,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[6211061, 1120528, 60255, 822, 1317050, 93740, 926, 4224, 0, 10000000, 157532, 50448, 647],
+    },
+    Entry {
+        cards: 5,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[6212900, 1121627, 59841, 793, 1318732, 0, 93065, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    Entry {
+        cards: 6,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[7574263, 1928368, 164715, 4499, 2551899, 345361, 4640, 23399, 0, 10000000, 633424, 245689, 3724],
+    },
+    Entry {
+        cards: 6,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[7575392, 1926723, 164885, 4439, 2549342, 253339, 345276, 41, 11189, 88605, 10990, 11700, 0, 356, 0, 686, 0],
+    },
+    Entry {
+        cards: 7,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[8615629, 2876208, 349588, 15166, 4134976, 887668, 12045, 74512, 0, 10000000, 1483231, 699332, 12159],
+    },
+    Entry {
+        cards: 7,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[8615094, 2873200, 348693, 15218, 4131881, 733403, 885370, 225, 58908, 393372, 63757, 60153, 0, 2239, 0, 4726, 0],
+    },
+    Entry {
+        cards: 8,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9307341, 3871183, 624687, 38801, 5844209, 1778729, 21695, 177449, 0, 10000000, 2618031, 1500669, 30723],
+    },
+    Entry {
+        cards: 8,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9307126, 3869794, 624306, 38598, 5844859, 1653732, 1777711, 866, 192926, 999998, 209779, 194823, 0, 8093, 0, 17289, 0],
+    },
+    Entry {
+        cards: 9,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9705087, 4829687, 992764, 81673, 7406345, 2977986, 32041, 353226, 0, 10000000, 3881251, 2664406, 64879],
+    },
+    Entry {
+        cards: 9,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9706161, 4827714, 992751, 81397, 7405985, 3056959, 2976886, 2466, 468318, 1892311, 517080, 467740, 0, 19331, 0, 47151, 0],
+    },
+    Entry {
+        cards: 10,
+        jokers: 3,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9897226, 5698033, 1444558, 151841, 8609001, 4327109, 45886, 620151, 0, 10000000, 5112006, 4104263, 120530],
+    },
+    Entry {
+        cards: 10,
+        jokers: 3,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9897448, 5703365, 1444698, 151323, 8610638, 4809360, 4331180, 5996, 926700, 2982683, 1048615, 914253, 0, 37270, 0, 105871, 0],
+    },
+    Entry {
+        cards: 5,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[6551067, 1436442, 101603, 1858, 1616789, 139250, 3387, 7913, 133, 10000000, 208857, 64680, 1327],
+    },
+    Entry {
+        cards: 5,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[6552146, 1434212, 101347, 1868, 1615123, 0, 138908, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    Entry {
+        cards: 6,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[7834854, 2388280, 267669, 10555, 2943353, 465952, 17111, 42302, 412, 10000000, 814386, 313431, 7429],
+    },
+    Entry {
+        cards: 6,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[7837021, 2388925, 266590, 10583, 2942524, 365722, 465359, 117, 19923, 122255, 15355, 20604, 88, 1523, 88, 1488, 0],
+    },
+    Entry {
+        cards: 7,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[8787003, 3461678, 546557, 34078, 4565939, 1124898, 42725, 128811, 991, 10000000, 1835650, 869448, 23762],
+    },
+    Entry {
+        cards: 7,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[8787194, 3461719, 546015, 34040, 4562098, 960188, 1123771, 745, 94827, 524954, 86516, 96634, 555, 8852, 555, 9517, 0],
+    },
+    Entry {
+        cards: 8,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9404303, 4546623, 946484, 83104, 6239340, 2160710, 75435, 297550, 1971, 10000000, 3127662, 1816088, 58273],
+    },
+    Entry {
+        cards: 8,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9406193, 4549530, 946642, 83471, 6240807, 2009025, 2161231, 2971, 291569, 1284080, 280957, 294316, 1729, 28955, 1729, 33743, 0],
+    },
+    Entry {
+        cards: 9,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9752653, 5546782, 1455443, 169307, 7712158, 3493842, 111107, 568904, 3525, 10000000, 4483074, 3126201, 120552],
+    },
+    Entry {
+        cards: 9,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9753147, 5549790, 1453420, 168212, 7711087, 3506110, 3493157, 8322, 678311, 2346080, 674406, 679806, 3467, 68375, 3467, 89008, 0],
+    },
+    Entry {
+        cards: 10,
+        jokers: 4,
+        hand_size: 5,
+        iterations: 10000000,
+        hits: &[9915518, 6417029, 2048086, 301578, 8803924, 4935602, 155886, 959784, 5649, 10000000, 5745208, 4670592, 219576],
+    },
+    Entry {
+        cards: 10,
+        jokers: 4,
+        hand_size: 6,
+        iterations: 10000000,
+        hits: &[9915752, 6416658, 2047544, 301168, 8803750, 5271611, 4933651, 19558, 1291226, 3569971, 1338647, 1284456, 5816, 128448, 5816, 195573, 0],
+    },
+];
@@ -0,0 +1,151 @@
+//! Coarse timing counters for the simulation loop.
+//!
+//! Counters are only collected with the `profiling` feature; otherwise
+//! `start` returns None and every method is a no-op the compiler removes.
+//! Timing each call has real overhead, so compare the numbers against each
+//! other rather than against an unprofiled run.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+pub enum Stage {
+    Deal,
+    Discard,
+    Detectors,
+    Observers,
+    Scoring,
+}
+
+impl Stage {
+    const ALL: [Stage; 5] = [
+        Stage::Deal,
+        Stage::Discard,
+        Stage::Detectors,
+        Stage::Observers,
+        Stage::Scoring,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Deal => "deal",
+            Stage::Discard => "discard",
+            Stage::Detectors => "detectors",
+            Stage::Observers => "observers",
+            Stage::Scoring => "scoring",
+        }
+    }
+}
+
+pub struct Profile {
+    stages: [Duration; Stage::ALL.len()],
+    // Per detector: name, time spent and number of calls.
+    detectors: Vec<(&'static str, Duration, u64)>,
+    deals: u64,
+    // Deals whose matched detectors were handed to observers as a bitmask.
+    masked_deals: u64,
+}
+
+impl Profile {
+    pub fn new(detector_names: impl IntoIterator<Item = &'static str>) -> Self {
+        Self {
+            stages: Default::default(),
+            detectors: detector_names
+                .into_iter()
+                .map(|name| (name, Duration::ZERO, 0))
+                .collect(),
+            deals: 0,
+            masked_deals: 0,
+        }
+    }
+
+    pub fn enabled() -> bool {
+        cfg!(feature = "profiling")
+    }
+
+    #[inline]
+    pub fn start() -> Option<Instant> {
+        Self::enabled().then(Instant::now)
+    }
+
+    #[inline]
+    pub fn add_stage(&mut self, stage: Stage, start: Option<Instant>) {
+        if let Some(start) = start {
+            self.stages[stage as usize] += start.elapsed();
+        }
+    }
+
+    #[inline]
+    pub fn add_detector(&mut self, detector: usize, start: Option<Instant>) {
+        if let Some(start) = start {
+            let (_, time, calls) = &mut self.detectors[detector];
+            *time += start.elapsed();
+            *calls += 1;
+        }
+    }
+
+    #[inline]
+    pub fn add_deal(&mut self, masked: bool) {
+        if Self::enabled() {
+            self.deals += 1;
+            self.masked_deals += masked as u64;
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let mut ret = String::new();
+        let total = self.stages.iter().sum::<Duration>();
+        let percent = |d: Duration| 100.0 * d.as_secs_f64() / total.as_secs_f64().max(1e-12);
+        writeln!(ret, "profile ({} deals):", self.deals).unwrap();
+        for stage in Stage::ALL {
+            let time = self.stages[stage as usize];
+            writeln!(
+                ret,
+                "  {:>10}: {:>10.3?} ({:5.1}%)",
+                stage.name(),
+                time,
+                percent(time)
+            )
+            .unwrap();
+        }
+        let mut detectors = self.detectors.iter().collect::<Vec<_>>();
+        detectors.sort_by_key(|&&(_, time, _)| std::cmp::Reverse(time));
+        for (name, time, calls) in detectors {
+            let per_call = time.as_nanos() as f64 / (*calls).max(1) as f64;
+            writeln!(
+                ret,
+                "  {:>20}: {:>10.3?} ({:5.1}%, {per_call:.0}ns/call)",
+                name,
+                time,
+                percent(*time)
+            )
+            .unwrap();
+        }
+        writeln!(
+            ret,
+            "  category bitmask read by observers: {} of {} deals",
+            self.masked_deals, self.deals
+        )
+        .unwrap();
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut profile = Profile::new(["Pair", "Flush"]);
+        let start = Some(Instant::now() - Duration::from_millis(2));
+        profile.add_stage(Stage::Detectors, start);
+        profile.add_detector(1, start);
+        profile.add_detector(0, None);
+        let report = profile.report();
+        // Slowest detector first.
+        assert!(report.find("Flush").unwrap() < report.find("Pair").unwrap());
+        assert!(report.contains("0ns/call"));
+        assert_eq!(Profile::enabled(), Profile::start().is_some());
+    }
+}
@@ -0,0 +1,184 @@
+//! `--progress-file`: a fixed-size binary snapshot of the run, rewritten in
+//! place after every batch, so GUI frontends can `mmap` it and poll progress
+//! without parsing output or a round trip through `poker serve`.
+//!
+//! All integers are little endian. The file starts with a 48 byte header:
+//!
+//! | offset | type     | field                                           |
+//! |--------|----------|-------------------------------------------------|
+//! | 0      | [u8; 8]  | magic, `b"PKRPROG1"`                            |
+//! | 8      | u64      | sequence number, odd while an update is written |
+//! | 16     | u64      | iterations so far                               |
+//! | 24     | u64      | `--max-iters`, or 0 if there isn't one          |
+//! | 32     | u64      | nanoseconds since the run started               |
+//! | 40     | u32      | number of hands, N                              |
+//! | 44     | u32      | 1 once the run has finished, otherwise 0        |
+//!
+//! followed by N 40 byte records, one per hand in the order of the report:
+//! the hand's name as UTF-8 padded with zeros to 32 bytes, then its count as
+//! a u64. The size of the file never changes during a run.
+//!
+//! Readers should read the sequence number, skip the update if it's odd,
+//! copy the rest, then check the sequence number is unchanged, retrying if
+//! not.
+
+use crate::HandCount;
+use std::io::{Seek, SeekFrom, Write};
+use std::time::Duration;
+
+pub const MAGIC: &[u8; 8] = b"PKRPROG1";
+pub const HEADER_LEN: usize = 48;
+pub const NAME_LEN: usize = 32;
+pub const RECORD_LEN: usize = NAME_LEN + 8;
+
+pub struct ProgressFile<W = std::fs::File> {
+    file: W,
+    seq: u64,
+}
+
+impl ProgressFile {
+    pub fn create(path: &std::path::Path) -> std::io::Result<Self> {
+        Ok(Self::new(std::fs::File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> ProgressFile<W> {
+    pub fn new(file: W) -> Self {
+        Self { file, seq: 0 }
+    }
+
+    // Marks the snapshot as being written with an odd sequence number,
+    // writes everything after it, then the next even sequence number last,
+    // so that a reader never sees an even number around a partial update.
+    pub fn update(
+        &mut self,
+        counts: &[HandCount],
+        num_iters: u64,
+        max_iters: Option<u64>,
+        elapsed: Duration,
+        done: bool,
+    ) -> std::io::Result<()> {
+        self.seq += 1;
+        // The magic too, which the first update is the first to write.
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(MAGIC)?;
+        self.file.write_all(&self.seq.to_le_bytes())?;
+        self.seq += 1;
+        let snapshot = encode(self.seq, counts, num_iters, max_iters, elapsed, done);
+        self.file.write_all(&snapshot[16..])?;
+        self.file.seek(SeekFrom::Start(8))?;
+        self.file.write_all(&snapshot[8..16])
+    }
+}
+
+pub fn encode(
+    seq: u64,
+    counts: &[HandCount],
+    num_iters: u64,
+    max_iters: Option<u64>,
+    elapsed: Duration,
+    done: bool,
+) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(HEADER_LEN + counts.len() * RECORD_LEN);
+    ret.extend_from_slice(MAGIC);
+    ret.extend_from_slice(&seq.to_le_bytes());
+    ret.extend_from_slice(&num_iters.to_le_bytes());
+    ret.extend_from_slice(&max_iters.unwrap_or(0).to_le_bytes());
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+    ret.extend_from_slice(&nanos.to_le_bytes());
+    ret.extend_from_slice(&(counts.len() as u32).to_le_bytes());
+    ret.extend_from_slice(&u32::from(done).to_le_bytes());
+    for c in counts {
+        let mut name = [0; NAME_LEN];
+        let len = c.name.len().min(NAME_LEN);
+        name[..len].copy_from_slice(&c.name.as_bytes()[..len]);
+        ret.extend_from_slice(&name);
+        ret.extend_from_slice(&c.count.to_le_bytes());
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut counts = vec![
+            HandCount::new("Pair", |_, _| true),
+            HandCount::new("Full House", |_, _| true),
+        ];
+        counts[0].count = 7;
+        counts[1].count = 3;
+        let bytes = encode(4, &counts, 10, None, Duration::from_millis(2), true);
+        assert_eq!(HEADER_LEN + 2 * RECORD_LEN, bytes.len());
+        assert_eq!(MAGIC, &bytes[..8]);
+        assert_eq!(4, u64_at(&bytes, 8));
+        assert_eq!(10, u64_at(&bytes, 16));
+        assert_eq!(0, u64_at(&bytes, 24));
+        assert_eq!(2_000_000, u64_at(&bytes, 32));
+        assert_eq!([2, 0, 0, 0, 1, 0, 0, 0], bytes[40..48]);
+        let second = &bytes[HEADER_LEN + RECORD_LEN..];
+        assert_eq!(b"Full House\0", &second[..11]);
+        assert_eq!(3, u64_at(second, NAME_LEN));
+    }
+
+    // Records the offset and contents of each write.
+    #[derive(Default)]
+    struct Writes {
+        cursor: std::io::Cursor<Vec<u8>>,
+        writes: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes.push((self.cursor.position(), buf.to_vec()));
+            self.cursor.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for Writes {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.cursor.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_update_order() {
+        let mut counts = vec![HandCount::new("Pair", |_, _| true)];
+        let mut progress = ProgressFile::new(Writes::default());
+        progress
+            .update(&counts, 1, None, Duration::ZERO, false)
+            .unwrap();
+        counts[0].count = 5;
+        progress.file.writes.clear();
+        progress
+            .update(&counts, 9, Some(10), Duration::ZERO, true)
+            .unwrap();
+        let writes = &progress.file.writes;
+        // The odd sequence number goes first, and the even one last, after
+        // the rest of the snapshot.
+        let seq_writes = writes
+            .iter()
+            .filter(|(offset, _)| *offset == 8)
+            .map(|(_, bytes)| u64_at(bytes, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![3, 4], seq_writes);
+        assert_eq!((8, 4u64.to_le_bytes().to_vec()), *writes.last().unwrap());
+        let first_odd = writes.iter().position(|(offset, _)| *offset == 8).unwrap();
+        let body = writes.iter().position(|(offset, _)| *offset == 16).unwrap();
+        assert!(first_odd < body);
+        assert_eq!(
+            encode(4, &counts, 9, Some(10), Duration::ZERO, true),
+            *progress.file.cursor.get_ref()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! `poker quiz`: guess how likely a hand is, then see the simulated answer.
+//!
+//! Every guess is kept in a stats file so calibration can be tracked across
+//! sessions:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "rounds": [{ "question": "...", "guess": 0.1, "answer": 0.08 }]
+//! }
+//! ```
+
+use crate::schema::{check_version, SCHEMA_VERSION};
+use crate::{
+    is_flush, is_full_house, is_n_of_a_kind, is_straight, is_straight_flush, is_two_pair,
+    split_jokers, standard_deck, Card,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{json, Value};
+
+type HandFn = fn(&[Card], u8) -> bool;
+
+// Hands asked about, each as a phrase completing "P(...)".
+const HANDS: [(&str, HandFn); 8] = [
+    ("a pair", |cards, num_jokers| {
+        is_n_of_a_kind(cards, 2, num_jokers)
+    }),
+    ("two pair", is_two_pair),
+    ("three of a kind", |cards, num_jokers| {
+        is_n_of_a_kind(cards, 3, num_jokers)
+    }),
+    ("a straight", |cards, num_jokers| {
+        is_straight(cards, num_jokers, 5)
+    }),
+    ("a flush", |cards, num_jokers| {
+        is_flush(cards, num_jokers, 5)
+    }),
+    ("a full house", is_full_house),
+    ("four of a kind", |cards, num_jokers| {
+        is_n_of_a_kind(cards, 4, num_jokers)
+    }),
+    ("a straight flush", |cards, num_jokers| {
+        is_straight_flush(cards, num_jokers, 5)
+    }),
+];
+
+// Answers outside this range make for dull questions.
+const MIN_ANSWER: f64 = 0.001;
+const MAX_ANSWER: f64 = 0.99;
+
+// Bigger draws are too many cards to picture.
+const MAX_QUESTION_CARDS: usize = 12;
+
+pub struct Question {
+    pub cards: usize,
+    pub decks: usize,
+    pub jokers: u8,
+    hand: usize,
+}
+
+impl Question {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            cards: rng.gen_range(5..=MAX_QUESTION_CARDS),
+            decks: if rng.gen_bool(0.8) { 1 } else { 2 },
+            jokers: if rng.gen_bool(0.7) {
+                0
+            } else {
+                rng.gen_range(1..=2)
+            },
+            hand: rng.gen_range(0..HANDS.len()),
+        }
+    }
+
+    pub fn hand(&self) -> &'static str {
+        HANDS[self.hand].0
+    }
+
+    pub fn prompt(&self) -> String {
+        let decks = if self.decks == 1 {
+            "1 deck".to_string()
+        } else {
+            format!("{} decks", self.decks)
+        };
+        let jokers = match self.jokers {
+            0 => String::new(),
+            1 => " + 1 joker".to_string(),
+            n => format!(" + {n} jokers"),
+        };
+        format!(
+            "{} cards from {decks}{jokers}: P({} among them)?",
+            self.cards,
+            self.hand()
+        )
+    }
+
+    // The fraction of `iters` random deals making the hand.
+    pub fn answer(&self, iters: u64, rng: &mut impl Rng) -> f64 {
+        let mut deck = standard_deck(self.decks, self.jokers);
+        let func = HANDS[self.hand].1;
+        let mut hits = 0;
+        for _ in 0..iters {
+            let (drawn, _) = deck.partial_shuffle(rng, self.cards);
+            let (cards, num_jokers) = split_jokers(drawn);
+            hits += func(&cards, num_jokers) as u64;
+        }
+        hits as f64 / iters as f64
+    }
+}
+
+// A random question along with its answer, skipping near certainties and
+// near impossibilities.
+pub fn random_question(iters: u64, rng: &mut impl Rng) -> (Question, f64) {
+    loop {
+        let question = Question::random(rng);
+        let answer = question.answer(iters, rng);
+        if (MIN_ANSWER..=MAX_ANSWER).contains(&answer) {
+            return (question, answer);
+        }
+    }
+}
+
+// Parses a guess like "0.25", "25%", "1 in 4" or "1/4".
+pub fn parse_guess(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let number = |n: &str| {
+        n.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("'{s}' is not a probability like 0.25, 25%, 1 in 4 or 1/4"))
+    };
+    let p = if let Some(percent) = s.strip_suffix('%') {
+        number(percent)? / 100.0
+    } else if let Some((a, b)) = s.split_once(" in ").or_else(|| s.split_once('/')) {
+        number(a)? / number(b)?
+    } else {
+        number(s)?
+    };
+    if !(p > 0.0 && p < 1.0) {
+        return Err("guess a probability between 0 and 1, exclusive".to_string());
+    }
+    Ok(p)
+}
+
+pub struct Round {
+    pub question: String,
+    pub guess: f64,
+    pub answer: f64,
+}
+
+impl Round {
+    // How many times too high the guess was, below 1 for underestimates.
+    pub fn ratio(&self) -> f64 {
+        self.guess / self.answer
+    }
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub rounds: Vec<Round>,
+}
+
+impl Stats {
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        check_version(value)?;
+        let rounds = value
+            .get("rounds")
+            .and_then(Value::as_array)
+            .ok_or("missing rounds")?
+            .iter()
+            .map(|r| {
+                let question = r.get("question").and_then(Value::as_str);
+                let guess = r.get("guess").and_then(Value::as_f64);
+                let answer = r.get("answer").and_then(Value::as_f64);
+                match (question, guess, answer) {
+                    (Some(question), Some(guess), Some(answer)) => Ok(Round {
+                        question: question.to_string(),
+                        guess,
+                        answer,
+                    }),
+                    _ => Err("rounds need a question, guess and answer".to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rounds })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let rounds = self
+            .rounds
+            .iter()
+            .map(|r| json!({ "question": r.question, "guess": r.guess, "answer": r.answer }))
+            .collect::<Vec<_>>();
+        json!({ "schema_version": SCHEMA_VERSION, "rounds": rounds })
+    }
+
+    // An empty history when the file doesn't exist yet.
+    pub fn read(path: &std::path::Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        let value =
+            serde_json::from_str::<Value>(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&value).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        std::fs::write(path, text + "\n").map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    // How far guesses are from the answers, over the last `n` rounds.
+    pub fn summary(&self, n: usize) -> Option<String> {
+        let recent = &self.rounds[self.rounds.len().saturating_sub(n)..];
+        if recent.is_empty() {
+            return None;
+        }
+        let logs = recent.iter().map(|r| r.ratio().log10()).collect::<Vec<_>>();
+        let len = logs.len() as f64;
+        // Geometric means, so that guessing double and half cancel out.
+        let typical = 10f64.powf(logs.iter().map(|l| l.abs()).sum::<f64>() / len);
+        let bias = 10f64.powf(logs.iter().sum::<f64>() / len);
+        let within_2x = recent
+            .iter()
+            .filter(|r| (0.5..=2.0).contains(&r.ratio()))
+            .count();
+        let tendency = if bias >= 1.0 {
+            format!("overestimate by {bias:.2}x")
+        } else {
+            format!("underestimate by {:.2}x", 1.0 / bias)
+        };
+        let which = match (recent.len(), recent.len() == self.rounds.len()) {
+            (1, true) => "the only round".to_string(),
+            (1, false) => "the last round".to_string(),
+            (n, true) => format!("all {n} rounds"),
+            (n, false) => format!("the last {n} rounds"),
+        };
+        Some(format!(
+            "{which}: typically off by {typical:.2}x, {within_2x} within 2x, tend to {tendency}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_guess() {
+        assert_eq!(Ok(0.25), parse_guess("0.25"));
+        assert_eq!(Ok(0.25), parse_guess(" 25% "));
+        assert_eq!(Ok(0.25), parse_guess("1 in 4"));
+        assert_eq!(Ok(0.25), parse_guess("1/4"));
+        assert!(parse_guess("1").is_err());
+        assert!(parse_guess("0%").is_err());
+        assert!(parse_guess("lots").is_err());
+    }
+
+    #[test]
+    fn test_question() {
+        let question = Question {
+            cards: 5,
+            decks: 1,
+            jokers: 0,
+            hand: 4,
+        };
+        assert_eq!(
+            "5 cards from 1 deck: P(a flush among them)?",
+            question.prompt()
+        );
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        // 5148 of the 2598960 5-card hands are flushes or straight flushes.
+        let answer = question.answer(200000, &mut rng);
+        assert!((answer - 0.00198).abs() < 0.0005, "{answer}");
+
+        let (question, answer) = random_question(2000, &mut rng);
+        assert!((MIN_ANSWER..=MAX_ANSWER).contains(&answer));
+        assert!(question.cards <= MAX_QUESTION_CARDS);
+    }
+
+    #[test]
+    fn test_summary() {
+        let round = |guess, answer| Round {
+            question: String::new(),
+            guess,
+            answer,
+        };
+        let stats = Stats {
+            rounds: vec![round(0.9, 0.1), round(0.2, 0.1), round(0.05, 0.1)],
+        };
+        assert_eq!(None, Stats::default().summary(10));
+        assert_eq!(
+            "the last 2 rounds: typically off by 2.00x, 2 within 2x, tend to overestimate by 1.00x",
+            stats.summary(2).unwrap()
+        );
+        assert!(stats
+            .summary(10)
+            .unwrap()
+            .ends_with("tend to overestimate by 2.08x"));
+    }
+
+    #[test]
+    fn test_schema() {
+        // Histories from before schema_version still read.
+        let v1 = json!({ "rounds": [{ "question": "q", "guess": 0.5, "answer": 0.25 }] });
+        let stats = Stats::from_json(&v1).unwrap();
+        assert_eq!(2.0, stats.rounds[0].ratio());
+
+        let value = stats.to_json();
+        let keys = |v: &Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(crate::schema::QUIZ_FIELDS, keys(&value));
+        assert_eq!(crate::schema::QUIZ_ROUND_FIELDS, keys(&value["rounds"][0]));
+
+        let newer = json!({ "schema_version": SCHEMA_VERSION + 1, "rounds": [] });
+        assert!(Stats::from_json(&newer).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! `--ranking`: the most likely order of the hands so far, and how sure we
+//! can be of it, long before every confidence interval has separated.
+//!
+//! Each hand's probability gets a Beta(count + 1, misses + 1) posterior, and
+//! the order is checked against draws from the posteriors. Hands are treated
+//! as independent, which ignores that they're counted from the same deals.
+
+use crate::HandCount;
+use rand::Rng;
+
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // Box-Muller. 1 - u keeps the log away from 0.
+    let u: f64 = rng.gen();
+    let v: f64 = rng.gen();
+    (-2.0 * (1.0 - u).ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+}
+
+// Marsaglia and Tsang's method, for `shape` >= 1.
+fn gamma(shape: f64, rng: &mut impl Rng) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+// A draw from Beta(`a`, `b`), for `a` and `b` >= 1.
+pub fn beta(a: f64, b: f64, rng: &mut impl Rng) -> f64 {
+    let x = gamma(a, rng);
+    x / (x + gamma(b, rng))
+}
+
+pub struct Ranking {
+    // Indices into the counts, most likely first.
+    pub order: Vec<usize>,
+    // Chance each hand really is more likely than the next in `order`, or
+    // None when their counts are tied and no order is claimed.
+    pub adjacent: Vec<Option<f64>>,
+    // Chance every claimed comparison in `order` holds at once.
+    pub correct: f64,
+}
+
+impl Ranking {
+    pub fn new(counts: &[HandCount], num_iters: u64, samples: usize, rng: &mut impl Rng) -> Self {
+        let mut order = (0..counts.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| std::cmp::Reverse(counts[i].count));
+        let claimed = order
+            .windows(2)
+            .map(|w| counts[w[0]].count > counts[w[1]].count)
+            .collect::<Vec<_>>();
+        let mut adjacent_hits = vec![0; claimed.len()];
+        let mut correct_hits = 0;
+        let mut draws = vec![0.0; counts.len()];
+        for _ in 0..samples {
+            for &i in &order {
+                let hits = counts[i].count as f64;
+                draws[i] = beta(hits + 1.0, num_iters as f64 - hits + 1.0, rng);
+            }
+            let mut correct = true;
+            for (k, w) in order.windows(2).enumerate() {
+                if !claimed[k] {
+                    continue;
+                }
+                if draws[w[0]] > draws[w[1]] {
+                    adjacent_hits[k] += 1;
+                } else {
+                    correct = false;
+                }
+            }
+            correct_hits += correct as u64;
+        }
+        let fraction = |n: u64| n as f64 / samples as f64;
+        Self {
+            order,
+            adjacent: claimed
+                .iter()
+                .zip(adjacent_hits)
+                .map(|(&c, n)| c.then(|| fraction(n)))
+                .collect(),
+            correct: fraction(correct_hits),
+        }
+    }
+
+    // Like "Pair >(1.00) 2 pair >(0.93) 3oak = 5oak, P(order) = 0.93".
+    pub fn report(&self, counts: &[HandCount]) -> String {
+        let mut ret = counts[self.order[0]].name.to_string();
+        for (k, &i) in self.order.iter().enumerate().skip(1) {
+            match self.adjacent[k - 1] {
+                Some(p) => ret += &format!(" >({p:.2}) "),
+                None => ret += " = ",
+            }
+            ret += counts[i].name;
+        }
+        format!("best guess order: {ret}, P(order) = {:.2}", self.correct)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_beta() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let n = 20000;
+        let mean = (0..n).map(|_| beta(3.0, 7.0, &mut rng)).sum::<f64>() / n as f64;
+        assert!((mean - 0.3).abs() < 0.01, "{mean}");
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut counts = vec![
+            HandCount::new("a", |_, _| true),
+            HandCount::new("b", |_, _| true),
+            HandCount::new("c", |_, _| true),
+            HandCount::new("d", |_, _| true),
+        ];
+        counts[0].count = 10;
+        counts[1].count = 5000;
+        counts[2].count = 5100;
+        counts[3].count = 10;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let ranking = Ranking::new(&counts, 10000, 2000, &mut rng);
+        assert_eq!(vec![2, 1, 0, 3], ranking.order);
+        assert_eq!(None, ranking.adjacent[2]);
+        // c and b are about 1.4 standard errors apart.
+        let close = ranking.adjacent[0].unwrap();
+        assert!((0.85..0.97).contains(&close), "{close}");
+        assert_eq!(Some(1.0), ranking.adjacent[1]);
+        assert_eq!(close, ranking.correct);
+        assert_eq!(
+            format!("best guess order: c >({close:.2}) b >(1.00) a = d, P(order) = {close:.2}"),
+            ranking.report(&counts)
+        );
+    }
+}
@@ -0,0 +1,702 @@
+//! JSON result files, so runs can be saved and later combined with `merge`.
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "metadata": { "version": "0.1.0", "seed": 1234, ... },
+//!   "config": { "cards": 7, ... },
+//!   "iterations": 1000000,
+//!   "hands": [{ "name": "Pair", "count": 12, "probability": 0.5, "ci": 0.01 }],
+//!   "warnings": [{ "kind": "invalid_interval", "message": "...", "hand": "Flush 5" }]
+//! }
+//! ```
+//!
+//! `probability` and `ci` are derived from the counts and ignored when read.
+//! `warnings` lists anything about the run a tool should show its users, each
+//! with a `kind`, a readable `message` and the fields described on `Warning`.
+//! Readers should skip kinds they don't know.
+//! `metadata` is described in metadata.rs and `schema_version` in schema.rs.
+
+use crate::interval::{bounds_with, interval_with, CiMethod};
+use crate::metadata::Metadata;
+use crate::schema::{check_version, SCHEMA_VERSION};
+use crate::{expected_wait, HandCount};
+use serde_json::{json, Value};
+
+pub struct RunResult {
+    // Where the run came from. Not compared when merging.
+    pub metadata: Option<Metadata>,
+    // Every option which affects the probabilities. Runs can only be merged
+    // when their configurations are identical.
+    pub config: Value,
+    pub iterations: u64,
+    pub counts: Vec<(String, u64)>,
+    pub warnings: Vec<Warning>,
+    // How confidence intervals are computed for reports and warnings. Not
+    // saved, so read files get the normal approximation.
+    pub ci_method: CiMethod,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    // `hands` were left out since no deal of `cards` cards makes them.
+    ImpossibleHands { hands: Vec<String>, cards: u64 },
+    // The interval for `hand` reaches outside [0, 1] or has no width, as the
+    // normal approximation does for rare hands.
+    InvalidInterval { hand: String },
+    // Merged counts for `hand` overflowed, so it stopped at u64::MAX.
+    CounterSaturated { hand: String },
+}
+
+impl Warning {
+    fn kind(&self) -> &'static str {
+        match self {
+            Warning::ImpossibleHands { .. } => "impossible_hands",
+            Warning::InvalidInterval { .. } => "invalid_interval",
+            Warning::CounterSaturated { .. } => "counter_saturated",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({ "kind": self.kind(), "message": self.to_string() });
+        match self {
+            Warning::ImpossibleHands { hands, cards } => {
+                value["hands"] = json!(hands);
+                value["cards"] = json!(cards);
+            }
+            Warning::InvalidInterval { hand } | Warning::CounterSaturated { hand } => {
+                value["hand"] = json!(hand);
+            }
+        }
+        value
+    }
+
+    // None for kinds this build doesn't know.
+    pub fn from_json(value: &Value) -> Result<Option<Self>, String> {
+        let field = |key: &str| value.get(key).ok_or(format!("warning missing {key}"));
+        let string = |key: &str| {
+            field(key)?
+                .as_str()
+                .map(str::to_string)
+                .ok_or(format!("invalid warning {key}"))
+        };
+        Ok(Some(match field("kind")?.as_str() {
+            Some("impossible_hands") => Warning::ImpossibleHands {
+                hands: field("hands")?
+                    .as_array()
+                    .and_then(|hands| {
+                        hands
+                            .iter()
+                            .map(|h| h.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .ok_or("invalid warning hands")?,
+                cards: field("cards")?.as_u64().ok_or("invalid warning cards")?,
+            },
+            Some("invalid_interval") => Warning::InvalidInterval {
+                hand: string("hand")?,
+            },
+            Some("counter_saturated") => Warning::CounterSaturated {
+                hand: string("hand")?,
+            },
+            _ => return Ok(None),
+        }))
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::ImpossibleHands { hands, cards } => {
+                write!(
+                    f,
+                    "skipped {}, which need more than {cards} cards",
+                    hands.join(", ")
+                )
+            }
+            Warning::InvalidInterval { hand } => write!(
+                f,
+                "{hand}'s confidence interval is too unreliable to use, try --ci-method wilson"
+            ),
+            Warning::CounterSaturated { hand } => {
+                write!(f, "{hand}'s count overflowed and is a lower bound")
+            }
+        }
+    }
+}
+
+// Whether the `method` interval for `count` hits in `iterations` deals is
+// meaningless.
+fn invalid_interval(method: CiMethod, iterations: u64, count: u64) -> bool {
+    let (lo, hi) = bounds_with(method, iterations, count);
+    !(lo >= 0.0 && hi <= 1.0 && hi > lo)
+}
+
+impl RunResult {
+    pub fn new(config: Value, counts: &[HandCount], iterations: u64) -> Self {
+        let mut ret = Self {
+            metadata: None,
+            config,
+            iterations,
+            counts: counts
+                .iter()
+                .map(|c| (c.name.to_string(), c.count))
+                .collect(),
+            warnings: Vec::new(),
+            ci_method: CiMethod::Normal,
+        };
+        ret.update_interval_warnings();
+        ret
+    }
+
+    // Reports intervals computed by `method`.
+    pub fn set_ci_method(&mut self, method: CiMethod) {
+        self.ci_method = method;
+        self.update_interval_warnings();
+    }
+
+    // Replaces the `InvalidInterval` warnings to match the counts.
+    fn update_interval_warnings(&mut self) {
+        self.warnings
+            .retain(|w| !matches!(w, Warning::InvalidInterval { .. }));
+        for (name, count) in &self.counts {
+            if invalid_interval(self.ci_method, self.iterations, *count) {
+                self.warnings
+                    .push(Warning::InvalidInterval { hand: name.clone() });
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let hands = self
+            .counts
+            .iter()
+            .map(|(name, count)| {
+                let (p, ci) = interval_with(self.ci_method, self.iterations, *count);
+                json!({ "name": name, "count": count, "probability": p, "ci": ci })
+            })
+            .collect::<Vec<_>>();
+        let mut value = serde_json::Map::new();
+        value.insert("schema_version".into(), SCHEMA_VERSION.into());
+        if let Some(metadata) = &self.metadata {
+            value.insert("metadata".into(), metadata.clone().into());
+        }
+        value.insert("config".into(), self.config.clone());
+        value.insert("iterations".into(), self.iterations.into());
+        value.insert("hands".into(), hands.into());
+        let warnings = self
+            .warnings
+            .iter()
+            .map(Warning::to_json)
+            .collect::<Vec<_>>();
+        value.insert("warnings".into(), warnings.into());
+        value.into()
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        check_version(value)?;
+        let metadata = value.get("metadata").and_then(Value::as_object).cloned();
+        let config = value.get("config").ok_or("missing config")?.clone();
+        let iterations = value
+            .get("iterations")
+            .and_then(Value::as_u64)
+            .ok_or("missing iterations")?;
+        let counts = value
+            .get("hands")
+            .and_then(Value::as_array)
+            .ok_or("missing hands")?
+            .iter()
+            .map(|h| {
+                let name = h.get("name").and_then(Value::as_str);
+                let count = h.get("count").and_then(Value::as_u64);
+                match (name, count) {
+                    (Some(name), Some(count)) => Ok((name.to_string(), count)),
+                    _ => Err("hands entries need a name and count".to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        // Files from before warnings have none.
+        let warnings = match value.get("warnings") {
+            None => Vec::new(),
+            Some(warnings) => warnings
+                .as_array()
+                .ok_or("invalid warnings")?
+                .iter()
+                .filter_map(|w| Warning::from_json(w).transpose())
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(Self {
+            metadata,
+            config,
+            iterations,
+            counts,
+            warnings,
+            ci_method: CiMethod::Normal,
+        })
+    }
+
+    pub fn read(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let value = serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&value).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        std::fs::write(path, text + "\n").map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    // Adds another run's deals to this one.
+    pub fn merge(&mut self, other: &RunResult) -> Result<(), String> {
+        if self.config != other.config {
+            return Err(format!(
+                "configurations differ: {} vs {}",
+                self.config, other.config
+            ));
+        }
+        let names = |r: &RunResult| r.counts.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>();
+        if names(self) != names(other) {
+            return Err("runs counted different hands".to_string());
+        }
+        self.iterations += other.iterations;
+        for ((name, a), (_, b)) in self.counts.iter_mut().zip(&other.counts) {
+            *a = a.checked_add(*b).unwrap_or_else(|| {
+                let warning = Warning::CounterSaturated { hand: name.clone() };
+                if !self.warnings.contains(&warning) {
+                    self.warnings.push(warning);
+                }
+                u64::MAX
+            });
+        }
+        for w in &other.warnings {
+            if !self.warnings.contains(w) {
+                self.warnings.push(w.clone());
+            }
+        }
+        self.update_interval_warnings();
+        Ok(())
+    }
+
+    // Keys of `config` whose values differ from this run's, like "discards
+    // (1 vs 0)" with `config`'s value first.
+    pub fn config_differences(&self, config: &Value) -> Vec<String> {
+        let empty = serde_json::Map::new();
+        let ours = config.as_object().unwrap_or(&empty);
+        let theirs = self.config.as_object().unwrap_or(&empty);
+        let mut keys = ours.keys().chain(theirs.keys()).collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|k| ours.get(*k) != theirs.get(*k))
+            .map(|k| {
+                let show = |v: Option<&Value>| v.map_or("unset".to_string(), |v| v.to_string());
+                format!("{k} ({} vs {})", show(ours.get(k)), show(theirs.get(k)))
+            })
+            .collect()
+    }
+
+    // One sentence about hand `name` to paste into a chat, like "Flush in 8
+    // cards from 2 decks + 2 jokers: 18.3% ± 0.1% (1 in 5.5)". None if the
+    // hand wasn't counted.
+    pub fn headline(&self, name: &str) -> Option<String> {
+        let (name, count) = self
+            .counts
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))?;
+        let setting = |key: &str| self.config.get(key).and_then(Value::as_u64).unwrap_or(0);
+        let plural = |n: u64, what: &str| {
+            if n == 1 {
+                format!("1 {what}")
+            } else {
+                format!("{n} {what}s")
+            }
+        };
+        let mut deck = match self.config.get("deck").and_then(Value::as_str) {
+            Some(edits) => format!("a deck with {edits}"),
+            None => plural(setting("decks"), "deck"),
+        };
+        for (key, what) in [("jokers", "joker"), ("wild", "wild card")] {
+            if setting(key) > 0 {
+                deck += &format!(" + {}", plural(setting(key), what));
+            }
+        }
+        if let Some(cards) = self.config.get("guarantee").and_then(Value::as_str) {
+            deck += &format!(", always drawing {cards}");
+        }
+        let (p, ci) = interval_with(self.ci_method, self.iterations, *count);
+        let odds = match expected_wait(self.ci_method, self.iterations, *count) {
+            Some((wait, _, _)) => format!("1 in {wait:.1}"),
+            None => format!("never seen in {} deals", self.iterations),
+        };
+        // Enough decimals to show the first significant digit of the interval.
+        let decimals = if ci > 0.0 {
+            (-(100.0 * ci).log10()).ceil().clamp(1.0, 6.0) as usize
+        } else {
+            1
+        };
+        Some(format!(
+            "{name} in {} cards from {deck}: {:.decimals$}% ± {:.decimals$}% ({odds})",
+            setting("cards"),
+            100.0 * p,
+            100.0 * ci,
+        ))
+    }
+
+    // Each hand's probability with its 99.73% confidence interval, count and
+    // odds, most likely first, plus the change from `baseline` if given.
+    pub fn to_text(&self, baseline: Option<&RunResult>) -> String {
+        use std::fmt::Write;
+        let mut ret = String::new();
+        let width = self.counts.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+        let mut counts = self.counts.iter().collect::<Vec<_>>();
+        counts.sort_by_key(|(name, count)| std::cmp::Reverse((*count, name)));
+        for (name, count) in counts {
+            let wait = match expected_wait(self.ci_method, self.iterations, *count) {
+                Some((wait, lo, hi)) => format!(" 1 in {wait:.1} [{lo:.1}, {hi:.1}]"),
+                None => String::new(),
+            };
+            let change = match baseline {
+                Some(b) => format!(
+                    " Δ {}",
+                    format_delta(delta(b, name, self.iterations, *count))
+                ),
+                None => String::new(),
+            };
+            let p = *count as f64 / self.iterations as f64;
+            let interval = format_interval(self.ci_method, self.iterations, *count);
+            writeln!(
+                ret,
+                "{name: >width$}: {p:.6} {interval} ({count}){wait}{change}"
+            )
+            .unwrap();
+        }
+        ret
+    }
+
+    // A GitHub-flavored Markdown table of each hand's probability, 99.73%
+    // confidence interval and odds, most likely first, plus the change from
+    // `baseline` if given.
+    pub fn to_markdown(&self, baseline: Option<&RunResult>) -> String {
+        use std::fmt::Write;
+        let mut ret = String::new();
+        let (header, align) = match baseline {
+            Some(_) => (" Δ baseline |", "-----------:|"),
+            None => ("", ""),
+        };
+        writeln!(ret, "| Hand | Probability | 99.73% CI | Odds |{header}").unwrap();
+        writeln!(ret, "|:-----|------------:|----------:|-----:|{align}").unwrap();
+        let mut counts = self.counts.iter().collect::<Vec<_>>();
+        counts.sort_by_key(|(name, count)| std::cmp::Reverse((*count, name)));
+        for (name, count) in counts {
+            let (p, ci) = interval_with(self.ci_method, self.iterations, *count);
+            let odds = match expected_wait(self.ci_method, self.iterations, *count) {
+                Some((wait, _, _)) => format!("1 in {wait:.1}"),
+                None => "never seen".to_string(),
+            };
+            let change = match baseline {
+                Some(b) => format!(
+                    " {} |",
+                    format_delta(delta(b, name, self.iterations, *count))
+                ),
+                None => String::new(),
+            };
+            // Pipes would end the cell early.
+            let name = name.replace('|', "\\|");
+            writeln!(ret, "| {name} | {p:.6} | ± {ci:.6} | {odds} |{change}").unwrap();
+        }
+        writeln!(ret, "\n{} iterations", self.iterations).unwrap();
+        ret
+    }
+
+    // One row per hand, in the order they were counted, plus the change from
+    // `baseline` and its z score if given.
+    pub fn to_csv(&self, baseline: Option<&RunResult>) -> String {
+        use std::fmt::Write;
+        let mut ret = String::new();
+        if let Some(metadata) = &self.metadata {
+            let mut comments = Vec::new();
+            crate::metadata::write_comments(metadata, &mut comments).unwrap();
+            ret += &String::from_utf8(comments).unwrap();
+        }
+        ret += "hand,count,probability,ci";
+        if baseline.is_some() {
+            ret += ",delta,z";
+        }
+        ret += "\n";
+        for (name, count) in &self.counts {
+            let (p, ci) = interval_with(self.ci_method, self.iterations, *count);
+            write!(ret, "{name},{count},{p},{ci}").unwrap();
+            if let Some(baseline) = baseline {
+                match delta(baseline, name, self.iterations, *count) {
+                    Some((change, z)) => write!(ret, ",{change},{z}").unwrap(),
+                    None => ret += ",,",
+                }
+            }
+            ret += "\n";
+        }
+        ret
+    }
+}
+
+// Change in a hand's probability from `baseline`, given `count` deals of
+// `iterations` making it now, and the z score of the change treating the
+// runs as independent. None if `baseline` didn't count the hand.
+pub fn delta(baseline: &RunResult, name: &str, iterations: u64, count: u64) -> Option<(f64, f64)> {
+    let (_, base_count) = baseline.counts.iter().find(|(n, _)| n == name)?;
+    let p = count as f64 / iterations as f64;
+    let q = *base_count as f64 / baseline.iterations as f64;
+    let se =
+        (p * (1.0 - p) / iterations as f64 + q * (1.0 - q) / baseline.iterations as f64).sqrt();
+    let z = if se > 0.0 { (p - q) / se } else { 0.0 };
+    Some((p - q, z))
+}
+
+// Like "± 0.001234" for the symmetric normal interval, or "[0.000100,
+// 0.002500]" for the others.
+pub fn format_interval(method: CiMethod, total_iters: u64, num_true: u64) -> String {
+    if method == CiMethod::Normal {
+        let (_, ci) = interval_with(method, total_iters, num_true);
+        return format!("± {ci:.6}");
+    }
+    let (lo, hi) = bounds_with(method, total_iters, num_true);
+    format!("[{lo:.6}, {hi:.6}]")
+}
+
+// Like "+0.001234 **": one star for changes at least 2 standard errors from
+// zero and two for at least 3, or "-" without a baseline value.
+pub fn format_delta(delta: Option<(f64, f64)>) -> String {
+    let Some((change, z)) = delta else {
+        return "-".to_string();
+    };
+    let stars = match z.abs() {
+        z if z >= 3.0 => " **",
+        z if z >= 2.0 => " *",
+        _ => "",
+    };
+    format!("{change:+.6}{stars}")
+}
+
+// A table of each hand's probability and confidence interval.
+impl std::fmt::Display for RunResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "total iterations: {}", self.iterations)?;
+        let width = self.counts.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+        for (name, count) in &self.counts {
+            let (p, ci) = interval_with(self.ci_method, self.iterations, *count);
+            writeln!(f, "{name: >width$}: {p:.6} ± {ci:.6} ({count})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(config: Value, iterations: u64, pairs: u64) -> RunResult {
+        RunResult {
+            metadata: None,
+            config,
+            iterations,
+            counts: vec![("Pair".to_string(), pairs), ("3oak".to_string(), 1)],
+            warnings: Vec::new(),
+            ci_method: CiMethod::Normal,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut a = run(json!({ "cards": 7 }), 100, 40);
+        a.metadata = Some(Metadata::from_iter([("seed".to_string(), 5.into())]));
+        let b = RunResult::from_json(&a.to_json()).unwrap();
+        assert_eq!(a.metadata, b.metadata);
+        assert_eq!(a.config, b.config);
+        assert_eq!(a.iterations, b.iterations);
+        assert_eq!(a.counts, b.counts);
+        assert_eq!(0.4, a.to_json()["hands"][0]["probability"]);
+        assert!(RunResult::from_json(&json!({ "config": {} })).is_err());
+    }
+
+    #[test]
+    fn test_schema() {
+        // Result files from before schema_version still read.
+        let v1 = json!({
+            "metadata": { "seed": 5 },
+            "config": { "cards": 7 },
+            "iterations": 100,
+            "hands": [{ "name": "Pair", "count": 40, "probability": 0.4, "ci": 0.1 }],
+        });
+        let result = RunResult::from_json(&v1).unwrap();
+        assert_eq!(vec![("Pair".to_string(), 40)], result.counts);
+
+        let mut result = run(json!({ "cards": 7 }), 100, 40);
+        result.metadata = Some(Metadata::new());
+        let value = result.to_json();
+        let keys = |v: &Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(crate::schema::RESULT_FIELDS, keys(&value));
+        assert_eq!(crate::schema::RESULT_HAND_FIELDS, keys(&value["hands"][0]));
+        assert_eq!(SCHEMA_VERSION, value["schema_version"]);
+
+        let mut newer = value.clone();
+        newer["schema_version"] = (SCHEMA_VERSION + 1).into();
+        assert!(RunResult::from_json(&newer).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "total iterations: 100\nPair: 0.400000 ± 0.146969 (40)\n3oak: 0.010000 ± 0.029850 (1)\n",
+            run(json!({}), 100, 40).to_string()
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let result = run(json!({}), 100, 40);
+        assert_eq!(
+            "Pair: 0.400000 ± 0.146969 (40) 1 in 2.5 [1.8, 4.0]\n\
+             3oak: 0.010000 ± 0.029850 (1) 1 in 100.0 [25.1, inf]\n",
+            result.to_text(None)
+        );
+        let baseline = run(json!({}), 100, 40);
+        assert!(result
+            .to_text(Some(&baseline))
+            .starts_with("Pair: 0.400000 ± 0.146969 (40) 1 in 2.5 [1.8, 4.0] Δ +0.000000\n"));
+    }
+
+    #[test]
+    fn test_format_interval() {
+        assert_eq!("± 0.150000", format_interval(CiMethod::Normal, 100, 50));
+        assert_eq!(
+            "[0.000000, 0.082569]",
+            format_interval(CiMethod::Wilson, 100, 0)
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let mut result = run(json!({}), 100, 0);
+        result.counts[0].0 = "a|b".to_string();
+        assert_eq!(
+            "| Hand | Probability | 99.73% CI | Odds |\n\
+             |:-----|------------:|----------:|-----:|\n\
+             | 3oak | 0.010000 | ± 0.029850 | 1 in 100.0 |\n\
+             | a\\|b | 0.000000 | ± 0.000000 | never seen |\n\
+             \n\
+             100 iterations\n",
+            result.to_markdown(None)
+        );
+    }
+
+    #[test]
+    fn test_headline() {
+        let config = json!({ "cards": 8, "decks": 2, "jokers": 2 });
+        assert_eq!(
+            "Pair in 8 cards from 2 decks + 2 jokers: 18.3% ± 0.1% (1 in 5.5)",
+            run(config, 1_000_000, 183_000).headline("pair").unwrap()
+        );
+        let config = json!({ "cards": 5, "deck": "As x3", "wild": 1, "guarantee": "Kh" });
+        assert_eq!(
+            "3oak in 5 cards from a deck with As x3 + 1 wild card, always drawing Kh: \
+             1.0% ± 3.0% (1 in 100.0)",
+            run(config, 100, 0).headline("3oak").unwrap()
+        );
+        assert_eq!(
+            "Pair in 5 cards from 1 deck: 0.0% ± 0.0% (never seen in 100 deals)",
+            run(json!({ "cards": 5, "decks": 1 }), 100, 0)
+                .headline("Pair")
+                .unwrap()
+        );
+        assert_eq!(None, run(json!({}), 100, 0).headline("Flush"));
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            "hand,count,probability,ci\nPair,40,0.4,0.14696938456699066\n3oak,1,0.01,0.0298496231131986\n",
+            run(json!({}), 100, 40).to_csv(None)
+        );
+        let mut result = run(json!({}), 100, 40);
+        result.metadata = Some(Metadata::from_iter([("seed".to_string(), 5.into())]));
+        assert!(result.to_csv(None).starts_with("# seed: 5\nhand,count"));
+    }
+
+    #[test]
+    fn test_baseline() {
+        let now = run(json!({ "cards": 7, "discards": 1 }), 10000, 5000);
+        let mut baseline = run(json!({ "cards": 7 }), 10000, 4000);
+        baseline.counts.pop();
+        assert_eq!(
+            vec!["discards (1 vs unset)"],
+            baseline.config_differences(&now.config)
+        );
+        let (change, z) = delta(&baseline, "Pair", 10000, 5000).unwrap();
+        assert!((change - 0.1).abs() < 1e-12);
+        assert!(z > 3.0);
+        assert_eq!(None, delta(&baseline, "3oak", 10000, 1));
+        assert_eq!("+0.100000 **", format_delta(Some((change, z))));
+        assert_eq!("-0.010000 *", format_delta(Some((-0.01, -2.5))));
+        assert_eq!("+0.000000", format_delta(Some((0.0, 0.0))));
+        assert!(now
+            .to_markdown(Some(&baseline))
+            .contains("| 1 in 2.0 | +0.100000 ** |"));
+        assert_eq!(
+            "hand,count,probability,ci,delta,z\n\
+             Pair,5000,0.5,0.015,0.09999999999999998,14.285714285714283\n\
+             3oak,1,0.0001,0.00029998499962498125,,\n",
+            now.to_csv(Some(&baseline))
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = run(json!({ "cards": 7 }), 100, 40);
+        a.merge(&run(json!({ "cards": 7 }), 300, 20)).unwrap();
+        assert_eq!(400, a.iterations);
+        assert_eq!(
+            vec![("Pair".to_string(), 60), ("3oak".to_string(), 2)],
+            a.counts
+        );
+        assert!(a.merge(&run(json!({ "cards": 8 }), 100, 40)).is_err());
+        let mut other_hands = run(json!({ "cards": 7 }), 100, 40);
+        other_hands.counts.pop();
+        assert!(a.merge(&other_hands).is_err());
+    }
+
+    #[test]
+    fn test_warnings() {
+        // The normal interval around 1 in 100 dips below zero.
+        let mut pair = HandCount::checked("Pair", crate::plan::Check::OfAKind(2));
+        pair.count = 1;
+        let mut result = RunResult::new(json!({}), &[pair], 100);
+        assert_eq!(
+            vec![Warning::InvalidInterval {
+                hand: "Pair".to_string()
+            }],
+            result.warnings
+        );
+        result.set_ci_method(CiMethod::Wilson);
+        assert_eq!(Vec::<Warning>::new(), result.warnings);
+
+        let mut a = run(json!({}), 100, u64::MAX - 1);
+        a.warnings.push(Warning::ImpossibleHands {
+            hands: vec!["6oak".to_string()],
+            cards: 5,
+        });
+        a.merge(&run(json!({}), 100, 40)).unwrap();
+        assert!(a.warnings.contains(&Warning::CounterSaturated {
+            hand: "Pair".to_string()
+        }));
+        assert_eq!(u64::MAX, a.counts[0].1);
+
+        let b = RunResult::from_json(&a.to_json()).unwrap();
+        assert_eq!(a.warnings, b.warnings);
+        assert_eq!(
+            "skipped 6oak, which need more than 5 cards",
+            a.to_json()["warnings"][0]["message"]
+        );
+        let mut value = a.to_json();
+        value["warnings"] = json!([{ "kind": "from_the_future", "message": "" }]);
+        assert!(RunResult::from_json(&value).unwrap().warnings.is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! The hands each rule set counts, described for frontends which build their
+//! hand lists from the library rather than hard-coding them.
+
+use crate::badugi;
+use crate::lowball::{self, Lowball};
+use crate::omaha::{self, OmahaHand};
+use crate::plan::Facts;
+use crate::{best_hand_counts, hand_counts, CardOrJoker, HandCount, HandRank, MAX_CARDS};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RuleSet {
+    /// Count every hand each deal makes, so a full house is also a pair
+    #[default]
+    Independent,
+    /// Count only each deal's best hand, so probabilities sum to 1
+    Best,
+    /// Count each deal's best Omaha hand, exactly two of the first four
+    /// cards and three of the rest, and whether it makes an 8-or-better low
+    Omaha,
+    /// Count each deal's best ace-to-five low, where straights and flushes
+    /// don't count, as "N Low" or better
+    Razz,
+    /// Count each deal's best deuce-to-seven low, where aces are high and
+    /// straights and flushes spoil a low, as "N Low" or better
+    #[value(name = "27lowball")]
+    DeuceToSeven,
+    /// Count each deal's best badugi hand, the most cards of different ranks
+    /// and suits it plays, from a four-card badugi down to one card
+    Badugi,
+}
+
+impl RuleSet {
+    // The counts for a run, or None if `hand_size` is out of range.
+    pub fn counts(self, hand_size: usize) -> Option<Vec<HandCount>> {
+        match self {
+            RuleSet::Independent => hand_counts(hand_size),
+            RuleSet::Best => best_hand_counts(hand_size),
+            // Omaha hands and lows are always five cards, and badugi hands
+            // four, so these leave --hand-size at its default.
+            RuleSet::Omaha => (hand_size == 5).then(omaha::counts),
+            RuleSet::Razz => (hand_size == 5).then(|| lowball::counts(Lowball::AceToFive)),
+            RuleSet::DeuceToSeven => {
+                (hand_size == 5).then(|| lowball::counts(Lowball::DeuceToSeven))
+            }
+            RuleSet::Badugi => (hand_size == 5).then(badugi::counts),
+        }
+    }
+
+    // The hands `counts` would count, in the same order.
+    pub fn hands_for(self, hand_size: usize) -> Option<Vec<HandInfo>> {
+        Some(
+            self.counts(hand_size)?
+                .into_iter()
+                .map(HandInfo::new)
+                .collect(),
+        )
+    }
+}
+
+pub struct HandInfo {
+    // Identifier for the command line, the `HandRank` id when there is one.
+    pub id: String,
+    pub name: &'static str,
+    // The fewest cards a deal needs to make the hand, counting jokers.
+    pub min_cards: usize,
+    count: HandCount,
+}
+
+impl HandInfo {
+    pub fn new(count: HandCount) -> Self {
+        let high = match count.omaha {
+            Some(OmahaHand::High(rank)) => Some(rank),
+            _ => None,
+        };
+        let rank = count.best.map(|(rank, _)| rank).or(high).or_else(|| {
+            HandRank::ALL
+                .into_iter()
+                .find(|r| r.check().is_some() && r.check() == count.check)
+        });
+        let id = match rank {
+            Some(rank) => rank.id().to_string(),
+            None => count.name.to_lowercase().replace(' ', "_"),
+        };
+        let min_cards = (1..=MAX_CARDS)
+            .find(|&n| count.possible_with(n))
+            .unwrap_or(usize::MAX);
+        Self {
+            id,
+            name: count.name,
+            min_cards,
+            count,
+        }
+    }
+
+    // Whether some deal of `num_cards` cards from `deck` makes the hand.
+    // Best-hand counts only ask whether the deck holds the rank's cards, so a
+    // deck where every deal makes a pair still allows high card.
+    pub fn possible_in(&self, deck: &[CardOrJoker], num_cards: usize) -> bool {
+        if num_cards < self.min_cards || deck.len() < self.min_cards {
+            return false;
+        }
+        let cards = deck.iter().filter_map(|c| match c {
+            CardOrJoker::Card(card) => Some(*card),
+            CardOrJoker::Joker => None,
+        });
+        let cards = cards.collect::<Vec<_>>();
+        let num_jokers = (deck.len() - cards.len()).min(MAX_CARDS) as u8;
+        let check = match (self.count.best, self.count.omaha) {
+            (Some((rank, _)), _) | (None, Some(OmahaHand::High(rank))) => rank.check(),
+            (None, Some(OmahaHand::Low)) => None,
+            (None, None) => self.count.check,
+        };
+        match check {
+            Some(check) => {
+                let facts = Facts::for_deck(&cards).skipping(self.count.skipped_in_straights);
+                check.matches_facts(&facts, num_jokers)
+            }
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standard_deck;
+
+    fn hand(rules: RuleSet, hand_size: usize, id: &str) -> HandInfo {
+        rules
+            .hands_for(hand_size)
+            .unwrap()
+            .into_iter()
+            .find(|h| h.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hands_for() {
+        assert!(RuleSet::Independent.hands_for(4).is_none());
+        assert!(RuleSet::Omaha.hands_for(6).is_none());
+        let hands = RuleSet::Independent.hands_for(5).unwrap();
+        assert_eq!(hand_counts(5).unwrap().len(), hands.len());
+        assert_eq!("pair", hands[0].id);
+        assert_eq!("Pair", hands[0].name);
+        for (expected, id) in [
+            (2, "pair"),
+            (4, "two_pair"),
+            (5, "royal_flush"),
+            (1, "high_card"),
+        ] {
+            assert_eq!(
+                expected,
+                hand(RuleSet::Independent, 5, id).min_cards,
+                "{id}"
+            );
+        }
+        assert_eq!(8, hand(RuleSet::Independent, 8, "4_pair").min_cards);
+        assert_eq!("straight6", hand(RuleSet::Independent, 6, "straight6").id);
+        for (info, rank) in RuleSet::Best
+            .hands_for(6)
+            .unwrap()
+            .iter()
+            .zip(HandRank::ALL)
+        {
+            assert_eq!(rank.id(), info.id);
+        }
+    }
+
+    #[test]
+    fn test_possible_in() {
+        let deck = standard_deck(1, 0);
+        let flush_house = hand(RuleSet::Independent, 5, "flush_house");
+        assert!(!hand(RuleSet::Independent, 5, "5oak").possible_in(&deck, 7));
+        assert!(!hand(RuleSet::Independent, 5, "flush_five").possible_in(&deck, 7));
+        assert!(!flush_house.possible_in(&deck, 7));
+        // Three of a rank in one suit takes three decks.
+        assert!(!flush_house.possible_in(&standard_deck(2, 0), 7));
+        assert!(flush_house.possible_in(&standard_deck(3, 0), 7));
+        assert!(hand(RuleSet::Independent, 5, "5oak").possible_in(&standard_deck(1, 1), 7));
+        assert!(!hand(RuleSet::Independent, 5, "straight").possible_in(&deck, 4));
+        assert!(hand(RuleSet::Best, 5, "straight_flush").possible_in(&deck, 5));
+        assert!(hand(RuleSet::Best, 5, "high_card").possible_in(&deck, 5));
+        assert!(!hand(RuleSet::Omaha, 5, "flush").possible_in(&deck, 6));
+        assert!(hand(RuleSet::Omaha, 5, "8_low").possible_in(&deck, 7));
+        assert!(!hand(RuleSet::Omaha, 5, "5oak").possible_in(&deck, 9));
+    }
+}
@@ -0,0 +1,184 @@
+//! Scenario files for `poker watch`: simulation options written TOML style,
+//! one `key = value` per line, turned back into command line flags.
+//!
+//! ```toml
+//! # 8 cards from an edited deck
+//! cards = 8
+//! deck_file = "my_deck.txt"
+//! short_straights = true
+//! derive = ["sf_ratio = straight_flush / flush"]
+//! ```
+//!
+//! Keys are the long flag names with "_" or "-". `true` passes a bare flag,
+//! `false` leaves it out and arrays repeat the flag once per item.
+
+fn parse_string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut ret = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => ret.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                c @ ('\\' | '"') => c,
+                _ => return None,
+            }),
+            '"' => return None,
+            c => ret.push(c),
+        }
+    }
+    Some(ret)
+}
+
+// A bare number or a quoted string, as a flag's value.
+fn parse_scalar(value: &str) -> Result<String, String> {
+    if value.starts_with('"') {
+        return parse_string(value).ok_or_else(|| format!("invalid string {value}"));
+    }
+    // TOML allows underscores between digits, like 1_000_000.
+    let number = value.replace('_', "");
+    if number.parse::<f64>().is_ok() {
+        return Ok(number);
+    }
+    Err(format!(
+        "expected a number, string, boolean or array, not '{value}'"
+    ))
+}
+
+// Splits the inside of an array on commas outside of strings.
+fn split_items(inner: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&inner[start..]);
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Strips a "#" comment, unless it's inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// Command line arguments for the options in `text`, like ["--cards", "8"].
+pub fn parse(text: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |e: String| format!("line {}: {e}", i + 1);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("expected 'key = value', not '{line}'")))?;
+        let key = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(error(format!("invalid key '{key}'")));
+        }
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value.trim() {
+            "true" => args.push(flag),
+            "false" => {}
+            value => {
+                let values = match value.strip_prefix('[') {
+                    Some(inner) => {
+                        let inner = inner
+                            .strip_suffix(']')
+                            .ok_or_else(|| error("arrays must be on one line".to_string()))?;
+                        split_items(inner)
+                    }
+                    None => vec![value],
+                };
+                for value in values {
+                    args.push(flag.clone());
+                    args.push(parse_scalar(value).map_err(error)?);
+                }
+            }
+        }
+    }
+    Ok(args)
+}
+
+pub fn read(path: &std::path::Path) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = r#"
+            # a comment
+            cards = 8
+            deck_file = "decks/my deck.txt"  # trailing
+            short-straights = true
+            tui = false
+            derive = ["a = pair / 3oak", "b = \"x,y\" # not a comment"]
+            max_iters = 1_000_000
+        "#;
+        assert_eq!(
+            vec![
+                "--cards",
+                "8",
+                "--deck-file",
+                "decks/my deck.txt",
+                "--short-straights",
+                "--derive",
+                "a = pair / 3oak",
+                "--derive",
+                "b = \"x,y\" # not a comment",
+                "--max-iters",
+                "1000000",
+            ],
+            parse(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err("line 2: expected 'key = value', not 'cards'".to_string()),
+            parse("\ncards")
+        );
+        assert!(parse("cards = eight").is_err());
+        assert!(parse("deck_file = \"unterminated").is_err());
+        assert!(parse("derive = [\"a\"").is_err());
+        assert!(parse("bad key = 1").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Versioning for the JSON this crate writes, so tools built on it can tell
+//! when the format has changed under them.
+//!
+//! Every JSON document has a top-level `schema_version`. Adding fields
+//! doesn't change it, so readers should ignore fields they don't know.
+//! Removing or renaming a field, or changing what one means, increments it.
+//! Documents without a `schema_version` predate it and are version 1.
+//!
+//! Version 1 documents:
+//!
+//! - Result files, from `--output`, `--out json:` and `merge` (results.rs),
+//!   and each line of `--out ndjson:` (sink.rs): `metadata`
+//!   (optional, see metadata.rs), `config` (an object of the options which
+//!   affect the probabilities), `iterations`, `hands`, an array of
+//!   `{ name, count, probability, ci }`, and `warnings`, an array of
+//!   `{ kind, message, ... }`.
+//! - Quiz histories (quiz.rs): `rounds`, an array of
+//!   `{ question, guess, answer }`.
+//! - `poker edit` sessions (session.rs): `deck` (a deck file's text),
+//!   `rules` and `results`, an array of result files.
+//! - `poker eval` output lines: `{ hand, categories }` plus `best` with
+//!   `--best`, or `{ hand, error }`. Lines are too small to repeat the
+//!   version on, so they follow the version of the result files.
+//!
+//! The `--progress-file` snapshot is binary and versions itself through its
+//! magic bytes, see progress.rs.
+
+use serde_json::Value;
+
+pub const SCHEMA_VERSION: u64 = 1;
+
+// The fields of each document in the current version, for tests that catch
+// fields being added without updating the docs above.
+pub const RESULT_FIELDS: &[&str] = &[
+    "schema_version",
+    "metadata",
+    "config",
+    "iterations",
+    "hands",
+    "warnings",
+];
+pub const RESULT_HAND_FIELDS: &[&str] = &["name", "count", "probability", "ci"];
+pub const QUIZ_FIELDS: &[&str] = &["schema_version", "rounds"];
+pub const QUIZ_ROUND_FIELDS: &[&str] = &["question", "guess", "answer"];
+pub const SESSION_FIELDS: &[&str] = &["schema_version", "deck", "rules", "results"];
+
+// Errors if `value` was written by a newer version of the format than this
+// build can read.
+pub fn check_version(value: &Value) -> Result<(), String> {
+    let version = match value.get("schema_version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| format!("invalid schema_version {v}"))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "written with schema version {version}, but this build only reads up to {SCHEMA_VERSION}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_version() {
+        assert_eq!(Ok(()), check_version(&json!({})));
+        assert_eq!(Ok(()), check_version(&json!({ "schema_version": 1 })));
+        assert_eq!(
+            Err(format!(
+                "written with schema version {}, but this build only reads up to {SCHEMA_VERSION}",
+                SCHEMA_VERSION + 1
+            )),
+            check_version(&json!({ "schema_version": SCHEMA_VERSION + 1 }))
+        );
+        assert!(check_version(&json!({ "schema_version": "1" })).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{Card, CardOrJoker, HandRank, Rank, MAX_CARDS};
+
+// Steel cards multiply mult while held in hand during a play.
+pub const STEEL_MULT: f64 = 1.5;
+// Gold cards pay out while held in hand at the end of the round.
+pub const GOLD_MONEY: u32 = 3;
+// Cards with a gold seal pay out when they score.
+pub const GOLD_SEAL_MONEY: u32 = 3;
+
+// Interest is $1 for every $5 held at the end of a round, up to $5.
+pub const INTEREST_STEP: u32 = 5;
+pub const MAX_INTEREST: u32 = 5;
+// Each hand left unplayed when the blind is beaten pays $1.
+pub const UNUSED_HAND_MONEY: u32 = 1;
+
+pub fn interest(money: u32) -> u32 {
+    (money / INTEREST_STEP).min(MAX_INTEREST)
+}
+
+// Enhanced and sealed cards. Every copy of a listed card is affected.
+#[derive(Clone, Default)]
+pub struct CardEffects {
+    pub steel: Vec<Card>,
+    pub gold: Vec<Card>,
+    pub gold_seal: Vec<Card>,
+}
+
+impl CardEffects {
+    fn num_steel(&self, cards: &[Card]) -> usize {
+        cards.iter().filter(|c| self.steel.contains(c)).count()
+    }
+}
+
+pub struct RoundResult {
+    // Score of each play, in order.
+    pub scores: Vec<u32>,
+    // Money from gold cards held after the last play.
+    pub gold_money: u32,
+    // Money from scoring cards with gold seals.
+    pub seal_money: u32,
+}
+
+// Chips a card adds when it scores: face value for 2-10, 10 for face cards
+// and 11 for aces.
+pub fn card_chips(card: Card) -> u32 {
+    match card.rank {
+        Rank::Jack | Rank::Queen | Rank::King => 10,
+        Rank::Ace => 11,
+        rank => rank as u32 + 2,
+    }
+}
+
+// The cards to play for the best hand: the fewest cards (indices into
+// `cards`, plus a number of jokers) which make it, preferring to keep steel
+// cards in hand and then the most chips. Only these cards score, so the rest
+// are kept in hand.
+pub fn best_play(
+    cards: &[Card],
+    num_jokers: u8,
+    hand_size: usize,
+    effects: &CardEffects,
+) -> (HandRank, Vec<usize>, u8) {
+    let rank = crate::evaluate_best(cards, num_jokers, hand_size);
+    let max_size = rank.num_cards().max(1);
+    // (size, steel cards played, chips, mask, jokers)
+    let mut best: Option<(usize, usize, u32, u64, u8)> = None;
+    for mask in subsets(cards.len(), max_size) {
+        let num_cards = mask.count_ones() as usize;
+        let subset = (0..cards.len())
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| cards[i])
+            .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+        let chips = subset.iter().map(|&c| card_chips(c)).sum::<u32>();
+        let steel = effects.num_steel(&subset);
+        for jokers in 0..=num_jokers.min((max_size - num_cards) as u8) {
+            let size = num_cards + jokers as usize;
+            if size == 0 || !rank.matches(&subset, jokers) {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((best_size, best_steel, best_chips, _, _)) => {
+                    (size, steel, std::cmp::Reverse(chips))
+                        < (best_size, best_steel, std::cmp::Reverse(best_chips))
+                }
+            };
+            if better {
+                best = Some((size, steel, chips, mask, jokers));
+            }
+            // More jokers only makes the play bigger.
+            break;
+        }
+    }
+    let Some((_, _, _, mask, jokers)) = best else {
+        return (rank, Vec::new(), 0);
+    };
+    let indices = (0..cards.len()).filter(|i| mask & (1 << i) != 0).collect();
+    (rank, indices, jokers)
+}
+
+// Bitmasks of the subsets of `n` items with at most `max_size` of them, in
+// increasing order within each size. Skipping bigger subsets keeps big draws
+// from visiting all 2^n.
+fn subsets(n: usize, max_size: usize) -> impl Iterator<Item = u64> {
+    (0..=max_size.min(n)).flat_map(move |size| {
+        let first = (1u64 << size) - 1;
+        std::iter::successors(Some(first), move |&mask| {
+            if mask == 0 {
+                return None;
+            }
+            // Gosper's hack: the next bigger number with as many bits set.
+            let lowest = mask & mask.wrapping_neg();
+            let ripple = mask + lowest;
+            let next = (((ripple ^ mask) >> 2) / lowest) | ripple;
+            (next < 1 << n).then_some(next)
+        })
+    })
+}
+
+// Chips times mult for playing `played` (plus jokers, which add no chips) as
+// `rank` while holding `held`.
+pub fn play_score(rank: HandRank, played: &[Card], held: &[Card], effects: &CardEffects) -> u32 {
+    let (chips, mult) = rank.base_score();
+    let chips = chips + played.iter().map(|&c| card_chips(c)).sum::<u32>();
+    let mult = mult as f64 * STEEL_MULT.powi(effects.num_steel(held) as i32);
+    (chips as f64 * mult).floor() as u32
+}
+
+// Plays the best hand up to `plays` times, refilling the hand from `pile`
+// after each play, and stopping early once the total score reaches
+// `stop_at`.
+pub fn play_round(
+    cards: &mut arrayvec::ArrayVec<Card, MAX_CARDS>,
+    num_jokers: &mut u8,
+    mut pile: &[CardOrJoker],
+    hand_size: usize,
+    plays: usize,
+    stop_at: Option<u32>,
+    effects: &CardEffects,
+) -> RoundResult {
+    let mut scores = Vec::with_capacity(plays);
+    let mut seal_money = 0;
+    for _ in 0..plays {
+        if cards.is_empty() && *num_jokers == 0 {
+            break;
+        }
+        if stop_at.is_some_and(|target| scores.iter().sum::<u32>() >= target) {
+            break;
+        }
+        let (rank, indices, jokers) = best_play(cards, *num_jokers, hand_size, effects);
+        let played = indices.iter().map(|&i| cards[i]).collect::<Vec<_>>();
+        for &i in indices.iter().rev() {
+            cards.remove(i);
+        }
+        scores.push(play_score(rank, &played, cards, effects));
+        let num_sealed = played
+            .iter()
+            .filter(|c| effects.gold_seal.contains(c))
+            .count();
+        seal_money += num_sealed as u32 * GOLD_SEAL_MONEY;
+        *num_jokers -= jokers;
+        let (drawn, rest) = pile.split_at((indices.len() + jokers as usize).min(pile.len()));
+        for &coj in drawn {
+            match coj {
+                CardOrJoker::Card(c) => cards.push(c),
+                CardOrJoker::Joker => *num_jokers += 1,
+            }
+        }
+        pile = rest;
+    }
+    let num_gold = cards.iter().filter(|c| effects.gold.contains(c)).count();
+    RoundResult {
+        scores,
+        gold_money: num_gold as u32 * GOLD_MONEY,
+        seal_money,
+    }
+}
+
+// Chips and mult for each hand, like Balatro's after leveling up hands with
+// planet cards. Read from TOML with a table per hand to change:
+//
+// ```toml
+// [flush]
+// chips = 50
+// mult = 6
+// ```
+//
+// Hands not listed keep their base values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreTable {
+    values: [(u32, u32); HandRank::ALL.len()],
+}
+
+impl Default for ScoreTable {
+    fn default() -> Self {
+        Self {
+            values: HandRank::ALL.map(HandRank::base_score),
+        }
+    }
+}
+
+impl ScoreTable {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut ret = Self::default();
+        let mut rank = None;
+        for (i, line) in text.lines().enumerate() {
+            let error = |e: String| format!("line {}: {e}", i + 1);
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(id) = line.strip_prefix('[') {
+                let id = id
+                    .strip_suffix(']')
+                    .ok_or_else(|| error(format!("invalid table header '{line}'")))?;
+                rank = Some(id.trim().parse::<HandRank>().map_err(error)?);
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected 'key = value', not '{line}'")))?;
+            let rank = rank.ok_or_else(|| error("expected a [hand] table first".to_string()))?;
+            let value = value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| error(format!("invalid number '{}'", value.trim())))?;
+            let (chips, mult) = &mut ret.values[rank as usize];
+            match key.trim() {
+                "chips" => *chips = value,
+                "mult" => *mult = value,
+                key => {
+                    return Err(error(format!(
+                        "unknown key '{key}', expected chips or mult"
+                    )))
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn read(path: &std::path::Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn chips_and_mult(&self, rank: HandRank) -> (u32, u32) {
+        self.values[rank as usize]
+    }
+
+    pub fn score(&self, rank: HandRank) -> u32 {
+        let (chips, mult) = self.chips_and_mult(rank);
+        chips * mult
+    }
+
+    // The highest scoring hand that can be played with at most `hand_size`
+    // cards, which after leveling may not be the strongest one. Ties go to
+    // the stronger hand.
+    pub fn best(&self, cards: &[Card], num_jokers: u8, hand_size: usize) -> HandRank {
+        let strongest = crate::evaluate_best(cards, num_jokers, hand_size);
+        let mut best = strongest;
+        for &rank in HandRank::ALL.iter().rev() {
+            if rank != strongest
+                && self.score(rank) > self.score(best)
+                && rank.num_cards() <= hand_size
+                && rank.matches(cards, num_jokers)
+            {
+                best = rank;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stacked_deck;
+
+    fn hand(s: &str) -> (Vec<Card>, u8) {
+        crate::parse_hand(s).unwrap()
+    }
+
+    #[test]
+    fn test_subsets() {
+        assert_eq!(
+            vec![
+                0b0000, 0b0001, 0b0010, 0b0100, 0b1000, 0b0011, 0b0101, 0b0110, 0b1001, 0b1010,
+                0b1100
+            ],
+            subsets(4, 2).collect::<Vec<_>>()
+        );
+        assert_eq!(1 << 5, subsets(5, 9).count());
+        assert_eq!(1 + 30 + 435, subsets(30, 2).count());
+    }
+
+    #[test]
+    fn test_best_play() {
+        let (cards, num_jokers) = hand("Ah 9d 9c 2s Kh Ks");
+        let (rank, indices, jokers) = best_play(&cards, num_jokers, 5, &CardEffects::default());
+        assert_eq!(HandRank::TwoPair, rank);
+        assert_eq!(vec![1, 2, 4, 5], indices);
+        assert_eq!(0, jokers);
+        // Only the highest card scores with nothing better.
+        let (cards, num_jokers) = hand("2c 7d Ah 9s");
+        assert_eq!(
+            (HandRank::HighCard, vec![2], 0),
+            best_play(&cards, num_jokers, 5, &CardEffects::default())
+        );
+        // The joker pairs the ace.
+        let (cards, num_jokers) = hand("2c 7d Ah J");
+        assert_eq!(
+            (HandRank::Pair, vec![2], 1),
+            best_play(&cards, num_jokers, 5, &CardEffects::default())
+        );
+    }
+
+    #[test]
+    fn test_play_round() {
+        let (cards, mut num_jokers) = hand("Ah Ad 2c 7h");
+        let mut cards = cards.into_iter().collect();
+        let pile = stacked_deck("7s 3d");
+        let result = play_round(
+            &mut cards,
+            &mut num_jokers,
+            &pile,
+            5,
+            2,
+            None,
+            &CardEffects::default(),
+        );
+        let pair = |chips| (HandRank::Pair.base_score().0 + chips) * HandRank::Pair.base_score().1;
+        assert_eq!(vec![pair(22), pair(14)], result.scores);
+        assert_eq!(0, result.gold_money);
+        assert_eq!(crate::hand!("2c 3d").to_vec(), cards.to_vec());
+    }
+
+    #[test]
+    fn test_held_effects() {
+        let (cards, mut num_jokers) = hand("Ks 7c 2c");
+        let effects = CardEffects {
+            steel: crate::hand!("Ks").to_vec(),
+            gold: crate::hand!("2c").to_vec(),
+            gold_seal: crate::hand!("7c").to_vec(),
+        };
+        // Playing the seven as a high card keeps the steel king in hand.
+        let (rank, indices, _) = best_play(&cards, num_jokers, 5, &effects);
+        assert_eq!(HandRank::HighCard, rank);
+        assert_eq!(vec![1], indices);
+        let (chips, mult) = HandRank::HighCard.base_score();
+        let held = crate::hand!("Ks 2c").to_vec();
+        assert_eq!(
+            ((chips + 7) as f64 * mult as f64 * STEEL_MULT).floor() as u32,
+            play_score(HandRank::HighCard, &crate::hand!("7c"), &held, &effects)
+        );
+        // The gold card pays out if it's still held at the end.
+        let mut cards = cards.into_iter().collect();
+        let result = play_round(&mut cards, &mut num_jokers, &[], 5, 1, None, &effects);
+        assert_eq!(GOLD_MONEY, result.gold_money);
+        assert_eq!(GOLD_SEAL_MONEY, result.seal_money);
+    }
+
+    #[test]
+    fn test_score_table() {
+        let table =
+            ScoreTable::parse("# leveled\n[pair]\nchips = 200 # lots\n\n[flush]\nmult = 1\n")
+                .unwrap();
+        assert_eq!((200, 2), table.chips_and_mult(HandRank::Pair));
+        assert_eq!((35, 1), table.chips_and_mult(HandRank::Flush));
+        assert_eq!(
+            HandRank::Straight.base_score(),
+            table.chips_and_mult(HandRank::Straight)
+        );
+        assert_eq!(ScoreTable::default(), ScoreTable::parse("").unwrap());
+
+        // The pair now outscores the flush it's part of.
+        let (cards, num_jokers) = hand("2h 2h 7h 9h Kh");
+        assert_eq!(
+            HandRank::Flush,
+            ScoreTable::default().best(&cards, num_jokers, 5)
+        );
+        assert_eq!(HandRank::Pair, table.best(&cards, num_jokers, 5));
+        let (cards, num_jokers) = hand("2h 3d 7h 9h Kh");
+        assert_eq!(HandRank::HighCard, table.best(&cards, num_jokers, 5));
+
+        assert_eq!(
+            Err("line 1: expected a [hand] table first".to_string()),
+            ScoreTable::parse("chips = 5")
+        );
+        assert!(ScoreTable::parse("[royal]").is_err());
+        assert!(ScoreTable::parse("[pair]\nchips = -1").is_err());
+        assert!(ScoreTable::parse("[pair]\nlevel = 2").is_err());
+    }
+
+    #[test]
+    fn test_interest() {
+        assert_eq!(0, interest(4));
+        assert_eq!(2, interest(13));
+        assert_eq!(MAX_INTEREST, interest(100));
+    }
+
+    #[test]
+    fn test_stop_at() {
+        let (cards, mut num_jokers) = hand("Ah Ad Kh Kd Qh Qd");
+        let mut cards = cards.into_iter().collect();
+        let effects = CardEffects::default();
+        let result = play_round(&mut cards, &mut num_jokers, &[], 5, 3, Some(1), &effects);
+        assert_eq!(1, result.scores.len());
+    }
+}
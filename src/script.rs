@@ -0,0 +1,383 @@
+//! Experiments written as Rhai scripts, run with `poker run script.rhai`.
+//!
+//! Cards are strings like "As", with "J" for a joker. A script builds decks
+//! as arrays of cards, runs simulations with extra hands and discard
+//! strategies written as closures, and post-processes the probabilities
+//! however it likes:
+//!
+//! ```text
+//! let deck = standard_deck(1, 0);
+//! deck.retain(|c| rank(c) >= 6);
+//! let odds = simulate(deck, #{
+//!     cards: 8,
+//!     iters: 100000,
+//!     discards: 1,
+//!     strategy: |cards, jokers| [0, 1],
+//!     hands: #{ "Two aces": |cards, jokers| cards.filter(|c| rank(c) == 14).len() + jokers >= 2 },
+//! });
+//! print(odds["Full House"] / odds["Two aces"]);
+//! ```
+//!
+//! `simulate` takes these options, all optional:
+//! - `cards`, `hand_size`: as on the command line, defaulting to 7 and 5
+//! - `iters`: number of deals, defaulting to 100000
+//! - `seed`: seeds the deals for reproducible results
+//! - `discards`: rounds of discarding, defaulting to 0
+//! - `strategy`: "keep-best", "target:<hand>", or a closure taking the hand's
+//!   cards and number of jokers and returning indices of cards to discard
+//! - `hands`: a map of extra hand names to closures taking the cards and
+//!   number of jokers and returning whether the hand is made
+//!
+//! and returns a map from every hand's name to its probability.
+
+use crate::strategy::{discard_and_draw, parse_strategy, DiscardStrategy, MAX_DISCARD_CARDS};
+use crate::{evaluate_best, hand_counts, split_jokers, standard_deck, Card, CardOrJoker};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, Map, NativeCallContext};
+use std::cell::RefCell;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+const DEFAULT_ITERS: i64 = 100_000;
+
+fn parse_card_or_joker(card: &Dynamic) -> Result<CardOrJoker, String> {
+    let s = card
+        .read_lock::<rhai::ImmutableString>()
+        .ok_or_else(|| format!("expected a card string, got {}", card.type_name()))?;
+    if s.eq_ignore_ascii_case("j") {
+        Ok(CardOrJoker::Joker)
+    } else {
+        s.parse().map(CardOrJoker::Card)
+    }
+}
+
+fn to_array(cards: &[Card]) -> Array {
+    cards.iter().map(|c| c.to_string().into()).collect()
+}
+
+fn deck_array(decks: i64, jokers: i64) -> ScriptResult<Array> {
+    let jokers = u8::try_from(jokers).map_err(|_| format!("invalid joker count {jokers}"))?;
+    Ok(standard_deck(decks.max(0) as usize, jokers)
+        .into_iter()
+        .map(|coj| match coj {
+            CardOrJoker::Card(c) => c.to_string().into(),
+            CardOrJoker::Joker => "J".into(),
+        })
+        .collect())
+}
+
+// 2 for deuces up to 14 for aces.
+fn rank(card: &str) -> ScriptResult<i64> {
+    Ok(card.parse::<Card>()?.rank as i64 + 2)
+}
+
+fn suit(card: &str) -> ScriptResult<String> {
+    let card = card.parse::<Card>()?.to_string();
+    Ok(card[card.len() - 1..].to_string())
+}
+
+fn best_hand(cards: Array, jokers: i64, hand_size: i64) -> ScriptResult<String> {
+    let mut parsed = Vec::new();
+    for card in &cards {
+        match parse_card_or_joker(card)? {
+            CardOrJoker::Card(c) => parsed.push(c),
+            CardOrJoker::Joker => return Err("pass jokers as a count, not in the cards".into()),
+        }
+    }
+    Ok(
+        evaluate_best(&parsed, jokers.max(0) as u8, hand_size.max(0) as usize)
+            .name()
+            .to_string(),
+    )
+}
+
+// A discard strategy written as a script closure. Strategies can't fail, so
+// the first script error is kept for `simulate` to report and every later
+// call discards nothing.
+struct ScriptStrategy<'a> {
+    context: &'a NativeCallContext<'a>,
+    func: FnPtr,
+    error: RefCell<Option<Box<EvalAltResult>>>,
+}
+
+impl ScriptStrategy<'_> {
+    fn try_discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        max_discards: usize,
+    ) -> ScriptResult<Vec<usize>> {
+        let ret: Array = self
+            .func
+            .call_within_context(self.context, (to_array(cards), num_jokers as i64))?;
+        let mut discards = Vec::new();
+        for i in ret {
+            let i = i
+                .as_int()
+                .map_err(|t| format!("discard indices must be integers, got {t}"))?;
+            match usize::try_from(i) {
+                Ok(i) if i < cards.len() && !discards.contains(&i) => discards.push(i),
+                _ => return Err(format!("invalid discard index {i}").into()),
+            }
+        }
+        discards.truncate(max_discards);
+        Ok(discards)
+    }
+}
+
+impl DiscardStrategy for ScriptStrategy<'_> {
+    fn discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        _hand_size: usize,
+        max_discards: usize,
+        _upcoming: &[CardOrJoker],
+    ) -> Vec<usize> {
+        if self.error.borrow().is_some() {
+            return Vec::new();
+        }
+        self.try_discards(cards, num_jokers, max_discards)
+            .unwrap_or_else(|e| {
+                *self.error.borrow_mut() = Some(e);
+                Vec::new()
+            })
+    }
+}
+
+fn int_option(opts: &Map, key: &str, default: i64) -> ScriptResult<i64> {
+    match opts.get(key) {
+        None => Ok(default),
+        Some(v) => v
+            .as_int()
+            .map_err(|t| format!("option '{key}' must be an integer, got {t}").into()),
+    }
+}
+
+fn simulate(context: NativeCallContext, deck: Array, opts: Map) -> ScriptResult<Map> {
+    for key in opts.keys() {
+        if ![
+            "cards",
+            "hand_size",
+            "iters",
+            "seed",
+            "discards",
+            "strategy",
+            "hands",
+        ]
+        .contains(&key.as_str())
+        {
+            return Err(format!("unknown simulate option '{key}'").into());
+        }
+    }
+    let mut deck = deck
+        .iter()
+        .map(parse_card_or_joker)
+        .collect::<Result<Vec<_>, _>>()?;
+    let num_cards = int_option(&opts, "cards", 7)?.max(0) as usize;
+    let hand_size = int_option(&opts, "hand_size", 5)?;
+    let iters = int_option(&opts, "iters", DEFAULT_ITERS)?.max(0) as u64;
+    let rounds = int_option(&opts, "discards", 0)?.max(0) as usize;
+    let mut rng = match opts.get("seed") {
+        Some(_) => rand::rngs::StdRng::seed_from_u64(int_option(&opts, "seed", 0)? as u64),
+        None => rand::rngs::StdRng::from_entropy(),
+    };
+    if num_cards > crate::MAX_CARDS {
+        return Err(format!("does not support more than {} cards", crate::MAX_CARDS).into());
+    }
+    if num_cards > deck.len() {
+        return Err(format!(
+            "cannot deal {num_cards} cards from a {} card deck",
+            deck.len()
+        )
+        .into());
+    }
+    let counts = usize::try_from(hand_size)
+        .ok()
+        .and_then(hand_counts)
+        .ok_or_else(|| format!("hand size must be between 5 and {}", crate::MAX_CARDS))?;
+    let hand_size = hand_size as usize;
+
+    let mut extra = Vec::new();
+    if let Some(hands) = opts.get("hands") {
+        let hands = hands
+            .read_lock::<Map>()
+            .ok_or("option 'hands' must be a map of names to functions")?;
+        for (name, func) in hands.iter() {
+            let func = func
+                .read_lock::<FnPtr>()
+                .ok_or_else(|| format!("hand '{name}' must be a function"))?;
+            extra.push((name.to_string(), func.clone()));
+        }
+    }
+    let script_strategy = match opts.get("strategy") {
+        Some(s) if !s.is_string() => Some(ScriptStrategy {
+            context: &context,
+            func: s
+                .read_lock::<FnPtr>()
+                .ok_or("option 'strategy' must be a string or a function")?
+                .clone(),
+            error: RefCell::new(None),
+        }),
+        _ => None,
+    };
+    let builtin;
+    let strategy: &dyn DiscardStrategy = match &script_strategy {
+        Some(s) => s,
+        None => {
+            let name = match opts.get("strategy") {
+                Some(s) => s.clone().into_string()?,
+                None => "keep-best".to_string(),
+            };
+            builtin = parse_strategy(&name)?;
+            builtin.as_ref()
+        }
+    };
+
+    let num_drawn = (num_cards + rounds * MAX_DISCARD_CARDS).min(deck.len());
+    let mut hits = vec![0u64; counts.len()];
+    let mut extra_hits = vec![0u64; extra.len()];
+    for _ in 0..iters {
+        let (drawn, _) = deck.partial_shuffle(&mut rng, num_drawn);
+        let (cards_or_jokers, pile) = drawn.split_at(num_cards);
+        let (mut cards, mut num_jokers) = split_jokers(cards_or_jokers);
+        discard_and_draw(
+            &mut cards,
+            &mut num_jokers,
+            pile,
+            strategy,
+            hand_size,
+            rounds,
+            0,
+        );
+        if let Some(e) = script_strategy.as_ref().and_then(|s| s.error.take()) {
+            return Err(e);
+        }
+        for (hit, count) in hits.iter_mut().zip(&counts) {
+            *hit += count.matches(&cards, num_jokers) as u64;
+        }
+        if !extra.is_empty() {
+            let array = to_array(&cards);
+            for (hit, (_, func)) in extra_hits.iter_mut().zip(&extra) {
+                let made: bool =
+                    func.call_within_context(&context, (array.clone(), num_jokers as i64))?;
+                *hit += made as u64;
+            }
+        }
+    }
+
+    let probability = |hits: u64| Dynamic::from_float(hits as f64 / iters.max(1) as f64);
+    let mut ret = Map::new();
+    for (count, &hits) in counts.iter().zip(&hits) {
+        ret.insert(count.name.into(), probability(hits));
+    }
+    for ((name, _), &hits) in extra.iter().zip(&extra_hits) {
+        ret.insert(name.into(), probability(hits));
+    }
+    Ok(ret)
+}
+
+// An engine with the card, deck and simulation functions registered.
+pub fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_fn("standard_deck", deck_array)
+        .register_fn("rank", |c: &str| rank(c))
+        .register_fn("suit", |c: &str| suit(c))
+        .register_fn("best_hand", best_hand)
+        .register_fn("simulate", simulate)
+        .register_fn("simulate", |context: NativeCallContext, deck: Array| {
+            simulate(context, deck, Map::new())
+        });
+    engine
+}
+
+pub fn run_file(path: &std::path::Path) -> Result<(), String> {
+    engine()
+        .run_file(path.to_path_buf())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval<T: Clone + Send + Sync + 'static>(script: &str) -> T {
+        engine().eval::<T>(script).unwrap()
+    }
+
+    #[test]
+    fn test_cards() {
+        assert_eq!(54, eval::<i64>("standard_deck(1, 2).len()"));
+        assert_eq!(14, eval::<i64>("rank(\"As\")"));
+        assert_eq!("h", eval::<String>("suit(\"10h\")"));
+        assert_eq!(
+            "Flush",
+            eval::<String>("best_hand([\"2h\", \"5h\", \"9h\", \"Jh\"], 1, 5)")
+        );
+        assert!(engine().eval::<i64>("rank(\"Xs\")").is_err());
+    }
+
+    #[test]
+    fn test_simulate() {
+        // Every deal from a deck of hearts is a flush, and the custom hand
+        // sees the cards the built-in detectors do.
+        let odds = eval::<Map>(
+            r#"
+            let deck = standard_deck(1, 0);
+            deck.retain(|c| suit(c) == "h");
+            simulate(deck, #{
+                cards: 6,
+                hand_size: 6,
+                iters: 100,
+                seed: 1,
+                hands: #{ "Hearts": |cards, jokers| cards.all(|c| suit(c) == "h") },
+            })
+            "#,
+        );
+        assert_eq!(1.0, odds["Flush"].as_float().unwrap());
+        assert_eq!(1.0, odds["Hearts"].as_float().unwrap());
+        assert_eq!(0.0, odds["Pair"].as_float().unwrap());
+    }
+
+    #[test]
+    fn test_script_strategy() {
+        // Discarding everything but aces makes holding an ace more likely.
+        let odds = eval::<Array>(
+            r#"
+            let deck = standard_deck(1, 0);
+            let opts = #{
+                cards: 5,
+                iters: 2000,
+                seed: 3,
+                hands: #{ "Ace": |cards, jokers| cards.some(|c| rank(c) == 14) },
+            };
+            let before = simulate(deck, opts)["Ace"];
+            opts.discards = 3;
+            opts.strategy = |cards, jokers| {
+                let out = [];
+                for (c, i) in cards {
+                    if rank(c) != 14 { out.push(i); }
+                }
+                out
+            };
+            [before, simulate(deck, opts)["Ace"]]
+            "#,
+        );
+        assert!(odds[0].as_float().unwrap() < odds[1].as_float().unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        for script in [
+            "simulate(standard_deck(1, 0), #{ card: 5 })",
+            "simulate(standard_deck(1, 0), #{ hand_size: 4 })",
+            "simulate([\"As\"], #{})",
+            "simulate(standard_deck(1, 0), #{ discards: 1, strategy: |c, j| [99] })",
+            "simulate(standard_deck(1, 0), #{ hands: #{ \"Bad\": |c, j| 1 / 0 } })",
+        ] {
+            assert!(engine().eval::<Map>(script).is_err(), "{script}");
+        }
+    }
+}
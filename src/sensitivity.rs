@@ -0,0 +1,519 @@
+use crate::interval::CiMethod;
+use crate::objective::{Objective, ObjectiveStats, Outcome};
+use crate::scoring::{play_round, CardEffects};
+use crate::{
+    split_jokers, standard_deck, Card, CardOrJoker, HandCount, Rank, MAX_CARDS, NUM_RANKS,
+    NUM_SUITS,
+};
+use rand::seq::SliceRandom;
+
+// Order in which cards are trimmed from the deck. Removing uniformly random
+// cards doesn't change draw probabilities at all (it's equivalent to burning
+// a card), so it's mostly useful as a control.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThinOrder {
+    Random,
+    // Lowest ranks first, like trimming a deck toward face cards.
+    LowRanks,
+    // Spades, then hearts, then diamonds, concentrating the deck into clubs.
+    Suits,
+}
+
+impl ThinOrder {
+    // Cards with smaller keys are removed first. Jokers are never removed
+    // before cards.
+    fn key(self, coj: CardOrJoker) -> u8 {
+        match (self, coj) {
+            (_, CardOrJoker::Joker) => u8::MAX,
+            (ThinOrder::Random, CardOrJoker::Card(_)) => 0,
+            (ThinOrder::LowRanks, CardOrJoker::Card(c)) => c.rank as u8,
+            (ThinOrder::Suits, CardOrJoker::Card(c)) => NUM_SUITS - c.suit as u8,
+        }
+    }
+}
+
+// Hand probabilities at several deck sizes, each level removing `step` more
+// cards than the last. Every level sees the same shuffles (common random
+// numbers), with removals nested across levels, so differences between
+// levels have much lower variance than independent runs would.
+pub struct Sensitivity {
+    pub order: ThinOrder,
+    pub step: usize,
+    // Objectives see one play of the best hand of this many cards, with no
+    // card effects.
+    pub hand_size: usize,
+    pub num_iters: u64,
+    // `hits[level][hand]`
+    pub hits: Vec<Vec<u64>>,
+    // Deals where the hand is made at full size but not after one step of
+    // thinning, and vice versa.
+    pub lost: Vec<u64>,
+    pub gained: Vec<u64>,
+    // `objective_stats[level][objective]`, and each objective's per-deal
+    // change from full size to one step of thinning.
+    pub objective_stats: Vec<Vec<ObjectiveStats>>,
+    pub objective_changes: Vec<ObjectiveStats>,
+}
+
+impl Sensitivity {
+    pub fn new(
+        num_hands: usize,
+        num_objectives: usize,
+        order: ThinOrder,
+        levels: usize,
+        step: usize,
+        hand_size: usize,
+    ) -> Self {
+        Self {
+            order,
+            step,
+            hand_size,
+            num_iters: 0,
+            hits: vec![vec![0; num_hands]; levels + 1],
+            lost: vec![0; num_hands],
+            gained: vec![0; num_hands],
+            objective_stats: vec![vec![ObjectiveStats::default(); num_objectives]; levels + 1],
+            objective_changes: vec![ObjectiveStats::default(); num_objectives],
+        }
+    }
+
+    pub fn run(
+        &mut self,
+        deck: &[CardOrJoker],
+        num_cards: usize,
+        counts: &[HandCount],
+        objectives: &[Box<dyn Objective>],
+        iters: u64,
+        rng: &mut impl rand::Rng,
+    ) {
+        let mut deal_order = (0..deck.len()).collect::<Vec<_>>();
+        let mut removal_order = (0..deck.len()).collect::<Vec<_>>();
+        let mut removed_at = vec![0; deck.len()];
+        let mut hit = vec![false; counts.len()];
+        let mut full_values = vec![0.0; objectives.len()];
+        for _ in 0..iters {
+            deal_order.shuffle(rng);
+            // Shuffle before the stable sort to break ties randomly.
+            removal_order.shuffle(rng);
+            removal_order.sort_by_key(|&i| self.order.key(deck[i]));
+            for (i, &card) in removal_order.iter().enumerate() {
+                removed_at[card] = i;
+            }
+            for level in 0..self.hits.len() {
+                let removed = level * self.step;
+                let mut cards = arrayvec::ArrayVec::<Card, MAX_CARDS>::new();
+                let mut num_jokers = 0;
+                for &i in deal_order
+                    .iter()
+                    .filter(|&&i| removed_at[i] >= removed)
+                    .take(num_cards)
+                {
+                    match deck[i] {
+                        CardOrJoker::Card(c) => cards.push(c),
+                        CardOrJoker::Joker => num_jokers += 1,
+                    }
+                }
+                for (h, c) in counts.iter().enumerate() {
+                    let is_hit = c.matches(&cards, num_jokers);
+                    if is_hit {
+                        self.hits[level][h] += 1;
+                    }
+                    if level == 1 {
+                        if hit[h] && !is_hit {
+                            self.lost[h] += 1;
+                        } else if !hit[h] && is_hit {
+                            self.gained[h] += 1;
+                        }
+                    }
+                    hit[h] = is_hit;
+                }
+                if objectives.is_empty() {
+                    continue;
+                }
+                let round = play_round(
+                    &mut cards.clone(),
+                    &mut num_jokers.clone(),
+                    &[],
+                    self.hand_size,
+                    1,
+                    None,
+                    &CardEffects::default(),
+                );
+                let outcome = Outcome {
+                    cards: &cards,
+                    num_jokers,
+                    round: &round,
+                };
+                for (o, objective) in objectives.iter().enumerate() {
+                    let value = objective.value(&outcome);
+                    self.objective_stats[level][o].record(value);
+                    if level == 0 {
+                        full_values[o] = value;
+                    } else if level == 1 {
+                        self.objective_changes[o].record(full_values[o] - value);
+                    }
+                }
+            }
+            self.num_iters += 1;
+        }
+    }
+
+    pub fn probability(&self, level: usize, hand: usize) -> f64 {
+        self.hits[level][hand] as f64 / self.num_iters as f64
+    }
+
+    // Finite difference estimate of d(P(hand))/d(deck size) at the full deck
+    // size, and its confidence interval half-width.
+    pub fn derivative(&self, hand: usize) -> (f64, f64) {
+        let n = self.num_iters as f64;
+        // Each deal contributes +1 (lost), -1 (gained) or 0 to the difference.
+        let changed = (self.lost[hand] + self.gained[hand]) as f64 / n;
+        let mean = (self.lost[hand] as f64 - self.gained[hand] as f64) / n;
+        // Same 3 standard errors as `confidence_interval`.
+        let ci = 3.0 * ((changed - mean * mean) / n).sqrt();
+        (mean / self.step as f64, ci / self.step as f64)
+    }
+
+    pub fn objective_mean(&self, level: usize, objective: usize) -> f64 {
+        self.objective_stats[level][objective]
+            .mean(CiMethod::Normal)
+            .0
+    }
+
+    // Like `derivative`, for an objective's mean.
+    pub fn objective_derivative(&self, objective: usize) -> (f64, f64) {
+        let (mean, ci) = self.objective_changes[objective].mean(CiMethod::Normal);
+        (mean / self.step as f64, ci / self.step as f64)
+    }
+}
+
+// `deck` after `edits` random edits, each either removing a random card or
+// adding a copy of a random standard card.
+pub fn perturb_deck(
+    deck: &[CardOrJoker],
+    edits: usize,
+    rng: &mut impl rand::Rng,
+) -> Vec<CardOrJoker> {
+    let standard = standard_deck(1, 0);
+    let mut deck = deck.to_vec();
+    for _ in 0..edits {
+        if !deck.is_empty() && rng.gen_bool(0.5) {
+            deck.swap_remove(rng.gen_range(0..deck.len()));
+        } else {
+            deck.push(*standard.choose(rng).unwrap());
+        }
+    }
+    deck
+}
+
+// Which kind of hand a random deck leans towards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bias {
+    None,
+    // One random suit is favored.
+    Flush,
+    // A random run of five ranks is favored.
+    Straight,
+    // Three random ranks are favored.
+    Pairs,
+}
+
+// How to generate random decks for --random-deck, parsed from
+// "size=45,bias=flush,weight=3,jokers=1". Every card is drawn independently
+// from the 52 standard cards, so decks can have duplicates like edited
+// Balatro decks, with favored cards `weight` times as likely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomDeckSpec {
+    pub size: usize,
+    pub bias: Bias,
+    pub weight: f64,
+    pub jokers: u8,
+}
+
+impl std::str::FromStr for RandomDeckSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spec = RandomDeckSpec {
+            size: 52,
+            bias: Bias::None,
+            weight: 3.0,
+            jokers: 0,
+        };
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, not '{part}'"))?;
+            let invalid = || format!("invalid {key} '{value}'");
+            match key.trim() {
+                "size" => spec.size = value.parse().map_err(|_| invalid())?,
+                "bias" => {
+                    spec.bias = match value {
+                        "none" => Bias::None,
+                        "flush" => Bias::Flush,
+                        "straight" => Bias::Straight,
+                        "pairs" => Bias::Pairs,
+                        _ => {
+                            return Err(format!(
+                                "{}, expected none, flush, straight or pairs",
+                                invalid()
+                            ))
+                        }
+                    }
+                }
+                "weight" => spec.weight = value.parse().map_err(|_| invalid())?,
+                "jokers" => spec.jokers = value.parse().map_err(|_| invalid())?,
+                key => {
+                    return Err(format!(
+                        "unknown key '{key}', expected size, bias, weight or jokers"
+                    ))
+                }
+            }
+        }
+        if spec.size == 0 {
+            return Err("size must be at least 1".to_string());
+        }
+        if !(spec.weight > 0.0 && spec.weight.is_finite()) {
+            return Err("weight must be positive".to_string());
+        }
+        Ok(spec)
+    }
+}
+
+impl RandomDeckSpec {
+    pub fn generate(&self, rng: &mut impl rand::Rng) -> Vec<CardOrJoker> {
+        let favored: Box<dyn Fn(&Card) -> bool> = match self.bias {
+            Bias::None => Box::new(|_| false),
+            Bias::Flush => {
+                let suit = rng.gen_range(0..NUM_SUITS);
+                Box::new(move |c| c.suit as u8 == suit)
+            }
+            Bias::Straight => {
+                // Runs from A-5 (an ace low) to T-A.
+                let low = rng.gen_range(0..=NUM_RANKS - 5) as i8 - 1;
+                Box::new(move |c| {
+                    let rank = c.rank as i8;
+                    (low..low + 5).contains(&rank) || (low == -1 && c.rank.is_ace())
+                })
+            }
+            Bias::Pairs => {
+                let ranks = Rank::ALL
+                    .choose_multiple(rng, 3)
+                    .copied()
+                    .collect::<Vec<_>>();
+                Box::new(move |c| ranks.contains(&c.rank))
+            }
+        };
+        let cards = standard_deck(1, 0);
+        let weights = cards
+            .iter()
+            .map(|c| match c {
+                CardOrJoker::Card(c) if favored(c) => self.weight,
+                _ => 1.0,
+            })
+            .collect::<Vec<_>>();
+        let dist = rand::distributions::WeightedIndex::new(&weights).unwrap();
+        let mut deck = (0..self.size)
+            .map(|_| cards[rng.sample(&dist)])
+            .collect::<Vec<_>>();
+        deck.extend((0..self.jokers).map(|_| CardOrJoker::Joker));
+        deck
+    }
+}
+
+// Hand probabilities across random perturbations of a deck, showing how much
+// a deck build's odds depend on its exact contents.
+pub struct Perturbation {
+    pub iters: u64,
+    // `probabilities[variation][hand]`
+    pub probabilities: Vec<Vec<f64>>,
+}
+
+impl Perturbation {
+    pub fn run(
+        deck: &[CardOrJoker],
+        num_cards: usize,
+        counts: &[HandCount],
+        edits: usize,
+        variations: usize,
+        iters: u64,
+        rng: &mut impl rand::Rng,
+    ) -> Self {
+        Self::run_decks(num_cards, counts, variations, iters, rng, |rng| {
+            perturb_deck(deck, edits, rng)
+        })
+    }
+
+    // Like `run`, for `variations` decks made by `make_deck`.
+    pub fn run_decks<R: rand::Rng>(
+        num_cards: usize,
+        counts: &[HandCount],
+        variations: usize,
+        iters: u64,
+        rng: &mut R,
+        mut make_deck: impl FnMut(&mut R) -> Vec<CardOrJoker>,
+    ) -> Self {
+        let probabilities = (0..variations)
+            .map(|_| {
+                let mut deck = make_deck(rng);
+                let num_cards = num_cards.min(deck.len());
+                let mut hits = vec![0u64; counts.len()];
+                for _ in 0..iters {
+                    let (drawn, _) = deck.partial_shuffle(rng, num_cards);
+                    let (cards, num_jokers) = split_jokers(drawn);
+                    for (h, c) in counts.iter().enumerate() {
+                        if c.matches(&cards, num_jokers) {
+                            hits[h] += 1;
+                        }
+                    }
+                }
+                hits.iter().map(|&h| h as f64 / iters as f64).collect()
+            })
+            .collect();
+        Self {
+            iters,
+            probabilities,
+        }
+    }
+
+    fn column(&self, hand: usize) -> impl Iterator<Item = f64> + '_ {
+        self.probabilities.iter().map(move |p| p[hand])
+    }
+
+    pub fn mean(&self, hand: usize) -> f64 {
+        self.column(hand).sum::<f64>() / self.probabilities.len() as f64
+    }
+
+    // Sample standard deviation across variations.
+    pub fn std_dev(&self, hand: usize) -> f64 {
+        let n = self.probabilities.len() as f64;
+        if n < 2.0 {
+            return 0.0;
+        }
+        let mean = self.mean(hand);
+        let sum_sq = self.column(hand).map(|p| (p - mean).powi(2)).sum::<f64>();
+        (sum_sq / (n - 1.0)).sqrt()
+    }
+
+    // Smallest probability at least a fraction `q` of variations don't
+    // exceed.
+    pub fn quantile(&self, hand: usize, q: f64) -> f64 {
+        let mut column = self.column(hand).collect::<Vec<_>>();
+        column.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let i = ((q * column.len() as f64).ceil() as usize).clamp(1, column.len());
+        column[i - 1]
+    }
+
+    pub fn range(&self, hand: usize) -> (f64, f64) {
+        self.column(hand)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                (lo.min(p), hi.max(p))
+            })
+    }
+
+    // Standard deviation expected from sampling alone, for comparison with
+    // `std_dev`.
+    pub fn sampling_noise(&self, hand: usize) -> f64 {
+        let p = self.mean(hand);
+        (p * (1.0 - p) / self.iters as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_sensitivity() {
+        let deck = crate::standard_deck(1, 0);
+        let counts = vec![HandCount::new("Pair", |cards, num_jokers| {
+            crate::is_n_of_a_kind(cards, 2, num_jokers)
+        })];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+
+        // Removing the four deuces takes P(pair in 2 cards) from 3/51 to 3/47.
+        let objectives = vec![crate::objective::parse_objective("prob:pair").unwrap()];
+        let mut low_ranks = Sensitivity::new(counts.len(), 1, ThinOrder::LowRanks, 1, 4, 5);
+        low_ranks.run(&deck, 2, &counts, &objectives, 20000, &mut rng);
+        assert_eq!(2, low_ranks.hits.len());
+        assert_eq!(
+            low_ranks.hits[0][0] as i64 - low_ranks.hits[1][0] as i64,
+            low_ranks.lost[0] as i64 - low_ranks.gained[0] as i64
+        );
+        assert!((low_ranks.probability(0, 0) - 3.0 / 51.0).abs() < 0.005);
+        assert!((low_ranks.probability(1, 0) - 3.0 / 47.0).abs() < 0.005);
+        let (derivative, ci) = low_ranks.derivative(0);
+        assert!(derivative + ci < 0.0);
+        // The objective sees the same deals as the hand.
+        assert_eq!(low_ranks.probability(1, 0), low_ranks.objective_mean(1, 0));
+        let (objective_derivative, objective_ci) = low_ranks.objective_derivative(0);
+        assert!((derivative - objective_derivative).abs() < 1e-12);
+        assert!((ci - objective_ci).abs() < 1e-12);
+
+        let mut random = Sensitivity::new(counts.len(), 0, ThinOrder::Random, 1, 4, 5);
+        random.run(&deck, 2, &counts, &[], 20000, &mut rng);
+        let (derivative, ci) = random.derivative(0);
+        assert!(derivative.abs() < ci);
+    }
+
+    #[test]
+    fn test_perturbation() {
+        let deck = crate::standard_deck(1, 0);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let perturbed = perturb_deck(&deck, 3, &mut rng);
+            assert!((49..=55).contains(&perturbed.len()));
+            // Three edits, each changing the size by one.
+            assert_eq!(1, perturbed.len() % 2);
+        }
+        assert!(deck == perturb_deck(&deck, 0, &mut rng));
+
+        let counts = vec![HandCount::new("Pair", |cards, num_jokers| {
+            crate::is_n_of_a_kind(cards, 2, num_jokers)
+        })];
+        let unchanged = Perturbation::run(&deck, 2, &counts, 0, 3, 20000, &mut rng);
+        assert_eq!(3, unchanged.probabilities.len());
+        assert!((unchanged.mean(0) - 3.0 / 51.0).abs() < 0.005);
+        let (lo, hi) = unchanged.range(0);
+        assert!(lo <= unchanged.mean(0) && unchanged.mean(0) <= hi);
+        // With no edits, only sampling noise separates the variations.
+        assert!(unchanged.std_dev(0) < 4.0 * unchanged.sampling_noise(0));
+        assert!(lo <= unchanged.quantile(0, 0.5) && unchanged.quantile(0, 0.5) <= hi);
+        assert_eq!(lo, unchanged.quantile(0, 0.0));
+        assert_eq!(hi, unchanged.quantile(0, 1.0));
+    }
+
+    #[test]
+    fn test_random_deck() {
+        let spec = "size=45, bias=flush, jokers=1"
+            .parse::<RandomDeckSpec>()
+            .unwrap();
+        assert_eq!(45, spec.size);
+        assert_eq!(Bias::Flush, spec.bias);
+        assert_eq!(3.0, spec.weight);
+        assert!("size=0".parse::<RandomDeckSpec>().is_err());
+        assert!("bias=royal".parse::<RandomDeckSpec>().is_err());
+        assert!("colour=red".parse::<RandomDeckSpec>().is_err());
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let deck = spec.generate(&mut rng);
+        assert_eq!(46, deck.len());
+        assert_eq!(1, deck.iter().filter(|c| **c == CardOrJoker::Joker).count());
+
+        // The favored suit makes up about half of a flush-biased deck.
+        let spec = "size=1000,bias=flush,weight=3"
+            .parse::<RandomDeckSpec>()
+            .unwrap();
+        let deck = spec.generate(&mut rng);
+        let most = (0..NUM_SUITS)
+            .map(|s| {
+                deck.iter()
+                    .filter(|c| matches!(c, CardOrJoker::Card(c) if c.suit as u8 == s))
+                    .count()
+            })
+            .max()
+            .unwrap();
+        assert!((400..600).contains(&most), "{most}");
+    }
+}
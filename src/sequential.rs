@@ -0,0 +1,199 @@
+//! `poker test`: whether one hand is more likely than another, decided as
+//! the deals come in rather than after a fixed number of them.
+//!
+//! Both hands are checked on the same deals, so only the deals which make
+//! exactly one of them say anything about which is more likely. Among those,
+//! A beats B with some probability p, and P(A) > P(B) exactly when p > 1/2.
+//! The test keeps, for each direction, the likelihood ratio of the
+//! discordant deals against p = 1/2, mixed over a uniform prior on that side
+//! of 1/2. Under the opposite hypothesis each is a nonnegative
+//! supermartingale, so by Ville's inequality it ever reaching 1/alpha has
+//! probability at most alpha, however often it's checked. Stopping as soon
+//! as either does is what makes it safe to watch, unlike rerunning a
+//! fixed-size test until its intervals separate.
+
+use crate::interval::{beta_cdf, ln_gamma};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    // P(A) > P(B)
+    Greater,
+    // P(A) < P(B)
+    Less,
+}
+
+// One "A > B" question, with the sides as indices into the run's hands and
+// objectives.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PairedTest {
+    pub a: usize,
+    pub b: usize,
+    pub deals: u64,
+    // Deals which made A but not B, and B but not A.
+    pub a_only: u64,
+    pub b_only: u64,
+}
+
+// The position of the first '>' or '<' in `s` that isn't part of an
+// objective's ">=", like in "score>=300".
+fn find_comparison(s: &str) -> Option<usize> {
+    s.char_indices()
+        .find(|&(i, c)| matches!(c, '<' | '>') && !s[i + 1..].starts_with('='))
+        .map(|(i, _)| i)
+}
+
+// Splits "A>B" into its sides, or "A<B" into "B>A".
+pub fn parse_comparison(s: &str) -> Result<(String, String), String> {
+    let invalid = || format!("expected a comparison like 'A>B', not '{s}'");
+    let i = find_comparison(s).ok_or_else(invalid)?;
+    let (a, b) = (s[..i].trim(), s[i + 1..].trim());
+    let (greater, less) = if s[i..].starts_with('>') {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    if greater.is_empty() || less.is_empty() || find_comparison(b).is_some() {
+        return Err(invalid());
+    }
+    Ok((greater.to_string(), less.to_string()))
+}
+
+// ln of the mixture likelihood ratio for p > 1/2 after `wins` of `wins +
+// losses` discordant deals went to the favored hand: the integral of
+// 2 p^wins (1 - p)^losses over (1/2, 1], divided by (1/2)^(wins + losses).
+fn ln_evidence(wins: u64, losses: u64) -> f64 {
+    let (w, l) = (wins as f64 + 1.0, losses as f64 + 1.0);
+    let ln_beta = ln_gamma(w) + ln_gamma(l) - ln_gamma(w + l);
+    // The mass of Beta(w, l) above 1/2.
+    let upper = beta_cdf(l, w, 0.5);
+    std::f64::consts::LN_2 * (w + l - 1.0) + ln_beta + upper.ln()
+}
+
+impl PairedTest {
+    pub fn new(a: usize, b: usize) -> Self {
+        Self {
+            a,
+            b,
+            ..Self::default()
+        }
+    }
+
+    // Records a deal from the mask of hands it made, as `Plan::evaluate`
+    // returns.
+    pub fn record(&mut self, mask: u64) {
+        self.record_sides(mask >> self.a & 1 != 0, mask >> self.b & 1 != 0);
+    }
+
+    // Records a deal from whether it made each side.
+    pub fn record_sides(&mut self, a: bool, b: bool) {
+        self.deals += 1;
+        self.a_only += u64::from(a && !b);
+        self.b_only += u64::from(b && !a);
+    }
+
+    // The verdict, if the evidence for either direction has reached
+    // 1/alpha.
+    pub fn verdict(&self, alpha: f64) -> Option<Verdict> {
+        let threshold = -alpha.ln();
+        if ln_evidence(self.a_only, self.b_only) >= threshold {
+            Some(Verdict::Greater)
+        } else if ln_evidence(self.b_only, self.a_only) >= threshold {
+            Some(Verdict::Less)
+        } else {
+            None
+        }
+    }
+
+    // P(A) - P(B) with a 99.73% normal interval, like the hands' own.
+    pub fn gap(&self) -> (f64, f64) {
+        let n = self.deals as f64;
+        let mean = (self.a_only as f64 - self.b_only as f64) / n;
+        let second_moment = (self.a_only + self.b_only) as f64 / n;
+        let variance = (second_moment - mean * mean).max(0.0);
+        (mean, 3.0 * (variance / n).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_parse_comparison() {
+        assert_eq!(
+            Ok(("flush_house".to_string(), "straight_flush".to_string())),
+            parse_comparison("flush_house>straight_flush")
+        );
+        assert_eq!(
+            Ok(("b".to_string(), "a".to_string())),
+            parse_comparison(" a < b ")
+        );
+        assert!(parse_comparison("a=b").is_err());
+        assert!(parse_comparison(">b").is_err());
+        assert!(parse_comparison("a>b>c").is_err());
+        assert_eq!(
+            Ok(("score>=300".to_string(), "prob:flush".to_string())),
+            parse_comparison("prob:flush<score>=300")
+        );
+        assert!(parse_comparison("score>=300").is_err());
+    }
+
+    #[test]
+    fn test_ln_evidence() {
+        // With no discordant deals there's no evidence either way.
+        assert!(ln_evidence(0, 0).abs() < 1e-12);
+        // One win: 2 * the integral of 2p over (1/2, 1] = 1.5.
+        assert!((ln_evidence(1, 0) - 1.5f64.ln()).abs() < 1e-12);
+        assert!(ln_evidence(100, 50) > 5.0);
+        assert!(ln_evidence(50, 100) < 0.0);
+        // Large counts stay finite.
+        assert!(ln_evidence(10_000_000, 9_990_000).is_finite());
+        assert_eq!(f64::NEG_INFINITY, ln_evidence(0, 1_000_000));
+    }
+
+    #[test]
+    fn test_verdict() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut test = PairedTest::new(0, 1);
+        while test.verdict(0.01).is_none() {
+            // A with 0.3, B with 0.25, independently.
+            let mask = u64::from(rng.gen_bool(0.3)) | u64::from(rng.gen_bool(0.25)) << 1;
+            test.record(mask);
+        }
+        assert_eq!(Some(Verdict::Greater), test.verdict(0.01));
+        let (gap, ci) = test.gap();
+        assert!((gap - 0.05).abs() < ci);
+
+        let swapped = PairedTest {
+            a: 1,
+            b: 0,
+            a_only: test.b_only,
+            b_only: test.a_only,
+            ..test
+        };
+        assert_eq!(Some(Verdict::Less), swapped.verdict(0.01));
+        assert_eq!(None, PairedTest::new(0, 1).verdict(0.01));
+    }
+
+    #[test]
+    fn test_equal_hands_rarely_decide() {
+        // With P(A) = P(B), a verdict either way is an error, which should
+        // happen in at most about alpha of runs however long they go on.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut decided = 0;
+        for _ in 0..200 {
+            let mut test = PairedTest::new(0, 1);
+            for _ in 0..20 {
+                for _ in 0..100 {
+                    test.record(rng.gen_range(0..4));
+                }
+                if test.verdict(0.05).is_some() {
+                    decided += 1;
+                    break;
+                }
+            }
+        }
+        assert!(decided <= 20, "{decided}");
+    }
+}
@@ -0,0 +1,529 @@
+//! Stateless hand evaluation service.
+//!
+//! Every message in either direction is a frame: a little-endian `u32` body
+//! length followed by the body. Connections may send any number of requests
+//! and each request gets exactly one response, in order.
+//!
+//! Request bodies start with an opcode byte:
+//!
+//! - `0x01` EVAL: `hand_size: u8`, then one byte per card.
+//! - `0x02` DRAW: `hand_size: u8`, `draw: u8`, `iterations: u32`, then one byte
+//!   per held card. Draws `draw` more cards from a single standard deck minus
+//!   the held cards, `iterations` times.
+//! - `0x03` SIMULATE: a deck configuration, then `iterations: u64`. Deals
+//!   `iterations` random hands.
+//! - `0x04` ENUMERATE: a deck configuration, then `start: u128` and
+//!   `end: u128`. Evaluates the draws numbered `start..end` in
+//!   `exact::CombinationSpace`.
+//!
+//! A deck configuration is four bytes: `cards`, `decks`, `jokers` and
+//! `hand_size`, as in the command line flags. Integers are little-endian.
+//!
+//! Cards are encoded as `rank * 4 + suit` (ranks 0..13 from deuce to ace,
+//! suits 0..4 as clubs, diamonds, hearts, spades), and `0xFF` is a joker.
+//!
+//! DRAW and SIMULATE requests for more than `MAX_ITERATIONS` iterations, and
+//! ENUMERATE requests for more than `MAX_ITERATIONS` draws, are errors, so
+//! that one request can't keep a worker busy for hours. ENUMERATE also
+//! rejects decks with more draws than its `u128` counts can hold.
+//!
+//! Response bodies start with a status byte, `0x00` for success and `0x01`
+//! for an error followed by a UTF-8 message. Successful payloads are:
+//!
+//! - EVAL: `best: u8` (index into the hand ranking, weakest first), then
+//!   `matched: u32`, a bitmask of every matching hand with that index.
+//! - DRAW: one `u32` per hand ranking counting how often it was the best hand.
+//! - SIMULATE: one `u64` per entry of `hand_counts(hand_size)`, counting the
+//!   deals which made it.
+//! - ENUMERATE: `total: u128`, the number of draws in the range counting
+//!   duplicate cards separately, then one `u128` per entry of
+//!   `hand_counts(hand_size)`.
+
+use crate::exact::{enumerate_range_par, num_draws, CombinationSpace};
+use crate::plan::Plan;
+use crate::{
+    evaluate_best, evaluate_best_iter, hand_counts, split_jokers, standard_deck, Card, CardOrJoker,
+    HandCount, HandRank, Rank, Suit, MAX_CARDS,
+};
+use std::io::{Read, Write};
+
+pub const OP_EVAL: u8 = 0x01;
+pub const OP_DRAW: u8 = 0x02;
+pub const OP_SIMULATE: u8 = 0x03;
+pub const OP_ENUMERATE: u8 = 0x04;
+
+pub const STATUS_OK: u8 = 0x00;
+pub const STATUS_ERROR: u8 = 0x01;
+
+pub const JOKER_CODE: u8 = 0xFF;
+
+// Bodies are tiny, so anything larger is a malformed or hostile frame.
+const MAX_FRAME_LEN: u32 = 1024;
+
+// About a second of dealing on a few cores. Clients split bigger runs into
+// several requests, as `coordinate` does.
+pub const MAX_ITERATIONS: u64 = 100_000_000;
+
+fn check_iterations(iterations: u128) -> Result<(), String> {
+    if iterations > MAX_ITERATIONS.into() {
+        return Err(format!(
+            "{iterations} iterations exceeds the maximum of {MAX_ITERATIONS}"
+        ));
+    }
+    Ok(())
+}
+
+fn decode_cards(bytes: &[u8]) -> Result<(Vec<Card>, u8), String> {
+    if bytes.len() > MAX_CARDS {
+        return Err(format!("does not support more than {MAX_CARDS} cards"));
+    }
+    let mut cards = Vec::new();
+    let mut num_jokers = 0;
+    for &b in bytes {
+        if b == JOKER_CODE {
+            num_jokers += 1;
+        } else {
+            cards.push(Card::try_from(b)?);
+        }
+    }
+    Ok((cards, num_jokers))
+}
+
+fn eval(body: &[u8]) -> Result<Vec<u8>, String> {
+    let [hand_size, cards @ ..] = body else {
+        return Err("EVAL request too short".to_string());
+    };
+    let (cards, num_jokers) = decode_cards(cards)?;
+    let hand_size = *hand_size as usize;
+    let best = evaluate_best(&cards, num_jokers, hand_size);
+    let matched = HandRank::ALL
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.num_cards() <= hand_size && r.matches(&cards, num_jokers))
+        .fold(0u32, |mask, (i, _)| mask | (1 << i));
+    let mut ret = vec![best as u8];
+    ret.extend_from_slice(&matched.to_le_bytes());
+    Ok(ret)
+}
+
+fn draw(body: &[u8]) -> Result<Vec<u8>, String> {
+    use rand::seq::SliceRandom;
+
+    let [hand_size, draw, i0, i1, i2, i3, held @ ..] = body else {
+        return Err("DRAW request too short".to_string());
+    };
+    let hand_size = *hand_size as usize;
+    let draw = *draw as usize;
+    let iterations = u32::from_le_bytes([*i0, *i1, *i2, *i3]);
+    check_iterations(iterations.into())?;
+    let (held, num_jokers) = decode_cards(held)?;
+    if held.len() + num_jokers as usize + draw > MAX_CARDS {
+        return Err(format!("does not support more than {MAX_CARDS} cards"));
+    }
+
+    let mut deck = Vec::new();
+    for suit in Suit::iter() {
+        for rank in Rank::iter() {
+            let c = Card::new(rank, suit);
+            if !held.contains(&c) {
+                deck.push(c);
+            }
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut counts = [0u32; HandRank::ALL.len()];
+    for _ in 0..iterations {
+        let cards = held
+            .iter()
+            .chain(deck.choose_multiple(&mut rng, draw))
+            .copied();
+        counts[evaluate_best_iter(cards, num_jokers, hand_size) as usize] += 1;
+    }
+    Ok(counts.iter().flat_map(|c| c.to_le_bytes()).collect())
+}
+
+// Deck and hand configuration for SIMULATE and ENUMERATE requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeckConfig {
+    pub cards: u8,
+    pub decks: u8,
+    pub jokers: u8,
+    pub hand_size: u8,
+}
+
+impl DeckConfig {
+    pub fn encode(&self) -> [u8; 4] {
+        [self.cards, self.decks, self.jokers, self.hand_size]
+    }
+
+    fn decode(body: &[u8]) -> Result<(Self, &[u8]), String> {
+        let [cards, decks, jokers, hand_size, rest @ ..] = body else {
+            return Err("deck configuration too short".to_string());
+        };
+        let config = Self {
+            cards: *cards,
+            decks: *decks,
+            jokers: *jokers,
+            hand_size: *hand_size,
+        };
+        config.validate()?;
+        Ok((config, rest))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cards as usize > MAX_CARDS {
+            return Err(format!("does not support more than {MAX_CARDS} cards"));
+        }
+        if self.cards as usize > self.deck().len() {
+            return Err("can't draw more cards than are in the deck".to_string());
+        }
+        self.counts()?;
+        Ok(())
+    }
+
+    // Whether every count of an exact enumeration fits in a u128.
+    pub fn check_enumerable(&self) -> Result<(), String> {
+        match num_draws(self.deck().len(), self.cards as usize) {
+            Some(_) => Ok(()),
+            None => Err("too many possible draws to enumerate".to_string()),
+        }
+    }
+
+    pub fn deck(&self) -> Vec<CardOrJoker> {
+        standard_deck(self.decks as usize, self.jokers)
+    }
+
+    pub fn counts(&self) -> Result<Vec<HandCount>, String> {
+        hand_counts(self.hand_size as usize)
+            .ok_or_else(|| format!("hand size must be between 5 and {MAX_CARDS}"))
+    }
+
+    // Deals `iterations` hands across threads, returning how many made each
+    // entry of `counts()`.
+    pub fn simulate(&self, iterations: u64) -> Result<Vec<u64>, String> {
+        use rand::seq::SliceRandom;
+        use rayon::prelude::*;
+
+        let counts = self.counts()?;
+        let plan = Plan::new(&counts);
+        const CHUNK: u64 = 1 << 16;
+        Ok((0..iterations.div_ceil(CHUNK))
+            .into_par_iter()
+            .map(|chunk| {
+                let mut deck = self.deck();
+                let mut rng = rand::thread_rng();
+                let mut hits = vec![0u64; counts.len()];
+                for _ in chunk * CHUNK..((chunk + 1) * CHUNK).min(iterations) {
+                    let (drawn, _) = deck.partial_shuffle(&mut rng, self.cards as usize);
+                    let (cards, num_jokers) = split_jokers(drawn);
+                    let mask = plan.evaluate(&counts, &cards, num_jokers);
+                    for (h, hit) in hits.iter_mut().enumerate() {
+                        *hit += mask >> h & 1;
+                    }
+                }
+                hits
+            })
+            .reduce(
+                || vec![0; counts.len()],
+                |mut a, b| {
+                    for (x, y) in a.iter_mut().zip(b) {
+                        *x += y;
+                    }
+                    a
+                },
+            ))
+    }
+}
+
+fn simulate(body: &[u8]) -> Result<Vec<u8>, String> {
+    let (config, rest) = DeckConfig::decode(body)?;
+    let Ok(iterations) = <[u8; 8]>::try_from(rest) else {
+        return Err("SIMULATE request has the wrong length".to_string());
+    };
+    let iterations = u64::from_le_bytes(iterations);
+    check_iterations(iterations.into())?;
+    let hits = config.simulate(iterations)?;
+    Ok(hits.iter().flat_map(|h| h.to_le_bytes()).collect())
+}
+
+fn enumerate(body: &[u8]) -> Result<Vec<u8>, String> {
+    let (config, rest) = DeckConfig::decode(body)?;
+    let Ok(range) = <[u8; 32]>::try_from(rest) else {
+        return Err("ENUMERATE request has the wrong length".to_string());
+    };
+    let start = u128::from_le_bytes(range[..16].try_into().unwrap());
+    let end = u128::from_le_bytes(range[16..].try_into().unwrap());
+    config.check_enumerable()?;
+    let space = CombinationSpace::new(&config.deck(), config.cards as usize);
+    if start > end || end > space.len() {
+        return Err(format!(
+            "range {start}..{end} is outside 0..{}",
+            space.len()
+        ));
+    }
+    check_iterations(end - start)?;
+    let result = enumerate_range_par(&space, start..end, &config.counts()?);
+    let mut ret = result.total.to_le_bytes().to_vec();
+    ret.extend(result.hits.iter().flat_map(|h| h.to_le_bytes()));
+    Ok(ret)
+}
+
+fn handle_request(body: &[u8]) -> Vec<u8> {
+    let result = match body.split_first() {
+        Some((&OP_EVAL, rest)) => eval(rest),
+        Some((&OP_DRAW, rest)) => draw(rest),
+        Some((&OP_SIMULATE, rest)) => simulate(rest),
+        Some((&OP_ENUMERATE, rest)) => enumerate(rest),
+        Some((op, _)) => Err(format!("unknown opcode {op}")),
+        None => Err("empty request".to_string()),
+    };
+    match result {
+        Ok(payload) => [&[STATUS_OK][..], &payload].concat(),
+        Err(e) => [&[STATUS_ERROR][..], e.as_bytes()].concat(),
+    }
+}
+
+fn read_frame(stream: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds {MAX_FRAME_LEN}"),
+        ));
+    }
+    let mut body = vec![0; len as usize];
+    stream.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_frame(stream: &mut impl Write, body: &[u8]) -> std::io::Result<()> {
+    stream.write_all(&(body.len() as u32).to_le_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+// Sends one request over `stream` and returns the successful response
+// payload.
+pub fn request(stream: &mut (impl Read + Write), body: &[u8]) -> Result<Vec<u8>, String> {
+    write_frame(stream, body).map_err(|e| e.to_string())?;
+    let response = read_frame(stream)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "connection closed".to_string())?;
+    match response.split_first() {
+        Some((&STATUS_OK, payload)) => Ok(payload.to_vec()),
+        Some((&STATUS_ERROR, message)) => Err(String::from_utf8_lossy(message).into_owned()),
+        _ => Err("malformed response".to_string()),
+    }
+}
+
+fn handle_connection(mut stream: impl Read + Write) -> std::io::Result<()> {
+    while let Some(body) = read_frame(&mut stream)? {
+        write_frame(&mut stream, &handle_request(&body))?;
+    }
+    Ok(())
+}
+
+fn spawn_handler(stream: impl Read + Write + Send + 'static) {
+    std::thread::spawn(move || {
+        if let Err(e) = handle_connection(stream) {
+            eprintln!("connection error: {e}");
+        }
+    });
+}
+
+pub fn serve_tcp(addr: &str) -> std::io::Result<()> {
+    let listener = std::net::TcpListener::bind(addr)?;
+    eprintln!("listening on {}", listener.local_addr()?);
+    serve_tcp_listener(listener)
+}
+
+pub fn serve_tcp_listener(listener: std::net::TcpListener) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        // Errors like running out of file descriptors only lose this
+        // connection.
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept error: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = stream.set_nodelay(true) {
+            eprintln!("connection error: {e}");
+            continue;
+        }
+        spawn_handler(stream);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn serve_unix(path: &std::path::Path) -> std::io::Result<()> {
+    let listener = std::os::unix::net::UnixListener::bind(path)?;
+    eprintln!("listening on {}", path.display());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => spawn_handler(stream),
+            Err(e) => eprintln!("accept error: {e}"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_request() {
+        // Pair of aces plus a joker: trips.
+        let response = handle_request(&[OP_EVAL, 5, 48, 49, JOKER_CODE]);
+        assert_eq!(STATUS_OK, response[0]);
+        assert_eq!(HandRank::ThreeOfAKind as u8, response[1]);
+        let matched = u32::from_le_bytes(response[2..6].try_into().unwrap());
+        assert_eq!(
+            (1 << HandRank::HighCard as u32)
+                | (1 << HandRank::Pair as u32)
+                | (1 << HandRank::ThreeOfAKind as u32),
+            matched
+        );
+    }
+
+    #[test]
+    fn test_draw_request() {
+        let response = handle_request(&[OP_DRAW, 5, 0, 10, 0, 0, 0, 48, 49]);
+        assert_eq!(STATUS_OK, response[0]);
+        assert_eq!(1 + 4 * HandRank::ALL.len(), response.len());
+        let pair = HandRank::Pair as usize;
+        let count = u32::from_le_bytes(response[1 + 4 * pair..5 + 4 * pair].try_into().unwrap());
+        assert_eq!(10, count);
+    }
+
+    const CONFIG: DeckConfig = DeckConfig {
+        cards: 2,
+        decks: 1,
+        jokers: 0,
+        hand_size: 5,
+    };
+
+    #[test]
+    fn test_simulate_request() {
+        let mut body = vec![OP_SIMULATE];
+        body.extend(CONFIG.encode());
+        body.extend(1000u64.to_le_bytes());
+        let response = handle_request(&body);
+        assert_eq!(STATUS_OK, response[0]);
+        let counts = CONFIG.counts().unwrap();
+        assert_eq!(1 + 8 * counts.len(), response.len());
+        // Two cards always make a high card and sometimes a pair, but
+        // nothing else.
+        for (c, hits) in counts.iter().zip(response[1..].chunks(8)) {
+            let hits = u64::from_le_bytes(hits.try_into().unwrap());
+            match c.name {
+                "High Card" => assert_eq!(1000, hits),
+                "Pair" => assert!(hits > 0 && hits < 1000),
+                _ => assert_eq!(0, hits, "{}", c.name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_enumerate_request() {
+        let mut body = vec![OP_ENUMERATE];
+        body.extend(CONFIG.encode());
+        body.extend(0u128.to_le_bytes());
+        body.extend(1326u128.to_le_bytes());
+        let response = handle_request(&body);
+        assert_eq!(STATUS_OK, response[0]);
+        assert_eq!(
+            1326,
+            u128::from_le_bytes(response[1..17].try_into().unwrap())
+        );
+        assert_eq!(
+            78,
+            u128::from_le_bytes(response[17..33].try_into().unwrap())
+        );
+        // Past the last draw.
+        body[36] = 0x01;
+        assert_eq!(STATUS_ERROR, handle_request(&body)[0]);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        assert_eq!(STATUS_ERROR, handle_request(&[])[0]);
+        assert_eq!(STATUS_ERROR, handle_request(&[0x7F])[0]);
+        assert_eq!(STATUS_ERROR, handle_request(&[OP_EVAL])[0]);
+        assert_eq!(STATUS_ERROR, handle_request(&[OP_EVAL, 5, 52])[0]);
+        assert_eq!(STATUS_ERROR, handle_request(&[OP_DRAW, 5, 1])[0]);
+        assert_eq!(STATUS_ERROR, handle_request(&[OP_SIMULATE, 2, 1, 0, 5])[0]);
+        assert_eq!(
+            STATUS_ERROR,
+            handle_request(&[
+                OP_SIMULATE,
+                2,
+                1,
+                0,
+                MAX_CARDS as u8 + 1,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0
+            ])[0]
+        );
+    }
+
+    #[test]
+    fn test_too_many_iterations() {
+        let mut body = vec![OP_SIMULATE];
+        body.extend(CONFIG.encode());
+        body.extend((MAX_ITERATIONS + 1).to_le_bytes());
+        let response = handle_request(&body);
+        assert_eq!(STATUS_ERROR, response[0]);
+        assert!(String::from_utf8_lossy(&response[1..]).contains("maximum"));
+        let mut body = vec![OP_DRAW, 5, 1];
+        body.extend(u32::MAX.to_le_bytes());
+        assert_eq!(STATUS_ERROR, handle_request(&body)[0]);
+
+        let enumerate = |config: DeckConfig, end: u128| {
+            let mut body = vec![OP_ENUMERATE];
+            body.extend(config.encode());
+            body.extend(0u128.to_le_bytes());
+            body.extend(end.to_le_bytes());
+            handle_request(&body)
+        };
+        let seven = DeckConfig { cards: 7, ..CONFIG };
+        let response = enumerate(seven, MAX_ITERATIONS as u128 + 1);
+        assert_eq!(STATUS_ERROR, response[0]);
+        assert!(String::from_utf8_lossy(&response[1..]).contains("maximum"));
+        // Counting every draw of 20 cards from 255 decks overflows.
+        let huge = DeckConfig {
+            cards: 20,
+            decks: 255,
+            ..CONFIG
+        };
+        let response = enumerate(huge, 1);
+        assert_eq!(STATUS_ERROR, response[0]);
+        assert!(String::from_utf8_lossy(&response[1..]).contains("too many"));
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &[1, 2, 3]).unwrap();
+        assert_eq!(vec![3, 0, 0, 0, 1, 2, 3], buf);
+        let mut reader = &buf[..];
+        assert_eq!(Some(vec![1, 2, 3]), read_frame(&mut reader).unwrap());
+        assert_eq!(None, read_frame(&mut reader).unwrap());
+    }
+}
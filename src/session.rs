@@ -0,0 +1,175 @@
+//! Saved state of `poker edit`, so that planning a deck can carry on where it
+//! left off. Each named session is its own file:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "deck": "As x2\nKh x0\n",
+//!   "rules": "best",
+//!   "results": [{ "config": { "cards": 7, ... }, "iterations": 200000, ... }]
+//! }
+//! ```
+//!
+//! `deck` is a deck file as written by `deck::to_text`, and `results` are the
+//! most recent simulations in the format of results.rs, oldest first.
+
+use crate::results::RunResult;
+use crate::rules::RuleSet;
+use crate::schema::{check_version, SCHEMA_VERSION};
+use crate::CardOrJoker;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+// Older results are dropped, since they're only there to compare against.
+pub const MAX_RESULTS: usize = 10;
+
+pub struct Session {
+    pub deck: Vec<CardOrJoker>,
+    pub rules: RuleSet,
+    pub results: Vec<RunResult>,
+}
+
+// The file for session `name` in `dir`. Names are kept to letters, digits,
+// '-' and '_' so that they can't reach outside `dir`.
+pub fn path(dir: &std::path::Path, name: &str) -> Result<std::path::PathBuf, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "invalid session name '{name}', use letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(dir.join(format!("{name}.json")))
+}
+
+impl Session {
+    pub fn new(deck: Vec<CardOrJoker>, rules: RuleSet) -> Self {
+        Self {
+            deck,
+            rules,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn push_result(&mut self, result: RunResult) {
+        self.results.push(result);
+        let extra = self.results.len().saturating_sub(MAX_RESULTS);
+        self.results.drain(..extra);
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schema_version": SCHEMA_VERSION,
+            "deck": crate::deck::to_text(&self.deck),
+            "rules": self.rules.to_possible_value().unwrap().get_name(),
+            "results": self.results.iter().map(RunResult::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        check_version(value)?;
+        let deck = value
+            .get("deck")
+            .and_then(Value::as_str)
+            .ok_or("missing deck")?;
+        let deck = crate::deck::parse(deck).map_err(|e| format!("deck {e}"))?;
+        let rules = value
+            .get("rules")
+            .and_then(Value::as_str)
+            .ok_or("missing rules")?;
+        let rules =
+            RuleSet::from_str(rules, false).map_err(|_| format!("unknown rules '{rules}'"))?;
+        let results = value
+            .get("results")
+            .and_then(Value::as_array)
+            .ok_or("missing results")?
+            .iter()
+            .map(RunResult::from_json)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            deck,
+            rules,
+            results,
+        })
+    }
+
+    // None when the session hasn't been saved yet.
+    pub fn read(path: &std::path::Path) -> Result<Option<Self>, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        let value =
+            serde_json::from_str::<Value>(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&value)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    // Creates the sessions directory if needed.
+    pub fn write(&self, path: &std::path::Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        let text = serde_json::to_string_pretty(&self.to_json()).unwrap();
+        std::fs::write(path, text + "\n").map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, hand_counts, standard_deck};
+
+    #[test]
+    fn test_path() {
+        let dir = std::path::Path::new("/sessions");
+        assert_eq!(
+            Ok(std::path::PathBuf::from("/sessions/bomb-pot_2.json")),
+            path(dir, "bomb-pot_2")
+        );
+        assert!(path(dir, "").is_err());
+        assert!(path(dir, "../x").is_err());
+        assert!(path(dir, "a b").is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let mut deck = standard_deck(1, 2);
+        deck[0] = CardOrJoker::Card(card!("2c").made_wild());
+        let mut session = Session::new(deck, RuleSet::Best);
+        let mut counts = hand_counts(5).unwrap();
+        counts[0].count = 7;
+        for i in 0..MAX_RESULTS as u64 + 2 {
+            session.push_result(RunResult::new(json!({ "cards": i }), &counts, 10));
+        }
+        assert_eq!(MAX_RESULTS, session.results.len());
+        assert_eq!(json!({ "cards": 2 }), session.results[0].config);
+
+        let keys = session
+            .to_json()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(crate::schema::SESSION_FIELDS, keys);
+
+        let read = Session::from_json(&session.to_json()).unwrap();
+        assert_eq!(
+            crate::deck::describe(&session.deck),
+            crate::deck::describe(&read.deck)
+        );
+        assert_eq!(RuleSet::Best, read.rules);
+        assert_eq!(MAX_RESULTS, read.results.len());
+        assert_eq!(("Pair".to_string(), 7), read.results[0].counts[0]);
+
+        let mut value = session.to_json();
+        value["rules"] = json!("lowball");
+        assert!(Session::from_json(&value).is_err());
+        assert!(Session::from_json(&json!({ "schema_version": SCHEMA_VERSION })).is_err());
+    }
+}
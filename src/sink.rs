@@ -0,0 +1,330 @@
+//! Where a run's results go. The terminal report is always one sink, and
+//! `--out KIND:PATH` adds more, so one run can feed a terminal, a file and a
+//! dashboard at once:
+//!
+//! - `json:PATH`, a result file as described in results.rs, written at the end.
+//! - `csv:PATH` and `markdown:PATH`, the `--format` tables, written at the end.
+//! - `ndjson:PATH`, one compact result document per line after every batch,
+//!   with the last line written at the end. `ndjson:-` streams to stdout.
+//! - `prometheus:PATH`, the counts in Prometheus' text format, rewritten after
+//!   every batch for node_exporter's textfile collector.
+//!
+//! `--live-file PATH` adds a `json:PATH` file which is also rewritten after
+//! every batch, so dashboards and scripts polling it always find the latest
+//! complete results of a long run.
+//!
+//! There's no SQLite sink yet; it's tracked as a follow-up request.
+
+use crate::interval::{interval_with, CiMethod};
+use crate::results::RunResult;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub trait OutputSink {
+    // The results so far, after each batch.
+    fn on_batch(&mut self, _result: &RunResult) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Aligned columns for the terminal
+    Text,
+    /// A GitHub-flavored Markdown table, for pasting into issues and posts
+    Markdown,
+    /// hand,count,probability,ci rows
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn render(self, result: &RunResult, baseline: Option<&RunResult>) -> String {
+        match self {
+            ReportFormat::Text => format!(
+                "total iterations: {}\n{}",
+                result.iterations,
+                result.to_text(baseline)
+            ),
+            ReportFormat::Markdown => result.to_markdown(baseline),
+            ReportFormat::Csv => result.to_csv(baseline),
+        }
+    }
+}
+
+// The final report on stdout, in `format`.
+pub struct Terminal {
+    pub format: ReportFormat,
+    pub baseline: Option<RunResult>,
+}
+
+impl OutputSink for Terminal {
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        print!("{}", self.format.render(result, self.baseline.as_ref()));
+        Ok(())
+    }
+}
+
+fn write_file(path: &std::path::Path, text: &str) -> Result<(), String> {
+    std::fs::write(path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+// Writes a temporary file and renames it over the old one, so readers never
+// see a partial file.
+fn replace_file(path: &std::path::Path, text: &str) -> Result<(), String> {
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_file(&tmp, text)?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+pub struct JsonFile(pub PathBuf);
+
+impl OutputSink for JsonFile {
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        result.write(&self.0)
+    }
+}
+
+// A `--format` table written to a file at the end.
+pub struct TableFile {
+    pub path: PathBuf,
+    pub format: ReportFormat,
+}
+
+impl OutputSink for TableFile {
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        write_file(&self.path, &self.format.render(result, None))
+    }
+}
+
+pub struct NdJson<W: Write> {
+    out: W,
+}
+
+impl<W: Write> NdJson<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn write(&mut self, result: &RunResult) -> Result<(), String> {
+        writeln!(self.out, "{}", result.to_json())
+            .and_then(|()| self.out.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl<W: Write> OutputSink for NdJson<W> {
+    fn on_batch(&mut self, result: &RunResult) -> Result<(), String> {
+        self.write(result)
+    }
+
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        self.write(result)
+    }
+}
+
+pub struct Prometheus(pub PathBuf);
+
+// Label values escape backslashes, quotes and newlines.
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// A per-hand metric's name, type, help and value from (interval method,
+// iterations, count).
+type HandMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(CiMethod, u64, u64) -> f64,
+);
+
+pub fn prometheus_text(result: &RunResult) -> String {
+    let mut ret = String::new();
+    ret += "# HELP poker_iterations Deals simulated so far.\n";
+    ret += "# TYPE poker_iterations counter\n";
+    ret += &format!("poker_iterations {}\n", result.iterations);
+    let metrics: [HandMetric; 3] = [
+        ("count", "counter", "Deals making each hand.", |_, _, c| {
+            c as f64
+        }),
+        (
+            "probability",
+            "gauge",
+            "Estimated probability of each hand.",
+            |m, n, c| interval_with(m, n, c).0,
+        ),
+        (
+            "ci",
+            "gauge",
+            "Half-width of each hand's 99.73% confidence interval.",
+            |m, n, c| interval_with(m, n, c).1,
+        ),
+    ];
+    for (name, kind, help, value) in metrics {
+        ret += &format!("# HELP poker_hand_{name} {help}\n");
+        ret += &format!("# TYPE poker_hand_{name} {kind}\n");
+        for (hand, count) in &result.counts {
+            ret += &format!(
+                "poker_hand_{name}{{hand=\"{}\"}} {}\n",
+                escape_label(hand),
+                value(result.ci_method, result.iterations, *count)
+            );
+        }
+    }
+    ret
+}
+
+impl OutputSink for Prometheus {
+    fn on_batch(&mut self, result: &RunResult) -> Result<(), String> {
+        replace_file(&self.0, &prometheus_text(result))
+    }
+
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        replace_file(&self.0, &prometheus_text(result))
+    }
+}
+
+// A result file rewritten after every batch. Batches before the last don't
+// have the run's metadata yet.
+pub struct LiveJson(pub PathBuf);
+
+impl LiveJson {
+    fn write(&self, result: &RunResult) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&result.to_json()).unwrap();
+        replace_file(&self.0, &(text + "\n"))
+    }
+}
+
+impl OutputSink for LiveJson {
+    fn on_batch(&mut self, result: &RunResult) -> Result<(), String> {
+        self.write(result)
+    }
+
+    fn on_finish(&mut self, result: &RunResult) -> Result<(), String> {
+        self.write(result)
+    }
+}
+
+// An `--out` flag, like "json:result.json".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SinkSpec {
+    Json(PathBuf),
+    Table(ReportFormat, PathBuf),
+    // None for stdout.
+    NdJson(Option<PathBuf>),
+    Prometheus(PathBuf),
+}
+
+impl std::str::FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, path) = s
+            .split_once(':')
+            .filter(|(_, path)| !path.is_empty())
+            .ok_or_else(|| format!("expected KIND:PATH, like json:result.json, not '{s}'"))?;
+        let path = PathBuf::from(path);
+        Ok(match kind {
+            "json" => SinkSpec::Json(path),
+            "csv" => SinkSpec::Table(ReportFormat::Csv, path),
+            "markdown" => SinkSpec::Table(ReportFormat::Markdown, path),
+            "ndjson" if path.as_os_str() == "-" => SinkSpec::NdJson(None),
+            "ndjson" => SinkSpec::NdJson(Some(path)),
+            "prometheus" => SinkSpec::Prometheus(path),
+            _ => {
+                return Err(format!(
+                    "unknown output '{kind}', expected json, csv, markdown, ndjson or prometheus"
+                ))
+            }
+        })
+    }
+}
+
+impl SinkSpec {
+    pub fn open(&self) -> Result<Box<dyn OutputSink>, String> {
+        Ok(match self {
+            SinkSpec::Json(path) => Box::new(JsonFile(path.clone())),
+            SinkSpec::Table(format, path) => Box::new(TableFile {
+                path: path.clone(),
+                format: *format,
+            }),
+            SinkSpec::NdJson(None) => Box::new(NdJson::new(std::io::stdout())),
+            SinkSpec::NdJson(Some(path)) => {
+                let file =
+                    std::fs::File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+                Box::new(NdJson::new(std::io::BufWriter::new(file)))
+            }
+            SinkSpec::Prometheus(path) => Box::new(Prometheus(path.clone())),
+        })
+    }
+}
+
+// Hands every sink the same results, stopping at the first error.
+pub fn on_batch(sinks: &mut [Box<dyn OutputSink>], result: &RunResult) -> Result<(), String> {
+    sinks.iter_mut().try_for_each(|s| s.on_batch(result))
+}
+
+pub fn on_finish(sinks: &mut [Box<dyn OutputSink>], result: &RunResult) -> Result<(), String> {
+    sinks.iter_mut().try_for_each(|s| s.on_finish(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> RunResult {
+        RunResult {
+            metadata: None,
+            config: json!({ "cards": 7 }),
+            iterations: 100,
+            counts: vec![("Pair".to_string(), 40), ("a\"b".to_string(), 1)],
+            warnings: Vec::new(),
+            ci_method: CiMethod::Normal,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(SinkSpec::Json(PathBuf::from("a:b.json"))),
+            "json:a:b.json".parse()
+        );
+        assert_eq!(
+            Ok(SinkSpec::Table(ReportFormat::Csv, PathBuf::from("x.csv"))),
+            "csv:x.csv".parse()
+        );
+        assert_eq!(Ok(SinkSpec::NdJson(None)), "ndjson:-".parse());
+        assert!("json:".parse::<SinkSpec>().is_err());
+        assert!("result.json".parse::<SinkSpec>().is_err());
+        assert!("sqlite:x.db".parse::<SinkSpec>().is_err());
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let mut out = Vec::new();
+        let mut sink = NdJson::new(&mut out);
+        sink.on_batch(&result()).unwrap();
+        sink.on_finish(&result()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        let value = serde_json::from_str::<serde_json::Value>(lines[0]).unwrap();
+        assert_eq!(100, value["iterations"]);
+    }
+
+    #[test]
+    fn test_prometheus_text() {
+        let text = prometheus_text(&result());
+        assert!(text.contains("\npoker_iterations 100\n"));
+        assert!(text.contains("\npoker_hand_count{hand=\"Pair\"} 40\n"));
+        assert!(text.contains("\npoker_hand_probability{hand=\"a\\\"b\"} 0.01\n"));
+        assert!(text.contains("# TYPE poker_hand_ci gauge\n"));
+    }
+}
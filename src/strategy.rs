@@ -0,0 +1,316 @@
+use crate::scoring::{best_play, CardEffects};
+use crate::{cards_needed, evaluate_best, Card, CardOrJoker, HandRank, MAX_CARDS};
+
+// Balatro lets you discard at most this many cards at once.
+pub const MAX_DISCARD_CARDS: usize = 5;
+
+// Rough chance that a single redrawn card is one a target hand needs, used to
+// discount hands by how many cards they're missing.
+const HIT_CHANCE: f64 = 0.25;
+
+pub trait DiscardStrategy {
+    // Indices into `cards` to discard, at most `max_discards` of them. Jokers
+    // are never discarded. `upcoming` holds the next cards in the deck when
+    // the strategy is allowed to peek at them.
+    fn discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        hand_size: usize,
+        max_discards: usize,
+        upcoming: &[CardOrJoker],
+    ) -> Vec<usize>;
+}
+
+fn base_value(rank: HandRank) -> f64 {
+    let (chips, mult) = rank.base_score();
+    (chips * mult) as f64
+}
+
+// Best hand after replacing `discards` with the first cards of `upcoming`.
+fn best_after_draw(
+    cards: &[Card],
+    num_jokers: u8,
+    hand_size: usize,
+    discards: &[usize],
+    upcoming: &[CardOrJoker],
+) -> HandRank {
+    let mut num_jokers = num_jokers;
+    let mut result = cards
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !discards.contains(i))
+        .map(|(_, &c)| c)
+        .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+    for &coj in upcoming.iter().take(discards.len()) {
+        match coj {
+            CardOrJoker::Card(c) => result.push(c),
+            CardOrJoker::Joker => num_jokers += 1,
+        }
+    }
+    evaluate_best(&result, num_jokers, hand_size)
+}
+
+// Greedily drops cards which don't bring `cards` any closer to `target`.
+// Returns the indices to discard and the number of cards still needed.
+fn minimal_keep(
+    cards: &[Card],
+    num_jokers: u8,
+    target: HandRank,
+    max_discards: usize,
+) -> (Vec<usize>, u8) {
+    let needed = cards_needed(cards, num_jokers, target);
+    let mut kept = cards
+        .iter()
+        .copied()
+        .enumerate()
+        .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+    let mut discards = Vec::new();
+    let mut i = 0;
+    while i < kept.len() && discards.len() < max_discards {
+        let without = kept
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, &(_, c))| c)
+            .collect::<arrayvec::ArrayVec<_, MAX_CARDS>>();
+        if cards_needed(&without, num_jokers, target) == needed {
+            discards.push(kept.remove(i).0);
+        } else {
+            i += 1;
+        }
+    }
+    (discards, needed)
+}
+
+// Discards everything that doesn't help make one specific hand.
+pub struct Chase(pub HandRank);
+
+impl DiscardStrategy for Chase {
+    fn discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        _hand_size: usize,
+        max_discards: usize,
+        _upcoming: &[CardOrJoker],
+    ) -> Vec<usize> {
+        minimal_keep(cards, num_jokers, self.0, max_discards).0
+    }
+}
+
+// Chases whichever playable hand has the best base score discounted by how
+// many cards it's missing. When every replacement card is visible in
+// `upcoming`, the hand that would actually result is scored instead.
+pub struct KeepBest;
+
+impl DiscardStrategy for KeepBest {
+    fn discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        hand_size: usize,
+        max_discards: usize,
+        upcoming: &[CardOrJoker],
+    ) -> Vec<usize> {
+        let mut best = (f64::MIN, Vec::new());
+        for target in HandRank::ALL {
+            if target.num_cards() > hand_size {
+                continue;
+            }
+            let (discards, needed) = minimal_keep(cards, num_jokers, target, max_discards);
+            let value = if !upcoming.is_empty() && discards.len() <= upcoming.len() {
+                base_value(best_after_draw(
+                    cards, num_jokers, hand_size, &discards, upcoming,
+                ))
+            } else {
+                base_value(target) * HIT_CHANCE.powi(needed as i32)
+            };
+            if value > best.0 {
+                best = (value, discards);
+            }
+        }
+        best.1
+    }
+}
+
+// Keeps the cards of the best hand already made and discards the rest,
+// lowest first, without chasing anything better.
+pub struct Greedy;
+
+impl DiscardStrategy for Greedy {
+    fn discards(
+        &self,
+        cards: &[Card],
+        num_jokers: u8,
+        hand_size: usize,
+        max_discards: usize,
+        _upcoming: &[CardOrJoker],
+    ) -> Vec<usize> {
+        let (_, played, _) = best_play(cards, num_jokers, hand_size, &CardEffects::default());
+        let mut discards = (0..cards.len())
+            .filter(|i| !played.contains(i))
+            .collect::<Vec<_>>();
+        discards.sort_by_key(|&i| cards[i].rank);
+        discards.truncate(max_discards);
+        discards
+    }
+}
+
+// Parses "keep-best", "greedy" or "target:<hand id>".
+pub fn parse_strategy(s: &str) -> Result<Box<dyn DiscardStrategy + Sync>, String> {
+    if s == "keep-best" {
+        return Ok(Box::new(KeepBest));
+    }
+    if s == "greedy" {
+        return Ok(Box::new(Greedy));
+    }
+    if let Some(target) = s.strip_prefix("target:") {
+        return Ok(Box::new(Chase(target.parse()?)));
+    }
+    Err(format!(
+        "unknown discard strategy '{s}', expected keep-best, greedy or target:<hand>"
+    ))
+}
+
+// Plays `rounds` rounds of discarding according to `strategy` and refilling
+// the hand from the top of `pile`, letting the strategy see the next `peek`
+// cards. Returns the number of cards drawn from `pile`.
+pub fn discard_and_draw(
+    cards: &mut arrayvec::ArrayVec<Card, MAX_CARDS>,
+    num_jokers: &mut u8,
+    mut pile: &[CardOrJoker],
+    strategy: &dyn DiscardStrategy,
+    hand_size: usize,
+    rounds: usize,
+    peek: usize,
+) -> usize {
+    let pile_len = pile.len();
+    for _ in 0..rounds {
+        let upcoming = &pile[..peek.min(pile.len())];
+        let mut discards =
+            strategy.discards(cards, *num_jokers, hand_size, MAX_DISCARD_CARDS, upcoming);
+        if discards.is_empty() {
+            break;
+        }
+        discards.sort_unstable();
+        for &i in discards.iter().rev() {
+            cards.remove(i);
+        }
+        let (drawn, rest) = pile.split_at(discards.len().min(pile.len()));
+        for &coj in drawn {
+            match coj {
+                CardOrJoker::Card(c) => cards.push(c),
+                CardOrJoker::Joker => *num_jokers += 1,
+            }
+        }
+        pile = rest;
+    }
+    pile_len - pile.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stacked_deck;
+
+    fn hand(s: &str) -> (Vec<Card>, u8) {
+        crate::parse_hand(s).unwrap()
+    }
+
+    #[test]
+    fn test_chase() {
+        let (cards, num_jokers) = hand("Ah Ad 2c 7h 9s");
+        assert_eq!(
+            vec![2, 3, 4],
+            Chase(HandRank::ThreeOfAKind).discards(&cards, num_jokers, 5, 5, &[])
+        );
+        assert_eq!(
+            vec![2, 3],
+            Chase(HandRank::ThreeOfAKind).discards(&cards, num_jokers, 5, 2, &[])
+        );
+        let (cards, num_jokers) = hand("Ah 2h 7h 9s 9c");
+        assert_eq!(
+            vec![3, 4],
+            Chase(HandRank::Flush).discards(&cards, num_jokers, 5, 5, &[])
+        );
+    }
+
+    #[test]
+    fn test_keep_best() {
+        // Four to a flush is worth more than the pair of nines.
+        let (cards, num_jokers) = hand("Ah 2h 7h Th 9s 9c Kd");
+        assert_eq!(
+            vec![4, 5, 6],
+            KeepBest.discards(&cards, num_jokers, 5, 5, &[])
+        );
+        // Already made hands are kept.
+        let (cards, num_jokers) = hand("Ah Ad As Ac 2c");
+        assert_eq!(vec![4], KeepBest.discards(&cards, num_jokers, 5, 5, &[]));
+    }
+
+    #[test]
+    fn test_greedy() {
+        // Keeps the pair of nines rather than drawing to the flush, throwing
+        // away the lowest cards when there are too many to discard.
+        let (cards, num_jokers) = hand("Ah 2h 7h Th 9s 9c Kd");
+        assert_eq!(
+            vec![1, 2, 3, 6],
+            Greedy.discards(&cards, num_jokers, 5, 4, &[])
+        );
+        // With nothing made, only the highest card is kept.
+        let (cards, num_jokers) = hand("Ah 2c 7d Th");
+        assert_eq!(
+            vec![1, 2, 3],
+            Greedy.discards(&cards, num_jokers, 5, 5, &[])
+        );
+        // A made flush is kept whole.
+        let (cards, num_jokers) = hand("Ah 2h 7h Th 9h");
+        assert!(Greedy.discards(&cards, num_jokers, 5, 5, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_keep_best_peek() {
+        // Without peeking the flush draw looks best, but the next cards are
+        // queens which would miss the flush and make trips with the queen.
+        let (cards, num_jokers) = hand("Ah 2h 7h Th 9s 9c Qd");
+        let upcoming = stacked_deck("Qs Qs Qs");
+        assert_eq!(
+            vec![4, 5, 6],
+            KeepBest.discards(&cards, num_jokers, 5, 5, &[])
+        );
+        let discards = KeepBest.discards(&cards, num_jokers, 5, 5, &upcoming);
+        assert_eq!(
+            HandRank::ThreeOfAKind,
+            best_after_draw(&cards, num_jokers, 5, &discards, &upcoming)
+        );
+    }
+
+    #[test]
+    fn test_discard_and_draw() {
+        let (cards, mut num_jokers) = hand("Ah Ad 2c 7h 9s");
+        let mut cards = cards.into_iter().collect();
+        let pile = stacked_deck("J As 3c 3d");
+        let drawn = discard_and_draw(
+            &mut cards,
+            &mut num_jokers,
+            &pile,
+            &Chase(HandRank::FourOfAKind),
+            5,
+            1,
+            0,
+        );
+        assert_eq!(3, drawn);
+        assert_eq!(1, num_jokers);
+        assert_eq!(crate::hand!("Ah Ad As 3c").to_vec(), cards.to_vec());
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert!(parse_strategy("keep-best").is_ok());
+        assert!(parse_strategy("target:flush").is_ok());
+        assert!(parse_strategy("target:nope").is_err());
+        assert!(parse_strategy("greedy").is_ok());
+        assert!(parse_strategy("chase").is_err());
+    }
+}
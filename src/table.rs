@@ -0,0 +1,443 @@
+//! Tables of numbers written row by row, like `--history`, `--features`,
+//! `--snapshot-file` and the `sensitivity` and `--perturb` sweeps.
+//!
+//! The format comes from the file extension: ".arrow", ".ipc" or ".feather"
+//! for Arrow IPC files and ".parquet" for Parquet, both of which need the
+//! `arrow` feature, and CSV for anything else. Arrow and Parquet output is
+//! buffered into record batches, so memory use stays flat however many rows
+//! are written.
+//!
+//! The deal logs of `--log-file` and `--dump-samples` are text for reading,
+//! not tables; `--features` is the table of per-deal samples.
+
+use crate::metadata::Metadata;
+use std::io::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    U8,
+    U64,
+    F64,
+}
+
+pub trait TableSink {
+    // Called at most once, before the header.
+    fn write_metadata(&mut self, metadata: &Metadata) -> std::io::Result<()>;
+
+    // Called once, before any rows.
+    fn write_header(&mut self, columns: &[(String, ColumnType)]) -> std::io::Result<()>;
+
+    // One value per column, converted to the column's type.
+    fn write_row(&mut self, row: &[f64]) -> std::io::Result<()>;
+
+    // Makes the rows so far visible to readers of a file being written.
+    fn flush(&mut self) -> std::io::Result<()>;
+
+    // Writes anything buffered along with any footer. No rows may follow.
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<T: TableSink + ?Sized> TableSink for Box<T> {
+    fn write_metadata(&mut self, metadata: &Metadata) -> std::io::Result<()> {
+        (**self).write_metadata(metadata)
+    }
+
+    fn write_header(&mut self, columns: &[(String, ColumnType)]) -> std::io::Result<()> {
+        (**self).write_header(columns)
+    }
+
+    fn write_row(&mut self, row: &[f64]) -> std::io::Result<()> {
+        (**self).write_row(row)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        (**self).flush()
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        (**self).finish()
+    }
+}
+
+pub struct CsvSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> TableSink for CsvSink<W> {
+    fn write_metadata(&mut self, metadata: &Metadata) -> std::io::Result<()> {
+        crate::metadata::write_comments(metadata, &mut self.out)
+    }
+
+    fn write_header(&mut self, columns: &[(String, ColumnType)]) -> std::io::Result<()> {
+        let names = columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        writeln!(self.out, "{}", names.join(","))
+    }
+
+    fn write_row(&mut self, row: &[f64]) -> std::io::Result<()> {
+        let values = row.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        writeln!(self.out, "{}", values.join(","))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(feature = "arrow")]
+pub use self::arrow::{ArrowFormat, ArrowSink};
+
+#[cfg(feature = "arrow")]
+mod arrow {
+    use super::{ColumnType, TableSink};
+    use crate::metadata::Metadata;
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt64Array, UInt8Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    // Schema metadata key holding the run's metadata as a JSON object.
+    const METADATA_KEY: &str = "poker.metadata";
+
+    // Rows per record batch.
+    const BATCH_ROWS: usize = 1 << 16;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ArrowFormat {
+        Ipc,
+        Parquet,
+    }
+
+    enum Writer<W: Write + Send> {
+        Ipc(arrow_ipc::writer::FileWriter<W>),
+        Parquet(parquet::arrow::ArrowWriter<W>),
+    }
+
+    pub struct ArrowSink<W: Write + Send> {
+        format: ArrowFormat,
+        // Until the header gives the schema the writer needs.
+        out: Option<W>,
+        writer: Option<Writer<W>>,
+        schema: Option<Arc<Schema>>,
+        metadata: HashMap<String, String>,
+        // Buffered values, per column.
+        pending: Vec<Vec<f64>>,
+    }
+
+    fn other(e: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
+        std::io::Error::other(e)
+    }
+
+    impl<W: Write + Send> ArrowSink<W> {
+        pub fn new(out: W, format: ArrowFormat) -> Self {
+            Self {
+                format,
+                out: Some(out),
+                writer: None,
+                schema: None,
+                metadata: HashMap::new(),
+                pending: Vec::new(),
+            }
+        }
+
+        fn write_pending(&mut self) -> std::io::Result<()> {
+            let (Some(writer), Some(schema)) = (&mut self.writer, &self.schema) else {
+                return Ok(());
+            };
+            if self.pending.first().is_none_or(|c| c.is_empty()) {
+                return Ok(());
+            }
+            let arrays = schema
+                .fields()
+                .iter()
+                .zip(&mut self.pending)
+                .map(|(field, values)| -> ArrayRef {
+                    let values = std::mem::take(values);
+                    match field.data_type() {
+                        DataType::UInt8 => Arc::new(UInt8Array::from_iter_values(
+                            values.into_iter().map(|v| v as u8),
+                        )),
+                        DataType::UInt64 => Arc::new(UInt64Array::from_iter_values(
+                            values.into_iter().map(|v| v as u64),
+                        )),
+                        _ => Arc::new(Float64Array::from(values)),
+                    }
+                })
+                .collect();
+            let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(other)?;
+            match writer {
+                Writer::Ipc(w) => w.write(&batch).map_err(other),
+                Writer::Parquet(w) => w.write(&batch).map_err(other),
+            }
+        }
+    }
+
+    impl<W: Write + Send> TableSink for ArrowSink<W> {
+        fn write_metadata(&mut self, metadata: &Metadata) -> std::io::Result<()> {
+            let json = serde_json::Value::Object(metadata.clone()).to_string();
+            self.metadata = HashMap::from([(METADATA_KEY.to_string(), json)]);
+            Ok(())
+        }
+
+        fn write_header(&mut self, columns: &[(String, ColumnType)]) -> std::io::Result<()> {
+            let fields = columns
+                .iter()
+                .map(|(name, ty)| {
+                    let ty = match ty {
+                        ColumnType::U8 => DataType::UInt8,
+                        ColumnType::U64 => DataType::UInt64,
+                        ColumnType::F64 => DataType::Float64,
+                    };
+                    Field::new(name, ty, false)
+                })
+                .collect::<Vec<_>>();
+            let metadata = std::mem::take(&mut self.metadata);
+            let schema = Arc::new(Schema::new(fields).with_metadata(metadata));
+            let out = self.out.take().expect("header written twice");
+            self.writer = Some(match self.format {
+                ArrowFormat::Ipc => Writer::Ipc(
+                    arrow_ipc::writer::FileWriter::try_new(out, &schema).map_err(other)?,
+                ),
+                ArrowFormat::Parquet => Writer::Parquet(
+                    parquet::arrow::ArrowWriter::try_new(out, schema.clone(), None)
+                        .map_err(other)?,
+                ),
+            });
+            self.pending = vec![Vec::with_capacity(BATCH_ROWS); columns.len()];
+            self.schema = Some(schema);
+            Ok(())
+        }
+
+        fn write_row(&mut self, row: &[f64]) -> std::io::Result<()> {
+            for (column, &value) in self.pending.iter_mut().zip(row) {
+                column.push(value);
+            }
+            if self.pending[0].len() >= BATCH_ROWS {
+                self.write_pending()?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.write_pending()?;
+            match &mut self.writer {
+                Some(Writer::Ipc(w)) => w.flush().map_err(other),
+                Some(Writer::Parquet(w)) => w.flush().map_err(other),
+                None => Ok(()),
+            }
+        }
+
+        fn finish(&mut self) -> std::io::Result<()> {
+            self.write_pending()?;
+            match self.writer.take() {
+                Some(Writer::Ipc(mut w)) => w.finish().map_err(other),
+                Some(Writer::Parquet(w)) => w.close().map(|_| ()).map_err(other),
+                None => Ok(()),
+            }
+        }
+    }
+
+    // The metadata written by `write_metadata`, if any.
+    pub fn read_metadata(path: &std::path::Path, format: ArrowFormat) -> Result<Metadata, String> {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        let schema = match format {
+            ArrowFormat::Ipc => arrow_ipc::reader::FileReader::try_new(file, None)
+                .map_err(|e| e.to_string())?
+                .schema(),
+            ArrowFormat::Parquet => {
+                parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)
+                    .map_err(|e| e.to_string())?
+                    .schema()
+                    .clone()
+            }
+        };
+        let Some(json) = schema.metadata().get(METADATA_KEY) else {
+            return Ok(Metadata::new());
+        };
+        match serde_json::from_str(json) {
+            Ok(serde_json::Value::Object(metadata)) => Ok(metadata),
+            _ => Err(format!("{METADATA_KEY} is not a JSON object")),
+        }
+    }
+}
+
+fn extension(path: &std::path::Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+// Fails for Arrow and Parquet files without the arrow feature.
+fn check_extension(path: &std::path::Path) -> Result<(), String> {
+    let ext = extension(path);
+    let is_arrow = matches!(ext.as_str(), "arrow" | "ipc" | "feather" | "parquet");
+    if is_arrow && !cfg!(feature = "arrow") {
+        return Err(format!(
+            ".{ext} files need the arrow feature, or use a .csv file"
+        ));
+    }
+    Ok(())
+}
+
+// The format of an Arrow or Parquet file, None for CSV.
+#[cfg(feature = "arrow")]
+fn arrow_format(path: &std::path::Path) -> Option<ArrowFormat> {
+    match extension(path).as_str() {
+        "arrow" | "ipc" | "feather" => Some(ArrowFormat::Ipc),
+        "parquet" => Some(ArrowFormat::Parquet),
+        _ => None,
+    }
+}
+
+// Creates the file at `path` and a sink for the format its extension names.
+pub fn create(path: &std::path::Path) -> Result<Box<dyn TableSink>, String> {
+    check_extension(path)?;
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Could not create {}: {e}", path.display()))?;
+    let out = std::io::BufWriter::new(file);
+    #[cfg(feature = "arrow")]
+    if let Some(format) = arrow_format(path) {
+        return Ok(Box::new(ArrowSink::new(out, format)));
+    }
+    Ok(Box::new(CsvSink::new(out)))
+}
+
+// Opens the table at `path` for more rows, and whether it's new and so
+// still needs its metadata and header. CSV files are appended to, but Arrow
+// and Parquet files end in a footer, so they're created afresh.
+pub fn append(path: &std::path::Path) -> Result<(Box<dyn TableSink>, bool), String> {
+    check_extension(path)?;
+    #[cfg(feature = "arrow")]
+    if arrow_format(path).is_some() {
+        return Ok((create(path)?, true));
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Could not open {}: {e}", path.display()))?;
+    let is_new = file.metadata().map_or(true, |m| m.len() == 0);
+    Ok((
+        Box::new(CsvSink::new(std::io::BufWriter::new(file))),
+        is_new,
+    ))
+}
+
+// Reads the metadata a table at `path` was written with, empty if it has
+// none.
+pub fn read_metadata(path: &std::path::Path) -> Result<Metadata, String> {
+    let error = |e: String| format!("{}: {e}", path.display());
+    check_extension(path).map_err(error)?;
+    #[cfg(feature = "arrow")]
+    if let Some(format) = arrow_format(path) {
+        return arrow::read_metadata(path, format).map_err(error);
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    Ok(crate::metadata::from_comments(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<(String, ColumnType)> {
+        vec![
+            ("iterations".to_string(), ColumnType::U64),
+            ("Pair".to_string(), ColumnType::F64),
+            ("jokers".to_string(), ColumnType::U8),
+        ]
+    }
+
+    fn metadata() -> Metadata {
+        Metadata::from_iter([("seed".to_string(), "3".into())])
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut out = Vec::new();
+        let mut sink = CsvSink::new(&mut out);
+        sink.write_metadata(&metadata()).unwrap();
+        sink.write_header(&columns()).unwrap();
+        sink.write_row(&[100.0, 0.25, 2.0]).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(
+            "# seed: 3\niterations,Pair,jokers\n100,0.25,2\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_append() {
+        let path = std::env::temp_dir().join(format!("poker-append-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        for expected_new in [true, false] {
+            let (mut sink, is_new) = append(&path).unwrap();
+            assert_eq!(expected_new, is_new);
+            if is_new {
+                sink.write_header(&columns()).unwrap();
+            }
+            sink.write_row(&[100.0, 0.25, 2.0]).unwrap();
+            sink.finish().unwrap();
+        }
+        assert_eq!(
+            "iterations,Pair,jokers\n100,0.25,2\n100,0.25,2\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_arrow() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, UInt64Type, UInt8Type};
+
+        let write = |format| {
+            let mut out = Vec::new();
+            let mut sink = ArrowSink::new(&mut out, format);
+            sink.write_metadata(&metadata()).unwrap();
+            sink.write_header(&columns()).unwrap();
+            for i in 0..70000 {
+                sink.write_row(&[i as f64, 0.5, 1.0]).unwrap();
+            }
+            sink.finish().unwrap();
+            drop(sink);
+            out
+        };
+
+        let ipc = write(ArrowFormat::Ipc);
+        let reader =
+            arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(ipc), None).unwrap();
+        assert_eq!(
+            "{\"seed\":\"3\"}",
+            reader.schema().metadata()["poker.metadata"]
+        );
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        // Split into a full batch and the rest.
+        assert_eq!(2, batches.len());
+        assert_eq!(70000, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+        let last = &batches[1];
+        assert_eq!(
+            69999,
+            last.column(0).as_primitive::<UInt64Type>().value(4463)
+        );
+        assert_eq!(0.5, last.column(1).as_primitive::<Float64Type>().value(0));
+        assert_eq!(1, last.column(2).as_primitive::<UInt8Type>().value(0));
+
+        let parquet = write(ArrowFormat::Parquet);
+        assert_eq!(b"PAR1", &parquet[..4]);
+        assert_eq!(b"PAR1", &parquet[parquet.len() - 4..]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Helpers for writing tests against this crate, available to other crates
+//! with the `test_util` feature.
+
+use crate::{split_jokers, standard_deck, Card, CardOrJoker, MAX_CARDS};
+use rand::SeedableRng;
+
+// Cards from a hand string like "Ah Kh 2c", panicking if it doesn't parse or
+// contains jokers.
+#[macro_export]
+macro_rules! cards {
+    ($s:expr) => {{
+        let (cards, num_jokers) = $crate::parse_hand($s).expect("invalid cards");
+        assert_eq!(0, num_jokers, "cards! doesn't allow jokers, use parse_hand");
+        cards
+    }};
+}
+
+// A deck in exactly the order given by a hand string, jokers included, for
+// tests that need to know which cards will be drawn.
+pub fn stacked_deck(s: &str) -> Vec<CardOrJoker> {
+    let mut deck = Vec::new();
+    for token in s.split_whitespace() {
+        if token == "J" {
+            deck.push(CardOrJoker::Joker);
+        } else {
+            deck.push(CardOrJoker::Card(token.parse().expect("invalid card")));
+        }
+    }
+    deck
+}
+
+// Deals random hands from standard decks, always the same sequence for a
+// given seed.
+pub struct Dealer {
+    deck: Vec<CardOrJoker>,
+    rng: rand::rngs::StdRng,
+}
+
+impl Dealer {
+    pub fn new(decks: usize, jokers: u8, seed: u64) -> Self {
+        Self {
+            deck: standard_deck(decks, jokers),
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn deal(&mut self, num_cards: usize) -> (arrayvec::ArrayVec<Card, MAX_CARDS>, u8) {
+        use rand::seq::SliceRandom;
+
+        let (drawn, _) = self.deck.partial_shuffle(&mut self.rng, num_cards);
+        split_jokers(drawn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cards() {
+        assert_eq!(
+            vec![crate::card!("Ah"), crate::card!("2c")],
+            cards!("Ah 2c")
+        );
+    }
+
+    #[test]
+    fn test_stacked_deck() {
+        let deck = stacked_deck("As J 2d");
+        assert_eq!(3, deck.len());
+        assert!(deck[1] == CardOrJoker::Joker);
+        assert!(deck[2] == CardOrJoker::Card(crate::card!("2d")));
+    }
+
+    #[test]
+    fn test_dealer() {
+        let mut a = Dealer::new(1, 2, 7);
+        let mut b = Dealer::new(1, 2, 7);
+        for _ in 0..10 {
+            let hand = a.deal(5);
+            assert_eq!(hand, b.deal(5));
+            assert_eq!(5, hand.0.len() + hand.1 as usize);
+        }
+    }
+}
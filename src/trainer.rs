@@ -0,0 +1,172 @@
+//! `poker trainer`: name the best hand in random draws, checked against
+//! `evaluate_best`, for practice at reading hands quickly.
+
+use crate::{evaluate_best, format_hand, split_jokers, Card, CardOrJoker, HandRank};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct Draw {
+    pub cards: Vec<Card>,
+    pub num_jokers: u8,
+    pub best: HandRank,
+}
+
+impl Draw {
+    // Deals `num_cards` cards, highest first.
+    pub fn random(
+        deck: &mut [CardOrJoker],
+        num_cards: usize,
+        hand_size: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let (drawn, _) = deck.partial_shuffle(rng, num_cards);
+        let (cards, num_jokers) = split_jokers(drawn);
+        let mut cards = cards.to_vec();
+        cards.sort_by(|a, b| b.cmp(a));
+        let best = evaluate_best(&cards, num_jokers, hand_size);
+        Self {
+            cards,
+            num_jokers,
+            best,
+        }
+    }
+}
+
+impl std::fmt::Display for Draw {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&format_hand(&self.cards, self.num_jokers))
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.trim()
+        .to_ascii_lowercase()
+        .replace([' ', '-'], "_")
+        .replace(['(', ')'], "")
+}
+
+// Parses a category by its id ("two_pair"), its name ("2 pair") or words
+// like "three of a kind", ignoring case.
+pub fn parse_answer(s: &str) -> Result<HandRank, String> {
+    let answer = normalize(s);
+    let answer = match answer.as_str() {
+        "three_of_a_kind" | "trips" => "3oak",
+        "four_of_a_kind" | "quads" => "4oak",
+        "five_of_a_kind" => "5oak",
+        "six_of_a_kind" => "6oak",
+        answer => answer,
+    };
+    HandRank::ALL
+        .iter()
+        .copied()
+        .find(|r| r.id() == answer || normalize(r.name()) == answer)
+        .ok_or_else(|| format!("unknown hand '{}'", s.trim()))
+}
+
+pub struct Attempt {
+    pub best: HandRank,
+    pub answer: HandRank,
+    pub time: Duration,
+}
+
+#[derive(Default)]
+pub struct Tally {
+    pub attempts: Vec<Attempt>,
+}
+
+impl Tally {
+    // Accuracy and answer times overall, then for each category that was
+    // missed, what it was taken for.
+    pub fn summary(&self) -> String {
+        let n = self.attempts.len();
+        if n == 0 {
+            return "no hands answered".to_string();
+        }
+        let right = self.attempts.iter().filter(|a| a.answer == a.best).count();
+        let mut times = self.attempts.iter().map(|a| a.time).collect::<Vec<_>>();
+        times.sort();
+        let mean = times.iter().sum::<Duration>() / n as u32;
+        let mut ret = format!(
+            "{right}/{n} right ({:.0}%), {:.1}s per hand on average, {:.1}s median",
+            100.0 * right as f64 / n as f64,
+            mean.as_secs_f64(),
+            times[n / 2].as_secs_f64()
+        );
+        for best in HandRank::ALL {
+            let attempts = self
+                .attempts
+                .iter()
+                .filter(|a| a.best == best)
+                .collect::<Vec<_>>();
+            let mut mistakes = attempts
+                .iter()
+                .filter(|a| a.answer != best)
+                .map(|a| a.answer.name())
+                .collect::<Vec<_>>();
+            if mistakes.is_empty() {
+                continue;
+            }
+            mistakes.sort();
+            mistakes.dedup();
+            let right = attempts.len() - attempts.iter().filter(|a| a.answer != best).count();
+            ret += &format!(
+                "\n{best}: {right}/{} right, mistaken for {}",
+                attempts.len(),
+                mistakes.join(", ")
+            );
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(Ok(HandRank::TwoPair), parse_answer("two_pair"));
+        assert_eq!(Ok(HandRank::TwoPair), parse_answer(" 2 Pair "));
+        assert_eq!(Ok(HandRank::ThreeOfAKind), parse_answer("Three of a kind"));
+        assert_eq!(Ok(HandRank::StraightFlush), parse_answer("strt flush"));
+        assert_eq!(Ok(HandRank::StraightFlush), parse_answer("straight-flush"));
+        assert_eq!(Ok(HandRank::SixFlush), parse_answer("Flush (6)"));
+        assert!(parse_answer("royal").is_err());
+    }
+
+    #[test]
+    fn test_random_draw() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let mut deck = crate::standard_deck(1, 1);
+        for num_cards in 5..=8 {
+            let draw = Draw::random(&mut deck, num_cards, 5, &mut rng);
+            assert_eq!(num_cards, draw.cards.len() + draw.num_jokers as usize);
+            assert!(draw.cards.windows(2).all(|w| w[0] >= w[1]));
+            assert_eq!(draw.best, evaluate_best(&draw.cards, draw.num_jokers, 5));
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let attempt = |best, answer, secs| Attempt {
+            best,
+            answer,
+            time: Duration::from_secs(secs),
+        };
+        let tally = Tally {
+            attempts: vec![
+                attempt(HandRank::Pair, HandRank::Pair, 1),
+                attempt(HandRank::Flush, HandRank::Straight, 4),
+                attempt(HandRank::Flush, HandRank::Flush, 2),
+                attempt(HandRank::Pair, HandRank::Pair, 1),
+            ],
+        };
+        assert_eq!(
+            "3/4 right (75%), 2.0s per hand on average, 2.0s median\n\
+             Flush: 1/2 right, mistaken for Straight",
+            tally.summary()
+        );
+    }
+}
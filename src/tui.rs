@@ -0,0 +1,164 @@
+//! `--tui`: instead of printing the counts after every batch, redraws one
+//! screen in place with each hand's probability and confidence interval drawn
+//! as a bar, plus progress and throughput. `poker edit` shows the same screen
+//! for each version of the deck being edited, see editor.rs.
+
+use crate::editor::Live;
+use crate::interval::{interval_with, CiMethod};
+use crate::HandCount;
+use std::time::Duration;
+
+// Moves the cursor to the top left and clears the screen.
+pub const CLEAR: &str = "\x1b[H\x1b[2J";
+
+const BAR_WIDTH: usize = 40;
+
+// Bars are on a log scale from this probability up to 1, since the hands of
+// interest span several orders of magnitude.
+const MIN_PROBABILITY: f64 = 1e-6;
+
+fn bar_position(p: f64) -> usize {
+    let min = MIN_PROBABILITY.log10();
+    let x = (p.max(MIN_PROBABILITY).log10() - min) / -min;
+    ((x * (BAR_WIDTH - 1) as f64).round() as usize).min(BAR_WIDTH - 1)
+}
+
+// The interval `p` ± `ci` as a run of "─" around a "●" at `p`. Hands never
+// seen have no bar.
+pub fn ci_bar(p: f64, ci: f64) -> String {
+    let mut bar = vec!['·'; BAR_WIDTH];
+    if p > 0.0 {
+        let (lo, hi) = (bar_position(p - ci), bar_position(p + ci));
+        bar[lo..=hi].fill('─');
+        bar[bar_position(p)] = '●';
+    }
+    bar.into_iter().collect()
+}
+
+fn progress_bar(fraction: f64) -> String {
+    let done = ((fraction * BAR_WIDTH as f64) as usize).min(BAR_WIDTH);
+    format!(
+        "[{}{}] {:.0}%",
+        "#".repeat(done),
+        " ".repeat(BAR_WIDTH - done),
+        100.0 * fraction
+    )
+}
+
+// The whole screen, most likely hand first, with `method` intervals.
+// Progress is only shown when there's a `max_iters` to make progress towards.
+pub fn frame(
+    counts: &[HandCount],
+    num_iters: u64,
+    method: CiMethod,
+    elapsed: Duration,
+    max_iters: Option<u64>,
+) -> String {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 {
+        num_iters as f64 / secs
+    } else {
+        0.0
+    };
+    let mut ret = format!("{num_iters} iterations in {secs:.1}s ({rate:.0}/s)\n");
+    if let Some(max_iters) = max_iters {
+        ret += &progress_bar(num_iters as f64 / max_iters as f64);
+        ret += "\n";
+    }
+    let max_str_len = counts.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut counts = counts.iter().collect::<Vec<_>>();
+    counts.sort_by_key(|c| std::cmp::Reverse((c.count, c.name)));
+    ret += &format!(
+        "\n{:width$}                      1e-6{:>pad$}\n",
+        "",
+        "1",
+        width = max_str_len,
+        pad = BAR_WIDTH - 4
+    );
+    for c in counts {
+        let (p, ci) = interval_with(method, num_iters, c.count);
+        ret += &format!(
+            "{name: >width$}: {p:.6} ± {ci:.6} {}\n",
+            ci_bar(p, ci),
+            name = c.name,
+            width = max_str_len,
+        );
+    }
+    ret
+}
+
+// The deck editor's screen: the deck, the simulation of it so far and what
+// the last command said, above a prompt.
+pub fn editor_frame(live: &Live, elapsed: Duration, max_iters: u64, message: &str) -> String {
+    let mut ret = format!(
+        "deck: {} cards, {}\n",
+        live.deck.len(),
+        crate::deck::describe(&live.deck)
+    );
+    ret += &frame(
+        &live.counts,
+        live.num_iters,
+        CiMethod::Normal,
+        elapsed,
+        Some(max_iters),
+    );
+    ret += "\n";
+    if !message.is_empty() {
+        ret += message;
+        ret += "\n";
+    }
+    ret += "add/remove CARD [N], dup/wild CARD, undo, reset, rules R, results HAND, save PATH or q\n> ";
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_bar() {
+        assert_eq!("·".repeat(BAR_WIDTH), ci_bar(0.0, 0.0));
+        let certain = ci_bar(1.0, 0.0);
+        assert!(certain.ends_with('●'));
+        assert_eq!(BAR_WIDTH, certain.chars().count());
+        // 1e-3 is halfway along the log scale.
+        let bar = ci_bar(1e-3, 5e-4).chars().collect::<Vec<_>>();
+        assert_eq!('●', bar[20]);
+        assert_eq!('─', bar[19]);
+        assert_eq!('·', bar[0]);
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut counts = vec![
+            HandCount::new("Pair", |_, _| true),
+            HandCount::new("3oak", |_, _| true),
+        ];
+        counts[0].count = 500;
+        counts[1].count = 10;
+        let frame = frame(
+            &counts,
+            1000,
+            CiMethod::Normal,
+            Duration::from_secs(2),
+            Some(4000),
+        );
+        let lines = frame.lines().collect::<Vec<_>>();
+        assert_eq!("1000 iterations in 2.0s (500/s)", lines[0]);
+        assert!(lines[1].ends_with("] 25%"));
+        assert!(lines[4].starts_with("Pair: 0.500000 ± 0.047434 "));
+        assert!(lines[5].starts_with("3oak: 0.010000 ± 0.009439 "));
+    }
+
+    #[test]
+    fn test_editor_frame() {
+        let deck = crate::standard_deck(1, 1);
+        let live = Live::new(deck, 7, crate::hand_counts(5).unwrap());
+        let frame = editor_frame(&live, Duration::from_secs(1), 1000, "the deck has no Kh");
+        let lines = frame.lines().collect::<Vec<_>>();
+        assert_eq!("deck: 53 cards, 1 joker", lines[0]);
+        assert_eq!("0 iterations in 1.0s (0/s)", lines[1]);
+        assert_eq!(Some(&"the deck has no Kh"), lines.iter().rev().nth(2));
+        assert!(frame.ends_with("\n> "));
+    }
+}
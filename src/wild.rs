@@ -0,0 +1,295 @@
+//! Wild cards which can only stand in for some cards, like a card wild for
+//! any heart, or one whose rank is wild but whose suit is fixed. Jokers can be
+//! any card and Balatro's wild cards any suit of their rank. Anything in
+//! between, like some enhancements and mods, is a `WildCard` with a set of
+//! ranks and a set of suits, standing in for any card with one of each.
+//!
+//! Written like cards, with '*' for any rank or suit and brackets for a set:
+//! "*h" is any heart, "[AKQ]*" any ace, king or queen, "*[dh]" any red card,
+//! "7*" any seven and "**" a joker.
+//!
+//! Hands are checked by trying each card the restricted wilds could be, so
+//! jokers and wild cards among them are passed to the detectors as usual, and
+//! identical wilds are only tried in one order.
+
+use crate::{evaluate_best, Card, HandCount, HandRank, Rank, Suit, NUM_RANKS, NUM_SUITS};
+
+const ALL_RANKS: u16 = (1 << NUM_RANKS) - 1;
+const ALL_SUITS: u8 = (1 << NUM_SUITS) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WildCard {
+    // Bit `rank as u8` for each rank it can be.
+    ranks: u16,
+    // Bit `suit as u8` for each suit it can be.
+    suits: u8,
+}
+
+// How a wild takes part in a deal.
+enum Stand {
+    Joker,
+    // A wild card of this rank, which counts as every suit.
+    Card(Card),
+    // One of these cards, each to be tried.
+    OneOf(Vec<Card>),
+}
+
+impl WildCard {
+    pub const JOKER: WildCard = WildCard {
+        ranks: ALL_RANKS,
+        suits: ALL_SUITS,
+    };
+
+    // None if it can't be any card.
+    pub fn new(ranks: &[Rank], suits: &[Suit]) -> Option<Self> {
+        let ranks = ranks.iter().fold(0, |mask, &r| mask | 1 << r as u8);
+        let suits = suits.iter().fold(0, |mask, &s| mask | 1 << s as u8);
+        (ranks != 0 && suits != 0).then_some(Self { ranks, suits })
+    }
+
+    // Any card of `suit`, like a heart-only wild.
+    pub fn of_suit(suit: Suit) -> Self {
+        Self {
+            ranks: ALL_RANKS,
+            suits: 1 << suit as u8,
+        }
+    }
+
+    // Any card of `rank`, which is the same as a wild card of that rank.
+    pub fn of_rank(rank: Rank) -> Self {
+        Self {
+            ranks: 1 << rank as u8,
+            suits: ALL_SUITS,
+        }
+    }
+
+    pub fn can_be(self, card: Card) -> bool {
+        self.ranks >> card.rank as u8 & 1 != 0 && self.suits >> card.suit as u8 & 1 != 0
+    }
+
+    // The cards it can stand in for.
+    pub fn cards(self) -> impl Iterator<Item = Card> {
+        Rank::ALL
+            .into_iter()
+            .flat_map(|rank| Suit::ALL.into_iter().map(move |suit| Card::new(rank, suit)))
+            .filter(move |&c| self.can_be(c))
+    }
+
+    fn stand(self) -> Stand {
+        if self == Self::JOKER {
+            Stand::Joker
+        } else if self.suits == ALL_SUITS && self.ranks.count_ones() == 1 {
+            let rank = Rank::ALL[self.ranks.trailing_zeros() as usize];
+            Stand::Card(Card::new(rank, Suit::ALL[0]).made_wild())
+        } else {
+            Stand::OneOf(self.cards().collect())
+        }
+    }
+}
+
+// Parses one side of a wild, a character from `chars`, '*' for all of them
+// or a bracketed set, returning the mask and the rest of `s`.
+fn parse_set<'a>(s: &'a str, chars: &[u8], what: &str) -> Result<(u16, &'a str), String> {
+    let bit = |c: u8| {
+        chars
+            .iter()
+            .position(|&x| x == c.to_ascii_lowercase() || x == c.to_ascii_uppercase())
+            .map(|i| 1 << i)
+            .ok_or_else(|| format!("invalid {what} '{}'", c as char))
+    };
+    match s.as_bytes() {
+        [b'*', ..] => Ok(((1 << chars.len()) - 1, &s[1..])),
+        [b'[', ..] => {
+            let end = s.find(']').ok_or_else(|| format!("missing ']' in '{s}'"))?;
+            let mut mask = 0;
+            for &c in &s.as_bytes()[1..end] {
+                mask |= bit(c)?;
+            }
+            Ok((mask, &s[end + 1..]))
+        }
+        [c, ..] => Ok((bit(*c)?, &s[1..])),
+        [] => Err(format!("missing {what}")),
+    }
+}
+
+impl std::str::FromStr for WildCard {
+    type Err = String;
+
+    // Parses wilds like "*h", "[AKQ]*" or "*[dh]".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |e: String| format!("invalid wild '{s}': {e}");
+        let (ranks, rest) = parse_set(s, crate::RANK_CHARS, "rank").map_err(error)?;
+        let (suits, rest) = parse_set(rest, crate::SUIT_CHARS, "suit").map_err(error)?;
+        if !rest.is_empty() {
+            return Err(error(format!("unexpected '{rest}'")));
+        }
+        if ranks == 0 || suits == 0 {
+            return Err(error("it can't be any card".to_string()));
+        }
+        Ok(Self {
+            ranks,
+            suits: suits as u8,
+        })
+    }
+}
+
+// Calls `f` with each deal the wilds could make, until it returns true.
+// Returns whether it did.
+fn any_substitution(
+    cards: &[Card],
+    num_jokers: u8,
+    wilds: &[WildCard],
+    f: &mut dyn FnMut(&[Card], u8) -> bool,
+) -> bool {
+    let mut deal = cards.to_vec();
+    let mut num_jokers = num_jokers;
+    let mut sorted = wilds.to_vec();
+    sorted.sort_unstable();
+    // Each restricted wild's cards, and whether it's the same as the wild
+    // before it, so that identical wilds only try cards in increasing order.
+    let mut choices = Vec::new();
+    for (i, wild) in sorted.iter().enumerate() {
+        match wild.stand() {
+            Stand::Joker => num_jokers = num_jokers.saturating_add(1),
+            Stand::Card(card) => deal.push(card),
+            Stand::OneOf(options) => {
+                let same_as_previous = i > 0 && sorted[i - 1] == *wild;
+                choices.push((options, same_as_previous));
+            }
+        }
+    }
+    substitute(&mut deal, num_jokers, &choices, 0, f)
+}
+
+fn substitute(
+    deal: &mut Vec<Card>,
+    num_jokers: u8,
+    choices: &[(Vec<Card>, bool)],
+    first: usize,
+    f: &mut dyn FnMut(&[Card], u8) -> bool,
+) -> bool {
+    let Some(((options, _), rest)) = choices.split_first() else {
+        return f(deal, num_jokers);
+    };
+    let next_is_same = rest.first().is_some_and(|&(_, same)| same);
+    for (i, &card) in options.iter().enumerate().skip(first) {
+        deal.push(card);
+        let first = if next_is_same { i } else { 0 };
+        let found = substitute(deal, num_jokers, rest, first, f);
+        deal.pop();
+        if found {
+            return true;
+        }
+    }
+    false
+}
+
+// Whether the cards, jokers and restricted wilds make `count`'s hand.
+pub fn matches(count: &HandCount, cards: &[Card], num_jokers: u8, wilds: &[WildCard]) -> bool {
+    any_substitution(cards, num_jokers, wilds, &mut |deal, num_jokers| {
+        count.matches(deal, num_jokers)
+    })
+}
+
+// The best hand playing at most `hand_size` cards, as `evaluate_best`.
+pub fn evaluate_best_with_wilds(
+    cards: &[Card],
+    num_jokers: u8,
+    wilds: &[WildCard],
+    hand_size: usize,
+) -> HandRank {
+    let top = HandRank::ALL
+        .into_iter()
+        .filter(|r| r.num_cards() <= hand_size)
+        .max()
+        .unwrap_or(HandRank::ALL[0]);
+    let mut best = None;
+    any_substitution(cards, num_jokers, wilds, &mut |deal, num_jokers| {
+        let rank = evaluate_best(deal, num_jokers, hand_size);
+        best = best.max(Some(rank));
+        rank == top
+    });
+    best.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card, cards, hand_counts};
+
+    fn count(name: &str) -> HandCount {
+        hand_counts(5)
+            .unwrap()
+            .into_iter()
+            .find(|c| c.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(WildCard::of_suit(Suit::Hearts)), "*h".parse());
+        assert_eq!(Ok(WildCard::of_rank(Rank::Seven)), "7*".parse());
+        assert_eq!(Ok(WildCard::JOKER), "**".parse());
+        assert_eq!(
+            WildCard::new(&[Rank::Ace, Rank::King], &[Suit::Diamonds, Suit::Hearts]),
+            "[ak][HD]".parse().ok()
+        );
+        assert_eq!(None, WildCard::new(&[], &[Suit::Hearts]));
+        for bad in ["", "*", "[]h", "Xh", "*x", "[Ah", "*hh"] {
+            assert!(bad.parse::<WildCard>().is_err(), "{bad}");
+        }
+        let red = "*[dh]".parse::<WildCard>().unwrap();
+        assert!(red.can_be(card!("2d")) && !red.can_be(card!("2s")));
+        assert_eq!(26, red.cards().count());
+    }
+
+    #[test]
+    fn test_matches() {
+        let flush = count("Flush");
+        let four = cards!("Ah 9h 6h 2h Kc");
+        assert!(matches(&flush, &four, 0, &["*h".parse().unwrap()]));
+        assert!(!matches(&flush, &four, 0, &["*s".parse().unwrap()]));
+        // Two pair needs another nine or two, which a face card wild can't be.
+        let two_pair = count("2 pair");
+        let hand = cards!("As Ad 9h 2c");
+        assert!(!matches(&two_pair, &hand, 0, &["[KQJ]*".parse().unwrap()]));
+        assert!(matches(&two_pair, &hand, 0, &["[9Q]c".parse().unwrap()]));
+        // Full jokers and rank wilds behave as they always have.
+        assert_eq!(
+            count("4oak").matches(&hand, 2),
+            matches(&count("4oak"), &hand, 0, &[WildCard::JOKER; 2])
+        );
+        assert!(matches(
+            &count("3oak"),
+            &hand,
+            0,
+            &[WildCard::of_rank(Rank::Ace)]
+        ));
+        // Identical wilds still make hands needing different cards.
+        let straight = count("Straight");
+        let wilds = ["*h".parse().unwrap(); 2];
+        assert!(matches(&straight, &cards!("5c 6d 9s"), 0, &wilds));
+        assert!(!matches(&straight, &cards!("5c 6d Ts"), 0, &wilds));
+    }
+
+    #[test]
+    fn test_evaluate_best_with_wilds() {
+        let hand = cards!("Ah Kh Qh Jh 2c");
+        assert_eq!(
+            HandRank::StraightFlush,
+            evaluate_best_with_wilds(&hand, 0, &["T[hs]".parse().unwrap()], 5)
+        );
+        assert_eq!(
+            HandRank::Straight,
+            evaluate_best_with_wilds(&hand, 0, &["Tc".parse().unwrap()], 5)
+        );
+        assert_eq!(
+            HandRank::Flush,
+            evaluate_best_with_wilds(&hand, 0, &["[2345]h".parse().unwrap()], 5)
+        );
+        assert_eq!(
+            HandRank::HighCard,
+            evaluate_best_with_wilds(&cards!("As 9d 6h 2c"), 0, &[], 5)
+        );
+    }
+}